@@ -0,0 +1,229 @@
+//! State of one booking as it moves through pricing, card enrollment and
+//! payment with the Payment Agent.
+
+use sha2::{Digest, Sha256};
+use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+use crate::claude::SessionUsage;
+
+/// How the Payment Agent consumer ID is derived for a traveler
+/// (`CONSUMER_ID_STRATEGY`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConsumerIdStrategy {
+    /// Hash of the traveler's normalized email (default), so the same person
+    /// finds their enrolled cards again without the address leaving Agent A
+    EmailHash,
+    /// Explicit user ID supplied by the frontend (`AGENT_A_USER_ID`)
+    UserId(String),
+}
+
+impl ConsumerIdStrategy {
+    pub fn from_env() -> Self {
+        match (
+            std::env::var("CONSUMER_ID_STRATEGY").as_deref(),
+            std::env::var("AGENT_A_USER_ID"),
+        ) {
+            (Ok("user_id"), Ok(user_id)) if !user_id.trim().is_empty() => {
+                ConsumerIdStrategy::UserId(user_id.trim().to_string())
+            }
+            _ => ConsumerIdStrategy::EmailHash,
+        }
+    }
+
+    /// Stable consumer ID for the traveler with `email`
+    pub fn consumer_id(&self, email: &str) -> String {
+        match self {
+            ConsumerIdStrategy::EmailHash => {
+                let digest = Sha256::digest(email.trim().to_lowercase().as_bytes());
+                format!("cons_{}", &hex(&digest)[..16])
+            }
+            ConsumerIdStrategy::UserId(user_id) => user_id.clone(),
+        }
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Step of the interactive booking flow, as shown to the traveler
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BookingStep {
+    Pricing,
+    /// Choosing between several enrolled cards
+    CardSelection,
+    CardEnrollment,
+    Payment,
+    /// Step-up authentication (3-D Secure / OTP) requested by the issuer
+    PaymentChallenge,
+    Booking,
+}
+
+impl BookingStep {
+    /// Steps the traveler sees; a challenge and the booking itself are part
+    /// of the payment step
+    pub const TOTAL: u32 = 3;
+
+    /// Step a tool belongs to, for log correlation; `None` for tools used
+    /// outside a booking
+    pub fn for_tool(tool_name: &str) -> Option<Self> {
+        match tool_name {
+            "get-ticket-price" | "get-ticket-price-v2" | "list-ancillaries" | "prove_ticket_price" => {
+                Some(BookingStep::Pricing)
+            }
+            "enroll-card" | "unenroll-card" => Some(BookingStep::CardEnrollment),
+            "initiate-purchase-instruction" | "get-payment-status" | "retrieve-payment-credentials"
+            | "confirm-transaction" | "refund-payment" => Some(BookingStep::Payment),
+            "complete-payment-challenge" => Some(BookingStep::PaymentChallenge),
+            "book-flight" | "get-booking" | "cancel-booking" => Some(BookingStep::Booking),
+            _ => None,
+        }
+    }
+
+    /// Name logged as the `step` field
+    pub fn name(self) -> &'static str {
+        match self {
+            BookingStep::Pricing => "pricing",
+            BookingStep::CardSelection => "card_selection",
+            BookingStep::CardEnrollment => "card_enrollment",
+            BookingStep::Payment => "payment",
+            BookingStep::PaymentChallenge => "payment_challenge",
+            BookingStep::Booking => "booking",
+        }
+    }
+
+    pub fn number(self) -> u32 {
+        match self {
+            BookingStep::Pricing => 1,
+            BookingStep::CardSelection | BookingStep::CardEnrollment => 2,
+            BookingStep::Payment | BookingStep::PaymentChallenge | BookingStep::Booking => 3,
+        }
+    }
+}
+
+/// Who is paying for the booking in progress, and how far payment got: a
+/// card token once enrollment is done, an instruction ID per issued part and
+/// `settled` once its credentials are retrieved. A retried payment skips
+/// whatever is already recorded here.
+#[derive(Debug, Clone)]
+pub struct BookingState {
+    /// Payment Agent consumer the card enrollment and payments belong to
+    pub consumer_id: String,
+    /// Payment Agent session, one per consumer, so saved cards carry over
+    /// between bookings
+    pub payment_session_id: String,
+    /// This booking's session with Agent A and Agent B. Random, so nobody
+    /// can look up its records from the traveler's email.
+    pub session_id: String,
+    /// Client-side reference for this booking's payment, sent with every
+    /// attempt so a retry cannot create a second purchase instruction
+    pub payment_reference: String,
+    /// Reference of this booking's card enrollment, if a card was enrolled
+    pub enrollment_reference: Option<String>,
+    /// Enrolled card (Payment Agent token) the traveler pays with
+    pub token_id: Option<String>,
+    /// Purchase instructions the booking is paid with; more than one when the
+    /// payment is split. Refunded on cancellation.
+    pub payments: Vec<PaymentPart>,
+    /// Claude usage of the conversation turn that started this booking,
+    /// reported to Agent A under the booking's session
+    pub claude_usage: SessionUsage,
+}
+
+/// One purchase instruction of a booking's payment
+#[derive(Debug, Clone)]
+pub struct PaymentPart {
+    /// Enrolled card charged
+    pub token_id: String,
+    /// Amount in minor units of the quote currency
+    pub amount_minor: i64,
+    /// Client-side reference of this instruction, derived from the booking's
+    /// payment reference
+    pub reference: String,
+    pub instruction_id: Option<String>,
+    /// Reference the payment credentials were retrieved under, for
+    /// reconciliation against the payment processor
+    pub transaction_reference: Option<String>,
+    /// Confirmed, with its credentials (and proof) retrieved
+    pub settled: bool,
+    /// Amount given back so far, in minor units of the quote currency
+    pub refunded_minor: i64,
+    /// Payment Agent proof returned with the credentials
+    pub proof_id: Option<String>,
+    /// Conversion into the card's currency, when it differs from the quote's
+    pub settlement: Option<Settlement>,
+}
+
+/// Amount a part settles for in the card's currency
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settlement {
+    /// ISO 4217 currency the card settles in
+    pub currency: String,
+    /// Amount in minor units of `currency`
+    pub amount_minor: i64,
+    /// Units of `currency` per unit of the quote currency
+    pub fx_rate: f64,
+}
+
+impl PaymentPart {
+    /// Start this part's payment transaction under a fresh reference,
+    /// returning it
+    pub fn new_transaction_reference(&mut self) -> String {
+        let reference = format!("txn_{}", Uuid::new_v4().simple());
+        self.transaction_reference = Some(reference.clone());
+        reference
+    }
+}
+
+impl BookingState {
+    pub fn new(consumer_id: String) -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default();
+        let digest = Sha256::digest(format!("{}:{}", consumer_id, nanos).as_bytes());
+        Self {
+            payment_session_id: format!("sess_{}", consumer_id),
+            session_id: format!("sess_{}", Uuid::new_v4().simple()),
+            payment_reference: format!("pay_{}", &hex(&digest)[..24]),
+            consumer_id,
+            enrollment_reference: None,
+            token_id: None,
+            payments: Vec::new(),
+            claude_usage: SessionUsage::default(),
+        }
+    }
+
+    /// Start a card enrollment under a fresh reference, returning it
+    pub fn new_enrollment_reference(&mut self) -> String {
+        let reference = format!("enroll_{}", Uuid::new_v4().simple());
+        self.enrollment_reference = Some(reference.clone());
+        reference
+    }
+
+    /// Add a part charging `amount_minor` to `token_id`. The first part keeps
+    /// the booking's payment reference; later ones get a numbered suffix.
+    pub fn add_payment(&mut self, token_id: &str, amount_minor: i64, settlement: Option<Settlement>) {
+        let reference = match self.payments.len() {
+            0 => self.payment_reference.clone(),
+            n => format!("{}_{}", self.payment_reference, n + 1),
+        };
+        self.payments.push(PaymentPart {
+            token_id: token_id.to_string(),
+            amount_minor,
+            reference,
+            instruction_id: None,
+            transaction_reference: None,
+            settled: false,
+            refunded_minor: 0,
+            proof_id: None,
+            settlement,
+        });
+    }
+
+    /// Total of the settled parts less what was refunded, in minor units
+    pub fn settled_minor(&self) -> i64 {
+        self.payments.iter().filter(|p| p.settled).map(|p| p.amount_minor - p.refunded_minor).sum()
+    }
+}
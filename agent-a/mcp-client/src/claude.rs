@@ -0,0 +1,580 @@
+//! Claude API client for Agent A
+//!
+//! Wraps the Anthropic Messages API call used to pick tools for a user query,
+//! and tracks token usage across the session so a configurable budget can be
+//! enforced. The CLI reports each call's usage to the Agent A server, which
+//! keeps the totals per session. With CLAUDE_STREAMING=true the reply is
+//! streamed and text deltas are passed to the caller as they arrive; the CLI
+//! prints them to the terminal.
+//!
+//! The system prompt is sent as two blocks: the fixed instructions and the
+//! tool definitions fetched at startup. Both are identical on every turn, so
+//! the second block carries a cache_control breakpoint and the whole prefix is
+//! served from Anthropic's prompt cache after the first call.
+
+use anyhow::{Result, anyhow};
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::{show_status, AgentConfig};
+
+const CLAUDE_MODEL: &str = "claude-3-haiku-20240307";
+
+/// Output token cap for normal turns
+const MAX_TOKENS: i32 = 1024;
+
+/// Output token cap once the session budget is exhausted
+const MAX_TOKENS_OVER_BUDGET: i32 = 512;
+
+/// Retries after the first attempt for rate-limited/overloaded responses
+const MAX_RETRIES: u32 = 4;
+
+/// First backoff delay; doubles on each retry
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(1);
+
+/// Longest backoff before jitter, when the server gives no retry-after
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(20);
+
+/// Upper bound on the total time spent waiting between retries
+const MAX_TOTAL_RETRY_WAIT: Duration = Duration::from_secs(60);
+
+/// claude-3-haiku list prices, USD per million tokens
+const INPUT_USD_PER_MTOK: f64 = 0.25;
+const OUTPUT_USD_PER_MTOK: f64 = 1.25;
+const CACHE_WRITE_USD_PER_MTOK: f64 = 0.30;
+const CACHE_READ_USD_PER_MTOK: f64 = 0.03;
+
+/// Claude API request
+#[derive(Debug, Serialize)]
+struct ClaudeRequest {
+    model: String,
+    max_tokens: i32,
+    system: Vec<SystemBlock>,
+    messages: Vec<ClaudeMessage>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+}
+
+/// Text block of the system prompt
+#[derive(Debug, Serialize)]
+struct SystemBlock {
+    #[serde(rename = "type")]
+    block_type: &'static str,
+    text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cache_control: Option<CacheControl>,
+}
+
+#[derive(Debug, Serialize)]
+struct CacheControl {
+    #[serde(rename = "type")]
+    cache_type: &'static str,
+}
+
+impl SystemBlock {
+    fn text(text: String) -> Self {
+        Self { block_type: "text", text, cache_control: None }
+    }
+
+    /// Mark this block as the end of the cacheable prompt prefix
+    fn cached(mut self) -> Self {
+        self.cache_control = Some(CacheControl { cache_type: "ephemeral" });
+        self
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct ClaudeMessage {
+    role: String,
+    content: String,
+}
+
+/// Claude API response
+#[derive(Debug, Deserialize)]
+struct ClaudeResponse {
+    content: Vec<ContentBlock>,
+    #[serde(default)]
+    usage: Usage,
+}
+
+#[derive(Debug, Deserialize)]
+struct ContentBlock {
+    #[serde(default)]
+    text: String,
+}
+
+/// Token counts reported by the Messages API for a single call
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+pub struct Usage {
+    #[serde(default)]
+    pub input_tokens: u64,
+    #[serde(default)]
+    pub output_tokens: u64,
+    #[serde(default)]
+    pub cache_creation_input_tokens: u64,
+    #[serde(default)]
+    pub cache_read_input_tokens: u64,
+}
+
+/// One server-sent event from the streaming Messages API.
+/// Only the fields Agent A uses are modelled.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum StreamEvent {
+    MessageStart { message: StreamMessage },
+    ContentBlockDelta { delta: StreamDelta },
+    MessageDelta {
+        #[serde(default)]
+        usage: Usage,
+    },
+    Error { error: Value },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamMessage {
+    #[serde(default)]
+    usage: Usage,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamDelta {
+    #[serde(default)]
+    text: String,
+}
+
+/// Per-session Claude budget (unset limits are not enforced)
+#[derive(Debug, Default, Clone, Copy)]
+pub struct UsageBudget {
+    pub max_tokens: Option<u64>,
+    pub max_usd: Option<f64>,
+}
+
+impl UsageBudget {
+    /// Read CLAUDE_SESSION_TOKEN_BUDGET and CLAUDE_SESSION_BUDGET_USD
+    pub fn from_env() -> Self {
+        Self {
+            max_tokens: std::env::var("CLAUDE_SESSION_TOKEN_BUDGET")
+                .ok()
+                .and_then(|v| v.parse().ok()),
+            max_usd: std::env::var("CLAUDE_SESSION_BUDGET_USD")
+                .ok()
+                .and_then(|v| v.parse().ok()),
+        }
+    }
+}
+
+/// Running Claude usage for the current session; serialized as the report
+/// `POST /session/{id}/usage` takes
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct SessionUsage {
+    pub calls: u32,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_input_tokens: u64,
+    pub cache_read_input_tokens: u64,
+}
+
+impl SessionUsage {
+    pub fn record(&mut self, usage: Usage) {
+        self.calls += 1;
+        self.input_tokens += usage.input_tokens;
+        self.output_tokens += usage.output_tokens;
+        self.cache_creation_input_tokens += usage.cache_creation_input_tokens;
+        self.cache_read_input_tokens += usage.cache_read_input_tokens;
+    }
+
+    /// Usage recorded since `earlier`, a snapshot of this session; a
+    /// snapshot from another session counts as nothing rather than wrapping
+    pub fn since(&self, earlier: &SessionUsage) -> SessionUsage {
+        SessionUsage {
+            calls: self.calls.saturating_sub(earlier.calls),
+            input_tokens: self.input_tokens.saturating_sub(earlier.input_tokens),
+            output_tokens: self.output_tokens.saturating_sub(earlier.output_tokens),
+            cache_creation_input_tokens: self.cache_creation_input_tokens.saturating_sub(earlier.cache_creation_input_tokens),
+            cache_read_input_tokens: self.cache_read_input_tokens.saturating_sub(earlier.cache_read_input_tokens),
+        }
+    }
+
+    pub fn total_tokens(&self) -> u64 {
+        self.input_tokens
+            + self.output_tokens
+            + self.cache_creation_input_tokens
+            + self.cache_read_input_tokens
+    }
+
+    /// Estimated spend at list prices
+    pub fn cost_usd(&self) -> f64 {
+        (self.input_tokens as f64 * INPUT_USD_PER_MTOK
+            + self.output_tokens as f64 * OUTPUT_USD_PER_MTOK
+            + self.cache_creation_input_tokens as f64 * CACHE_WRITE_USD_PER_MTOK
+            + self.cache_read_input_tokens as f64 * CACHE_READ_USD_PER_MTOK)
+            / 1_000_000.0
+    }
+
+    pub fn exceeds(&self, budget: &UsageBudget) -> bool {
+        budget.max_tokens.is_some_and(|max| self.total_tokens() >= max)
+            || budget.max_usd.is_some_and(|max| self.cost_usd() >= max)
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "{} Claude call(s), {} input + {} output tokens, {} cache read / {} cache write (~${:.4})",
+            self.calls,
+            self.input_tokens,
+            self.output_tokens,
+            self.cache_read_input_tokens,
+            self.cache_creation_input_tokens,
+            self.cost_usd()
+        )
+    }
+}
+
+/// Reduce tool definitions to name + description, dropping input schemas.
+/// Used once the session budget is exhausted to keep prompts short.
+fn compact_tool_definitions(tool_definitions: &Value) -> Value {
+    let tools: Vec<Value> = tool_definitions
+        .get("tools")
+        .and_then(|t| t.as_array())
+        .map(|tools| {
+            tools
+                .iter()
+                .map(|tool| {
+                    json!({
+                        "name": tool.get("name").cloned().unwrap_or(Value::Null),
+                        "description": tool.get("description").cloned().unwrap_or(Value::Null),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    json!({ "tools": tools })
+}
+
+/// Fixed instructions; the tool list is appended as a separate system block
+const SYSTEM_INSTRUCTIONS: &str = r#"You are Agent A, an AI travel coordinator with payment capabilities.
+
+You have access to the tools listed at the end of this prompt.
+
+When the user makes a request, analyze what tool(s) they need and provide a JSON response in this exact format:
+{
+  "reasoning": "explanation of what you're doing",
+  "tool_calls": [
+    {"name": "tool_name", "arguments": {"param1": "value1", ...}}
+  ],
+  "user_message": "friendly message to the user explaining the action"
+}
+
+TRAVEL & PRICING TOOLS (from Agent B MCP Server):
+- For ticket pricing: use get-ticket-price
+  - Requires: from, to, optional vip boolean, optional travel_date (YYYY-MM-DD), optional cabin (economy|premium|business|first)
+  - from and to are 3-letter IATA city codes (New York → NYC, London → LON, Tokyo → TYO); list-destinations returns every supported code
+  - Only include cabin if the user named one; otherwise the user will be asked to pick a cabin
+  - If the user mentions a promo or discount code (e.g. "I have code SAVE20"), pass it as promo_code exactly as given
+  - Include travel_date whenever the user mentions when they want to fly; prices vary by season and weekday
+  - IMPORTANT: When user asks to book, ONLY suggest this tool first. Do NOT suggest book-flight yet.
+- For flight booking: use book-flight
+  - Requires: from, to, passenger_name, passenger_email
+  - IMPORTANT: Do NOT suggest this. The AI will call this automatically after payment completes.
+
+PAYMENT WORKFLOW:
+1. When user requests booking:
+   - ONLY suggest get-ticket-price first (with from, to, vip, travel_date if known)
+   - Do NOT suggest other tools yet
+2. After user confirms and completes payment:
+   - book-flight will be called automatically with passenger details
+   - No need to suggest it
+
+OTHER TOOLS:
+- For formatting: use format_zk_input
+- For proof generation: first call estimate_attestation with the program_id and tell the user the expected wait
+  (queue_wait_secs + proving_secs, in minutes), then use request_attestation or prove_ticket_price
+- For verification: use verify_on_chain (or verify_attestation to check off-chain without gas)
+
+PAYMENT TOOLS (if available):
+- For card enrollment: use enroll-card
+  - Requires: sessionId, consumerId, enrollmentReferenceId
+- For payment initiation: use initiate-purchase-instruction
+  - Requires: sessionId, consumerId, tokenId (from enroll-card), amount, merchant
+- For retrieving credentials: use retrieve-payment-credentials
+  - Requires: sessionId, consumerId, tokenId, instructionId (from initiate-purchase), transactionReferenceId
+- To remove a saved card (e.g. "remove my saved card"): use unenroll-card
+  - Optional: passenger_email if the user gave it; Agent A asks which card to remove and fills in the rest
+
+IMPORTANT:
+- Only suggest tools that match the user's request
+- Always use sessionId format: sess_<username> or sess_<uuid>
+- For payment tools, use consumerId from user context; Agent A generates enrollmentReferenceId and transactionReferenceId
+- If unsure what to do, ask the user for clarification"#;
+
+/// Build the system prompt blocks, marking the static prefix as cacheable
+fn system_prompt(tool_definitions: &Value, prompt_caching: bool) -> Vec<SystemBlock> {
+    let tools = SystemBlock::text(format!("AVAILABLE TOOLS:\n{}", tool_definitions));
+    vec![
+        SystemBlock::text(SYSTEM_INSTRUCTIONS.to_string()),
+        if prompt_caching { tools.cached() } else { tools },
+    ]
+}
+
+/// 429 (rate limited), 529 (overloaded) and 503 are transient on the Anthropic API
+fn is_retryable(status: reqwest::StatusCode) -> bool {
+    matches!(status.as_u16(), 429 | 503 | 529)
+}
+
+/// Delay before retry number `attempt` (0-based): the server's retry-after if
+/// given, otherwise exponential backoff capped at MAX_RETRY_DELAY, with up to
+/// 50% jitter
+fn retry_delay(attempt: u32, retry_after: Option<Duration>) -> Duration {
+    if let Some(delay) = retry_after {
+        return delay;
+    }
+    let backoff = RETRY_BASE_DELAY
+        .checked_mul(2u32.saturating_pow(attempt))
+        .map_or(MAX_RETRY_DELAY, |delay| delay.min(MAX_RETRY_DELAY));
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or(0);
+    let jitter = backoff.mul_f64((nanos % 1000) as f64 / 2000.0);
+    backoff + jitter
+}
+
+/// Parse a retry-after header value, either delay-seconds or an HTTP-date
+/// (a date in the past means retry now)
+fn parse_retry_after(value: &str, now: SystemTime) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(date.duration_since(now).unwrap_or(Duration::ZERO))
+}
+
+/// Server-sent events split out of the raw response body. Bytes are kept
+/// until an event is complete, so a multibyte character split across network
+/// chunks is decoded whole.
+#[derive(Debug, Default)]
+struct EventBuffer {
+    bytes: Vec<u8>,
+}
+
+impl EventBuffer {
+    /// Add a chunk, returning the events it completed
+    fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.bytes.extend_from_slice(chunk);
+        let mut events = Vec::new();
+        // Events are separated by a blank line; keep any partial event buffered
+        while let Some(end) = self.bytes.windows(2).position(|w| w == b"\n\n") {
+            let event: Vec<u8> = self.bytes.drain(..end + 2).collect();
+            events.push(String::from_utf8_lossy(&event).into_owned());
+        }
+        events
+    }
+}
+
+/// Read a streaming Messages API response, forwarding text deltas to `on_text`.
+/// Returns the full text and the usage reported across message_start and
+/// message_delta events.
+async fn read_stream(
+    response: reqwest::Response,
+    on_text: &mut dyn FnMut(&str),
+) -> Result<(String, Usage)> {
+    let mut stream = response.bytes_stream();
+    let mut buffer = EventBuffer::default();
+    let mut text = String::new();
+    let mut usage = Usage::default();
+
+    while let Some(chunk) = stream.next().await {
+        for event in buffer.push(&chunk?) {
+            for data in event.lines().filter_map(|l| l.strip_prefix("data:")) {
+                match serde_json::from_str::<StreamEvent>(data.trim()) {
+                    Ok(StreamEvent::MessageStart { message }) => {
+                        usage = Usage { output_tokens: 0, ..message.usage };
+                    }
+                    Ok(StreamEvent::ContentBlockDelta { delta }) => {
+                        on_text(&delta.text);
+                        text.push_str(&delta.text);
+                    }
+                    Ok(StreamEvent::MessageDelta { usage: delta }) => {
+                        usage.output_tokens = delta.output_tokens;
+                    }
+                    Ok(StreamEvent::Error { error }) => {
+                        return Err(anyhow!("Claude API stream error: {}", error));
+                    }
+                    Ok(StreamEvent::Other) | Err(_) => {}
+                }
+            }
+        }
+    }
+
+    Ok((text, usage))
+}
+
+/// Call Claude API to get tool recommendations
+///
+/// Records token usage into `usage`. Once the session budget is exceeded the
+/// call still goes through, but with compact tool definitions and a lower
+/// output cap. Rate-limited and overloaded responses are retried with
+/// backoff, honouring retry-after, for at most MAX_TOTAL_RETRY_WAIT.
+/// When streaming is enabled, `on_text` receives each text delta.
+pub async fn call_claude(
+    client: &reqwest::Client,
+    config: &AgentConfig,
+    user_query: &str,
+    tool_definitions: &Value,
+    usage: &mut SessionUsage,
+    on_text: &mut dyn FnMut(&str),
+) -> Result<String> {
+    let over_budget = usage.exceeds(&config.claude_budget);
+
+    let (system, max_tokens) = if over_budget {
+        (
+            system_prompt(&compact_tool_definitions(tool_definitions), config.claude_prompt_caching),
+            MAX_TOKENS_OVER_BUDGET,
+        )
+    } else {
+        (system_prompt(tool_definitions, config.claude_prompt_caching), MAX_TOKENS)
+    };
+
+    let request = ClaudeRequest {
+        model: CLAUDE_MODEL.to_string(),
+        max_tokens,
+        system,
+        messages: vec![ClaudeMessage {
+            role: "user".to_string(),
+            content: user_query.to_string(),
+        }],
+        stream: config.claude_streaming,
+    };
+
+    let mut attempt = 0;
+    let mut waited = Duration::ZERO;
+
+    let response = loop {
+        let response = client
+            .post(format!("{}/v1/messages", config.claude_api_url))
+            .header("x-api-key", &config.claude_api_key)
+            .header("anthropic-version", "2023-06-01")
+            .json(&request)
+            .send()
+            .await?;
+
+        let status = response.status();
+        if status.is_success() {
+            break response;
+        }
+
+        let retry_after = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| parse_retry_after(v, SystemTime::now()));
+        let error_text = response.text().await?;
+
+        let delay = retry_delay(attempt, retry_after);
+        if !is_retryable(status) || attempt >= MAX_RETRIES || waited + delay > MAX_TOTAL_RETRY_WAIT {
+            return Err(anyhow!("Claude API error: {}", error_text));
+        }
+
+        show_status(&format!(
+            "Claude is busy ({}), retrying in {:.1}s (attempt {}/{})...",
+            status,
+            delay.as_secs_f64(),
+            attempt + 1,
+            MAX_RETRIES
+        ));
+        tokio::time::sleep(delay).await;
+        waited += delay;
+        attempt += 1;
+    };
+
+    if config.claude_streaming {
+        let (text, stream_usage) = read_stream(response, on_text).await?;
+        usage.record(stream_usage);
+        return if text.is_empty() {
+            Err(anyhow!("No response from Claude"))
+        } else {
+            Ok(text)
+        };
+    }
+
+    let claude_response: ClaudeResponse = response.json().await?;
+    usage.record(claude_response.usage);
+
+    if let Some(content) = claude_response.content.first() {
+        Ok(content.text.clone())
+    } else {
+        Err(anyhow!("No response from Claude"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_usage_budget() {
+        let mut usage = SessionUsage::default();
+        usage.record(Usage { input_tokens: 3_000, output_tokens: 500, ..Default::default() });
+        let first = usage.clone();
+        usage.record(Usage { input_tokens: 3_000, output_tokens: 500, ..Default::default() });
+        assert_eq!(usage.total_tokens(), 7_000);
+        assert_eq!(usage.since(&first), first);
+
+        assert!(!usage.exceeds(&UsageBudget::default()));
+        assert!(usage.exceeds(&UsageBudget { max_tokens: Some(7_000), max_usd: None }));
+        assert!(!usage.exceeds(&UsageBudget { max_tokens: Some(10_000), max_usd: None }));
+        // 6000 * 0.25/M + 1000 * 1.25/M = $0.00275
+        assert!(usage.exceeds(&UsageBudget { max_tokens: None, max_usd: Some(0.002) }));
+        assert!(!usage.exceeds(&UsageBudget { max_tokens: None, max_usd: Some(0.01) }));
+    }
+
+    #[test]
+    fn test_retry_classification_and_delay() {
+        for (status, retryable) in [(429, true), (529, true), (503, true), (500, false), (502, false), (400, false), (401, false), (404, false)] {
+            assert_eq!(is_retryable(reqwest::StatusCode::from_u16(status).unwrap()), retryable, "{}", status);
+        }
+
+        // Backoff doubles with up to 50% jitter, and stops growing at the cap
+        for attempt in 0..3 {
+            let base = RETRY_BASE_DELAY * 2u32.pow(attempt);
+            let delay = retry_delay(attempt, None);
+            assert!(delay >= base && delay <= base.mul_f64(1.5), "attempt {}: {:?}", attempt, delay);
+        }
+        for attempt in [5, 31, 64] {
+            let delay = retry_delay(attempt, None);
+            assert!(delay >= MAX_RETRY_DELAY && delay <= MAX_RETRY_DELAY.mul_f64(1.5), "attempt {}: {:?}", attempt, delay);
+        }
+        // The server's retry-after wins over backoff
+        assert_eq!(retry_delay(3, Some(Duration::from_secs(2))), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_event_buffer_keeps_split_characters() {
+        let event = "event: content_block_delta\ndata: {\"type\":\"content_block_delta\",\"delta\":{\"text\":\"Zürich ✈\"}}\n\n";
+        let bytes = event.as_bytes();
+        // Split inside the two-byte ü
+        let split = event.find('ü').unwrap() + 1;
+
+        let mut buffer = EventBuffer::default();
+        assert!(buffer.push(&bytes[..split]).is_empty());
+        let events = buffer.push(&bytes[split..]);
+        assert_eq!(events, [event]);
+        assert!(!events[0].contains('\u{FFFD}'));
+        assert!(buffer.bytes.is_empty());
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        // Sun, 06 Nov 1994 08:49:37 GMT
+        let now = UNIX_EPOCH + Duration::from_secs(784_111_777);
+        assert_eq!(parse_retry_after(" 7 ", now), Some(Duration::from_secs(7)));
+        assert_eq!(parse_retry_after("Sun, 06 Nov 1994 08:50:07 GMT", now), Some(Duration::from_secs(30)));
+        assert_eq!(parse_retry_after("Sun, 06 Nov 1994 08:49:00 GMT", now), Some(Duration::ZERO));
+        assert_eq!(parse_retry_after("soon", now), None);
+        assert_eq!(parse_retry_after("-5", now), None);
+    }
+}
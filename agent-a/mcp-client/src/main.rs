@@ -1,65 +1,93 @@
-/// Agent A - AI-powered MCP Client using Claude
-///
-/// This client:
-/// 1. Takes user queries via stdin
-/// 2. Calls Claude API to determine which tool to use and extract parameters
-/// 3. Invokes the MCP server via HTTP with the appropriate tool
-/// 4. Returns results to the user
-///
-/// Requires: ANTHROPIC_API_KEY environment variable (or in .env file)
-/// Usage: mcp-client-ai (loads from .env or ANTHROPIC_API_KEY env var)
+//! Agent A - AI-powered MCP Client using Claude
+//!
+//! This client:
+//! 1. Takes user queries via stdin
+//! 2. Calls Claude API to determine which tool to use and extract parameters
+//! 3. Invokes the MCP server via HTTP with the appropriate tool
+//! 4. Returns results to the user
+//!
+//! Requires: ANTHROPIC_API_KEY environment variable (or in .env file),
+//! unless LLM_PROVIDER=mock answers from canned scripts instead
+//! Usage: mcp-client-ai (loads from .env or ANTHROPIC_API_KEY env var)
 
 use anyhow::{Result, anyhow};
-use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use std::collections::HashMap;
 use std::io::{self, BufRead, Write};
+use std::time::Duration;
+
+mod booking;
+mod claude;
+mod mock_llm;
+mod payment;
+mod policy;
+mod redact;
+mod tools;
+
+use booking::{BookingState, BookingStep, ConsumerIdStrategy};
+use claude::{call_claude, SessionUsage, UsageBudget};
+use mock_llm::MockLlmProvider;
+use payment::{
+    complete_challenge, display_amount, enroll_card, enrolled_cards, format_amount, initiate_payment, parse_amount, payment_receipt, refund_payment,
+    retrieve_credentials, supported_currencies_from_env, unenroll_card, wait_for_settlement, EnrollCardResponse, EnrolledCard, FxRates,
+    Merchant, PaymentChallenge, PaymentMethod, PaymentStage, PaymentStatus, PaymentTimeouts,
+    StageTimedOut,
+};
+use policy::{SpendDecision, SpendPolicy};
+use tools::{PaymentAgentDiagnostic, ToolClient};
+use zk_protocol::telemetry;
 
 // Load .env file on startup
 fn init_env() {
     let _ = dotenv::dotenv();
 }
 
-/// Claude API request
-#[derive(Debug, Serialize)]
-struct ClaudeRequest {
-    model: String,
-    max_tokens: i32,
-    system: String,
-    messages: Vec<ClaudeMessage>,
-}
-
-#[derive(Debug, Serialize)]
-struct ClaudeMessage {
-    role: String,
-    content: String,
-}
-
-/// Claude API response
-#[derive(Debug, Deserialize)]
-struct ClaudeResponse {
-    content: Vec<ContentBlock>,
-    #[serde(default)]
-    stop_reason: String,
-}
-
-#[derive(Debug, Deserialize)]
-struct ContentBlock {
-    #[serde(default)]
-    text: String,
-}
-
 /// Agent configuration
 struct AgentConfig {
     claude_api_key: String,
+    /// Messages API base URL (`ANTHROPIC_BASE_URL`), e.g. a local stub in tests
+    claude_api_url: String,
+    /// Scripted replies used instead of Claude (`LLM_PROVIDER=mock`)
+    mock_llm: Option<MockLlmProvider>,
     server_url: String,
     payment_agent_url: Option<String>,
     payment_agent_enabled: bool,
+    claude_budget: UsageBudget,
+    claude_streaming: bool,
+    claude_prompt_caching: bool,
+    /// Prove the booked fare with Agent B's pricing guest after booking
+    attest_bookings: bool,
+    /// Where the session's proofs are written on exit
+    traces_path: String,
+    /// Session the transcript is stored under on Agent A (`CONVERSATION_ID`,
+    /// to continue one), served by `GET /sessions/{id}/messages`
+    conversation_id: String,
+    /// How travelers map to Payment Agent consumer IDs
+    consumer_ids: ConsumerIdStrategy,
+    /// Merchant named on purchase instructions
+    merchant: Merchant,
+    /// Payment methods offered at checkout
+    payment_methods: Vec<PaymentMethod>,
+    /// Quote currencies the Payment Agent can charge
+    payment_currencies: Vec<String>,
+    /// How long to wait for a payment to settle before giving up on the booking
+    payment_confirmation_timeout: Duration,
+    /// Limits on enrollment, initiation and credential retrieval
+    payment_timeouts: PaymentTimeouts,
+    /// Spending caps per session and per consumer
+    spend_policy: SpendPolicy,
+    /// Rates for cards settling in another currency than the quote
+    fx_rates: FxRates,
 }
 
 impl AgentConfig {
     fn from_env() -> Result<Self> {
-        let claude_api_key = std::env::var("ANTHROPIC_API_KEY")
-            .map_err(|_| anyhow!("ANTHROPIC_API_KEY environment variable not set"))?;
+        let mock_llm = MockLlmProvider::from_env()?;
+        let claude_api_key = match std::env::var("ANTHROPIC_API_KEY") {
+            Ok(key) => key,
+            Err(_) if mock_llm.is_some() => String::new(),
+            Err(_) => return Err(anyhow!("ANTHROPIC_API_KEY environment variable not set")),
+        };
         
         let server_url = std::env::var("AGENT_A_SERVER_URL")
             .unwrap_or_else(|_| "http://localhost:3001".to_string());
@@ -71,179 +99,43 @@ impl AgentConfig {
 
         Ok(Self {
             claude_api_key,
+            claude_api_url: std::env::var("ANTHROPIC_BASE_URL")
+                .unwrap_or_else(|_| "https://api.anthropic.com".to_string()),
+            mock_llm,
             server_url,
             payment_agent_url,
             payment_agent_enabled,
+            claude_budget: UsageBudget::from_env(),
+            claude_streaming: std::env::var("CLAUDE_STREAMING")
+                .map(|v| v.to_lowercase() == "true")
+                .unwrap_or(false),
+            claude_prompt_caching: std::env::var("CLAUDE_PROMPT_CACHING")
+                .map(|v| v.to_lowercase() != "false")
+                .unwrap_or(true),
+            attest_bookings: std::env::var("ATTEST_BOOKINGS")
+                .map(|v| v.to_lowercase() == "true")
+                .unwrap_or(false),
+            traces_path: std::env::var("CRYPTOGRAPHIC_TRACES_PATH")
+                .unwrap_or_else(|_| "cryptographic_traces.json".to_string()),
+            conversation_id: std::env::var("CONVERSATION_ID")
+                .unwrap_or_else(|_| format!("conv_{}", uuid::Uuid::new_v4().simple())),
+            consumer_ids: ConsumerIdStrategy::from_env(),
+            merchant: Merchant::from_env(),
+            payment_methods: PaymentMethod::registry_from_env(),
+            payment_currencies: supported_currencies_from_env(),
+            payment_confirmation_timeout: Duration::from_secs(
+                std::env::var("PAYMENT_CONFIRMATION_TIMEOUT_SECS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(120),
+            ),
+            payment_timeouts: PaymentTimeouts::from_env(),
+            spend_policy: SpendPolicy::from_env(),
+            fx_rates: FxRates::from_env(),
         })
     }
 }
 
-/// Fetch tool definitions from a server
-async fn fetch_tool_definitions(
-    client: &reqwest::Client,
-    server_url: &str,
-) -> Result<Value> {
-    let url = format!("{}/tools", server_url);
-    let response = client.get(&url).send().await?;
-
-    if !response.status().is_success() {
-        let error_text = response.text().await?;
-        return Err(anyhow!("Failed to fetch tools: {}", error_text));
-    }
-
-    let tools: Value = response.json().await?;
-    Ok(tools)
-}
-
-/// Fetch and merge tool definitions from Agent A Server and Agent B MCP Server
-async fn fetch_all_tools(
-    client: &reqwest::Client,
-    agent_a_url: &str,
-    agent_b_url: &str,
-    payment_agent_url: Option<&str>,
-) -> Result<Value> {
-    // Fetch Agent A tools
-    let mut all_tools: Vec<Value> = Vec::new();
-    
-    let agent_a_response = fetch_tool_definitions(client, agent_a_url).await;
-    if let Ok(resp) = agent_a_response {
-        if let Some(tools) = resp.get("tools").and_then(|t| t.as_array()) {
-            all_tools.extend(tools.clone());
-            println!("  [Agent A Server] Loaded {} tools", tools.len());
-        }
-    }
-    
-    // Fetch Agent B MCP Server tools
-    match fetch_tool_definitions(client, agent_b_url).await {
-        Ok(response) => {
-            if let Some(tools) = response.get("tools").and_then(|t| t.as_array()) {
-                all_tools.extend(tools.clone());
-                println!("  [Agent B MCP Server] Loaded {} pricing/booking tools", tools.len());
-            }
-        }
-        Err(e) => {
-            println!("  ⚠️  Agent B MCP Server unavailable: {}", e);
-            println!("     (Continuing with Agent A tools only)");
-        }
-    }
-    
-    // Fetch Payment Agent tools if available
-    if let Some(payment_url) = payment_agent_url {
-        match fetch_tool_definitions(client, payment_url).await {
-            Ok(payment_response) => {
-                // Payment Agent returns tools in data.tools
-                let payment_tools = payment_response
-                    .get("data")
-                    .and_then(|d| d.get("tools"))
-                    .or_else(|| payment_response.get("tools")) // fallback to direct "tools"
-                    .and_then(|t| t.as_array());
-                
-                if let Some(tools) = payment_tools {
-                    all_tools.extend(tools.clone());
-                    println!("  [Payment Agent] Loaded {} payment tools", tools.len());
-                }
-            }
-            Err(e) => {
-                println!("  ⚠️  Payment Agent unavailable: {}", e);
-                println!("     (Continuing without payment capabilities)");
-            }
-        }
-    }
-    
-    Ok(json!({ "tools": all_tools }))
-}
-
-/// Call Claude API to get tool recommendations
-async fn call_claude(
-    client: &reqwest::Client,
-    config: &AgentConfig,
-    user_query: &str,
-    tool_definitions: &Value,
-) -> Result<String> {
-    let system = format!(
-        r#"You are Agent A, an AI travel coordinator with payment capabilities.
-
-You have access to these tools:
-{}
-
-When the user makes a request, analyze what tool(s) they need and provide a JSON response in this exact format:
-{{
-  "reasoning": "explanation of what you're doing",
-  "tool_calls": [
-    {{"name": "tool_name", "arguments": {{"param1": "value1", ...}}}}
-  ],
-  "user_message": "friendly message to the user explaining the action"
-}}
-
-TRAVEL & PRICING TOOLS (from Agent B MCP Server):
-- For ticket pricing: use get-ticket-price
-  - Requires: from, to, optional vip boolean
-  - IMPORTANT: When user asks to book, ONLY suggest this tool first. Do NOT suggest book-flight yet.
-- For flight booking: use book-flight
-  - Requires: from, to, passenger_name, passenger_email
-  - IMPORTANT: Do NOT suggest this. The AI will call this automatically after payment completes.
-
-PAYMENT WORKFLOW:
-1. When user requests booking:
-   - ONLY suggest get-ticket-price first (with from, to, vip)
-   - Do NOT suggest other tools yet
-2. After user confirms and completes payment:
-   - book-flight will be called automatically with passenger details
-   - No need to suggest it
-
-OTHER TOOLS:
-- For formatting: use format_zk_input
-- For proof generation: use request_attestation (inform user it takes 11-27 minutes)
-- For verification: use verify_on_chain
-
-PAYMENT TOOLS (if available):
-- For card enrollment: use enroll-card
-  - Requires: sessionId, consumerId, enrollmentReferenceId
-- For payment initiation: use initiate-purchase-instruction
-  - Requires: sessionId, consumerId, tokenId (from enroll-card), amount, merchant
-- For retrieving credentials: use retrieve-payment-credentials
-  - Requires: sessionId, consumerId, tokenId, instructionId (from initiate-purchase), transactionReferenceId
-
-IMPORTANT:
-- Only suggest tools that match the user's request
-- Always use sessionId format: sess_<username> or sess_<uuid>
-- For payment tools, use consumerId and enrollmentReferenceId from user context
-- If unsure what to do, ask the user for clarification"#,
-        tool_definitions.to_string()
-    );
-
-    let request = ClaudeRequest {
-        model: "claude-3-haiku-20240307".to_string(),
-        max_tokens: 1024,
-        system,
-        messages: vec![ClaudeMessage {
-            role: "user".to_string(),
-            content: user_query.to_string(),
-        }],
-    };
-
-    let response = client
-        .post("https://api.anthropic.com/v1/messages")
-        .header("x-api-key", &config.claude_api_key)
-        .header("anthropic-version", "2023-06-01")
-        .json(&request)
-        .send()
-        .await?;
-
-    if !response.status().is_success() {
-        let error_text = response.text().await?;
-        return Err(anyhow!("Claude API error: {}", error_text));
-    }
-
-    let claude_response: ClaudeResponse = response.json().await?;
-    
-    if let Some(content) = claude_response.content.first() {
-        Ok(content.text.clone())
-    } else {
-        Err(anyhow!("No response from Claude"))
-    }
-}
-
 /// Parse Claude's tool recommendations from JSON response
 fn parse_tool_calls(claude_response: &str) -> Result<Vec<(String, Value)>> {
     // Try to extract JSON from the response
@@ -271,77 +163,6 @@ fn parse_tool_calls(claude_response: &str) -> Result<Vec<(String, Value)>> {
     }
 }
 
-/// Call server tool via HTTP (routes to appropriate server: Agent A, Agent B, or Payment Agent)
-async fn call_server_tool(
-    client: &reqwest::Client,
-    agent_a_url: &str,
-    agent_b_url: &str,
-    payment_agent_url: Option<&str>,
-    tool_name: &str,
-    arguments: Value,
-) -> Result<String> {
-    // Determine which server to call based on tool name
-    let payment_tools = [
-        "enroll-card",
-        "initiate-purchase-instruction",
-        "retrieve-payment-credentials",
-        "confirm-transaction",
-    ];
-    
-    let agent_b_tools = [
-        "get-ticket-price",
-        "book-flight",
-    ];
-    
-    let target_url = if payment_tools.contains(&tool_name) {
-        if let Some(payment_url) = payment_agent_url {
-            payment_url.to_string()
-        } else {
-            return Err(anyhow!(
-                "Tool '{}' requires Payment Agent, but PAYMENT_AGENT_URL not configured",
-                tool_name
-            ));
-        }
-    } else if agent_b_tools.contains(&tool_name) {
-        agent_b_url.to_string()
-    } else {
-        agent_a_url.to_string()
-    };
-
-    let url = format!("{}/tools/{}", target_url, tool_name);
-
-    let response = client
-        .post(&url)
-        .json(&arguments)
-        .send()
-        .await?;
-
-    if !response.status().is_success() {
-        let error_text = response.text().await?;
-        return Err(anyhow!("Server error: {}", error_text));
-    }
-
-    let result: Value = response.json().await?;
-
-    if let Some(error) = result.get("error") {
-        // Check if error is not null
-        if error.is_null() {
-            // Error field exists but is null, check for data
-            if let Some(data) = result.get("data") {
-                Ok(data.to_string())
-            } else {
-                Err(anyhow!("Invalid server response"))
-            }
-        } else {
-            Err(anyhow!("Tool error: {}", error))
-        }
-    } else if let Some(data) = result.get("data") {
-        Ok(data.to_string())
-    } else {
-        Err(anyhow!("Invalid server response"))
-    }
-}
-
 /// Helper: Ask user for confirmation (using pre-created stdin)
 fn ask_confirmation_from_reader(question: &str, reader: &mut std::io::StdinLock, stdout: &mut std::io::Stdout) -> Result<bool> {
     loop {
@@ -359,26 +180,209 @@ fn ask_confirmation_from_reader(question: &str, reader: &mut std::io::StdinLock,
     }
 }
 
-/// Helper: Ask user for confirmation (legacy, creates new stdin)
-fn ask_confirmation(question: &str) -> Result<bool> {
-    let stdin = io::stdin();
-    let mut stdout = io::stdout();
-    
+/// Cabin classes offered before pricing, as sent to Agent B
+const CABINS: &[(&str, &str)] = &[
+    ("economy", "Economy"),
+    ("premium", "Premium Economy"),
+    ("business", "Business"),
+    ("first", "First"),
+];
+
+/// Helper: Ask the user to pick a cabin class (using pre-created stdin)
+fn ask_cabin_from_reader(reader: &mut std::io::StdinLock, stdout: &mut std::io::Stdout) -> Result<&'static str> {
+    println!("Which cabin would you like?");
+    for (i, (_, label)) in CABINS.iter().enumerate() {
+        println!("  {}. {}", i + 1, label);
+    }
+    println!();
+
     loop {
-        print!("{} [y/n] ", question);
+        print!("Choose cabin [1-{}]: ", CABINS.len());
         stdout.flush()?;
-        
+
         let mut input = String::new();
-        stdin.read_line(&mut input)?;
-        
-        match input.trim().to_lowercase().as_str() {
-            "y" | "yes" => return Ok(true),
-            "n" | "no" => return Ok(false),
-            _ => println!("Please answer 'y' or 'n'."),
+        reader.read_line(&mut input)?;
+
+        match input.trim().parse::<usize>() {
+            Ok(n) if (1..=CABINS.len()).contains(&n) => return Ok(CABINS[n - 1].0),
+            _ => println!("Please enter a number between 1 and {}.", CABINS.len()),
         }
     }
 }
 
+/// Helper: Offer Agent B's add-ons for `cabin` and return the chosen codes
+async fn ask_ancillaries_from_reader(
+    tool_client: &ToolClient,
+    cabin: &str,
+    reader: &mut std::io::StdinLock<'_>,
+    stdout: &mut std::io::Stdout,
+) -> Result<Vec<String>> {
+    let offers = match tool_client.call("list-ancillaries", json!({ "cabin": cabin })).await {
+        Ok(outcome) => outcome.data,
+        Err(e) => {
+            show_warning(&format!("Add-ons unavailable: {}", e));
+            return Ok(Vec::new());
+        }
+    };
+
+    let mut chosen = Vec::new();
+    for offer in offers.as_array().into_iter().flatten() {
+        let (Some(code), Some(name)) = (offer["code"].as_str(), offer["name"].as_str()) else {
+            continue;
+        };
+        if offer["included"].as_bool().unwrap_or(false) {
+            println!("Agent A: {} is included with your cabin.", name);
+            continue;
+        }
+        let currency = offer["amount"]["currency"].as_str().unwrap_or("USD");
+        let price = offer["amount"]["amount_minor"].as_i64().map(|m| display_amount(m, currency)).unwrap_or_default();
+        if ask_confirmation_from_reader(&format!("Add {} for {}?", name, price), reader, stdout)? {
+            chosen.push(code.to_string());
+        }
+    }
+    println!();
+    Ok(chosen)
+}
+
+/// Prove the booked fare with Agent B's pricing guest (Agent B bincode-encodes
+/// the pricing input, the attester proves it) and return the trace to keep
+async fn attest_booking(tool_client: &ToolClient, pricing_args: &Value, confirmation_code: &str) -> Result<Value> {
+    let mut args = pricing_args.clone();
+    if args.get("vip").is_none() {
+        args["vip"] = json!(false);
+    }
+    let proof = tool_client.call("prove_ticket_price", args).await?.data;
+    Ok(json!({
+        "kind": "sp1_pricing_proof",
+        "workflow_stage": "pricing_zk",
+        "confirmation_code": confirmation_code,
+        "program_id": proof["program_id"],
+        "vk_hash": proof["vk_hash"],
+        "amount_minor": proof["amount_minor"],
+        "fare_table_digest": proof["fare_table_digest"],
+        "proof": proof["proof"],
+        "public_values": proof["public_values"],
+    }))
+}
+
+/// Why Agent B refused the promo code, if that is what a pricing error says
+fn promo_rejection(error: &str) -> Option<&'static str> {
+    if error.contains("unknown promo code") {
+        Some("it isn't a valid code")
+    } else if error.contains("promo code has expired") {
+        Some("it has expired")
+    } else {
+        None
+    }
+}
+
+/// Append a turn to the transcript on Agent A; the chat goes on without it
+async fn store_turn(tool_client: &ToolClient, conversation_id: &str, role: &str, content: &str) {
+    if let Err(e) = tool_client.store_message(conversation_id, role, content).await {
+        tracing::warn!(session_id = %conversation_id, role = %role, error = %e, "could not store the message");
+    }
+}
+
+/// Report Claude usage to Agent A, which totals it per session; the chat
+/// goes on without it
+async fn report_usage(tool_client: &ToolClient, session_id: &str, usage: &SessionUsage) {
+    if usage.calls == 0 {
+        return;
+    }
+    if let Err(e) = tool_client.record_usage(session_id, usage).await {
+        tracing::warn!(session_id = %session_id, error = %e, "could not report Claude usage");
+    }
+}
+
+/// Keep a proof-bearing trace for the traces file and store it with Agent A,
+/// which serves it as an MCP resource
+async fn keep_proof(tool_client: &ToolClient, traces: &mut Vec<Value>, session_id: &str, trace: Value) {
+    if let Err(e) = tool_client.store_proof(session_id, &trace).await {
+        tracing::warn!(session_id = %session_id, kind = %trace["kind"], error = %e, "could not store the proof with Agent A");
+    }
+    traces.push(trace);
+}
+
+/// Refund a cancelled booking through the Payment Agent for the amount Agent
+/// B's `cancel-booking` calculated, and return the trace to keep
+async fn refund_cancelled_booking(tool_client: &ToolClient, booking: &mut BookingState, cancellation: &Value) -> Result<Option<Value>> {
+    let refund = &cancellation["refund"]["refund"];
+    let Some(amount_minor) = refund["amount_minor"].as_i64().filter(|amount| *amount > 0) else {
+        return Ok(None);
+    };
+    let currency = refund["currency"].as_str().unwrap_or("USD");
+    let records = refund_parts(tool_client, booking, amount_minor, currency).await?;
+    Ok(Some(json!({
+        "kind": "payment_refund",
+        "workflow_stage": "refund",
+        "booking_id": cancellation["booking"]["response"]["booking_id"],
+        "instruction_ids": booking.payments.iter().map(|p| &p.instruction_id).collect::<Vec<_>>(),
+        "refunded_amount": refund,
+        "refund_rule": cancellation["refund"]["rule"],
+        "refunds": records.iter().map(|r| json!({"refund_id": r["refundId"], "proof": r["proof"]})).collect::<Vec<_>>(),
+    })))
+}
+
+/// Refund `amount_minor` across the booking's settled payment parts, each
+/// up to what it paid and was not yet refunded, recording each refund on its
+/// part and returning the Payment Agent's refund records
+async fn refund_parts(tool_client: &ToolClient, booking: &mut BookingState, amount_minor: i64, currency: &str) -> Result<Vec<Value>> {
+    let mut remaining = amount_minor;
+    let mut records = Vec::new();
+    for i in 0..booking.payments.len() {
+        if remaining <= 0 {
+            break;
+        }
+        let part = &booking.payments[i];
+        let refundable = part.amount_minor - part.refunded_minor;
+        if !part.settled || refundable <= 0 {
+            continue;
+        }
+        let amount = remaining.min(refundable);
+        records.push(refund_payment(tool_client, booking, part, amount, currency).await?);
+        booking.payments[i].refunded_minor += amount;
+        remaining -= amount;
+    }
+    Ok(records)
+}
+
+/// Refund the settled parts of a payment that never became a booking and
+/// return the trace to keep
+async fn refund_unbooked(tool_client: &ToolClient, booking: &mut BookingState, currency: &str, reason: &str) -> Result<Value> {
+    let amount_minor = booking.settled_minor();
+    let records = refund_parts(tool_client, booking, amount_minor, currency).await?;
+    Ok(json!({
+        "kind": "payment_refund",
+        "workflow_stage": "refund",
+        "instruction_ids": booking.payments.iter().map(|p| &p.instruction_id).collect::<Vec<_>>(),
+        "refunded_amount": { "amount_minor": amount_minor, "currency": currency },
+        "reason": reason,
+        "refunds": records.iter().map(|r| json!({"refund_id": r["refundId"], "proof": r["proof"]})).collect::<Vec<_>>(),
+    }))
+}
+
+/// Re-fetch the price for `pricing_args` and return the new quote with its
+/// amount if that differs from the `amount_minor` the traveler accepted
+async fn requote_if_changed(tool_client: &ToolClient, pricing_args: &Value, amount_minor: i64) -> Result<Option<(Value, i64)>> {
+    let result = tool_client.call("get-ticket-price", pricing_args.clone()).await?;
+    let requoted = result.data["amount"]["amount_minor"]
+        .as_i64()
+        .ok_or_else(|| anyhow!("Quote has no amount"))?;
+    Ok((requoted != amount_minor).then_some((result.data, requoted)))
+}
+
+/// Trace recording why a payment never started, kept with the session's
+/// other traces for support
+fn preflight_trace(booking: &BookingState, diagnostic: &PaymentAgentDiagnostic) -> Value {
+    json!({
+        "kind": "payment_preflight",
+        "workflow_stage": "preflight",
+        "session_id": booking.session_id,
+        "diagnostic": diagnostic.code(),
+        "detail": diagnostic.to_string(),
+    })
+}
+
 /// Helper: Show status message
 fn show_status(message: &str) {
     println!("\n⏳ {}", message);
@@ -390,15 +394,278 @@ fn show_success(message: &str) {
     println!("\n✅ {}", message);
 }
 
+/// Helper: Show a failed step; errors can quote traveler details, so the
+/// message is masked like the rest of the client's output
+fn show_error(message: &str) {
+    println!("✗ {}\n", redact::text(message));
+}
+
+/// Helper: Show a step that failed without stopping the booking, masked
+/// like `show_error`
+fn show_warning(message: &str) {
+    println!("⚠️  {}\n", redact::text(message));
+}
+
+/// Prints streamed Claude text as it arrives.
+///
+/// Conversational replies are echoed live. Replies that start with `{` are the
+/// structured tool-call format and stay silent; the flow prints their
+/// user_message once tools have run.
+#[derive(Default)]
+struct StreamPrinter {
+    buffered: String,
+    echoing: Option<bool>,
+}
+
+impl StreamPrinter {
+    fn push(&mut self, delta: &str) {
+        match self.echoing {
+            Some(true) => {
+                print!("{}", delta);
+                io::stdout().flush().ok();
+            }
+            Some(false) => {}
+            None => {
+                self.buffered.push_str(delta);
+                let trimmed = self.buffered.trim_start();
+                if let Some(first) = trimmed.chars().next() {
+                    let echo = first != '{';
+                    if echo {
+                        print!("Agent A: {}", trimmed);
+                        io::stdout().flush().ok();
+                    }
+                    self.echoing = Some(echo);
+                    self.buffered.clear();
+                }
+            }
+        }
+    }
+
+    /// Whether the reply has already been shown to the user
+    fn finish(&self) -> bool {
+        let echoed = self.echoing == Some(true);
+        if echoed {
+            println!("\n");
+        }
+        echoed
+    }
+}
+
 /// Helper: Show step indicator
-fn show_step(step: u32, total: u32, message: &str) {
-    println!("\n[Step {}/{}] {}", step, total, message);
+fn show_step(step: BookingStep, message: &str) {
+    println!("\n[Step {}/{}] {}", step.number(), BookingStep::TOTAL, message);
+}
+
+/// Helper: Ask how the traveler wants to pay, explaining which methods are
+/// accepted when they pick one that isn't (using pre-created stdin)
+fn ask_payment_method_from_reader<'a>(
+    methods: &'a [PaymentMethod],
+    reader: &mut std::io::StdinLock,
+    stdout: &mut std::io::Stdout,
+) -> Result<&'a PaymentMethod> {
+    println!("How would you like to pay?");
+    for (i, method) in methods.iter().enumerate() {
+        println!("  {}. {}", i + 1, method.label);
+    }
+    println!();
+
+    loop {
+        print!("Choose payment method [1-{}]: ", methods.len());
+        stdout.flush()?;
+
+        let mut input = String::new();
+        reader.read_line(&mut input)?;
+
+        match input.trim().parse::<usize>() {
+            Ok(n) if (1..=methods.len()).contains(&n) && methods[n - 1].accepted => return Ok(&methods[n - 1]),
+            Ok(n) if (1..=methods.len()).contains(&n) => {
+                let accepted: Vec<&str> = methods.iter().filter(|m| m.accepted).map(|m| m.label).collect();
+                println!(
+                    "Agent A: Sorry, {} isn't supported yet. I can only take payment by {}.",
+                    methods[n - 1].label,
+                    accepted.join(" or ")
+                );
+            }
+            _ => println!("Please enter a number between 1 and {}.", methods.len()),
+        }
+    }
+}
+
+/// Helper: Ask the traveler to type `phrase` exactly to confirm (using
+/// pre-created stdin)
+fn ask_phrase_from_reader(phrase: &str, reader: &mut std::io::StdinLock, stdout: &mut std::io::Stdout) -> Result<bool> {
+    print!("To go ahead anyway, type \"{}\" (anything else cancels): ", phrase);
+    stdout.flush()?;
+
+    let mut input = String::new();
+    reader.read_line(&mut input)?;
+    Ok(input.trim() == phrase)
+}
+
+/// Helper: Ask the traveler to pick one of their enrolled cards; the extra
+/// last option (`other`, e.g. "Add a new card") returns `None`
+fn ask_card_from_reader<'a>(
+    question: &str,
+    cards: &'a [EnrolledCard],
+    other: &str,
+    reader: &mut std::io::StdinLock,
+    stdout: &mut std::io::Stdout,
+) -> Result<Option<&'a EnrolledCard>> {
+    println!("Agent A: {}", question);
+    for (i, card) in cards.iter().enumerate() {
+        println!("  {}. {}", i + 1, card.description);
+    }
+    println!("  {}. {}\n", cards.len() + 1, other);
+
+    loop {
+        print!("Choose card [1-{}]: ", cards.len() + 1);
+        stdout.flush()?;
+
+        let mut input = String::new();
+        reader.read_line(&mut input)?;
+
+        match input.trim().parse::<usize>() {
+            Ok(n) if (1..=cards.len()).contains(&n) => return Ok(Some(&cards[n - 1])),
+            Ok(n) if n == cards.len() + 1 => return Ok(None),
+            _ => println!("Please enter a number between 1 and {}.", cards.len() + 1),
+        }
+    }
+}
+
+/// Helper: Offer to split `amount_minor` into a first payment (e.g. a
+/// deposit) on `token_id` and the balance on the same or another saved card.
+/// Returns the `(token_id, amount_minor)` parts to charge.
+fn ask_split_from_reader(
+    amount_minor: i64,
+    currency: &str,
+    token_id: &str,
+    cards: &[EnrolledCard],
+    reader: &mut std::io::StdinLock,
+    stdout: &mut std::io::Stdout,
+) -> Result<Vec<(String, i64)>> {
+    if amount_minor < 2
+        || !ask_confirmation_from_reader("Would you like to split this payment (e.g. a deposit now and the balance separately)?", reader, stdout)?
+    {
+        return Ok(vec![(token_id.to_string(), amount_minor)]);
+    }
+
+    let first = loop {
+        print!("How much should the first payment be? [{}-{}]: ", format_amount(1, currency), display_amount(amount_minor - 1, currency));
+        stdout.flush()?;
+
+        let mut input = String::new();
+        reader.read_line(&mut input)?;
+
+        match parse_amount(&input, currency) {
+            Some(first) if first > 0 && first < amount_minor => break first,
+            _ => println!("Please enter an amount between {} and {}.", format_amount(1, currency), display_amount(amount_minor - 1, currency)),
+        }
+    };
+
+    let balance = amount_minor - first;
+    let others: Vec<EnrolledCard> = cards.iter().filter(|card| card.token_id != token_id).cloned().collect();
+    let balance_token = if others.is_empty() {
+        token_id.to_string()
+    } else {
+        let question = format!("Which card should pay the remaining {}?", display_amount(balance, currency));
+        match ask_card_from_reader(&question, &others, "The same card", reader, stdout)? {
+            Some(card) => card.token_id.clone(),
+            None => token_id.to_string(),
+        }
+    };
+    Ok(vec![(token_id.to_string(), first), (balance_token, balance)])
+}
+
+/// Helper: Remove one of the traveler's saved cards at the Payment Agent and
+/// return the trace to keep, or `None` if nothing was removed
+async fn unenroll_saved_card(
+    tool_client: &ToolClient,
+    consumer_ids: &ConsumerIdStrategy,
+    arguments: &Value,
+    reader: &mut std::io::StdinLock<'_>,
+    stdout: &mut std::io::Stdout,
+) -> Result<Option<Value>> {
+    let email = match arguments.get("passenger_email").and_then(|e| e.as_str()) {
+        Some(email) => email.to_string(),
+        None => {
+            print!("Please enter the email your cards are saved under: ");
+            stdout.flush()?;
+            let mut email = String::new();
+            reader.read_line(&mut email)?;
+            email.trim().to_string()
+        }
+    };
+    let booking = BookingState::new(consumer_ids.consumer_id(&email));
+    tool_client.set_session(&booking.session_id);
+
+    let cards = enrolled_cards(tool_client, &booking).await?;
+    let card = match cards.as_slice() {
+        [] => {
+            println!("Agent A: You don't have any saved cards.\n");
+            return Ok(None);
+        }
+        [card] => card,
+        _ => match ask_card_from_reader("Which card would you like to remove?", &cards, "Keep all my cards", reader, stdout)? {
+            Some(card) => card,
+            None => return Ok(None),
+        },
+    };
+    if !ask_confirmation_from_reader(&format!("Remove {}?", card.description), reader, stdout)? {
+        return Ok(None);
+    }
+
+    let record = unenroll_card(tool_client, &booking, &card.token_id).await?;
+    Ok(Some(json!({
+        "kind": "card_unenrollment",
+        "workflow_stage": "unenrollment",
+        "session_id": booking.session_id,
+        "consumer_id": booking.consumer_id,
+        "token_id": card.token_id,
+        "card": card.description,
+        "proof": record["proof"],
+    })))
+}
+
+/// Helper: Show why a payment step failed; a timeout gets its own status line
+fn show_payment_error(e: &anyhow::Error) {
+    match e.downcast_ref::<StageTimedOut>() {
+        Some(timed_out) => println!("\n⏱️  {}\n", timed_out),
+        None => show_error(&format!("Error: {}", e)),
+    }
+}
+
+/// Helper: Walk the traveler through a 3-D Secure challenge. Returns the
+/// one-time code for OTP challenges, `Some("")` once a redirect challenge is
+/// done, and `None` if the traveler gives up.
+fn ask_challenge_from_reader(
+    challenge: &PaymentChallenge,
+    reader: &mut std::io::StdinLock,
+    stdout: &mut std::io::Stdout,
+) -> Result<Option<String>> {
+    println!("Agent A: Your bank needs to confirm it's you before this payment goes through.");
+    if let Some(message) = &challenge.message {
+        println!("Agent A: {}", message);
+    }
+    if challenge.otp {
+        print!("Enter the one-time code (or leave empty to cancel): ");
+        stdout.flush()?;
+        let mut code = String::new();
+        reader.read_line(&mut code)?;
+        let code = code.trim();
+        return Ok((!code.is_empty()).then(|| code.to_string()));
+    }
+    if let Some(url) = &challenge.url {
+        println!("Agent A: Please complete the verification at:\n  {}\n", url);
+    }
+    Ok(ask_confirmation_from_reader("Have you completed the verification?", reader, stdout)?.then(String::new))
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     // Load .env file
     init_env();
+    // Diagnostics go to stderr (JSON with LOG_FORMAT=json), the conversation to stdout
+    let _telemetry = telemetry::init("agent-a-client");
     
     let config = AgentConfig::from_env()?;
     let client = reqwest::Client::new();
@@ -411,15 +678,21 @@ async fn main() -> Result<()> {
     // Fetch tool definitions from servers
     println!("Fetching tool definitions...");
     let payment_agent_url = if config.payment_agent_enabled {
-        config.payment_agent_url.as_deref()
+        config.payment_agent_url.clone()
     } else {
         None
     };
     
     let agent_b_url = std::env::var("AGENT_B_MCP_URL")
         .unwrap_or_else(|_| "http://localhost:8001".to_string());
+
+    let tool_client = ToolClient::new(
+        config.server_url.clone(),
+        agent_b_url,
+        payment_agent_url,
+    )?;
     
-    let tool_definitions = match fetch_all_tools(&client, &config.server_url, &agent_b_url, payment_agent_url).await {
+    let tool_definitions = match tool_client.fetch_all_tools().await {
         Ok(tools) => {
             println!("✓ Loaded {} tools from server(s)\n", 
                 tools.get("tools")
@@ -430,7 +703,7 @@ async fn main() -> Result<()> {
             tools
         }
         Err(e) => {
-            eprintln!("✗ Failed to fetch tools: {}\n", e);
+            eprintln!("✗ Failed to fetch tools: {}\n", redact::text(&e.to_string()));
             eprintln!("Make sure the MCP server is running on {}\n", config.server_url);
             return Err(e);
         }
@@ -453,11 +726,20 @@ async fn main() -> Result<()> {
     println!("  'Verify a ZK proof on Sepolia'");
     println!("  'Request a ZK attestation'\n");
 
-    println!("Type 'exit' or 'quit' to end.\n");
+    if config.mock_llm.is_some() {
+        println!("Replies are scripted (LLM_PROVIDER=mock); Claude is not called.\n");
+    }
+    println!("Type 'usage' to see Claude token usage, 'exit' or 'quit' to end.");
+    println!("Conversation: {}\n", config.conversation_id);
 
     let stdin = io::stdin();
     let mut stdout = io::stdout();
     let mut reader = stdin.lock();
+    let mut claude_usage = SessionUsage::default();
+    let mut budget_warned = false;
+    let mut cryptographic_traces: Vec<Value> = Vec::new();
+    // Paid bookings by Agent B booking ID, refunded if cancelled later
+    let mut paid_bookings: HashMap<String, BookingState> = HashMap::new();
 
     loop {
         print!("\nYou: ");
@@ -479,17 +761,53 @@ async fn main() -> Result<()> {
                 break;
             }
 
+            if input.eq_ignore_ascii_case("usage") {
+                println!("\nAgent A: {}\n", claude_usage.summary());
+                continue;
+            }
+
             println!("\nAgent A: Processing your request...\n");
+            store_turn(&tool_client, &config.conversation_id, "user", input).await;
 
             // Call Claude to determine tools
-            match call_claude(&client, &config, input, &tool_definitions).await {
+            let usage_before = claude_usage.clone();
+            let mut stream_printer = StreamPrinter::default();
+            let claude_result = match &config.mock_llm {
+                Some(mock_llm) => Ok(mock_llm.reply(input)),
+                None => {
+                    call_claude(
+                        &client,
+                        &config,
+                        input,
+                        &tool_definitions,
+                        &mut claude_usage,
+                        &mut |delta| stream_printer.push(delta),
+                    )
+                    .await
+                }
+            };
+            let already_shown = stream_printer.finish();
+            let turn_usage = claude_usage.since(&usage_before);
+            report_usage(&tool_client, &config.conversation_id, &turn_usage).await;
+            if !budget_warned && claude_usage.exceeds(&config.claude_budget) {
+                println!("⚠️  Claude session budget reached ({}). Switching to compact prompts.\n", claude_usage.summary());
+                budget_warned = true;
+            }
+
+            if let Ok(claude_response) = &claude_result {
+                store_turn(&tool_client, &config.conversation_id, "assistant", claude_response).await;
+            }
+
+            match claude_result {
                 Ok(claude_response) => {
                     // Parse tool calls
                     match parse_tool_calls(&claude_response) {
                         Ok(tool_calls) => {
                             if tool_calls.is_empty() {
                                 // No tools needed, just show Claude's response
-                                println!("Agent A: {}\n", claude_response);
+                                if !already_shown {
+                                    println!("Agent A: {}\n", claude_response);
+                                }
                             } else {
                                 // Track if this is a payment flow (triggered by get-ticket-price tool)
                                 let is_payment_flow = tool_calls.iter()
@@ -497,21 +815,19 @@ async fn main() -> Result<()> {
                                 
                                 if is_payment_flow {
                                     // Interactive payment workflow
-                                    show_step(1, 3, "Processing booking request...");
+                                    show_step(BookingStep::Pricing, "Processing booking request...");
                                     
                                     // First tool (usually call_agent_b for pricing)
-                                    let mut step = 1;
-                                    let mut enrollment_complete = false;
-                                    let mut payment_confirmed = false;
                                     let mut pricing_result = None;
+                                    let mut pricing_args = None;
                                     let mut trip_from = "".to_string();
                                     let mut trip_to = "".to_string();
+                                    let mut cabin = "economy".to_string();
                                     
                                     for (tool_name, arguments) in &tool_calls {
+                                        let mut arguments = arguments.clone();
                                         // Non-payment tools
                                         if !tool_name.contains("enroll") && !tool_name.contains("purchase") && !tool_name.contains("retrieve") {
-                                            println!("→ Invoking: {} with args {}", tool_name, arguments);
-
                                             // Extract from/to from pricing tool arguments
                                             if tool_name == "get-ticket-price" {
                                                 if let Some(from_val) = arguments.get("from").and_then(|v| v.as_str()) {
@@ -520,28 +836,39 @@ async fn main() -> Result<()> {
                                                 if let Some(to_val) = arguments.get("to").and_then(|v| v.as_str()) {
                                                     trip_to = to_val.to_string();
                                                 }
+
+                                                // Let the user pick a cabin unless they already named one
+                                                match arguments.get("cabin").and_then(|v| v.as_str()) {
+                                                    Some(c) => cabin = c.to_string(),
+                                                    None => {
+                                                        cabin = ask_cabin_from_reader(&mut reader, &mut stdout)?.to_string();
+                                                        arguments["cabin"] = json!(cabin);
+                                                    }
+                                                }
+                                                pricing_args = Some(arguments.clone());
                                             }
 
-                                            match call_server_tool(
-                                                &client,
-                                                &config.server_url,
-                                                &agent_b_url,
-                                                payment_agent_url,
-                                                tool_name,
-                                                arguments.clone(),
-                                            )
-                                            .await
-                                            {
+                                            let mut result = tool_client.call(tool_name, arguments.clone()).await;
+                                            // Agent B rejects a code it can't apply; quote without it instead
+                                            if let (Err(e), Some(code)) = (&result, arguments.get("promo_code").and_then(|c| c.as_str())) {
+                                                if let Some(reason) = promo_rejection(&e.to_string()) {
+                                                    println!("Agent A: I couldn't apply promo code {}: {}. Here is the price without it.\n", code, reason);
+                                                    if let Some(args) = arguments.as_object_mut() {
+                                                        args.remove("promo_code");
+                                                    }
+                                                    pricing_args = Some(arguments.clone());
+                                                    result = tool_client.call(tool_name, arguments).await;
+                                                }
+                                            }
+                                            match result {
                                                 Ok(result) => {
-                                                    println!("✓ Result: {}\n", result);
-                                                    
                                                     // Store pricing result
                                                     if tool_name == "get-ticket-price" {
-                                                        pricing_result = Some(result.clone());
+                                                        pricing_result = Some(result.data);
                                                     }
                                                 }
                                                 Err(e) => {
-                                                    println!("✗ Error: {}\n", e);
+                                                    show_error(&format!("Error: {}", e));
                                                 }
                                             }
                                         }
@@ -549,275 +876,440 @@ async fn main() -> Result<()> {
                                     
                                     // If we have pricing, present it and ask for confirmation
                                     if let Some(pricing) = pricing_result {
-                                        if let Ok(parsed) = serde_json::from_str::<Value>(&pricing) {
-                                            if let Some(price) = parsed.get("price") {
-                                                println!("Agent A: Great! I found a flight from {} to {} for ${}.", trip_from, trip_to, price);
-                                                println!("Agent A: This includes all taxes and fees.\n");
+                                        if let Some(price) = pricing.get("price") {
+                                            let currency = pricing
+                                                .get("amount")
+                                                .and_then(|a| a.get("currency"))
+                                                .or_else(|| pricing.get("currency"))
+                                                .and_then(|c| c.as_str())
+                                                .unwrap_or("USD")
+                                                .to_uppercase();
+                                            // Pay exactly the quoted minor-unit amount, not the display float
+                                            let amount = pricing
+                                                .get("amount")
+                                                .and_then(|a| a.get("amount_minor"))
+                                                .and_then(|m| m.as_i64())
+                                                .map(|m| format_amount(m, &currency))
+                                                .unwrap_or_else(|| price.to_string());
+                                            if !config.payment_currencies.contains(&currency) {
+                                                println!(
+                                                    "Agent A: This fare is quoted in {}, but I can only take payment in {}. I can't book it for you.\n",
+                                                    currency,
+                                                    config.payment_currencies.join(", ")
+                                                );
+                                                continue;
+                                            }
+                                            let cabin_label = CABINS.iter().find(|(id, _)| *id == cabin).map(|(_, label)| *label).unwrap_or(cabin.as_str());
+                                            println!("Agent A: Great! I found a {} flight from {} to {} for {} {}.", cabin_label, trip_from, trip_to, amount, currency);
+                                            if let Some(code) = pricing.get("promo_code").and_then(|c| c.as_str()) {
+                                                println!("Agent A: Promo code {} has been applied to this price.", code);
+                                            }
+                                            println!("Agent A: This includes all taxes and fees.\n");
+                                            
+                                            // Ask user if they want to proceed
+                                            if ask_confirmation_from_reader("Would you like to proceed with this booking?", &mut reader, &mut stdout)? {
+                                                // Get passenger details
+                                                print!("Please enter your full name: ");
+                                                stdout.flush()?;
+                                                let mut passenger_name = String::new();
+                                                reader.read_line(&mut passenger_name)?;
+                                                let passenger_name = passenger_name.trim().to_string();
                                                 
-                                                // Ask user if they want to proceed
-                                                if ask_confirmation_from_reader("Would you like to proceed with this booking?", &mut reader, &mut stdout)? {
-                                                    // Get passenger details
-                                                    print!("Please enter your full name: ");
-                                                    stdout.flush()?;
-                                                    let mut passenger_name = String::new();
-                                                    reader.read_line(&mut passenger_name)?;
-                                                    let passenger_name = passenger_name.trim().to_string();
-                                                    
-                                                    print!("Please enter your email address: ");
-                                                    stdout.flush()?;
-                                                    let mut passenger_email = String::new();
-                                                    reader.read_line(&mut passenger_email)?;
-                                                    let passenger_email = passenger_email.trim().to_string();
-                                                    
-                                                    // Ask about payment method
-                                                    println!("\nAgent A: Great! Let's set up your payment.\n");
-                                                    println!("How would you like to pay?");
-                                                    println!("  1. Visa Credit Card");
-                                                    println!("  2. Other payment method\n");
-                                                    
-                                                    print!("Choose payment method [1-2]: ");
-                                                    stdout.flush()?;
-                                                    
-                                                    let mut payment_choice = String::new();
-                                                    reader.read_line(&mut payment_choice)?;
-                                                    
-                                                    let payment_method = match payment_choice.trim() {
-                                                        "1" => "Visa Credit Card",
-                                                        "2" => {
-                                                            println!("Agent A: Other payment methods are not yet supported. Please choose Visa.\n");
-                                                            "Visa Credit Card"
+                                                print!("Please enter your email address: ");
+                                                stdout.flush()?;
+                                                let mut passenger_email = String::new();
+                                                reader.read_line(&mut passenger_email)?;
+                                                let passenger_email = passenger_email.trim().to_string();
+                                                let mut booking = BookingState::new(config.consumer_ids.consumer_id(&passenger_email));
+                                                tool_client.set_session(&booking.session_id);
+                                                booking.claude_usage = turn_usage.clone();
+                                                report_usage(&tool_client, &booking.session_id, &booking.claude_usage).await;
+
+                                                // Offer add-ons now that we know who is flying, and re-quote if any were picked
+                                                println!("\nAgent A: Would you like any extras for your trip?\n");
+                                                let ancillaries = ask_ancillaries_from_reader(&tool_client, &cabin, &mut reader, &mut stdout).await?;
+                                                let (pricing, amount) = match pricing_args.clone() {
+                                                    Some(mut args) if !ancillaries.is_empty() => {
+                                                        args["ancillaries"] = json!(ancillaries);
+                                                        match tool_client.call("get-ticket-price", args).await {
+                                                            Ok(result) => {
+                                                                let total = result.data["amount"]["amount_minor"]
+                                                                    .as_i64()
+                                                                    .map(|m| format_amount(m, &currency))
+                                                                    .unwrap_or_else(|| amount.clone());
+                                                                println!("Agent A: With your extras the total is {} {}.\n", total, currency);
+                                                                (result.data, total)
+                                                            }
+                                                            Err(e) => {
+                                                                show_error(&format!("Error: {}. Continuing without extras.", e));
+                                                                (pricing.clone(), amount)
+                                                            }
                                                         }
-                                                        _ => {
-                                                            println!("Agent A: Invalid choice. Using Visa Credit Card.\n");
-                                                            "Visa Credit Card"
+                                                    }
+                                                    _ => (pricing.clone(), amount),
+                                                };
+                                                let Some(amount_minor) = parse_amount(&amount, &currency) else {
+                                                    println!("✗ Could not read the quoted amount {}\n", amount);
+                                                    continue;
+                                                };
+                                                let proof_args = pricing_args.clone().map(|mut args| {
+                                                    if !ancillaries.is_empty() {
+                                                        args["ancillaries"] = json!(ancillaries);
+                                                    }
+                                                    args
+                                                });
+                                                
+                                                // Ask about payment method
+                                                println!("\nAgent A: Great! Let's set up your payment.\n");
+                                                let payment_method = ask_payment_method_from_reader(&config.payment_methods, &mut reader, &mut stdout)?;
+                                                
+                                                println!("Agent A: Perfect! I'll set up your {} for this transaction.\n", payment_method.label);
+                                                
+                                                // User confirmed, proceed directly with payment
+                                                println!("Agent A: To proceed with the booking, I'll need to set up payment.\n");
+                                                
+                                                // Make sure the Payment Agent can take the payment before enrolling
+                                                if let Err(diagnostic) = tool_client.payment_agent_health().await {
+                                                    println!("Agent A: {}\n", diagnostic.user_message());
+                                                    cryptographic_traces.push(preflight_trace(&booking, &diagnostic));
+                                                    continue;
+                                                }
+                                                
+                                                // Enrollment step
+                                                show_step(BookingStep::CardEnrollment, "Enrolling your payment card...");
+                                                
+                                                let mut enrollment_complete = false;
+                                                
+                                                // Check for cards already enrolled; if the session check fails, enroll one
+                                                let cards = enrolled_cards(&tool_client, &booking).await.unwrap_or_default();
+                                                match cards.as_slice() {
+                                                    [card] => {
+                                                        println!("Agent A: I found an existing payment card in your account: {}\n", card.description);
+                                                        show_success("Your card is already enrolled with biometric authentication!");
+                                                        booking.token_id = Some(card.token_id.clone());
+                                                        enrollment_complete = true;
+                                                    }
+                                                    [_, _, ..] => {
+                                                        show_step(BookingStep::CardSelection, "Choosing your payment card...");
+                                                        if let Some(card) = ask_card_from_reader("You have several cards saved. Which one would you like to use?", &cards, "Add a new card", &mut reader, &mut stdout)? {
+                                                            show_success(&format!("Paying with {}", card.description));
+                                                            booking.token_id = Some(card.token_id.clone());
+                                                            enrollment_complete = true;
                                                         }
-                                                    };
-                                                    
-                                                    println!("Agent A: Perfect! I'll set up your {} for this transaction.\n", payment_method);
-                                                    
-                                                    // User confirmed, proceed directly with payment
-                                                    println!("Agent A: To proceed with the booking, I'll need to set up payment.\n");
-                                                    
-                                                    // Enrollment step
-                                                    show_step(2, 3, "Enrolling your payment card...");
-                                                    
-                                                    let mut enrollment_complete = false;
-                                                    let mut enrollment_token_id = "token_789".to_string();
-                                                    
-                                                    // Check if card is already enrolled
-                                                    let session_id = "sess_user_123".to_string();
-                                                    let session_url = format!("{}/session/{}", 
-                                                        payment_agent_url.unwrap_or("http://localhost:3002"), 
-                                                        session_id);
+                                                    }
+                                                    [] => {}
+                                                }
+                                                
+                                                // If not enrolled, ask user to enroll
+                                                if !enrollment_complete {
+                                                    println!("Agent A: Let me securely add your card for this transaction.");
+                                                    println!("Agent A: You'll authenticate using your device's biometric authentication (Face ID/Fingerprint).\n");
                                                     
-                                                    match client.get(&session_url).send().await {
-                                                        Ok(response) => {
-                                                            if let Ok(session_data) = response.json::<Value>().await {
-                                                                if let Some(data) = session_data.get("data") {
-                                                                    if let Some(token_count) = data.get("enrolledTokenCount").and_then(|c| c.as_u64()) {
-                                                                        if token_count > 0 {
-                                                                            println!("Agent A: I found an existing payment card in your account.\n");
-                                                                            show_success("Your card is already enrolled with biometric authentication!");
-                                                                            enrollment_complete = true;
-                                                                            
-                                                                            // Extract the first enrolled token ID
-                                                                            if let Some(token_ids) = data.get("enrolledTokenIds").and_then(|ids| ids.as_array()) {
-                                                                                if let Some(first_token) = token_ids.first().and_then(|t| t.as_str()) {
-                                                                                    enrollment_token_id = first_token.to_string();
-                                                                                }
-                                                                            }
-                                                                        }
-                                                                    }
-                                                                }
+                                                    if !ask_confirmation_from_reader("Ready to add your card?", &mut reader, &mut stdout)? {
+                                                        println!("Agent A: Card enrollment cancelled. Unable to proceed with payment.\n");
+                                                        continue;
+                                                    }
+
+                                                    // A failed attempt can be retried under the same enrollment reference
+                                                    loop {
+                                                        show_status("Adding your card...");
+                                                        
+                                                        let enrollment_reference = match booking.enrollment_reference.clone() {
+                                                            Some(reference) => reference,
+                                                            None => booking.new_enrollment_reference(),
+                                                        };
+                                                        match config.payment_timeouts.run(PaymentStage::Enrollment, enroll_card(&tool_client, &booking, &enrollment_reference)).await {
+                                                            Ok(response) if !response.is_success() => {
+                                                                println!("✗ Enrollment failed: {}\n", response.status.as_deref().unwrap_or("unknown status"));
+                                                                // A rejected enrollment is final; try again under a new reference
+                                                                booking.enrollment_reference = None;
+                                                            }
+                                                            Ok(EnrollCardResponse { token_id: Some(token_id), proof, .. }) => {
+                                                                booking.token_id = Some(token_id);
+                                                                show_success("Your card has been enrolled with biometric authentication!");
+                                                                keep_proof(&tool_client, &mut cryptographic_traces, &booking.session_id, json!({
+                                                                    "kind": "card_enrollment",
+                                                                    "workflow_stage": "enrollment",
+                                                                    "session_id": booking.session_id,
+                                                                    "enrollment_reference_id": enrollment_reference,
+                                                                    "token_id": booking.token_id,
+                                                                    "proof": proof,
+                                                                })).await;
+                                                                enrollment_complete = true;
+                                                            }
+                                                            Ok(_) => {
+                                                                println!("✗ Enrollment succeeded but returned no tokenId\n");
                                                             }
+                                                            Err(e) => show_payment_error(&e),
                                                         }
-                                                        Err(_) => {
-                                                            // Session check failed, proceed with enrollment
+                                                        if enrollment_complete || !ask_confirmation_from_reader("Try adding your card again?", &mut reader, &mut stdout)? {
+                                                            break;
                                                         }
                                                     }
+                                                }
+                                                
+                                                // Payment confirmation step
+                                                if let (true, Some(token_id)) = (enrollment_complete, booking.token_id.clone()) {
+                                                    show_step(BookingStep::Payment, "Confirming payment...");
                                                     
-                                                    // If not enrolled, ask user to enroll
-                                                    if !enrollment_complete {
-                                                        println!("Agent A: Let me securely add your card for this transaction.");
-                                                        println!("Agent A: You'll authenticate using your device's biometric authentication (Face ID/Fingerprint).\n");
-                                                        
-                                                        if ask_confirmation_from_reader("Ready to add your card?", &mut reader, &mut stdout)? {
-                                                            show_status("Adding your card...");
-                                                            
-                                                            let enroll_args = json!({
-                                                                "sessionId": session_id,
-                                                                "consumerId": "user_123",
-                                                                "enrollmentReferenceId": "enroll_ref_456"
-                                                            });
-                                                            
-                                                            println!("→ Invoking: enroll-card with args {}", enroll_args);
-
-                                                            match call_server_tool(
-                                                                &client,
-                                                                &config.server_url,
-                                                                &agent_b_url,
-                                                                payment_agent_url,
-                                                                "enroll-card",
-                                                                enroll_args,
-                                                            )
-                                                            .await
-                                                            {
-                                                                Ok(result) => {
-                                                                    if let Ok(parsed) = serde_json::from_str::<Value>(&result) {
-                                                                        let is_success = parsed.get("success").and_then(|s| s.as_bool()).unwrap_or(false) ||
-                                                                            parsed.get("status").and_then(|s| s.as_str()).map(|s| s == "SUCCESS").unwrap_or(false);
-                                                                        
-                                                                        if is_success {
-                                                                            if let Some(token_id) = parsed.get("tokenId").and_then(|t| t.as_str()) {
-                                                                                enrollment_token_id = token_id.to_string();
-                                                                            }
-                                                                            show_success("Your card has been enrolled with biometric authentication!");
-                                                                            enrollment_complete = true;
-                                                                        } else {
-                                                                            println!("✗ Enrollment failed: {}\n", result);
-                                                                        }
-                                                                    } else {
-                                                                        println!("✓ Result: {}\n", result);
-                                                                        enrollment_complete = true;
+                                                    println!("Agent A: Your card is ready. Shall I proceed with the payment?\n");
+                                                    
+                                                    if ask_confirmation_from_reader("Proceed with payment?", &mut reader, &mut stdout)? {
+                                                        // Fares can move while the traveler enrolls a card; re-quote before charging
+                                                        let (pricing, amount, amount_minor) = match &proof_args {
+                                                            Some(args) => match requote_if_changed(&tool_client, args, amount_minor).await {
+                                                                Ok(Some((requote, new_minor))) => {
+                                                                    println!("Agent A: The fare has changed since your quote: the total is now {} (was {} {}).\n", display_amount(new_minor, &currency), amount, currency);
+                                                                    if !ask_confirmation_from_reader("Accept the new total?", &mut reader, &mut stdout)? {
+                                                                        println!("Agent A: Okay, I haven't charged you or booked the flight.\n");
+                                                                        continue;
                                                                     }
+                                                                    (requote, format_amount(new_minor, &currency), new_minor)
                                                                 }
+                                                                Ok(None) => (pricing, amount, amount_minor),
                                                                 Err(e) => {
-                                                                    println!("✗ Error: {}\n", e);
+                                                                    show_warning(&format!("Could not re-check the fare ({}). Using your quote.", e));
+                                                                    (pricing, amount, amount_minor)
                                                                 }
+                                                            },
+                                                            None => (pricing, amount, amount_minor),
+                                                        };
+
+                                                        // Spending caps apply to what this session and this consumer already paid
+                                                        let session_spent: i64 = paid_bookings.values().map(BookingState::settled_minor).sum();
+                                                        let consumer_spent: i64 = paid_bookings
+                                                            .values()
+                                                            .filter(|paid| paid.consumer_id == booking.consumer_id)
+                                                            .map(BookingState::settled_minor)
+                                                            .sum();
+                                                        let decision = config.spend_policy.check(amount_minor, &currency, session_spent, consumer_spent);
+                                                        let confirmed = match &decision {
+                                                            SpendDecision::Within => true,
+                                                            SpendDecision::OverLimit { scope, limit, total } => {
+                                                                println!(
+                                                                    "Agent A: This payment would bring your {} total to {}, over the {} limit.",
+                                                                    scope,
+                                                                    display_amount(*total, &currency),
+                                                                    display_amount(*limit, &currency)
+                                                                );
+                                                                ask_phrase_from_reader(&SpendDecision::confirmation_phrase(amount_minor, &currency), &mut reader, &mut stdout)?
                                                             }
-                                                        } else {
-                                                            println!("Agent A: Card enrollment cancelled. Unable to proceed with payment.\n");
+                                                        };
+                                                        cryptographic_traces.push(decision.trace(&booking.session_id, amount_minor, confirmed));
+                                                        if !confirmed {
+                                                            println!("Agent A: Okay, I haven't charged you or booked the flight.\n");
                                                             continue;
                                                         }
-                                                    }
-                                                    
-                                                    // Payment confirmation step
-                                                    if enrollment_complete {
-                                                        show_step(3, 3, "Confirming payment...");
-                                                        
-                                                        println!("Agent A: Your card is ready. Shall I proceed with the payment?\n");
-                                                        
-                                                        if ask_confirmation_from_reader("Proceed with payment?", &mut reader, &mut stdout)? {
+
+                                                        let parts = ask_split_from_reader(amount_minor, &currency, &token_id, &cards, &mut reader, &mut stdout)?;
+
+                                                        loop {
                                                             show_status("Processing payment...");
                                                             show_status("You'll be asked to authenticate with biometric on your device...");
-                                                            
-                                                            // Execute purchase
-                                                            let purchase_args = json!({
-                                                                "sessionId": "sess_user_123",
-                                                                "consumerId": "user_123",
-                                                                "tokenId": enrollment_token_id,
-                                                                "amount": price.to_string(),
-                                                                "merchant": "ZeroProof Travel"
-                                                            });
-                                                            
-                                                            println!("→ Invoking: initiate-purchase-instruction with args {}", purchase_args);
-
-                                                            match call_server_tool(
-                                                                &client,
-                                                                &config.server_url,
-                                                                &agent_b_url,
-                                                                payment_agent_url,
-                                                                "initiate-purchase-instruction",
-                                                                purchase_args,
-                                                            )
-                                                            .await
-                                                            {
-                                                                Ok(result) => {
-                                                                    println!("✓ Result: {}\n", result);
-                                                                    
-                                                                    // Extract instructionId from purchase result
-                                                                    if let Ok(purchase_response) = serde_json::from_str::<Value>(&result) {
-                                                                        if let Some(instruction_id) = purchase_response.get("instructionId").and_then(|id| id.as_str()) {
-                                                                            // Execute credential retrieval with actual instructionId
-                                                                            let retrieve_args = json!({
-                                                                                "sessionId": "sess_user_123",
-                                                                                "consumerId": "user_123",
-                                                                                "tokenId": enrollment_token_id,
-                                                                                "instructionId": instruction_id,
-                                                                                "transactionReferenceId": "txn_202"
-                                                                            });
-                                                                            
-                                                                            println!("→ Invoking: retrieve-payment-credentials with args {}", retrieve_args);
-
-                                                                            match call_server_tool(
-                                                                                &client,
-                                                                                &config.server_url,
-                                                                                &agent_b_url,
-                                                                                payment_agent_url,
-                                                                                "retrieve-payment-credentials",
-                                                                                retrieve_args,
-                                                                            )
-                                                                            .await
-                                                                            {
-                                                                                Ok(result) => {
-                                                                                    println!("✓ Result: {}\n", result);
-                                                                                    payment_confirmed = true;
+                                                        
+                                                            // Execute purchase; instructions already issued are not created again
+                                                            let settlement = |token_id: &str, amount_minor: i64| {
+                                                                config.fx_rates.settlement(&currency, amount_minor, cards.iter().find(|card| card.token_id == token_id))
+                                                            };
+                                                            let initiation = initiate_payment(&tool_client, &mut booking, &parts, &currency, &config.merchant, settlement);
+                                                            if let Err(e) = config.payment_timeouts.run(PaymentStage::Initiation, initiation).await {
+                                                                show_payment_error(&e);
+                                                            }
+
+                                                            // Settle each part in turn, stopping at the first that doesn't go through;
+                                                            // settled parts are skipped on retry
+                                                            let mut retryable = false;
+                                                            for i in 0..booking.payments.len() {
+                                                                if booking.payments[i].settled {
+                                                                    continue;
+                                                                }
+                                                                let Some(instruction_id) = booking.payments[i].instruction_id.clone() else {
+                                                                    retryable = true;
+                                                                    break;
+                                                                };
+                                                                if booking.payments.len() > 1 {
+                                                                    show_status(&format!("Payment {} of {}: {}", i + 1, booking.payments.len(), display_amount(booking.payments[i].amount_minor, &currency)));
+                                                                }
+
+                                                                // Only a settled payment moves on to credential retrieval
+                                                                show_status("Waiting for payment confirmation...");
+                                                                let mut settlement = wait_for_settlement(&tool_client, &booking, &instruction_id, config.payment_confirmation_timeout).await;
+
+                                                                // Step-up authentication, then resume waiting for settlement
+                                                                while let Ok(PaymentStatus::ChallengeRequired(challenge)) = &settlement {
+                                                                    show_step(BookingStep::PaymentChallenge, "Verifying your payment with your bank...");
+                                                                    let Some(code) = ask_challenge_from_reader(challenge, &mut reader, &mut stdout)? else {
+                                                                        settlement = Ok(PaymentStatus::Declined("verification not completed".to_string()));
+                                                                        break;
+                                                                    };
+                                                                    let otp = challenge.otp.then_some(code.as_str());
+                                                                    settlement = match complete_challenge(&tool_client, &booking, &instruction_id, challenge, otp).await {
+                                                                        Ok(()) => wait_for_settlement(&tool_client, &booking, &instruction_id, config.payment_confirmation_timeout).await,
+                                                                        Err(e) => Err(e),
+                                                                    };
+                                                                }
+
+                                                                match settlement {
+                                                                    Ok(PaymentStatus::Confirmed) => {
+                                                                        let transaction_reference = match booking.payments[i].transaction_reference.clone() {
+                                                                            Some(reference) => reference,
+                                                                            None => booking.payments[i].new_transaction_reference(),
+                                                                        };
+                                                                        match config.payment_timeouts.run(PaymentStage::Credentials, retrieve_credentials(&tool_client, &booking, &booking.payments[i], &currency)).await {
+                                                                            Ok(credentials) => {
+                                                                                booking.payments[i].proof_id = credentials.proof_id().map(str::to_string);
+                                                                                keep_proof(&tool_client, &mut cryptographic_traces, &booking.session_id, json!({
+                                                                                    "kind": "payment_credentials",
+                                                                                    "workflow_stage": "payment",
+                                                                                    "session_id": booking.session_id,
+                                                                                    "instruction_id": instruction_id,
+                                                                                    "payment_reference": booking.payments[i].reference,
+                                                                                    "transaction_reference_id": transaction_reference,
+                                                                                    "amount_minor": booking.payments[i].amount_minor,
+                                                                                    "settlement_currency": booking.payments[i].settlement.as_ref().map(|s| &s.currency),
+                                                                                    "settlement_amount_minor": booking.payments[i].settlement.as_ref().map(|s| s.amount_minor),
+                                                                                    "fx_rate": booking.payments[i].settlement.as_ref().map(|s| s.fx_rate),
+                                                                                    "proof": credentials.proof,
+                                                                                })).await;
+                                                                                booking.payments[i].settled = true;
+
+                                                                                let part = &booking.payments[i];
+                                                                                if let Some(settlement) = &part.settlement {
+                                                                                    println!(
+                                                                                        "Agent A: Your card was charged {} ({} at {:.4}).\n",
+                                                                                        display_amount(settlement.amount_minor, &settlement.currency),
+                                                                                        display_amount(part.amount_minor, &currency),
+                                                                                        settlement.fx_rate
+                                                                                    );
                                                                                 }
-                                                                                Err(e) => {
-                                                                                    println!("✗ Error: {}\n", e);
+                                                                                let card = cards
+                                                                                    .iter()
+                                                                                    .find(|card| card.token_id == part.token_id)
+                                                                                    .map(|card| card.description.clone())
+                                                                                    .unwrap_or_else(|| format!("Card ({})", part.token_id));
+                                                                                let receipt = payment_receipt(part, &card, &currency, &credentials);
+                                                                                if let Err(e) = tool_client.store_payment_receipt(&booking.session_id, &receipt).await {
+                                                                                    tracing::warn!(session_id = %booking.session_id, step = "payment", error = %e, "could not store the payment receipt");
                                                                                 }
                                                                             }
-                                                                        } else {
-                                                                            println!("✗ Error: Could not extract instructionId from purchase response\n");
+                                                                            Err(e) => {
+                                                                                show_payment_error(&e);
+                                                                                retryable = true;
+                                                                                break;
+                                                                            }
                                                                         }
-                                                                    } else {
-                                                                        println!("✗ Error: Could not parse purchase response\n");
+                                                                    }
+                                                                    Ok(PaymentStatus::Declined(reason)) => {
+                                                                        println!("Agent A: Your payment was declined ({}). Your flight has not been booked.\n", reason);
+                                                                        break;
+                                                                    }
+                                                                    Ok(PaymentStatus::Pending | PaymentStatus::ChallengeRequired(_)) => {
+                                                                        println!("Agent A: Your payment is still pending, so I haven't booked the flight yet. Please check with your bank and try again.\n");
+                                                                        retryable = true;
+                                                                        break;
+                                                                    }
+                                                                    Err(e) => {
+                                                                        show_error(&format!("Error checking payment status: {}", e));
+                                                                        retryable = true;
+                                                                        break;
                                                                     }
                                                                 }
-                                                                Err(e) => {
-                                                                    println!("✗ Error: {}\n", e);
+                                                            }
+
+                                                            if booking.settled_minor() >= amount_minor
+                                                                || !retryable
+                                                                || !ask_confirmation_from_reader("Retry the payment from where it stopped?", &mut reader, &mut stdout)?
+                                                            {
+                                                                break;
+                                                            }
+                                                        }
+
+                                                        // Book only once the settled parts cover the whole quote
+                                                        let settled = booking.settled_minor();
+                                                        let payment_confirmed = settled >= amount_minor;
+                                                        if !payment_confirmed && settled > 0 {
+                                                            println!("Agent A: Only {} of {} {} went through, so I haven't booked the flight.", display_amount(settled, &currency), amount, currency);
+                                                            show_status("Refunding the partial payment...");
+                                                            match refund_unbooked(&tool_client, &mut booking, &currency, "partial_payment").await {
+                                                                Ok(trace) => {
+                                                                    show_success(&format!("{} has been refunded to your card.", display_amount(settled, &currency)));
+                                                                    keep_proof(&tool_client, &mut cryptographic_traces, &booking.session_id, trace).await;
                                                                 }
+                                                                Err(e) => show_warning(&format!("Could not refund the partial payment: {}", e)),
                                                             }
+                                                        }
+                                                        
+                                                        if payment_confirmed {
+                                                            show_success("Payment confirmed! Now I am going to complete your booking!");
+                                                            
+                                                            // Now call book-flight with passenger details
+                                                            show_step(BookingStep::Booking, "Completing your flight booking...");
+                                                            
+                                                            let book_args = json!({
+                                                                "from": trip_from,
+                                                                "to": trip_to,
+                                                                "passenger_name": passenger_name,
+                                                                "passenger_email": passenger_email,
+                                                                "cabin": cabin,
+                                                                "session_id": booking.session_id,
+                                                                "travel_date": pricing.get("travel_date"),
+                                                                "amount": pricing.get("amount"),
+                                                                "vip": pricing.get("vip"),
+                                                                "promo_code": pricing.get("promo_code"),
+                                                                "ancillaries": pricing["breakdown"]["ancillaries"]
+                                                                    .as_array()
+                                                                    .map(|charges| charges.iter().filter_map(|c| c.get("code")).cloned().collect::<Vec<_>>())
+                                                                    .unwrap_or_default(),
+                                                                "proof_ids": booking.payments.iter().filter_map(|p| p.proof_id.as_deref()).collect::<Vec<_>>()
+                                                            });
                                                             
-                                                            if payment_confirmed {
-                                                                show_success("Payment confirmed! Now I am going to complete your booking!");
-                                                                
-                                                                // Now call book-flight with passenger details
-                                                                show_step(3, 3, "Completing your flight booking...");
-                                                                
-                                                                let book_args = json!({
-                                                                    "from": trip_from,
-                                                                    "to": trip_to,
-                                                                    "passenger_name": passenger_name,
-                                                                    "passenger_email": passenger_email
-                                                                });
-                                                                
-                                                                println!("→ Invoking: book-flight with args {}", book_args);
-
-                                                                match call_server_tool(
-                                                                    &client,
-                                                                    &config.server_url,
-                                                                    &agent_b_url,
-                                                                    payment_agent_url,
-                                                                    "book-flight",
-                                                                    book_args,
-                                                                )
-                                                                .await
-                                                                {
-                                                                    Ok(result) => {
-                                                                        println!("✓ Result: {}\n", result);
-                                                                        if let Ok(booking) = serde_json::from_str::<Value>(&result) {
-                                                                            if let Some(conf_code) = booking.get("confirmation_code").and_then(|c| c.as_str()) {
-                                                                                show_success("Flight booking confirmed!");
-                                                                                println!("Agent A: Your flight booking from {} to {} has been confirmed.\n", trip_from, trip_to);
-                                                                                println!("Agent A: Confirmation code: {}\n", conf_code);
-                                                                                println!("Agent A: You'll receive a confirmation email shortly with your flight details and receipt.\n");
+                                                            match tool_client.call("book-flight", book_args).await {
+                                                                Ok(result) => {
+                                                                    if let Some(booking_id) = result.data.get("booking_id").and_then(|b| b.as_str()) {
+                                                                        paid_bookings.insert(booking_id.to_string(), booking.clone());
+                                                                    }
+                                                                    if let Some(conf_code) = result.data.get("confirmation_code").and_then(|c| c.as_str()) {
+                                                                        show_success("Flight booking confirmed!");
+                                                                        println!("Agent A: Your flight booking from {} to {} has been confirmed.\n", trip_from, trip_to);
+                                                                        println!("Agent A: Confirmation code: {}\n", conf_code);
+                                                                        println!("Agent A: You'll receive a confirmation email shortly with your flight details and receipt.\n");
+
+                                                                        // Optional: prove the fare that was charged
+                                                                        if let (true, Some(args)) = (config.attest_bookings, &proof_args) {
+                                                                            show_status("Generating a zero-knowledge proof of your fare (this can take several minutes)...");
+                                                                            match attest_booking(&tool_client, args, conf_code).await {
+                                                                                Ok(trace) => {
+                                                                                    show_success("Your fare is now backed by a zero-knowledge proof!");
+                                                                                    println!("Agent A: Verification key hash: {}\n", trace["vk_hash"].as_str().unwrap_or("unknown"));
+                                                                                    keep_proof(&tool_client, &mut cryptographic_traces, &booking.session_id, trace).await;
+                                                                                }
+                                                                                Err(e) => show_warning(&format!("Could not prove the fare: {}", e)),
                                                                             }
                                                                         }
                                                                     }
-                                                                    Err(e) => {
-                                                                        println!("✗ Error booking flight: {}\n", e);
+                                                                }
+                                                                Err(e) => {
+                                                                    show_error(&format!("Error booking flight: {}", e));
+                                                                    // The payment already settled, so give it back
+                                                                    show_status("Refunding your payment...");
+                                                                    let settled = booking.settled_minor();
+                                                                    match refund_unbooked(&tool_client, &mut booking, &currency, "booking_failed").await {
+                                                                        Ok(trace) => {
+                                                                            show_success(&format!("{} has been refunded to your card.", display_amount(settled, &currency)));
+                                                                            keep_proof(&tool_client, &mut cryptographic_traces, &booking.session_id, trace).await;
+                                                                        }
+                                                                        Err(e) => show_warning(&format!("Could not refund your payment: {}", e)),
                                                                     }
                                                                 }
                                                             }
-                                                        } else {
-                                                            println!("Agent A: Payment cancelled. Your booking has been cancelled.\n");
                                                         }
+                                                    } else {
+                                                        println!("Agent A: Payment cancelled. Your booking has been cancelled.\n");
                                                     }
-                                                } else {
-                                                    println!("Agent A: Okay, I've cancelled the booking. Let me know if you'd like to try different dates or destinations.\n");
-                                                    continue;
                                                 }
+                                            } else {
+                                                println!("Agent A: Okay, I've cancelled the booking. Let me know if you'd like to try different dates or destinations.\n");
+                                                continue;
                                             }
                                         }
                                     }
@@ -825,23 +1317,49 @@ async fn main() -> Result<()> {
                                 } else {
                                     // Non-payment tool flow (existing behavior)
                                     for (tool_name, arguments) in tool_calls {
-                                        println!("→ Invoking: {} with args {}", tool_name, arguments);
-
-                                        match call_server_tool(
-                                            &client,
-                                            &config.server_url,
-                                            &agent_b_url,
-                                            payment_agent_url,
-                                            &tool_name,
-                                            arguments,
-                                        )
-                                        .await
-                                        {
+                                        // Removing a saved card needs the traveler to pick it first
+                                        if tool_name == "unenroll-card" {
+                                            match unenroll_saved_card(&tool_client, &config.consumer_ids, &arguments, &mut reader, &mut stdout).await {
+                                                Ok(Some(trace)) => {
+                                                    // Paid bookings no longer point at the removed card
+                                                    for booking in paid_bookings.values_mut() {
+                                                        if booking.token_id.as_deref() == trace["token_id"].as_str() {
+                                                            booking.token_id = None;
+                                                        }
+                                                    }
+                                                    show_success("Your card has been removed.");
+                                                    let session_id = trace["session_id"].as_str().unwrap_or_default().to_string();
+                                                    keep_proof(&tool_client, &mut cryptographic_traces, &session_id, trace).await;
+                                                }
+                                                Ok(None) => println!("Agent A: No cards were removed.\n"),
+                                                Err(e) => show_error(&format!("Error removing card: {}", e)),
+                                            }
+                                            continue;
+                                        }
+
+                                        let booking_id = arguments.get("booking_id").and_then(|b| b.as_str()).map(str::to_string);
+
+                                        match tool_client.call(&tool_name, arguments).await {
                                             Ok(result) => {
-                                                println!("✓ Result: {}\n", result);
+                                                // A cancelled booking we paid for gets its refund from the Payment Agent
+                                                let paid = booking_id.as_ref().and_then(|id| paid_bookings.get_mut(id));
+                                                if let (true, Some(booking)) = (tool_name == "cancel-booking", paid) {
+                                                    show_status("Refunding your payment...");
+                                                    match refund_cancelled_booking(&tool_client, booking, &result.data).await {
+                                                        Ok(Some(trace)) => {
+                                                            let refunded = &trace["refunded_amount"];
+                                                            let currency = refunded["currency"].as_str().unwrap_or("USD");
+                                                            let refunded = refunded["amount_minor"].as_i64().map(|m| display_amount(m, currency)).unwrap_or_default();
+                                                            show_success(&format!("{} has been refunded to your card.", refunded));
+                                                            keep_proof(&tool_client, &mut cryptographic_traces, &booking.session_id, trace).await;
+                                                        }
+                                                        Ok(None) => println!("Agent A: This fare is not refundable.\n"),
+                                                        Err(e) => show_warning(&format!("Could not refund the payment: {}", e)),
+                                                    }
+                                                }
                                             }
                                             Err(e) => {
-                                                println!("✗ Error: {}\n", e);
+                                                show_error(&format!("Error: {}", e));
                                             }
                                         }
                                     }
@@ -857,15 +1375,24 @@ async fn main() -> Result<()> {
                         }
                         Err(_) => {
                             // Parse failed, show as conversational response
-                            println!("Agent A: {}\n", claude_response);
+                            if !already_shown {
+                                println!("Agent A: {}\n", claude_response);
+                            }
                         }
                     }
                 }
                 Err(e) => {
-                    eprintln!("✗ Claude API error: {}\n", e);
+                    eprintln!("✗ Claude API error: {}\n", redact::text(&e.to_string()));
                 }
             }
     }
 
+    println!("Session usage: {}", claude_usage.summary());
+
+    if !cryptographic_traces.is_empty() {
+        std::fs::write(&config.traces_path, serde_json::to_string_pretty(&cryptographic_traces)?)?;
+        println!("Saved {} cryptographic trace(s) to {}", cryptographic_traces.len(), config.traces_path);
+    }
+
     Ok(())
 }
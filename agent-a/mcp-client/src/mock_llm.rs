@@ -0,0 +1,161 @@
+//! Scripted stand-in for Claude (`LLM_PROVIDER=mock`)
+//!
+//! Answers each query from a canned script keyed by the booking step it
+//! starts (`pricing`, `cancellation`, `card_removal`, or `conversation` for
+//! anything else), so CI and demos run the whole booking flow without an
+//! Anthropic key or network access. Each script entry gives the tool to call,
+//! its arguments and the message shown to the traveler; the built-in scripts
+//! can be replaced per step from a JSON file named by `MOCK_LLM_SCRIPT`:
+//!
+//! ```json
+//! { "pricing": { "tool": "get-ticket-price", "arguments": { "from": "SFO", "to": "TYO", "cabin": "business" }, "message": "Checking fares..." } }
+//! ```
+//!
+//! An argument given as `"{booking_id}"` is filled with the first word of the
+//! query containing a digit, e.g. `cancel booking bk_17`.
+
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// Steps a query can start, with the words that pick them, checked in order
+const STEP_KEYWORDS: &[(&str, &[&str])] = &[
+    ("cancellation", &["cancel", "refund"]),
+    ("card_removal", &["remove", "unenroll", "delete"]),
+    ("pricing", &["book", "flight", "fly", "price", "fare", "ticket"]),
+];
+
+/// Step of queries no keyword matches
+const CONVERSATION: &str = "conversation";
+
+/// Canned reply for one booking step
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScriptedReply {
+    /// Tool to call; a reply without one is plain conversation
+    #[serde(default)]
+    pub tool: Option<String>,
+    #[serde(default)]
+    pub arguments: Value,
+    pub message: String,
+}
+
+/// Deterministic LLM replies from canned scripts
+#[derive(Debug, Clone)]
+pub struct MockLlmProvider {
+    scripts: HashMap<String, ScriptedReply>,
+}
+
+impl Default for MockLlmProvider {
+    fn default() -> Self {
+        let reply = |tool: Option<&str>, arguments: Value, message: &str| ScriptedReply {
+            tool: tool.map(str::to_string),
+            arguments,
+            message: message.to_string(),
+        };
+        let scripts = HashMap::from([
+            (
+                "pricing".to_string(),
+                reply(
+                    Some("get-ticket-price"),
+                    json!({ "from": "NYC", "to": "LON" }),
+                    "Let me find you a fare from New York to London.",
+                ),
+            ),
+            (
+                "cancellation".to_string(),
+                reply(Some("cancel-booking"), json!({ "booking_id": "{booking_id}" }), "Cancelling your booking."),
+            ),
+            (
+                "card_removal".to_string(),
+                reply(Some("unenroll-card"), json!({}), "Let's remove one of your saved cards."),
+            ),
+            (
+                CONVERSATION.to_string(),
+                reply(
+                    None,
+                    Value::Null,
+                    "I'm running on scripted replies (LLM_PROVIDER=mock). Try \"Book a flight from NYC to London\".",
+                ),
+            ),
+        ]);
+        Self { scripts }
+    }
+}
+
+impl MockLlmProvider {
+    /// The mock provider if `LLM_PROVIDER=mock`, with the built-in scripts
+    /// overridden by `MOCK_LLM_SCRIPT`
+    pub fn from_env() -> Result<Option<Self>> {
+        if !std::env::var("LLM_PROVIDER").is_ok_and(|provider| provider.eq_ignore_ascii_case("mock")) {
+            return Ok(None);
+        }
+        let mut provider = Self::default();
+        if let Ok(path) = std::env::var("MOCK_LLM_SCRIPT") {
+            let script = std::fs::read_to_string(&path).map_err(|e| anyhow!("Cannot read MOCK_LLM_SCRIPT {}: {}", path, e))?;
+            let overrides: HashMap<String, ScriptedReply> =
+                serde_json::from_str(&script).map_err(|e| anyhow!("Invalid MOCK_LLM_SCRIPT {}: {}", path, e))?;
+            provider.scripts.extend(overrides);
+        }
+        Ok(Some(provider))
+    }
+
+    /// Reply to `query` the way Claude would: the tool-call JSON for a
+    /// scripted tool, otherwise the plain message
+    pub fn reply(&self, query: &str) -> String {
+        let step = step_for(query);
+        let Some(script) = self.scripts.get(step).or_else(|| self.scripts.get(CONVERSATION)) else {
+            return String::new();
+        };
+        let Some(tool) = &script.tool else {
+            return script.message.clone();
+        };
+
+        let mut arguments = script.arguments.clone();
+        if let Some(arguments) = arguments.as_object_mut() {
+            let booking_id = query
+                .split_whitespace()
+                .map(|word| word.trim_matches(|c: char| !c.is_alphanumeric() && c != '_' && c != '-'))
+                .find(|word| word.chars().any(|c| c.is_ascii_digit()));
+            for value in arguments.values_mut() {
+                if value == "{booking_id}" {
+                    *value = json!(booking_id.unwrap_or_default());
+                }
+            }
+        }
+        json!({
+            "reasoning": format!("Scripted {} reply", step),
+            "tool_calls": [{ "name": tool, "arguments": arguments }],
+            "user_message": script.message,
+        })
+        .to_string()
+    }
+}
+
+/// Booking step a query starts, by keyword
+fn step_for(query: &str) -> &'static str {
+    let query = query.to_lowercase();
+    STEP_KEYWORDS
+        .iter()
+        .find(|(_, keywords)| keywords.iter().any(|keyword| query.contains(keyword)))
+        .map_or(CONVERSATION, |(step, _)| step)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scripted_replies_by_step() {
+        let provider = MockLlmProvider::default();
+
+        let pricing: Value = serde_json::from_str(&provider.reply("Book me a flight to London")).unwrap();
+        assert_eq!(pricing["tool_calls"][0]["name"], "get-ticket-price");
+        assert_eq!(pricing["tool_calls"][0]["arguments"], json!({ "from": "NYC", "to": "LON" }));
+
+        let cancellation: Value = serde_json::from_str(&provider.reply("Please cancel booking bk_42.")).unwrap();
+        assert_eq!(cancellation["tool_calls"][0]["arguments"]["booking_id"], "bk_42");
+
+        assert!(provider.reply("hello").starts_with("I'm running on scripted replies"));
+    }
+}
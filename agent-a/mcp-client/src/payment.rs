@@ -0,0 +1,824 @@
+//! Payment Agent calls made while booking: the traveler's enrolled cards
+//! (and removing them), purchase instructions against one of them, waiting for them to settle
+//! (including step-up challenges), and refunds of them when a booking is
+//! cancelled.
+
+use anyhow::{anyhow, Result};
+use pricing_core::money::{minor_unit_exponent, Money};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Value};
+use std::fmt;
+use std::future::Future;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::{
+    booking::{BookingState, PaymentPart, Settlement},
+    tools::ToolClient,
+};
+
+/// Attempts at creating one purchase instruction before giving up
+const MAX_PAYMENT_ATTEMPTS: u32 = 3;
+
+/// Delay between `get-payment-status` polls
+const PAYMENT_POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Card networks cut statement descriptors at 22 characters
+const MAX_STATEMENT_DESCRIPTOR_LEN: usize = 22;
+
+/// Format a minor-unit amount as the decimal string payment APIs expect
+/// ("450.00" USD, "45000" JPY)
+pub fn format_amount(amount_minor: i64, currency: &str) -> String {
+    Money::new(amount_minor, currency).to_string()
+}
+
+/// Amount with its currency code, as shown to the traveler ("450.00 USD")
+pub fn display_amount(amount_minor: i64, currency: &str) -> String {
+    format!("{} {}", format_amount(amount_minor, currency), currency.to_uppercase())
+}
+
+/// Parse a non-negative decimal amount in `currency` ("120", "120.", "120.5",
+/// "120.50" USD) into minor units. Signs and more decimals than the currency
+/// has are rejected.
+pub fn parse_amount(amount: &str, currency: &str) -> Option<i64> {
+    let exponent = minor_unit_exponent(currency) as usize;
+    let (units, fraction) = amount.trim().split_once('.').unwrap_or((amount.trim(), ""));
+    let digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+    if units.is_empty() || !digits(units) || !digits(fraction) || fraction.len() > exponent {
+        return None;
+    }
+    let fraction: i64 = if exponent == 0 { 0 } else { format!("{:0<width$}", fraction, width = exponent).parse().ok()? };
+    units.parse::<i64>().ok()?.checked_mul(10i64.pow(exponent as u32))?.checked_add(fraction)
+}
+
+/// Currencies the Payment Agent can charge (`PAYMENT_AGENT_CURRENCIES`,
+/// comma-separated ISO 4217 codes, default `USD`)
+pub fn supported_currencies_from_env() -> Vec<String> {
+    std::env::var("PAYMENT_AGENT_CURRENCIES")
+        .unwrap_or_else(|_| "USD".to_string())
+        .split(',')
+        .map(|code| code.trim().to_uppercase())
+        .filter(|code| !code.is_empty())
+        .collect()
+}
+
+/// Exchange rates for cards that settle in another currency than the quote
+/// (`FX_RATES`, e.g. `USD/EUR=0.92,USD/GBP=0.79`: units of the second
+/// currency per unit of the first; inverse pairs are derived)
+#[derive(Debug, Clone, Default)]
+pub struct FxRates {
+    rates: Vec<(String, String, f64)>,
+}
+
+impl FxRates {
+    pub fn from_env() -> Self {
+        let rates = std::env::var("FX_RATES")
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|pair| {
+                let (currencies, rate) = pair.split_once('=')?;
+                let (from, to) = currencies.split_once('/')?;
+                let rate: f64 = rate.trim().parse().ok().filter(|r: &f64| *r > 0.0)?;
+                Some((from.trim().to_uppercase(), to.trim().to_uppercase(), rate))
+            })
+            .collect();
+        Self { rates }
+    }
+
+    fn rate(&self, from: &str, to: &str) -> Option<f64> {
+        self.rates.iter().find_map(|(f, t, rate)| match (f == from, t == to, f == to && t == from) {
+            (true, true, _) => Some(*rate),
+            (_, _, true) => Some(1.0 / rate),
+            _ => None,
+        })
+    }
+
+    /// What `amount_minor` in `currency` settles for on `card`, if the card
+    /// settles in another currency and a rate is configured for the pair
+    pub fn settlement(&self, currency: &str, amount_minor: i64, card: Option<&EnrolledCard>) -> Option<Settlement> {
+        let card_currency = card?.settlement_currency.as_deref()?;
+        if card_currency.eq_ignore_ascii_case(currency) {
+            return None;
+        }
+        let fx_rate = self.rate(&currency.to_uppercase(), card_currency)?;
+        // Rates are per major unit, so rescale between the currencies' minor units
+        let exponents = minor_unit_exponent(card_currency) as i32 - minor_unit_exponent(currency) as i32;
+        Some(Settlement {
+            currency: card_currency.to_string(),
+            amount_minor: (amount_minor as f64 * fx_rate * 10f64.powi(exponents)).round() as i64,
+            fx_rate,
+        })
+    }
+}
+
+/// Payment step bounded by its own timeout
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaymentStage {
+    Enrollment,
+    Initiation,
+    Credentials,
+}
+
+impl PaymentStage {
+    fn name(self) -> &'static str {
+        match self {
+            PaymentStage::Enrollment => "card enrollment",
+            PaymentStage::Initiation => "payment initiation",
+            PaymentStage::Credentials => "credential retrieval",
+        }
+    }
+}
+
+/// Time limits per payment step (`PAYMENT_ENROLLMENT_TIMEOUT_SECS`,
+/// `PAYMENT_INITIATION_TIMEOUT_SECS`, `PAYMENT_CREDENTIALS_TIMEOUT_SECS`).
+/// Enrollment waits on the traveler's biometric check, so it gets longest.
+#[derive(Debug, Clone, Copy)]
+pub struct PaymentTimeouts {
+    pub enrollment: Duration,
+    pub initiation: Duration,
+    pub credentials: Duration,
+}
+
+impl PaymentTimeouts {
+    pub fn from_env() -> Self {
+        let secs = |var: &str, default: u64| {
+            Duration::from_secs(std::env::var(var).ok().and_then(|s| s.parse().ok()).unwrap_or(default))
+        };
+        Self {
+            enrollment: secs("PAYMENT_ENROLLMENT_TIMEOUT_SECS", 180),
+            initiation: secs("PAYMENT_INITIATION_TIMEOUT_SECS", 60),
+            credentials: secs("PAYMENT_CREDENTIALS_TIMEOUT_SECS", 60),
+        }
+    }
+
+    fn get(&self, stage: PaymentStage) -> Duration {
+        match stage {
+            PaymentStage::Enrollment => self.enrollment,
+            PaymentStage::Initiation => self.initiation,
+            PaymentStage::Credentials => self.credentials,
+        }
+    }
+
+    /// Run one payment step, failing with [`StageTimedOut`] past its limit.
+    /// Whatever the step recorded in `BookingState` before that is kept.
+    pub async fn run<T>(&self, stage: PaymentStage, step: impl Future<Output = Result<T>>) -> Result<T> {
+        let after = self.get(stage);
+        tokio::time::timeout(after, step)
+            .await
+            .map_err(|_| anyhow::Error::new(StageTimedOut { stage, after }))?
+    }
+}
+
+/// A payment step ran past its timeout
+#[derive(Debug, Clone, Copy)]
+pub struct StageTimedOut {
+    pub stage: PaymentStage,
+    pub after: Duration,
+}
+
+impl fmt::Display for StageTimedOut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} timed out after {}s, you can retry", self.stage.name(), self.after.as_secs())
+    }
+}
+
+impl std::error::Error for StageTimedOut {}
+
+/// Payment methods that can be offered at checkout, by ID
+const KNOWN_PAYMENT_METHODS: &[(&str, &str)] = &[
+    ("visa", "Visa Credit Card"),
+    ("bank_transfer", "Bank transfer"),
+    ("wallet", "Digital wallet"),
+];
+
+/// Methods the Payment Agent can charge, via card enrollment
+const CARD_PAYMENT_METHODS: &[&str] = &["visa"];
+
+/// A payment method offered at checkout
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaymentMethod {
+    pub id: &'static str,
+    pub label: &'static str,
+    /// Enabled in `PAYMENT_METHODS` and chargeable through the Payment Agent
+    pub accepted: bool,
+}
+
+impl PaymentMethod {
+    /// Every known method, accepted if listed in `PAYMENT_METHODS`
+    /// (comma-separated IDs, default `visa`) and backed by a card flow
+    pub fn registry_from_env() -> Vec<Self> {
+        let enabled = std::env::var("PAYMENT_METHODS").unwrap_or_else(|_| "visa".to_string());
+        let mut enabled: Vec<&str> = enabled.split(',').map(str::trim).collect();
+        if !enabled.iter().any(|id| CARD_PAYMENT_METHODS.contains(id)) {
+            tracing::warn!("PAYMENT_METHODS enables no supported method; accepting visa");
+            enabled.push("visa");
+        }
+        KNOWN_PAYMENT_METHODS
+            .iter()
+            .map(|&(id, label)| PaymentMethod {
+                id,
+                label,
+                accepted: enabled.contains(&id) && CARD_PAYMENT_METHODS.contains(&id),
+            })
+            .collect()
+    }
+}
+
+/// Merchant the traveler is charged by, as recorded by the Payment Agent
+/// and shown on card statements
+#[derive(Debug, Clone)]
+pub struct Merchant {
+    /// `MERCHANT_NAME`
+    pub name: String,
+    /// `MERCHANT_ID`
+    pub id: String,
+    /// `MERCHANT_STATEMENT_DESCRIPTOR`, defaulting to the upper-cased name
+    pub statement_descriptor: String,
+}
+
+impl Merchant {
+    pub fn from_env() -> Self {
+        let name = std::env::var("MERCHANT_NAME").unwrap_or_else(|_| "ZeroProof Travel".to_string());
+        let id = std::env::var("MERCHANT_ID").unwrap_or_else(|_| "zeroproof-travel".to_string());
+        let statement_descriptor = std::env::var("MERCHANT_STATEMENT_DESCRIPTOR")
+            .unwrap_or_else(|_| name.to_uppercase())
+            .chars()
+            .take(MAX_STATEMENT_DESCRIPTOR_LEN)
+            .collect();
+        Self { name, id, statement_descriptor }
+    }
+}
+
+/// `enroll-card` response
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnrollCardResponse {
+    #[serde(default)]
+    pub success: bool,
+    pub status: Option<String>,
+    pub token_id: Option<String>,
+    pub proof: Option<Value>,
+}
+
+impl EnrollCardResponse {
+    pub fn is_success(&self) -> bool {
+        self.success || self.status.as_deref().is_some_and(|s| s.eq_ignore_ascii_case("SUCCESS"))
+    }
+}
+
+/// `initiate-purchase-instruction` response
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PurchaseInstructionResponse {
+    instruction_id: String,
+}
+
+/// `retrieve-payment-credentials` response; the credentials themselves
+/// stay with the Payment Agent, only the proof of retrieval is kept
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaymentCredentials {
+    pub proof_id: Option<String>,
+    pub proof: Option<Value>,
+}
+
+impl PaymentCredentials {
+    /// ID of the proof backing the credentials, top-level or inside `proof`
+    pub fn proof_id(&self) -> Option<&str> {
+        self.proof_id
+            .as_deref()
+            .or_else(|| self.proof.as_ref().and_then(|proof| proof["id"].as_str()))
+    }
+}
+
+/// Parse a Payment Agent tool result into `T`. Besides plain JSON objects,
+/// this accepts the other shapes the Payment Agent returns: a JSON document
+/// encoded as a string (possibly more than once), and MCP text content
+/// (`{"content": [{"type": "text", "text": "..."}]}`).
+fn parse_tool_data<T: DeserializeOwned>(tool_name: &str, data: &Value) -> Result<T> {
+    let mut data = data.clone();
+    // Bounded so a string that decodes to itself can't loop forever
+    for _ in 0..3 {
+        if let Some(text) = data["content"][0]["text"].as_str() {
+            data = Value::String(text.to_string());
+        }
+        match data {
+            Value::String(text) => {
+                data = serde_json::from_str(&text)
+                    .map_err(|e| anyhow!("{} returned a string that is not JSON ({}): {}", tool_name, e, text))?;
+            }
+            _ => break,
+        }
+    }
+    serde_json::from_value(data.clone())
+        .map_err(|e| anyhow!("Unexpected {} response ({}): {}", tool_name, e, data))
+}
+
+/// Enroll a card for the booking's consumer under `enrollment_reference`
+pub async fn enroll_card(
+    tool_client: &ToolClient,
+    booking: &BookingState,
+    enrollment_reference: &str,
+) -> Result<EnrollCardResponse> {
+    let enroll_args = json!({
+        "sessionId": booking.payment_session_id,
+        "consumerId": booking.consumer_id,
+        "enrollmentReferenceId": enrollment_reference
+    });
+
+    let response = tool_client
+        .call("enroll-card", enroll_args)
+        .await
+        .and_then(|result| parse_tool_data::<EnrollCardResponse>("enroll-card", &result.data));
+
+    let outcome = match &response {
+        Ok(response) if !response.is_success() => {
+            format!("failed: {}", response.status.as_deref().unwrap_or("enrollment not completed"))
+        }
+        other => ledger_outcome(other),
+    };
+    let token_id = response.as_ref().ok().and_then(|r| r.token_id.clone());
+    let proof_id = response.as_ref().ok().and_then(|r| r.proof.as_ref()).and_then(|p| p["id"].as_str());
+    record_ledger(tool_client, booking, json!({
+        "kind": "enrollment",
+        "reference": enrollment_reference,
+        "external_id": token_id,
+        "proof_id": proof_id,
+        "outcome": outcome,
+    }))
+    .await;
+    response
+}
+
+/// Delete the enrolled card `token_id` at the Payment Agent, returning its
+/// record of the removal (including its proof)
+pub async fn unenroll_card(tool_client: &ToolClient, booking: &BookingState, token_id: &str) -> Result<Value> {
+    let unenroll_args = json!({
+        "sessionId": booking.payment_session_id,
+        "consumerId": booking.consumer_id,
+        "tokenId": token_id
+    });
+
+    let result = tool_client.call("unenroll-card", unenroll_args).await?;
+    Ok(result.data)
+}
+
+/// Create the purchase instructions paying for the booking, one per
+/// `(token_id, amount_minor)` part (a single part unless the traveler splits
+/// the payment), and record them in `booking.payments`. `settlement` gives
+/// the conversion for a part whose card settles in another currency.
+///
+/// Stops at the first part that fails; the parts created before it stay in
+/// `booking.payments`. Calling it again resumes: once `booking.payments` is
+/// filled, `parts` is ignored and only parts without an instruction are
+/// created.
+pub async fn initiate_payment(
+    tool_client: &ToolClient,
+    booking: &mut BookingState,
+    parts: &[(String, i64)],
+    currency: &str,
+    merchant: &Merchant,
+    settlement: impl Fn(&str, i64) -> Option<Settlement>,
+) -> Result<()> {
+    if booking.payments.is_empty() {
+        for (token_id, amount_minor) in parts {
+            booking.add_payment(token_id, *amount_minor, settlement(token_id, *amount_minor));
+        }
+    }
+    for i in 0..booking.payments.len() {
+        if booking.payments[i].instruction_id.is_some() {
+            continue;
+        }
+        let part = &booking.payments[i];
+        let instruction = create_instruction(tool_client, booking, part, currency, merchant).await;
+        record_ledger(tool_client, booking, json!({
+            "kind": "instruction",
+            "reference": part.reference,
+            "external_id": instruction.as_ref().ok(),
+            "amount": format_amount(part.amount_minor, currency),
+            "currency": currency,
+            "outcome": ledger_outcome(&instruction),
+        }))
+        .await;
+        booking.payments[i].instruction_id = Some(instruction?);
+    }
+    Ok(())
+}
+
+/// Create one purchase instruction for `part`, returning its instruction ID.
+///
+/// Every attempt carries the part's `clientReferenceId`. When a request
+/// fails in transit (the instruction may exist even though no answer came
+/// back), the session is checked for an instruction with that reference
+/// before retrying, and the Payment Agent can deduplicate on it too.
+async fn create_instruction(
+    tool_client: &ToolClient,
+    booking: &BookingState,
+    part: &PaymentPart,
+    currency: &str,
+    merchant: &Merchant,
+) -> Result<String> {
+    let mut purchase_args = json!({
+        "sessionId": booking.payment_session_id,
+        "consumerId": booking.consumer_id,
+        "tokenId": part.token_id,
+        "amount": format_amount(part.amount_minor, currency),
+        "currency": currency,
+        "merchant": merchant.name,
+        "merchantId": merchant.id,
+        "statementDescriptor": merchant.statement_descriptor,
+        "clientReferenceId": part.reference
+    });
+    add_settlement_args(&mut purchase_args, part);
+
+    let mut attempt = 1;
+    loop {
+        match tool_client.call("initiate-purchase-instruction", purchase_args.clone()).await {
+            Ok(result) => {
+                let response: PurchaseInstructionResponse =
+                    parse_tool_data("initiate-purchase-instruction", &result.data)?;
+                return Ok(response.instruction_id);
+            }
+            // Only transport failures are ambiguous; a declined or rejected
+            // request did not create an instruction
+            Err(e) if attempt < MAX_PAYMENT_ATTEMPTS && e.downcast_ref::<reqwest::Error>().is_some() => {
+                tracing::warn!(
+                    session_id = %booking.session_id,
+                    step = "payment",
+                    tool_name = "initiate-purchase-instruction",
+                    attempt,
+                    error = %e,
+                    "payment request failed, checking for an existing instruction"
+                );
+                if let Some(instruction_id) = find_instruction(tool_client, booking, &part.reference).await {
+                    tracing::info!(
+                        session_id = %booking.session_id,
+                        step = "payment",
+                        tool_name = "initiate-purchase-instruction",
+                        "reusing the existing purchase instruction"
+                    );
+                    return Ok(instruction_id);
+                }
+                tokio::time::sleep(Duration::from_secs(2u64.pow(attempt))).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Settlement state of a purchase instruction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PaymentStatus {
+    /// Not settled yet (or not within the confirmation timeout)
+    Pending,
+    /// Confirmed or settled; credentials may be retrieved
+    Confirmed,
+    /// Declined by the issuer or the Payment Agent, with its reason
+    Declined(String),
+    /// The issuer wants the traveler to authenticate (3-D Secure) first
+    ChallengeRequired(PaymentChallenge),
+}
+
+/// Step-up authentication requested for a payment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaymentChallenge {
+    pub challenge_id: Option<String>,
+    /// Page the traveler completes the challenge on (redirect challenges)
+    pub url: Option<String>,
+    /// Whether a one-time code must be entered here
+    pub otp: bool,
+    /// Issuer's instructions, e.g. where the code was sent
+    pub message: Option<String>,
+}
+
+impl PaymentChallenge {
+    fn from_response(challenge: &Value) -> Self {
+        let text = |key: &str| challenge[key].as_str().map(str::to_string);
+        Self {
+            challenge_id: text("challengeId"),
+            url: text("url"),
+            otp: challenge["type"].as_str().is_some_and(|t| t.eq_ignore_ascii_case("otp")),
+            message: text("message"),
+        }
+    }
+}
+
+impl PaymentStatus {
+    fn from_response(data: &Value) -> Self {
+        let status = data["status"].as_str().unwrap_or_default().to_uppercase();
+        match status.as_str() {
+            "CONFIRMED" | "SETTLED" | "APPROVED" | "SUCCESS" => PaymentStatus::Confirmed,
+            "CHALLENGE_REQUIRED" | "PENDING_AUTHENTICATION" => {
+                PaymentStatus::ChallengeRequired(PaymentChallenge::from_response(&data["challenge"]))
+            }
+            "DECLINED" | "FAILED" | "REJECTED" | "CANCELLED" => PaymentStatus::Declined(
+                data["reason"]
+                    .as_str()
+                    .or_else(|| data["message"].as_str())
+                    .unwrap_or(&status)
+                    .to_string(),
+            ),
+            _ => PaymentStatus::Pending,
+        }
+    }
+}
+
+/// Wait until the instruction is confirmed, declined or challenged, giving
+/// up as `Pending` after `timeout`. Between `get-payment-status` polls this
+/// waits on Agent A's payment webhook, so an event the Payment Agent pushes
+/// settles the wait right away.
+pub async fn wait_for_settlement(
+    tool_client: &ToolClient,
+    booking: &BookingState,
+    instruction_id: &str,
+    timeout: Duration,
+) -> Result<PaymentStatus> {
+    let status_args = json!({
+        "sessionId": booking.payment_session_id,
+        "consumerId": booking.consumer_id,
+        "instructionId": instruction_id
+    });
+    let started = Instant::now();
+    loop {
+        let result = tool_client.call("get-payment-status", status_args.clone()).await?;
+        let status = PaymentStatus::from_response(&result.data);
+        if status != PaymentStatus::Pending || started.elapsed() >= timeout {
+            return Ok(status);
+        }
+        println!("⏳ Waiting for your bank to confirm the payment... ({}s)", started.elapsed().as_secs());
+
+        let waited = Instant::now();
+        if let Ok(Some(event)) = tool_client.payment_event(instruction_id, PAYMENT_POLL_INTERVAL).await {
+            let status = PaymentStatus::from_response(&event);
+            if status != PaymentStatus::Pending {
+                return Ok(status);
+            }
+        }
+        // Without webhooks (or on a non-final event) this is a plain poll interval
+        tokio::time::sleep(PAYMENT_POLL_INTERVAL.saturating_sub(waited.elapsed())).await;
+    }
+}
+
+/// Report a completed challenge (with the code for OTP challenges) so the
+/// Payment Agent resumes the payment
+pub async fn complete_challenge(
+    tool_client: &ToolClient,
+    booking: &BookingState,
+    instruction_id: &str,
+    challenge: &PaymentChallenge,
+    otp: Option<&str>,
+) -> Result<()> {
+    let challenge_args = json!({
+        "sessionId": booking.payment_session_id,
+        "consumerId": booking.consumer_id,
+        "instructionId": instruction_id,
+        "challengeId": challenge.challenge_id,
+        "otp": otp
+    });
+
+    tool_client.call("complete-payment-challenge", challenge_args).await?;
+    Ok(())
+}
+
+/// Retrieve the payment credentials of a confirmed part under its
+/// transaction reference
+pub async fn retrieve_credentials(
+    tool_client: &ToolClient,
+    booking: &BookingState,
+    part: &PaymentPart,
+    currency: &str,
+) -> Result<PaymentCredentials> {
+    let mut retrieve_args = json!({
+        "sessionId": booking.payment_session_id,
+        "consumerId": booking.consumer_id,
+        "tokenId": part.token_id,
+        "instructionId": part.instruction_id,
+        "transactionReferenceId": part.transaction_reference
+    });
+    // The rate goes into the credentials proof so verification can bound the conversion
+    add_settlement_args(&mut retrieve_args, part);
+
+    let credentials = tool_client
+        .call("retrieve-payment-credentials", retrieve_args)
+        .await
+        .and_then(|result| parse_tool_data::<PaymentCredentials>("retrieve-payment-credentials", &result.data));
+
+    record_ledger(tool_client, booking, json!({
+        "kind": "credentials",
+        "reference": part.transaction_reference.as_deref().unwrap_or(&part.reference),
+        "external_id": part.instruction_id,
+        "amount": format_amount(part.amount_minor, currency),
+        "currency": currency,
+        "proof_id": credentials.as_ref().ok().and_then(|c| c.proof_id()),
+        "outcome": ledger_outcome(&credentials),
+    }))
+    .await;
+    credentials
+}
+
+/// Receipt for a settled part, as stored by the Agent A server
+/// (`payment_method` is the card's masked descriptor)
+pub fn payment_receipt(
+    part: &PaymentPart,
+    payment_method: &str,
+    currency: &str,
+    credentials: &PaymentCredentials,
+) -> Value {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    json!({
+        "instruction_id": part.instruction_id,
+        "payment_method": payment_method,
+        "amount": format_amount(part.amount_minor, currency),
+        "currency": currency,
+        "transaction_reference_id": part.transaction_reference,
+        "proof_id": credentials.proof_id(),
+        "timestamp": timestamp,
+        "settlement_amount": part.settlement.as_ref().map(|s| format_amount(s.amount_minor, &s.currency)),
+        "settlement_currency": part.settlement.as_ref().map(|s| &s.currency),
+        "fx_rate": part.settlement.as_ref().map(|s| s.fx_rate),
+    })
+}
+
+/// Refund `amount_minor` in `currency` of one part of the booking's
+/// payment, returning the Payment Agent's refund record (refund ID, status
+/// and proof)
+pub async fn refund_payment(
+    tool_client: &ToolClient,
+    booking: &BookingState,
+    part: &PaymentPart,
+    amount_minor: i64,
+    currency: &str,
+) -> Result<Value> {
+    let instruction_id = part
+        .instruction_id
+        .as_deref()
+        .ok_or_else(|| anyhow!("Payment has no purchase instruction to refund"))?;
+    let refund_args = json!({
+        "sessionId": booking.payment_session_id,
+        "consumerId": booking.consumer_id,
+        "instructionId": instruction_id,
+        "amount": format_amount(amount_minor, currency),
+        "currency": currency,
+        "clientReferenceId": format!("{}_refund", part.reference)
+    });
+
+    let refund = tool_client.call("refund-payment", refund_args.clone()).await.map(|result| result.data);
+
+    let record = refund.as_ref().ok();
+    record_ledger(tool_client, booking, json!({
+        "kind": "refund",
+        "reference": refund_args["clientReferenceId"],
+        "external_id": record.and_then(|r| r["refundId"].as_str()),
+        "amount": refund_args["amount"],
+        "currency": currency,
+        "proof_id": record.and_then(|r| r["proofId"].as_str().or_else(|| r["proof"]["id"].as_str())),
+        "outcome": ledger_outcome(&refund),
+    }))
+    .await;
+    refund
+}
+
+/// Record a payment event in the session's ledger. The ledger is for
+/// reconciliation only, so failing to record doesn't fail the payment step.
+async fn record_ledger(tool_client: &ToolClient, booking: &BookingState, mut entry: Value) {
+    entry["timestamp"] = json!(SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default());
+    if let Err(e) = tool_client.record_ledger_entry(&booking.session_id, &entry).await {
+        tracing::warn!(session_id = %booking.session_id, kind = %entry["kind"], error = %e, "could not record the payment in the ledger");
+    }
+}
+
+fn ledger_outcome<T>(result: &Result<T>) -> String {
+    match result {
+        Ok(_) => "success".to_string(),
+        Err(e) => format!("failed: {}", e),
+    }
+}
+
+/// A card enrolled with the Payment Agent, as shown to the traveler
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnrolledCard {
+    pub token_id: String,
+    /// Masked descriptor, e.g. "Visa •••• 4242"
+    pub description: String,
+    /// Currency the card settles in, if the Payment Agent reports it
+    pub settlement_currency: Option<String>,
+}
+
+/// Cards the booking's consumer has enrolled, from the session's
+/// `enrolledCards` (falling back to bare `enrolledTokenIds`)
+pub async fn enrolled_cards(tool_client: &ToolClient, booking: &BookingState) -> Result<Vec<EnrolledCard>> {
+    let session = tool_client.payment_session(&booking.payment_session_id).await?;
+    let data = &session["data"];
+
+    if let Some(cards) = data["enrolledCards"].as_array() {
+        return Ok(cards
+            .iter()
+            .filter_map(|card| {
+                let token_id = card["tokenId"].as_str()?.to_string();
+                let brand = card["brand"].as_str().unwrap_or("Card");
+                let description = match (card["maskedNumber"].as_str(), card["last4"].as_str()) {
+                    (Some(masked), _) => format!("{} {}", brand, masked),
+                    (None, Some(last4)) => format!("{} •••• {}", brand, last4),
+                    (None, None) => format!("{} ({})", brand, token_id),
+                };
+                let settlement_currency = card["settlementCurrency"]
+                    .as_str()
+                    .or_else(|| card["currency"].as_str())
+                    .map(str::to_uppercase);
+                Some(EnrolledCard { token_id, description, settlement_currency })
+            })
+            .collect());
+    }
+
+    Ok(data["enrolledTokenIds"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|id| id.as_str())
+        .map(|token_id| EnrolledCard {
+            token_id: token_id.to_string(),
+            description: format!("Card ({})", token_id),
+            settlement_currency: None,
+        })
+        .collect())
+}
+
+/// Add the settlement amount, currency and FX rate of a cross-currency part
+fn add_settlement_args(args: &mut Value, part: &PaymentPart) {
+    if let Some(settlement) = &part.settlement {
+        args["settlementAmount"] = json!(format_amount(settlement.amount_minor, &settlement.currency));
+        args["settlementCurrency"] = json!(settlement.currency);
+        args["fxRate"] = json!(settlement.fx_rate);
+    }
+}
+
+/// Instruction the Payment Agent already created under the client
+/// `reference`, if the booking's session lists one
+async fn find_instruction(tool_client: &ToolClient, booking: &BookingState, reference: &str) -> Option<String> {
+    let session = tool_client.payment_session(&booking.payment_session_id).await.ok()?;
+    session["data"]["instructions"]
+        .as_array()?
+        .iter()
+        .find(|instruction| instruction["clientReferenceId"].as_str() == Some(reference))
+        .and_then(|instruction| instruction["instructionId"].as_str())
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_amounts_use_the_currency_exponent() {
+        assert_eq!(format_amount(45_000, "USD"), "450.00");
+        assert_eq!(format_amount(45_000, "jpy"), "45000");
+        assert_eq!(format_amount(45_000, "KWD"), "45.000");
+        assert_eq!(display_amount(-5, "EUR"), "-0.05 EUR");
+        assert_eq!(parse_amount("450.5", "USD"), Some(45_050));
+        assert_eq!(parse_amount("45000", "JPY"), Some(45_000));
+        assert_eq!(parse_amount("1.25", "JPY"), None);
+    }
+
+    #[test]
+    fn test_parse_amount_rejects_signs() {
+        assert_eq!(parse_amount("1.", "USD"), Some(100));
+        assert_eq!(parse_amount(" 0.5\n", "USD"), Some(50));
+        assert_eq!(parse_amount("12000.", "JPY"), Some(12_000));
+        for invalid in ["-1.50", "-0.50", "+1", "-0", "", ".50", "1.2.3", "1,50", "1.505", "1e3"] {
+            assert_eq!(parse_amount(invalid, "USD"), None, "{:?}", invalid);
+        }
+        assert_eq!(parse_amount("92233720368547758.08", "USD"), None);
+    }
+
+    #[test]
+    fn test_fx_settlement_uses_both_exponents() {
+        // 450.00 USD at 150 yen per dollar, and back
+        let card = |currency: &str| EnrolledCard { token_id: "tok_1".into(), description: "Visa".into(), settlement_currency: Some(currency.into()) };
+        let rates = FxRates { rates: vec![("USD".into(), "JPY".into(), 150.0)] };
+        assert_eq!(rates.settlement("USD", 45_000, Some(&card("JPY"))).unwrap().amount_minor, 67_500);
+        assert_eq!(rates.settlement("JPY", 67_500, Some(&card("USD"))).unwrap().amount_minor, 45_000);
+    }
+
+    #[test]
+    fn test_parse_tool_data_shapes() {
+        let plain = json!({"success": true, "tokenId": "tok_1"});
+        let encoded = Value::String(plain.to_string());
+        let double_encoded = Value::String(encoded.to_string());
+        let mcp_text = json!({"content": [{"type": "text", "text": plain.to_string()}]});
+
+        for data in [&plain, &encoded, &double_encoded, &mcp_text] {
+            let response: EnrollCardResponse = parse_tool_data("enroll-card", data).unwrap();
+            assert!(response.is_success());
+            assert_eq!(response.token_id.as_deref(), Some("tok_1"));
+        }
+
+        assert!(parse_tool_data::<PurchaseInstructionResponse>("initiate-purchase-instruction", &json!({})).is_err());
+        assert!(parse_tool_data::<EnrollCardResponse>("enroll-card", &json!("tokenId=[REDACTED]")).is_err());
+    }
+}
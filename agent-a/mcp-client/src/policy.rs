@@ -0,0 +1,112 @@
+//! Spending limits Agent A enforces before charging a traveler
+//!
+//! `SPEND_LIMIT_PER_SESSION` caps what one client session pays in total and
+//! `SPEND_LIMIT_PER_CONSUMER` what one Payment Agent consumer pays within it
+//! (decimal amounts in the quote currency; unset means no cap). A payment
+//! that would go over a cap needs an explicit confirmation phrase.
+
+use serde_json::{json, Value};
+
+use crate::payment::{format_amount, parse_amount};
+
+/// Configured caps as decimal amounts, read in the currency of the payment
+/// they are checked against
+#[derive(Debug, Clone, Default)]
+pub struct SpendPolicy {
+    pub per_session: Option<String>,
+    pub per_consumer: Option<String>,
+}
+
+/// Outcome of checking a payment against the caps
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SpendDecision {
+    Within,
+    /// Paying would bring the total for `scope` ("session" or "consumer") to
+    /// `total`, over its `limit`
+    OverLimit { scope: &'static str, limit: i64, total: i64 },
+}
+
+impl SpendPolicy {
+    pub fn from_env() -> Self {
+        let limit = |var: &str| std::env::var(var).ok().filter(|v| !v.trim().is_empty());
+        Self {
+            per_session: limit("SPEND_LIMIT_PER_SESSION"),
+            per_consumer: limit("SPEND_LIMIT_PER_CONSUMER"),
+        }
+    }
+
+    /// Check paying `amount_minor` in `currency` on top of what the session
+    /// and the consumer already paid. A cap that isn't a valid amount in
+    /// `currency` is ignored.
+    pub fn check(&self, amount_minor: i64, currency: &str, session_spent: i64, consumer_spent: i64) -> SpendDecision {
+        let caps = [
+            ("session", &self.per_session, session_spent),
+            ("consumer", &self.per_consumer, consumer_spent),
+        ];
+        for (scope, limit, spent) in caps {
+            if let Some(limit) = limit.as_deref().and_then(|limit| parse_amount(limit, currency)) {
+                let total = spent + amount_minor;
+                if total > limit {
+                    return SpendDecision::OverLimit { scope, limit, total };
+                }
+            }
+        }
+        SpendDecision::Within
+    }
+}
+
+impl SpendDecision {
+    /// Phrase the traveler must type to pay over the limit
+    pub fn confirmation_phrase(amount_minor: i64, currency: &str) -> String {
+        format!("APPROVE {}", format_amount(amount_minor, currency))
+    }
+
+    /// Trace recording the decision (and, over the limit, whether the
+    /// traveler confirmed) in the session's traces
+    pub fn trace(&self, session_id: &str, amount_minor: i64, confirmed: bool) -> Value {
+        let (decision, scope, limit, total) = match self {
+            SpendDecision::Within => ("within_limit", None, None, None),
+            SpendDecision::OverLimit { scope, limit, total } => (
+                if confirmed { "over_limit_confirmed" } else { "over_limit_declined" },
+                Some(*scope),
+                Some(*limit),
+                Some(*total),
+            ),
+        };
+        json!({
+            "kind": "spend_policy",
+            "workflow_stage": "policy",
+            "session_id": session_id,
+            "amount_minor": amount_minor,
+            "decision": decision,
+            "scope": scope,
+            "limit_minor": limit,
+            "total_minor": total,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spend_policy_caps() {
+        let policy = SpendPolicy { per_session: Some("1000".to_string()), per_consumer: Some("500".to_string()) };
+        assert_eq!(policy.check(40_000, "USD", 0, 0), SpendDecision::Within);
+        assert_eq!(
+            policy.check(40_000, "USD", 20_000, 20_000),
+            SpendDecision::OverLimit { scope: "consumer", limit: 50_000, total: 60_000 }
+        );
+        assert_eq!(
+            policy.check(40_000, "USD", 70_000, 0),
+            SpendDecision::OverLimit { scope: "session", limit: 100_000, total: 110_000 }
+        );
+        // Yen have no minor unit
+        assert_eq!(
+            policy.check(400, "JPY", 0, 200),
+            SpendDecision::OverLimit { scope: "consumer", limit: 500, total: 600 }
+        );
+        assert_eq!(SpendPolicy::default().check(i64::MAX / 2, "USD", 0, 0), SpendDecision::Within);
+    }
+}
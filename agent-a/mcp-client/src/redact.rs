@@ -0,0 +1,140 @@
+//! PCI-safe logging for Agent A
+//!
+//! Masks traveler emails, payment tokens, instruction and consumer IDs and
+//! card credentials in what the client prints about tool calls. On by default
+//! in release builds (`PCI_SAFE_LOGS=false` turns it off) and off in debug
+//! builds unless `PCI_SAFE_LOGS=true`.
+
+use serde_json::Value;
+use std::borrow::Cow;
+use std::fmt;
+use std::sync::LazyLock;
+
+static ENABLED: LazyLock<bool> = LazyLock::new(|| match std::env::var("PCI_SAFE_LOGS") {
+    Ok(v) => v.to_lowercase() != "false",
+    Err(_) => !cfg!(debug_assertions),
+});
+
+/// Keys whose values are masked wherever they appear (case-insensitive,
+/// ignoring `_`)
+const SENSITIVE_KEYS: &[&str] = &[
+    "email",
+    "passengeremail",
+    "consumerid",
+    "sessionid",
+    "tokenid",
+    "instructionid",
+    "transactionreferenceid",
+    "enrollmentreferenceid",
+    "clientreferenceid",
+    "cardnumber",
+    "pan",
+    "cvv",
+    "cvc",
+    "expiry",
+    "expirydate",
+    "cryptogram",
+    "credentials",
+    "otp",
+];
+
+/// Whether log output is sanitized
+pub fn enabled() -> bool {
+    *ENABLED
+}
+
+/// JSON value that prints with sensitive fields masked when sanitizing
+pub struct Redacted<'a>(pub &'a Value);
+
+impl fmt::Display for Redacted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if enabled() {
+            write!(f, "{}", value(self.0))
+        } else {
+            write!(f, "{}", self.0)
+        }
+    }
+}
+
+/// Free text with email addresses masked when sanitizing
+pub fn text(s: &str) -> Cow<'_, str> {
+    if enabled() && s.contains('@') {
+        Cow::Owned(mask_emails(s))
+    } else {
+        Cow::Borrowed(s)
+    }
+}
+
+/// Copy of `v` with sensitive fields masked and emails in other strings hidden
+fn value(v: &Value) -> Value {
+    match v {
+        Value::Object(map) => map
+            .iter()
+            .map(|(key, v)| {
+                let masked = if is_sensitive(key) { mask_value(v) } else { value(v) };
+                (key.clone(), masked)
+            })
+            .collect(),
+        Value::Array(items) => items.iter().map(value).collect(),
+        Value::String(s) if s.contains('@') => Value::String(mask_emails(s)),
+        other => other.clone(),
+    }
+}
+
+fn is_sensitive(key: &str) -> bool {
+    let normalized: String = key.chars().filter(|c| *c != '_').flat_map(char::to_lowercase).collect();
+    SENSITIVE_KEYS.contains(&normalized.as_str())
+}
+
+/// Strings keep their last 4 characters, e.g. `****a1b2`; anything
+/// structured is replaced outright
+fn mask_value(v: &Value) -> Value {
+    match v {
+        Value::Null => Value::Null,
+        Value::String(s) if s.contains('@') => Value::String(mask_emails(s)),
+        Value::String(s) => {
+            let chars: Vec<char> = s.chars().collect();
+            let tail: String = if chars.len() > 8 { chars[chars.len() - 4..].iter().collect() } else { String::new() };
+            Value::String(format!("****{}", tail))
+        }
+        _ => Value::String("****".to_string()),
+    }
+}
+
+/// `jane.doe@example.com` becomes `j***@example.com`
+fn mask_emails(s: &str) -> String {
+    s.split(' ')
+        .map(|word| match word.split_once('@') {
+            Some((local, domain)) if !local.is_empty() && domain.contains('.') => {
+                let first: String = local.chars().take(1).collect();
+                format!("{}***@{}", first, domain)
+            }
+            _ => word.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_value_masks_payment_fields() {
+        let args = json!({
+            "sessionId": "sess_cons_0123456789abcdef",
+            "tokenId": "tok_abc",
+            "amount": "450.00",
+            "passenger_email": "jane.doe@example.com",
+            "nested": {"card_number": "4111111111111111", "note": "mail jane@example.com"}
+        });
+        let masked = value(&args);
+        assert_eq!(masked["sessionId"], "****cdef");
+        assert_eq!(masked["tokenId"], "****");
+        assert_eq!(masked["amount"], "450.00");
+        assert_eq!(masked["passenger_email"], "j***@example.com");
+        assert_eq!(masked["nested"]["card_number"], "****1111");
+        assert_eq!(masked["nested"]["note"], "mail j***@example.com");
+    }
+}
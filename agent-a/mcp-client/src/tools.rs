@@ -0,0 +1,465 @@
+//! Tool client for Agent A
+//!
+//! Owns the HTTP client and the routing table that maps each tool to the
+//! server hosting it (Agent A server, Agent B MCP server, or Payment Agent).
+//! Every server answers `POST /tools/<name>` with a `{ success, data, error }`
+//! envelope, which is unwrapped here once.
+//!
+//! Each server gets its own HTTP client so that mutual TLS can be configured
+//! per peer (`AGENT_A_TLS_*`, `AGENT_B_TLS_*`, `PAYMENT_AGENT_TLS_*`, falling
+//! back to `TLS_*`). When `AGENT_HMAC_SECRET` is set, tool calls carry an
+//! HMAC signature so receiving agents can reject spoofed orchestrators.
+//!
+//! Every call is logged as a `tracing` event with `session_id`, `step` and
+//! `tool_name`, and carries the session ID to the receiving agent in
+//! `X-ZP-Session-Id`.
+
+use anyhow::{Result, anyhow};
+use serde_json::{json, Value};
+use std::fmt;
+use std::sync::RwLock;
+use zk_protocol::{signing::SigningKey, telemetry::SESSION_HEADER, tls::MtlsConfig};
+
+use crate::{booking::BookingStep, claude::SessionUsage, redact::{self, Redacted}};
+
+/// Tools hosted by the Payment Agent
+const PAYMENT_TOOLS: &[&str] = &[
+    "enroll-card",
+    "unenroll-card",
+    "initiate-purchase-instruction",
+    "get-payment-status",
+    "complete-payment-challenge",
+    "retrieve-payment-credentials",
+    "confirm-transaction",
+    "refund-payment",
+];
+
+/// Tools hosted by the Agent B MCP server
+const AGENT_B_TOOLS: &[&str] = &[
+    "get-ticket-price",
+    "get-ticket-price-v2",
+    "book-flight",
+    "list-destinations",
+    "list-ancillaries",
+    "get-booking",
+    "cancel-booking",
+];
+
+/// Server a tool call is routed to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolServer {
+    AgentA,
+    AgentB,
+    PaymentAgent,
+}
+
+impl ToolServer {
+    /// Prefix of the per-peer TLS environment variables
+    fn env_prefix(self) -> &'static str {
+        match self {
+            ToolServer::AgentA => "AGENT_A",
+            ToolServer::AgentB => "AGENT_B",
+            ToolServer::PaymentAgent => "PAYMENT_AGENT",
+        }
+    }
+
+    /// Route a tool name to its server; anything unknown goes to Agent A
+    pub fn for_tool(tool_name: &str) -> Self {
+        if PAYMENT_TOOLS.contains(&tool_name) {
+            ToolServer::PaymentAgent
+        } else if AGENT_B_TOOLS.contains(&tool_name) {
+            ToolServer::AgentB
+        } else {
+            ToolServer::AgentA
+        }
+    }
+}
+
+/// Successful result of a tool call
+#[derive(Debug, Clone)]
+pub struct ToolOutcome {
+    /// `data` field of the server's response envelope
+    pub data: Value,
+}
+
+impl fmt::Display for ToolOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Redacted(&self.data))
+    }
+}
+
+/// Why a payment can't start, from the Payment Agent preflight check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PaymentAgentDiagnostic {
+    /// `PAYMENT_AGENT_URL` is not set
+    NotConfigured,
+    /// Connection failed or timed out
+    Unreachable(String),
+    /// 401/403: Agent A's credentials or certificate were rejected
+    Unauthorized,
+    /// The Payment Agent reports planned downtime, with its message
+    Maintenance(Option<String>),
+    /// Any other unhealthy answer, by HTTP status
+    Unhealthy(u16),
+}
+
+impl PaymentAgentDiagnostic {
+    /// Stable identifier recorded in the session traces
+    pub fn code(&self) -> &'static str {
+        match self {
+            PaymentAgentDiagnostic::NotConfigured => "not_configured",
+            PaymentAgentDiagnostic::Unreachable(_) => "unreachable",
+            PaymentAgentDiagnostic::Unauthorized => "unauthorized",
+            PaymentAgentDiagnostic::Maintenance(_) => "maintenance",
+            PaymentAgentDiagnostic::Unhealthy(_) => "unhealthy",
+        }
+    }
+
+    /// What to tell the traveler
+    pub fn user_message(&self) -> String {
+        match self {
+            PaymentAgentDiagnostic::NotConfigured => {
+                "Payments aren't set up for this agent, so I can't take your payment.".to_string()
+            }
+            PaymentAgentDiagnostic::Unreachable(_) => {
+                "I can't reach the payment service right now. Please try again in a few minutes.".to_string()
+            }
+            PaymentAgentDiagnostic::Unauthorized => {
+                "The payment service rejected my credentials. Please contact support; you have not been charged.".to_string()
+            }
+            PaymentAgentDiagnostic::Maintenance(message) => format!(
+                "The payment service is down for maintenance{}. Please try again later.",
+                message.as_ref().map(|m| format!(" ({})", m)).unwrap_or_default()
+            ),
+            PaymentAgentDiagnostic::Unhealthy(_) => {
+                "The payment service is having problems. Please try again later; you have not been charged.".to_string()
+            }
+        }
+    }
+}
+
+impl fmt::Display for PaymentAgentDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PaymentAgentDiagnostic::NotConfigured => write!(f, "PAYMENT_AGENT_URL not configured"),
+            PaymentAgentDiagnostic::Unreachable(e) => write!(f, "Payment Agent unreachable: {}", e),
+            PaymentAgentDiagnostic::Unauthorized => write!(f, "Payment Agent rejected Agent A's credentials"),
+            PaymentAgentDiagnostic::Maintenance(message) => {
+                write!(f, "Payment Agent in maintenance: {}", message.as_deref().unwrap_or("no details"))
+            }
+            PaymentAgentDiagnostic::Unhealthy(status) => write!(f, "Payment Agent unhealthy (HTTP {})", status),
+        }
+    }
+}
+
+/// Base URL and HTTP client for one server
+struct Peer {
+    url: String,
+    http: reqwest::Client,
+}
+
+/// Default limit on a Payment Agent call (`PAYMENT_AGENT_TIMEOUT_SECS`);
+/// payment initiation retries past it instead of waiting forever
+const PAYMENT_AGENT_TIMEOUT_SECS: u64 = 30;
+
+impl Peer {
+    fn new(server: ToolServer, url: String) -> Result<Self> {
+        let mut builder = reqwest::Client::builder();
+        if server == ToolServer::PaymentAgent {
+            let secs = std::env::var("PAYMENT_AGENT_TIMEOUT_SECS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(PAYMENT_AGENT_TIMEOUT_SECS);
+            builder = builder.timeout(std::time::Duration::from_secs(secs));
+        }
+        let builder = match MtlsConfig::for_peer(server.env_prefix()) {
+            Some(tls) => builder.use_preconfigured_tls(tls.client_config()?),
+            None => builder,
+        };
+        Ok(Self { url, http: builder.build()? })
+    }
+}
+
+/// HTTP client for Agent A, Agent B and Payment Agent tools
+pub struct ToolClient {
+    agent_a: Peer,
+    agent_b: Peer,
+    payment_agent: Option<Peer>,
+    signing_key: Option<SigningKey>,
+    /// Payment Agent session of the booking in progress, for log correlation
+    session_id: RwLock<Option<String>>,
+}
+
+impl ToolClient {
+    pub fn new(
+        agent_a_url: String,
+        agent_b_url: String,
+        payment_agent_url: Option<String>,
+    ) -> Result<Self> {
+        Ok(Self {
+            agent_a: Peer::new(ToolServer::AgentA, agent_a_url)?,
+            agent_b: Peer::new(ToolServer::AgentB, agent_b_url)?,
+            payment_agent: payment_agent_url
+                .map(|url| Peer::new(ToolServer::PaymentAgent, url))
+                .transpose()?,
+            signing_key: SigningKey::from_env(),
+            session_id: RwLock::new(None),
+        })
+    }
+
+    /// Tag the following tool calls with the session of the booking in progress
+    pub fn set_session(&self, session_id: &str) {
+        *self.session_id.write().unwrap() = Some(session_id.to_string());
+    }
+
+    fn peer(&self, server: ToolServer) -> Option<&Peer> {
+        match server {
+            ToolServer::AgentA => Some(&self.agent_a),
+            ToolServer::AgentB => Some(&self.agent_b),
+            ToolServer::PaymentAgent => self.payment_agent.as_ref(),
+        }
+    }
+
+    /// Fetch tool definitions from a server
+    async fn fetch_tool_definitions(&self, peer: &Peer) -> Result<Value> {
+        let url = format!("{}/tools", peer.url);
+        let response = peer.http.get(&url).send().await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(anyhow!("Failed to fetch tools: {}", error_text));
+        }
+
+        let tools: Value = response.json().await?;
+        Ok(tools)
+    }
+
+    /// Fetch and merge tool definitions from Agent A Server, Agent B MCP Server
+    /// and the Payment Agent (if configured)
+    pub async fn fetch_all_tools(&self) -> Result<Value> {
+        // Fetch Agent A tools
+        let mut all_tools: Vec<Value> = Vec::new();
+
+        let agent_a_response = self.fetch_tool_definitions(&self.agent_a).await;
+        if let Ok(resp) = agent_a_response {
+            if let Some(tools) = resp.get("tools").and_then(|t| t.as_array()) {
+                all_tools.extend(tools.clone());
+                println!("  [Agent A Server] Loaded {} tools", tools.len());
+            }
+        }
+
+        // Fetch Agent B MCP Server tools
+        match self.fetch_tool_definitions(&self.agent_b).await {
+            Ok(response) => {
+                if let Some(tools) = response.get("tools").and_then(|t| t.as_array()) {
+                    all_tools.extend(tools.clone());
+                    println!("  [Agent B MCP Server] Loaded {} pricing/booking tools", tools.len());
+                }
+            }
+            Err(e) => {
+                println!("  ⚠️  Agent B MCP Server unavailable: {}", redact::text(&e.to_string()));
+                println!("     (Continuing with Agent A tools only)");
+            }
+        }
+
+        // Fetch Payment Agent tools if available
+        if let Some(payment_agent) = &self.payment_agent {
+            match self.fetch_tool_definitions(payment_agent).await {
+                Ok(payment_response) => {
+                    // Payment Agent returns tools in data.tools
+                    let payment_tools = payment_response
+                        .get("data")
+                        .and_then(|d| d.get("tools"))
+                        .or_else(|| payment_response.get("tools")) // fallback to direct "tools"
+                        .and_then(|t| t.as_array());
+
+                    if let Some(tools) = payment_tools {
+                        all_tools.extend(tools.clone());
+                        println!("  [Payment Agent] Loaded {} payment tools", tools.len());
+                    }
+                }
+                Err(e) => {
+                    println!("  ⚠️  Payment Agent unavailable: {}", redact::text(&e.to_string()));
+                    println!("     (Continuing without payment capabilities)");
+                }
+            }
+        }
+
+        Ok(json!({ "tools": all_tools }))
+    }
+
+    /// Call a tool on whichever server hosts it and unwrap the response envelope
+    pub async fn call(&self, tool_name: &str, arguments: Value) -> Result<ToolOutcome> {
+        let session_id = arguments["sessionId"]
+            .as_str()
+            .map(str::to_string)
+            .or_else(|| self.session_id.read().unwrap().clone())
+            .unwrap_or_default();
+        let step = BookingStep::for_tool(tool_name).map_or("conversation", BookingStep::name);
+        tracing::info!(session_id, step, tool_name, args = %Redacted(&arguments), "invoking tool");
+
+        let outcome = self.send(tool_name, &session_id, arguments).await;
+        match &outcome {
+            Ok(result) => tracing::info!(session_id, step, tool_name, result = %result, "tool succeeded"),
+            Err(e) => tracing::warn!(session_id, step, tool_name, error = %e, "tool failed"),
+        }
+        outcome
+    }
+
+    async fn send(&self, tool_name: &str, session_id: &str, arguments: Value) -> Result<ToolOutcome> {
+        let server = ToolServer::for_tool(tool_name);
+        let peer = self.peer(server).ok_or_else(|| {
+            anyhow!(
+                "Tool '{}' requires Payment Agent, but PAYMENT_AGENT_URL not configured",
+                tool_name
+            )
+        })?;
+
+        let url = format!("{}/tools/{}", peer.url, tool_name);
+
+        let body = serde_json::to_vec(&arguments)?;
+        let mut request = peer.http
+            .post(&url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json");
+        if !session_id.is_empty() {
+            request = request.header(SESSION_HEADER, session_id);
+        }
+
+        let response = self.signed(request, body).send().await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(anyhow!("Server error: {}", error_text));
+        }
+
+        let result: Value = response.json().await?;
+
+        match result.get("error") {
+            Some(error) if !error.is_null() => Err(anyhow!("Tool error: {}", error)),
+            _ => result
+                .get("data")
+                .cloned()
+                .map(|data| ToolOutcome { data })
+                .ok_or_else(|| anyhow!("Invalid server response")),
+        }
+    }
+
+    /// Attach `body` to a JSON POST, with the HMAC headers when
+    /// `AGENT_HMAC_SECRET` is set
+    fn signed(&self, mut request: reqwest::RequestBuilder, body: Vec<u8>) -> reqwest::RequestBuilder {
+        if let Some(key) = &self.signing_key {
+            for (name, value) in key.headers(&body) {
+                request = request.header(name, value);
+            }
+        }
+        request.body(body)
+    }
+
+    /// Check `GET /health` on the Payment Agent before a payment starts
+    pub async fn payment_agent_health(&self) -> Result<(), PaymentAgentDiagnostic> {
+        let payment_agent = self.payment_agent.as_ref().ok_or(PaymentAgentDiagnostic::NotConfigured)?;
+        let health_url = format!("{}/health", payment_agent.url);
+        let response = payment_agent
+            .http
+            .get(&health_url)
+            .send()
+            .await
+            .map_err(|e| PaymentAgentDiagnostic::Unreachable(e.to_string()))?;
+
+        let status = response.status();
+        let body: Value = response.json().await.unwrap_or(Value::Null);
+        let in_maintenance = body["status"].as_str().is_some_and(|s| s.eq_ignore_ascii_case("maintenance"));
+        match status.as_u16() {
+            401 | 403 => Err(PaymentAgentDiagnostic::Unauthorized),
+            _ if in_maintenance || status == reqwest::StatusCode::SERVICE_UNAVAILABLE => Err(
+                PaymentAgentDiagnostic::Maintenance(body["message"].as_str().map(str::to_string)),
+            ),
+            _ if status.is_success() => Ok(()),
+            code => Err(PaymentAgentDiagnostic::Unhealthy(code)),
+        }
+    }
+
+    /// Latest event the Payment Agent pushed to Agent A's webhook for
+    /// `instruction_id`, waiting up to `wait` for one to arrive. The request
+    /// is signed over the instruction ID, so it can't be replayed for another.
+    pub async fn payment_event(&self, instruction_id: &str, wait: std::time::Duration) -> Result<Option<Value>> {
+        let url = format!("{}/webhooks/payment/{}", self.agent_a.url, instruction_id);
+        let mut request = self.agent_a.http.get(&url).query(&[("wait_secs", wait.as_secs())]);
+        if let Some(key) = &self.signing_key {
+            for (name, value) in key.headers(instruction_id.as_bytes()) {
+                request = request.header(name, value);
+            }
+        }
+        let response = request.send().await?.error_for_status()?;
+        if response.status() == reqwest::StatusCode::NO_CONTENT {
+            return Ok(None);
+        }
+        let envelope: Value = response.json().await?;
+        Ok(envelope.get("data").cloned())
+    }
+
+    /// POST `body` to `/session/{id}/{path}` on the Agent A server, signed
+    /// like tool calls
+    async fn post_session(&self, session_id: &str, path: &str, body: &impl serde::Serialize) -> Result<()> {
+        let url = format!("{}/session/{}/{}", self.agent_a.url, session_id, path);
+        let request = self.agent_a.http
+            .post(&url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json");
+        let response = self.signed(request, serde_json::to_vec(body)?).send().await?;
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(anyhow!("Server error: {}", error_text));
+        }
+        Ok(())
+    }
+
+    /// Store a payment receipt with the Agent A server
+    /// (`POST /session/{id}/payment-receipt`)
+    pub async fn store_payment_receipt(&self, session_id: &str, receipt: &Value) -> Result<()> {
+        self.post_session(session_id, "payment-receipt", receipt).await
+    }
+
+    /// Append a conversation turn to the session transcript on the Agent A
+    /// server (`POST /session/{id}/messages`)
+    pub async fn store_message(&self, session_id: &str, role: &str, content: &str) -> Result<()> {
+        self.post_session(session_id, "messages", &json!({ "role": role, "content": content })).await
+    }
+
+    /// Store a proof-bearing trace with the Agent A server, which serves it
+    /// as an MCP resource (`POST /session/{id}/proofs`)
+    pub async fn store_proof(&self, session_id: &str, trace: &Value) -> Result<()> {
+        self.post_session(session_id, "proofs", trace).await
+    }
+
+    /// Record a payment event in the session's ledger on the Agent A server
+    /// (`POST /session/{id}/ledger`)
+    pub async fn record_ledger_entry(&self, session_id: &str, entry: &Value) -> Result<()> {
+        self.post_session(session_id, "ledger", entry).await
+    }
+
+    /// Add Claude usage to the session's total on the Agent A server
+    /// (`POST /session/{id}/usage`)
+    pub async fn record_usage(&self, session_id: &str, usage: &SessionUsage) -> Result<()> {
+        self.post_session(session_id, "usage", usage).await
+    }
+
+    /// Fetch the Payment Agent's view of a session (enrolled tokens etc.); a
+    /// session it doesn't know yet comes back empty
+    pub async fn payment_session(&self, session_id: &str) -> Result<Value> {
+        let payment_agent = self
+            .payment_agent
+            .as_ref()
+            .ok_or_else(|| anyhow!("PAYMENT_AGENT_URL not configured"))?;
+        let session_url = format!("{}/session/{}", payment_agent.url, session_id);
+        let response = payment_agent.http.get(&session_url).send().await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(json!({ "data": {} }));
+        }
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(anyhow!("Payment Agent error: {}", error_text));
+        }
+        let session: Value = response.json().await?;
+        Ok(session)
+    }
+}
@@ -0,0 +1,294 @@
+//! End-to-end booking flow against stubbed Claude, Agent A, Agent B and
+//! Payment Agent servers
+//!
+//! The Agent A stub stands in for the attester too: the client reaches
+//! proving only through Agent A's `prove_ticket_price` tool.
+
+mod support;
+
+use axum::http::StatusCode;
+use serde_json::{json, Value};
+use support::{run_client, run_client_with_env, stub, CallLog, Services};
+
+/// What the Payment Agent stub does once a purchase instruction exists
+#[derive(Clone, Copy)]
+enum Payment {
+    Approved,
+    Declined,
+    /// Settles, but the proof backing the credentials has expired
+    ProofExpired,
+    /// Settles, but Agent B fails to book the flight
+    BookingFails,
+}
+
+const BOOKING_REQUEST: &str = "Book an economy flight from NYC to London";
+const CANCEL_REQUEST: &str = "Cancel booking bk_1";
+
+/// The traveler's answers up to and including the split prompt
+const CHECKOUT: &[&str] = &[
+    BOOKING_REQUEST,
+    "y",               // proceed with this booking
+    "Ada Lovelace",    // full name
+    "ada@example.com", // email
+    "1",               // Visa
+    "y",               // ready to add the card
+    "y",               // proceed with payment
+    "n",               // don't split
+];
+
+fn ok(data: Value) -> (StatusCode, Value) {
+    (StatusCode::OK, json!({ "success": true, "data": data, "error": null }))
+}
+
+fn tool_error(error: &str) -> (StatusCode, Value) {
+    (StatusCode::OK, json!({ "success": false, "data": null, "error": error }))
+}
+
+async fn services(log: &CallLog, payment: Payment) -> Services {
+    let claude = stub("claude", log, |_, body| {
+        let cancelling = body["messages"].as_array().and_then(|m| m.last()).is_some_and(|m| m.to_string().contains(CANCEL_REQUEST));
+        let tool_calls = if cancelling {
+            json!({ "tool_calls": [{ "name": "cancel-booking", "arguments": { "booking_id": "bk_1" } }] })
+        } else {
+            json!({
+                "tool_calls": [{
+                    "name": "get-ticket-price",
+                    "arguments": { "from": "NYC", "to": "London", "cabin": "economy" }
+                }]
+            })
+        };
+        (
+            StatusCode::OK,
+            json!({
+                "content": [{ "type": "text", "text": tool_calls.to_string() }],
+                "usage": { "input_tokens": 100, "output_tokens": 20 }
+            }),
+        )
+    })
+    .await;
+
+    let agent_a = stub("agent_a", log, |path, _| match path {
+        "/tools" => (StatusCode::OK, json!({ "tools": [] })),
+        "/tools/prove_ticket_price" => ok(json!({
+            "program_id": "pricing",
+            "vk_hash": "0xvk",
+            "amount_minor": 45000,
+            "fare_table_digest": "0xfares",
+            "proof": "0xproof",
+            "public_values": "0xpublic"
+        })),
+        // Payment receipts and ledger entries
+        _ => ok(Value::Null),
+    })
+    .await;
+
+    let agent_b = stub("agent_b", log, move |path, _| match path {
+        "/tools" => (StatusCode::OK, json!({ "tools": [{ "name": "get-ticket-price" }] })),
+        "/tools/get-ticket-price" => ok(json!({
+            "price": 450.0,
+            "amount": { "amount_minor": 45000, "currency": "USD" },
+            "travel_date": "2026-11-20"
+        })),
+        "/tools/list-ancillaries" => ok(json!([])),
+        "/tools/book-flight" => match payment {
+            Payment::BookingFails => tool_error("seat inventory unavailable"),
+            _ => ok(json!({ "booking_id": "bk_1", "confirmation_code": "ZP7K2Q" })),
+        },
+        "/tools/cancel-booking" => ok(json!({
+            "booking": { "response": { "booking_id": "bk_1" } },
+            "refund": { "refund": { "amount_minor": 45000, "currency": "USD" }, "rule": "full_refund" }
+        })),
+        _ => (StatusCode::NOT_FOUND, json!({ "error": "not found" })),
+    })
+    .await;
+
+    let payment_agent = stub("payment_agent", log, move |path, _| match path {
+        "/tools" => (StatusCode::OK, json!({ "data": { "tools": [] } })),
+        "/health" => (StatusCode::OK, json!({ "status": "ok" })),
+        "/tools/enroll-card" => ok(json!({ "success": true, "tokenId": "tok_visa", "proof": { "id": "prf_enroll" } })),
+        "/tools/initiate-purchase-instruction" => ok(json!({ "instructionId": "ins_1" })),
+        "/tools/get-payment-status" => match payment {
+            Payment::Declined => ok(json!({ "status": "DECLINED", "reason": "insufficient funds" })),
+            Payment::Approved | Payment::ProofExpired | Payment::BookingFails => ok(json!({ "status": "CONFIRMED" })),
+        },
+        "/tools/retrieve-payment-credentials" => match payment {
+            Payment::ProofExpired => tool_error("proof expired"),
+            _ => ok(json!({ "proofId": "prf_credentials", "proof": { "id": "prf_credentials" } })),
+        },
+        "/tools/refund-payment" => ok(json!({ "refundId": "rf_1", "status": "COMPLETED", "proof": { "id": "prf_refund" } })),
+        // No cards enrolled yet
+        path if path.starts_with("/session/") => (StatusCode::OK, json!({ "data": { "enrolledCards": [] } })),
+        _ => (StatusCode::NOT_FOUND, json!({ "error": "not found" })),
+    })
+    .await;
+
+    Services { claude, agent_a, agent_b, payment_agent }
+}
+
+fn ledger_outcomes(log: &CallLog) -> Vec<(String, String)> {
+    log.bodies("agent_a", "/ledger")
+        .iter()
+        .map(|entry| (entry["kind"].as_str().unwrap().to_string(), entry["outcome"].as_str().unwrap().to_string()))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_booking_happy_path() {
+    let log = CallLog::default();
+    let services = services(&log, Payment::Approved).await;
+    let session = run_client("happy", &services, &[CHECKOUT, &["exit"]].concat()).await;
+
+    assert_eq!(
+        log.tool_calls(),
+        [
+            "get-ticket-price",
+            "list-ancillaries",
+            "enroll-card",
+            "get-ticket-price",
+            "initiate-purchase-instruction",
+            "get-payment-status",
+            "retrieve-payment-credentials",
+            "book-flight",
+            "prove_ticket_price",
+        ]
+    );
+    assert!(session.stdout.contains("Confirmation code: ZP7K2Q"));
+
+    let instruction = &log.bodies("payment_agent", "/initiate-purchase-instruction")[0];
+    assert_eq!(instruction["tokenId"], "tok_visa");
+    assert_eq!(instruction["amount"], "450.00");
+    let booking = &log.bodies("agent_b", "/book-flight")[0];
+    assert_eq!(booking["passenger_email"], "ada@example.com");
+    // Agent A and Agent B see a random booking session; the Payment Agent one
+    // stays per consumer so saved cards carry over
+    let session_id = booking["session_id"].as_str().unwrap();
+    assert_ne!(booking["session_id"], instruction["sessionId"]);
+    assert!(instruction["sessionId"].as_str().unwrap().starts_with("sess_cons_"));
+    assert!(log.calls().iter().any(|call| call.path == format!("/session/{}/payment-receipt", session_id)));
+    assert_eq!(booking["proof_ids"], json!(["prf_credentials"]));
+
+    // Proofs: the receipt and ledger carry the credentials proof, the traces every proof
+    let receipt = &log.bodies("agent_a", "/payment-receipt")[0];
+    assert_eq!(receipt["proof_id"], "prf_credentials");
+    assert_eq!(
+        ledger_outcomes(&log),
+        [
+            ("enrollment".to_string(), "success".to_string()),
+            ("instruction".to_string(), "success".to_string()),
+            ("credentials".to_string(), "success".to_string()),
+        ]
+    );
+    assert_eq!(
+        session.trace_kinds(),
+        ["card_enrollment", "spend_policy", "payment_credentials", "sp1_pricing_proof"]
+    );
+    assert_eq!(session.trace("card_enrollment").unwrap()["proof"]["id"], "prf_enroll");
+    assert_eq!(session.trace("payment_credentials").unwrap()["proof"]["id"], "prf_credentials");
+    let pricing_proof = session.trace("sp1_pricing_proof").unwrap();
+    assert_eq!(pricing_proof["confirmation_code"], "ZP7K2Q");
+    assert_eq!(pricing_proof["proof"], "0xproof");
+
+    // Claude usage is reported for the conversation and for the booking it started
+    let usage = log.bodies("agent_a", "/usage");
+    assert_eq!(usage.len(), 2);
+    assert!(usage.iter().all(|u| u["calls"] == 1 && u["input_tokens"] == 100 && u["output_tokens"] == 20));
+
+    // Session records are signed like tool calls
+    let unsigned: Vec<String> = log
+        .calls()
+        .into_iter()
+        .filter(|call| call.service == "agent_a" && call.path.starts_with("/session/") && !call.signed)
+        .map(|call| call.path)
+        .collect();
+    assert!(unsigned.is_empty(), "unsigned: {:?}", unsigned);
+
+    // Every proof-bearing trace is also stored with Agent A for MCP hosts
+    let stored: Vec<Value> = log.bodies("agent_a", "/proofs").iter().map(|t| t["kind"].clone()).collect();
+    assert_eq!(stored, ["card_enrollment", "payment_credentials", "sp1_pricing_proof"]);
+}
+
+#[tokio::test]
+async fn test_declined_payment_does_not_book() {
+    let log = CallLog::default();
+    let services = services(&log, Payment::Declined).await;
+    let session = run_client("declined", &services, &[CHECKOUT, &["exit"]].concat()).await;
+
+    let tool_calls = log.tool_calls();
+    assert_eq!(tool_calls.last().map(String::as_str), Some("get-payment-status"));
+    assert!(!tool_calls.iter().any(|tool| tool == "retrieve-payment-credentials" || tool == "book-flight"));
+    assert!(session.stdout.contains("Your payment was declined (insufficient funds)"));
+
+    assert!(log.bodies("agent_a", "/payment-receipt").is_empty());
+    assert_eq!(session.trace_kinds(), ["card_enrollment", "spend_policy"]);
+}
+
+#[tokio::test]
+async fn test_expired_credentials_proof_does_not_book() {
+    let log = CallLog::default();
+    let services = services(&log, Payment::ProofExpired).await;
+    // Decline the offer to retry the payment
+    let session = run_client("proof-expired", &services, &[CHECKOUT, &["n", "exit"]].concat()).await;
+
+    let tool_calls = log.tool_calls();
+    assert_eq!(tool_calls.last().map(String::as_str), Some("retrieve-payment-credentials"));
+    assert!(!tool_calls.iter().any(|tool| tool == "book-flight" || tool == "refund-payment"));
+    assert!(session.stdout.contains("proof expired"));
+
+    let (kind, outcome) = ledger_outcomes(&log).pop().unwrap();
+    assert_eq!(kind, "credentials");
+    assert!(outcome.starts_with("failed:") && outcome.contains("proof expired"), "{}", outcome);
+    assert!(session.trace("payment_credentials").is_none());
+}
+
+#[tokio::test]
+async fn test_failed_booking_refunds_the_payment() {
+    let log = CallLog::default();
+    let services = services(&log, Payment::BookingFails).await;
+    let session = run_client("booking-fails", &services, &[CHECKOUT, &["exit"]].concat()).await;
+
+    let tool_calls = log.tool_calls();
+    assert_eq!(&tool_calls[tool_calls.len() - 2..], ["book-flight", "refund-payment"]);
+    let refund = &log.bodies("payment_agent", "/refund-payment")[0];
+    assert_eq!((refund["instructionId"].as_str(), refund["amount"].as_str()), (Some("ins_1"), Some("450.00")));
+    assert!(session.stdout.contains("seat inventory unavailable"));
+
+    assert_eq!(ledger_outcomes(&log).pop(), Some(("refund".to_string(), "success".to_string())));
+    let trace = session.trace("payment_refund").expect("refund trace");
+    assert_eq!((trace["reason"].as_str(), trace["refunded_amount"]["amount_minor"].as_i64()), (Some("booking_failed"), Some(45000)));
+}
+
+#[tokio::test]
+async fn test_split_payment_settles_before_booking() {
+    let log = CallLog::default();
+    let services = services(&log, Payment::Approved).await;
+    // Split, try a negative deposit, then pay 100.00 now and the balance on the same card
+    let answers = [&CHECKOUT[..CHECKOUT.len() - 1], &["y", "-1.50", "100", "exit"]].concat();
+    let session = run_client("split", &services, &answers).await;
+    assert!(session.stdout.contains("Please enter an amount between 0.01 and 449.99 USD."));
+
+    let amounts: Vec<Value> = log.bodies("payment_agent", "/initiate-purchase-instruction").iter().map(|i| i["amount"].clone()).collect();
+    assert_eq!(amounts, ["100.00", "350.00"]);
+
+    // Both parts are confirmed and their credentials retrieved before book-flight
+    let tool_calls = log.tool_calls();
+    let booked = tool_calls.iter().position(|tool| tool == "book-flight").expect("book-flight");
+    let settled = tool_calls[..booked].iter().filter(|tool| *tool == "retrieve-payment-credentials").count();
+    assert_eq!(settled, 2);
+    let booking = &log.bodies("agent_b", "/book-flight")[0];
+    assert_eq!(booking["amount"]["amount_minor"], 45000);
+    assert_eq!(booking["proof_ids"], json!(["prf_credentials", "prf_credentials"]));
+}
+
+#[tokio::test]
+async fn test_refunded_booking_frees_the_spend_limit() {
+    let log = CallLog::default();
+    let services = services(&log, Payment::Approved).await;
+    // Book, cancel for a full refund, then book the same fare again
+    let answers = [CHECKOUT, &[CANCEL_REQUEST], CHECKOUT, &["exit"]].concat();
+    let session = run_client_with_env("rebook", &services, &answers, &[("SPEND_LIMIT_PER_SESSION", "500")]).await;
+
+    assert_eq!(log.bodies("payment_agent", "/refund-payment").len(), 1);
+    assert_eq!(log.bodies("agent_b", "/book-flight").len(), 2);
+    let decisions: Vec<&Value> = session.traces.iter().filter(|t| t["kind"] == "spend_policy").map(|t| &t["decision"]).collect();
+    assert_eq!(decisions, ["within_limit", "within_limit"]);
+}
@@ -0,0 +1,169 @@
+//! Stub downstream services for driving the client binary end to end
+//!
+//! Each stub is an axum server on an ephemeral port that answers every
+//! request through a closure and records it in a log shared by all stubs,
+//! so a test can assert on the order in which the client reached them.
+
+use axum::{
+    body::Bytes,
+    http::{HeaderMap, StatusCode, Uri},
+    Json, Router,
+};
+use serde_json::Value;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use zk_protocol::signing::{SigningKey, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+
+/// `AGENT_HMAC_SECRET` the client runs with
+pub const HMAC_SECRET: &str = "test-hmac-secret";
+
+/// A request one of the stubs received
+#[derive(Debug, Clone)]
+pub struct Call {
+    pub service: &'static str,
+    pub path: String,
+    pub body: Value,
+    /// Carried a valid signature under HMAC_SECRET
+    pub signed: bool,
+}
+
+/// Requests received by all stubs, in arrival order
+#[derive(Clone, Default)]
+pub struct CallLog(Arc<Mutex<Vec<Call>>>);
+
+impl CallLog {
+    pub fn calls(&self) -> Vec<Call> {
+        self.0.lock().unwrap().clone()
+    }
+
+    /// Names of the tools called (`POST /tools/{name}`), in order
+    pub fn tool_calls(&self) -> Vec<String> {
+        self.calls()
+            .iter()
+            .filter_map(|call| call.path.strip_prefix("/tools/").map(str::to_string))
+            .collect()
+    }
+
+    /// Bodies `service` received on paths ending in `suffix`
+    pub fn bodies(&self, service: &str, suffix: &str) -> Vec<Value> {
+        self.calls()
+            .into_iter()
+            .filter(|call| call.service == service && call.path.ends_with(suffix))
+            .map(|call| call.body)
+            .collect()
+    }
+}
+
+/// Start a stub for `service` answering `(path, body)` with a status and
+/// JSON body; returns its base URL
+pub async fn stub<F>(service: &'static str, log: &CallLog, respond: F) -> String
+where
+    F: Fn(&str, &Value) -> (StatusCode, Value) + Send + Sync + 'static,
+{
+    let respond = Arc::new(respond);
+    let log = log.clone();
+    let app = Router::new().fallback(move |uri: Uri, headers: HeaderMap, body: Bytes| {
+        let respond = respond.clone();
+        let log = log.clone();
+        async move {
+            let path = uri.path().to_string();
+            let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+            let signed = SigningKey::new(HMAC_SECRET).verify(header(TIMESTAMP_HEADER), header(SIGNATURE_HEADER), &body).is_ok();
+            let body: Value = serde_json::from_slice(&body).unwrap_or(Value::Null);
+            let (status, reply) = respond(&path, &body);
+            log.0.lock().unwrap().push(Call { service, path, body, signed });
+            (status, Json(reply))
+        }
+    });
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    url
+}
+
+/// Base URLs of the stubs the client talks to
+pub struct Services {
+    pub claude: String,
+    pub agent_a: String,
+    pub agent_b: String,
+    pub payment_agent: String,
+}
+
+/// What the client printed and the traces it saved on exit
+pub struct Session {
+    pub stdout: String,
+    pub traces: Vec<Value>,
+}
+
+impl Session {
+    /// Kinds of the saved traces, in order
+    pub fn trace_kinds(&self) -> Vec<&str> {
+        self.traces.iter().filter_map(|trace| trace["kind"].as_str()).collect()
+    }
+
+    pub fn trace(&self, kind: &str) -> Option<&Value> {
+        self.traces.iter().find(|trace| trace["kind"] == kind)
+    }
+}
+
+/// Run the client binary against `services`, typing `input` one line at a
+/// time, and wait for it to exit
+pub async fn run_client(name: &str, services: &Services, input: &[&str]) -> Session {
+    run_client_with_env(name, services, input, &[]).await
+}
+
+/// `run_client` with extra environment variables set for the client
+pub async fn run_client_with_env(name: &str, services: &Services, input: &[&str], env: &[(&str, &str)]) -> Session {
+    // A fresh working directory keeps a developer's .env out of the run
+    let dir: PathBuf = std::env::temp_dir().join(format!("zp-client-{}-{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let traces_path = dir.join("traces.json");
+    let _ = std::fs::remove_file(&traces_path);
+
+    let mut child = tokio::process::Command::new(env!("CARGO_BIN_EXE_mcp-client"))
+        .current_dir(&dir)
+        .env_clear()
+        .env("ANTHROPIC_API_KEY", "test-key")
+        .env("AGENT_HMAC_SECRET", HMAC_SECRET)
+        .env("ANTHROPIC_BASE_URL", &services.claude)
+        .env("AGENT_A_SERVER_URL", &services.agent_a)
+        .env("AGENT_B_MCP_URL", &services.agent_b)
+        .env("PAYMENT_AGENT_URL", &services.payment_agent)
+        .env("ATTEST_BOOKINGS", "true")
+        .env("CRYPTOGRAPHIC_TRACES_PATH", &traces_path)
+        .env("RUST_LOG", "warn")
+        .envs(env.iter().copied())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .expect("client binary should start");
+
+    let mut stdin = child.stdin.take().unwrap();
+    stdin.write_all(format!("{}\n", input.join("\n")).as_bytes()).await.unwrap();
+    drop(stdin);
+
+    // A prompt the script didn't answer would re-ask forever on EOF
+    let output = tokio::time::timeout(Duration::from_secs(60), child.wait_with_output())
+        .await
+        .expect("client should finish the scripted session")
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    assert!(
+        output.status.success(),
+        "client failed: {}\n{}",
+        stdout,
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let traces = std::fs::read_to_string(&traces_path)
+        .map(|json| serde_json::from_str(&json).unwrap())
+        .unwrap_or_default();
+    let _ = std::fs::remove_dir_all(&dir);
+    Session { stdout, traces }
+}
@@ -0,0 +1,150 @@
+//! Session payment ledger
+//!
+//! Every payment-related event of a session (card enrollment, purchase
+//! instruction, credential retrieval, refund) with its amount, proof and
+//! outcome, in the order the client reported them. Served by
+//! `GET /session/{id}/ledger` as JSON or CSV for reconciliation against the
+//! Payment Agent and the payment processor.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+/// Payment step a ledger entry records
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LedgerEventKind {
+    Enrollment,
+    Instruction,
+    Credentials,
+    Refund,
+}
+
+impl LedgerEventKind {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            LedgerEventKind::Enrollment => "enrollment",
+            LedgerEventKind::Instruction => "instruction",
+            LedgerEventKind::Credentials => "credentials",
+            LedgerEventKind::Refund => "refund",
+        }
+    }
+}
+
+/// One payment event of a session
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct LedgerEntry {
+    pub kind: LedgerEventKind,
+    /// Client reference the event was sent under (enrollment, payment,
+    /// transaction or refund reference)
+    pub reference: String,
+    /// Payment Agent ID of what the event created (token, instruction,
+    /// refund), if it got that far
+    #[serde(default)]
+    pub external_id: Option<String>,
+    /// Decimal amount, e.g. "450.00"; none for enrollments
+    #[serde(default)]
+    pub amount: Option<String>,
+    /// ISO 4217 currency code
+    #[serde(default)]
+    pub currency: Option<String>,
+    /// Payment Agent proof backing the event, if it returned one
+    #[serde(default)]
+    pub proof_id: Option<String>,
+    /// "success", or the failure reported for the step
+    pub outcome: String,
+    /// When the event happened (Unix seconds)
+    pub timestamp: u64,
+}
+
+/// Ledger entries by session ID, kept for the life of the server
+#[derive(Debug, Default)]
+pub struct Ledger {
+    sessions: RwLock<HashMap<String, Vec<LedgerEntry>>>,
+}
+
+impl Ledger {
+    pub fn record(&self, session_id: &str, entry: LedgerEntry) {
+        self.sessions
+            .write()
+            .unwrap()
+            .entry(session_id.to_string())
+            .or_default()
+            .push(entry);
+    }
+
+    /// The session's entries in the order they were recorded, if it has any
+    pub fn entries(&self, session_id: &str) -> Option<Vec<LedgerEntry>> {
+        self.sessions.read().unwrap().get(session_id).cloned()
+    }
+
+    pub fn session_ids(&self) -> Vec<String> {
+        self.sessions.read().unwrap().keys().cloned().collect()
+    }
+}
+
+const CSV_HEADER: &str = "timestamp,kind,reference,external_id,amount,currency,proof_id,outcome";
+
+/// Entries as CSV, one row per event under a header row
+pub fn to_csv(entries: &[LedgerEntry]) -> String {
+    let mut csv = String::from(CSV_HEADER);
+    csv.push('\n');
+    for entry in entries {
+        let fields = [
+            entry.timestamp.to_string(),
+            entry.kind.as_str().to_string(),
+            entry.reference.clone(),
+            entry.external_id.clone().unwrap_or_default(),
+            entry.amount.clone().unwrap_or_default(),
+            entry.currency.clone().unwrap_or_default(),
+            entry.proof_id.clone().unwrap_or_default(),
+            entry.outcome.clone(),
+        ];
+        let row: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// Quote a field containing a separator, quote or line break
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ledger_csv_export() {
+        let ledger = Ledger::default();
+        ledger.record(
+            "sess_1",
+            LedgerEntry {
+                kind: LedgerEventKind::Instruction,
+                reference: "pay_abc".to_string(),
+                external_id: Some("instr_1".to_string()),
+                amount: Some("450.00".to_string()),
+                currency: Some("USD".to_string()),
+                proof_id: None,
+                outcome: "declined: \"limit\", retry later".to_string(),
+                timestamp: 1760600000,
+            },
+        );
+        assert!(ledger.entries("sess_2").is_none());
+
+        let csv = to_csv(&ledger.entries("sess_1").unwrap());
+        let rows: Vec<&str> = csv.lines().collect();
+        assert_eq!(rows[0], CSV_HEADER);
+        assert_eq!(
+            rows[1],
+            "1760600000,instruction,pay_abc,instr_1,450.00,USD,,\"declined: \"\"limit\"\", retry later\""
+        );
+    }
+}
@@ -1,17 +1,54 @@
-/// Agent A MCP Server Library
-/// 
-/// Exposes ZK proof operations as MCP tools:
-/// - verify_on_chain: Verify proofs on Sepolia testnet
-/// - request_attestation: Request attestation from attester service
-/// - format_zk_input: Format input for zkVM
-/// - call_agent_b: Call Agent B pricing/booking endpoints
+//! Agent A MCP Server Library
+//! 
+//! Exposes ZK proof operations as MCP tools:
+//! - verify_on_chain: Verify proofs on Sepolia testnet
+//! - request_attestation: Request attestation from attester service
+//! - format_zk_input: Format input for zkVM
+//! - call_agent_b: Call Agent B pricing/booking endpoints
+//! - prove_ticket_price: Groth16 proof of a quote from Agent B's pricing guest
 
 use serde::{Deserialize, Serialize};
 use schemars::JsonSchema;
 use anyhow::Result;
 
 // Re-export from zk-protocol
-pub use zk_protocol::{AttestRequest, AttestResponse, AgentResponse};
+pub use zk_protocol::{
+    ArtifactPart, AttestRequest, AttestResponse, AgentResponse, EstimateResponse, ProofMode, VerifyRequest,
+    VerifyResponse, PROTOCOL_VERSION,
+};
+use zk_protocol::{signing::SigningKey, telemetry, tls::MtlsConfig};
+
+pub mod ledger;
+pub mod proof_view;
+pub mod proofs;
+pub mod sessions;
+
+/// HTTP client builder for calls to `peer` (`AGENT_B`, `ATTESTER`), using
+/// mutual TLS when `<PEER>_TLS_*` or `TLS_*` certificates are configured.
+/// Requests carry the current span's trace context, so build the client
+/// inside the span making the call.
+pub fn peer_client(peer: &str) -> Result<reqwest::ClientBuilder> {
+    let builder = reqwest::Client::builder().default_headers(telemetry::propagation_headers());
+    match MtlsConfig::for_peer(peer) {
+        Some(tls) => Ok(builder.use_preconfigured_tls(tls.client_config()?)),
+        None => Ok(builder),
+    }
+}
+
+/// Attach `body` as JSON, signed with `AGENT_HMAC_SECRET` when it is set
+fn signed_json<T: Serialize>(
+    request: reqwest::RequestBuilder,
+    body: &T,
+) -> Result<reqwest::RequestBuilder> {
+    let body = serde_json::to_vec(body)?;
+    let mut request = request.header(reqwest::header::CONTENT_TYPE, "application/json");
+    if let Some(key) = SigningKey::from_env() {
+        for (name, value) in key.headers(&body) {
+            request = request.header(name, value);
+        }
+    }
+    Ok(request.body(body))
+}
 
 /// Pricing input for Agent B
 #[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
@@ -22,17 +59,52 @@ pub struct PricingInput {
     pub to: String,
     /// VIP status
     pub vip: bool,
+    /// Departure date (YYYY-MM-DD)
+    #[serde(default)]
+    pub travel_date: Option<String>,
+    /// Cabin class (economy, premium, business, first)
+    #[serde(default)]
+    pub cabin: Option<String>,
+    /// Promo/discount code supplied by the user
+    #[serde(default)]
+    pub promo_code: Option<String>,
+    /// Add-on codes (checked_bag, extra_legroom, meal)
+    #[serde(default)]
+    pub ancillaries: Vec<String>,
 }
 
 /// Response from pricing service
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct PricingResponse {
-    /// Calculated price
+    /// Calculated price (display value)
     pub price: f64,
+    /// Exact price in minor units (cents); use this for payment
+    pub amount_minor: i64,
     /// Program ID for attestation
     pub program_id: String,
     /// ELF hash for verification
     pub elf_hash: String,
+    /// Program ID of Agent B's pricing guest, if it is registered
+    pub pricing_program_id: Option<String>,
+    /// Hex SHA-256 of the fare table the quote was priced against
+    pub fare_table_digest: Option<String>,
+}
+
+/// Groth16 attestation that a quote follows Agent B's committed fare table
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct PriceProof {
+    /// Proven price in minor units
+    pub amount_minor: i64,
+    /// Hex SHA-256 of the fare table committed by the proof
+    pub fare_table_digest: Option<String>,
+    /// Pricing guest program ID
+    pub program_id: String,
+    /// Hex-encoded Groth16 proof
+    pub proof: String,
+    /// Hex-encoded public values (request, fare table digest, price)
+    pub public_values: String,
+    /// VK hash for the on-chain verifier
+    pub vk_hash: String,
 }
 
 /// On-chain verification result
@@ -128,7 +200,7 @@ pub async fn verify_on_chain(
     call_data.extend(encoded);
     let call_data_hex = format!("0x{}", hex::encode(&call_data));
 
-    tracing::debug!("Proof Type: sp1-zkvm ({})", hex::encode(&proof_type));
+    tracing::debug!("Proof Type: sp1-zkvm ({})", hex::encode(proof_type));
     tracing::debug!("VK Hash: {}", vk_hash);
     tracing::debug!("Public Values ({} bytes)", public_values_hex.len() / 2);
 
@@ -175,14 +247,17 @@ pub async fn get_ticket_price(
 ) -> Result<PricingResponse> {
     tracing::info!("→ Calling Agent B at {}", agent_b_url);
     
-    let client = reqwest::Client::new();
-    let response_json = client
-        .post(&format!("{}/price", agent_b_url))
-        .json(&serde_json::json!({
-            "from": input.from,
-            "to": input.to,
-            "vip": input.vip
-        }))
+    let client = peer_client("AGENT_B")?.build()?;
+    let payload = serde_json::json!({
+        "from": input.from,
+        "to": input.to,
+        "vip": input.vip,
+        "travel_date": input.travel_date,
+        "cabin": input.cabin,
+        "promo_code": input.promo_code,
+        "ancillaries": input.ancillaries
+    });
+    let response_json = signed_json(client.post(format!("{}/price", agent_b_url)), &payload)?
         .send()
         .await?
         .json::<serde_json::Value>()
@@ -193,6 +268,13 @@ pub async fn get_ticket_price(
         .get("price")
         .and_then(|p| p.as_f64())
         .unwrap_or(0.0);
+
+    // Older Agent B builds only return the float price
+    let amount_minor = response_json
+        .get("amount")
+        .and_then(|a| a.get("amount_minor"))
+        .and_then(|m| m.as_i64())
+        .unwrap_or_else(|| (price * 100.0).round() as i64);
     
     let program_id = response_json
         .get("program_id")
@@ -206,12 +288,59 @@ pub async fn get_ticket_price(
         .unwrap_or("")
         .to_string();
 
+    let pricing_program_id = response_json
+        .get("pricing_program_id")
+        .and_then(|p| p.as_str())
+        .map(|s| s.to_string());
+
+    let fare_table_digest = response_json
+        .get("fare_table_digest")
+        .and_then(|d| d.as_str())
+        .map(|s| s.to_string());
+
     tracing::info!("✓ Agent B response: price={}, program_id={}", price, program_id);
 
     Ok(PricingResponse {
         price,
+        amount_minor,
         program_id,
         elf_hash,
+        pricing_program_id,
+        fare_table_digest,
+    })
+}
+
+/// Quote `input` with Agent B and have the attester prove the price with
+/// Agent B's pricing guest, so downstream agents need not trust the quote
+pub async fn prove_ticket_price(
+    agent_b_url: &str,
+    attester_url: &str,
+    input: &PricingInput,
+) -> Result<PriceProof> {
+    let quote = get_ticket_price(agent_b_url, input).await?;
+    let program_id = quote
+        .pricing_program_id
+        .ok_or_else(|| anyhow::anyhow!("Agent B has no pricing guest registered"))?;
+
+    let zk_input = format_zk_input(agent_b_url, "price-proof", &serde_json::to_value(input)?).await?;
+    let attestation = request_attestation(
+        attester_url,
+        &program_id,
+        vec![zk_input.input_array],
+        // Checked by the attester against the decoded PriceCommitment
+        Some(serde_json::json!({ "price": { "amount_minor": quote.amount_minor }, "error": null })),
+        true,
+        ProofMode::Groth16,
+    )
+    .await?;
+
+    Ok(PriceProof {
+        amount_minor: quote.amount_minor,
+        fare_table_digest: quote.fare_table_digest,
+        program_id,
+        proof: attestation.proof,
+        public_values: attestation.public_values,
+        vk_hash: attestation.vk_hash,
     })
 }
 
@@ -223,13 +352,12 @@ pub async fn format_zk_input(
 ) -> Result<ZkInputResult> {
     tracing::info!("→ Getting ZK input format from Agent B");
     
-    let client = reqwest::Client::new();
-    let response = client
-        .post(&format!("{}/zk-input", agent_b_url))
-        .json(&serde_json::json!({
-            "endpoint": endpoint,
-            "input": input
-        }))
+    let client = peer_client("AGENT_B")?.build()?;
+    let payload = serde_json::json!({
+        "endpoint": endpoint,
+        "input": input
+    });
+    let response = signed_json(client.post(format!("{}/zk-input", agent_b_url)), &payload)?
         .send()
         .await?
         .json::<serde_json::Value>()
@@ -252,50 +380,346 @@ pub async fn format_zk_input(
     })
 }
 
-/// Request attestation from attester service
+/// Request attestation from attester service. Each input segment is a
+/// separate stdin write, read by the guest in order.
 pub async fn request_attestation(
     attester_url: &str,
     program_id: &str,
-    input_bytes: Vec<u8>,
+    mut input_segments: Vec<Vec<u8>>,
     claimed_output: Option<serde_json::Value>,
     verify_locally: bool,
+    proof_mode: ProofMode,
 ) -> Result<AttestResponse> {
     tracing::info!("→ Requesting attestation from {}", attester_url);
     
-    let client = reqwest::Client::builder()
+    let client = peer_client("ATTESTER")?
         .timeout(std::time::Duration::from_secs(7200))
         .build()?;
 
+    // A single input goes in input_bytes so older attesters understand it
+    let input_bytes = if input_segments.is_empty() { Vec::new() } else { input_segments.remove(0) };
     let request = AttestRequest {
+        protocol_version: PROTOCOL_VERSION,
         program_id: program_id.to_string(),
         input_bytes,
+        input_segments,
         claimed_output,
         verify_locally,
+        proof_mode,
+        hints: Default::default(),
+        job_id: None,
     };
 
-    let response = client
-        .post(&format!("{}/attest", attester_url))
-        .json(&request)
+    let response = signed_json(client.post(format!("{}/attest", attester_url)), &request)?
         .send()
-        .await?
-        .json::<AttestResponse>()
         .await?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        anyhow::bail!("Attester rejected request ({}): {}", status, body);
+    }
+    let mut response = response.json::<AttestResponse>().await?;
+
+    // v2 attesters keep the proof out of the JSON; download the raw artifacts
+    if response.proof.is_empty() && !response.job_id.is_empty() {
+        response.proof = hex::encode(fetch_artifact(&client, attester_url, &response.job_id, ArtifactPart::Proof).await?);
+        response.public_values =
+            hex::encode(fetch_artifact(&client, attester_url, &response.job_id, ArtifactPart::PublicValues).await?);
+    }
 
     tracing::info!("✓ Attestation response: verified_output={}", response.verified_output);
 
     Ok(response)
 }
 
+/// Have the attester verify a Groth16 proof off-chain
+pub async fn verify_attestation(
+    attester_url: &str,
+    proof: &str,
+    public_values: &str,
+    vk_hash: &str,
+) -> Result<VerifyResponse> {
+    let client = peer_client("ATTESTER")?.build()?;
+    let request = VerifyRequest {
+        proof: proof.to_string(),
+        public_values: public_values.to_string(),
+        vk_hash: Some(vk_hash.to_string()),
+        program_id: None,
+        proof_mode: ProofMode::Groth16,
+    };
+    let response = client
+        .post(format!("{}/verify-sp1", attester_url))
+        .json(&request)
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        anyhow::bail!("Attester rejected verification ({}): {}", status, body);
+    }
+    Ok(response.json::<VerifyResponse>().await?)
+}
+
+/// Ask the attester how long a new proof for `program_id` would take,
+/// based on its recent proving times and current queue
+pub async fn estimate_attestation(
+    attester_url: &str,
+    program_id: &str,
+    proof_mode: ProofMode,
+) -> Result<EstimateResponse> {
+    let client = peer_client("ATTESTER")?.build()?;
+    let proof_mode = match proof_mode {
+        ProofMode::Groth16 => "groth16",
+        ProofMode::Compressed => "compressed",
+    };
+    let response = client
+        .get(format!("{}/attest/estimate", attester_url))
+        .query(&[("program_id", program_id), ("proof_mode", proof_mode)])
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        anyhow::bail!("Attester rejected estimate ({}): {}", status, body);
+    }
+    Ok(response.json::<EstimateResponse>().await?)
+}
+
+/// Download one raw artifact of a completed attestation
+async fn fetch_artifact(
+    client: &reqwest::Client,
+    attester_url: &str,
+    job_id: &str,
+    part: ArtifactPart,
+) -> Result<Vec<u8>> {
+    let part_name = match part {
+        ArtifactPart::Proof => "proof",
+        ArtifactPart::PublicValues => "public_values",
+        ArtifactPart::Metadata => "metadata",
+    };
+    let response = client
+        .get(format!("{}/attest/{}/artifact", attester_url, job_id))
+        .query(&[("part", part_name)])
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(response.bytes().await?.to_vec())
+}
+
+/// One turn of a conversation with Claude, kept so the frontend can restore
+/// the transcript
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ClaudeMessage {
+    /// `user` or `assistant`
+    pub role: String,
+    pub content: String,
+    /// Unix time the server stored it
+    #[serde(default)]
+    pub timestamp: u64,
+}
+
+/// Claude tokens a session has used, as reported by the CLI after each call
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ClaudeUsage {
+    #[serde(default)]
+    pub calls: u32,
+    #[serde(default)]
+    pub input_tokens: u64,
+    #[serde(default)]
+    pub output_tokens: u64,
+    #[serde(default)]
+    pub cache_creation_input_tokens: u64,
+    #[serde(default)]
+    pub cache_read_input_tokens: u64,
+}
+
+impl ClaudeUsage {
+    /// Add another report to this total
+    pub fn add(&mut self, other: &ClaudeUsage) {
+        self.calls += other.calls;
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cache_creation_input_tokens += other.cache_creation_input_tokens;
+        self.cache_read_input_tokens += other.cache_read_input_tokens;
+    }
+}
+
+/// Record of one settled payment, kept per session for the UI and
+/// accounting exports
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PaymentReceipt {
+    /// Payment Agent purchase instruction
+    pub instruction_id: String,
+    /// Masked card or token the payment was charged to
+    pub payment_method: String,
+    /// Decimal amount, e.g. "450.00"
+    pub amount: String,
+    /// ISO 4217 currency code
+    pub currency: String,
+    /// Transaction reference the credentials were retrieved under
+    pub transaction_reference_id: Option<String>,
+    /// Payment Agent proof backing the payment, if it returned one
+    pub proof_id: Option<String>,
+    /// When the payment settled (Unix seconds)
+    pub timestamp: u64,
+    /// Amount charged in the card's currency, when it differs from `currency`
+    #[serde(default)]
+    pub settlement_amount: Option<String>,
+    #[serde(default)]
+    pub settlement_currency: Option<String>,
+    /// Units of `settlement_currency` per unit of `currency`
+    #[serde(default)]
+    pub fx_rate: Option<f64>,
+}
+
+/// Outcome of one readiness check on the server's own configuration
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ConfigCheck {
+    /// What was checked (e.g. mtls)
+    pub name: String,
+    pub ok: bool,
+    /// What is wrong and which setting to fix
+    pub error: Option<String>,
+}
+
+impl ConfigCheck {
+    pub fn new(name: &str, result: Result<()>) -> Self {
+        Self {
+            name: name.to_string(),
+            ok: result.is_ok(),
+            error: result.err().map(|e| e.to_string()),
+        }
+    }
+}
+
+/// Reachability of a configured peer service
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct DependencyStatus {
+    /// Dependency name (agent_b, attester, zkfetch, payment_agent, rpc)
+    pub name: String,
+    /// URL that was probed
+    pub url: String,
+    /// Whether the peer answered the probe
+    pub reachable: bool,
+    /// Round-trip time of the probe
+    pub latency_ms: u64,
+    /// What went wrong and which setting to check
+    pub error: Option<String>,
+}
+
+/// Timeout for a single dependency probe
+const PROBE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Check that a peer accepts HTTP connections; any HTTP status counts as
+/// reachable since the peers don't share a common health route
+pub async fn probe_http_dependency(
+    name: &str,
+    env_var: &str,
+    peer: &str,
+    url: &str,
+) -> DependencyStatus {
+    let started = std::time::Instant::now();
+    let result = async {
+        peer_client(peer)?
+            .timeout(PROBE_TIMEOUT)
+            .build()?
+            .get(url)
+            .send()
+            .await?;
+        Ok(())
+    }
+    .await;
+
+    dependency_status(name, env_var, url, started, result)
+}
+
+/// Check that the JSON-RPC endpoint answers `eth_chainId`
+pub async fn probe_rpc_dependency(url: &str) -> DependencyStatus {
+    let started = std::time::Instant::now();
+    let result = async {
+        let response: serde_json::Value = reqwest::Client::builder()
+            .timeout(PROBE_TIMEOUT)
+            .build()?
+            .post(url)
+            .json(&serde_json::json!({
+                "jsonrpc": "2.0",
+                "method": "eth_chainId",
+                "params": [],
+                "id": 1,
+            }))
+            .send()
+            .await?
+            .json()
+            .await?;
+
+        match response.get("result") {
+            Some(_) => Ok(()),
+            None => Err(anyhow::anyhow!("unexpected JSON-RPC response: {}", response)),
+        }
+    }
+    .await;
+
+    dependency_status("rpc", "RPC_URL", url, started, result)
+}
+
+fn dependency_status(
+    name: &str,
+    env_var: &str,
+    url: &str,
+    started: std::time::Instant,
+    result: Result<()>,
+) -> DependencyStatus {
+    DependencyStatus {
+        name: name.to_string(),
+        url: url.to_string(),
+        reachable: result.is_ok(),
+        latency_ms: started.elapsed().as_millis() as u64,
+        error: result.err().map(|e| {
+            format!("{} (check {} and that the service is running)", e, env_var)
+        }),
+    }
+}
+
+/// MCP protocol revisions this server speaks, newest first
+pub const SUPPORTED_PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+
+/// Protocol revision to answer `initialize` with: the client's own when
+/// supported, else the newest supported one older than it (revisions are
+/// dates, so they order as strings). `Err` when the client sent none or only
+/// predates every supported revision.
+pub fn negotiate_protocol_version(requested: Option<&str>) -> Result<&'static str, String> {
+    let requested = requested.ok_or("protocolVersion is required")?;
+    SUPPORTED_PROTOCOL_VERSIONS
+        .iter()
+        .find(|version| **version <= requested)
+        .copied()
+        .ok_or_else(|| format!("Unsupported protocol version: {}", requested))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_protocol_version_negotiation() {
+        assert_eq!(negotiate_protocol_version(Some("2025-03-26")), Ok("2025-03-26"));
+        // A newer client gets our newest revision; one in between gets the one before it
+        assert_eq!(negotiate_protocol_version(Some("2099-01-01")), Ok("2025-06-18"));
+        assert_eq!(negotiate_protocol_version(Some("2025-01-01")), Ok("2024-11-05"));
+        assert!(negotiate_protocol_version(Some("2024-10-07")).is_err());
+        assert!(negotiate_protocol_version(None).is_err());
+    }
+
     #[test]
     fn test_pricing_input_schema() {
-        let input = PricingInput {
+        let _input = PricingInput {
             from: "NYC".to_string(),
             to: "LON".to_string(),
             vip: true,
+            travel_date: Some("2026-07-04".to_string()),
+            cabin: Some("business".to_string()),
+            promo_code: None,
+            ancillaries: vec!["checked_bag".to_string()],
         };
         let schema = schemars::schema_for!(PricingInput);
         assert!(schema.schema.object.is_some());
@@ -1,29 +1,48 @@
-/// Agent A MCP Server - JSON-RPC (stdio) + HTTP API
-///
-/// Dual-protocol server:
-/// 1. JSON-RPC over stdin/stdout (for direct MCP protocol)
-/// 2. HTTP endpoints (for remote/network access)
-///
-/// Run with HTTP: AGENT_A_MODE=http ./agent-a-mcp
-/// Run with MCP:  ./agent-a-mcp (default)
-
-use anyhow::{Result, anyhow};
+//! Agent A MCP Server - JSON-RPC (stdio) + HTTP API
+//!
+//! Dual-protocol server:
+//! 1. JSON-RPC over stdin/stdout (for direct MCP protocol)
+//! 2. HTTP endpoints (for remote/network access)
+//!
+//! Run with HTTP: AGENT_A_MODE=http ./agent-a-mcp
+//! Run with MCP:  ./agent-a-mcp (default)
+
+use anyhow::{Context, Result, anyhow};
 use axum::{
-    extract::Json,
-    http::StatusCode,
-    response::IntoResponse,
+    body::Bytes,
+    extract::{Json, Path, Query, Request},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware::{self, Next},
+    response::{Html, IntoResponse, Response},
     routing::{get, post},
     Router,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use std::collections::HashMap;
 use std::io::{self, BufRead};
-use std::sync::Arc;
-use tower_http::cors::CorsLayer;
+use axum_server::tls_rustls::RustlsConfig;
+use std::sync::{Arc, LazyLock, RwLock};
+use tower_http::cors::{Any, CorsLayer};
+use tracing::Instrument;
+use zk_protocol::audit::{self, AuditKind, AuditQuery};
+use zk_protocol::shutdown;
+use zk_protocol::signing::{AdminToken, SigningKey, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+use zk_protocol::telemetry;
+use zp_config::AgentAMode;
+use zk_protocol::tls::MtlsConfig;
 
 use agent_a_mcp::{
-    PricingInput,
-    verify_on_chain, get_ticket_price, format_zk_input, request_attestation,
+    ClaudeMessage, ClaudeUsage, DependencyStatus, PaymentReceipt, PricingInput, ProofMode,
+    verify_on_chain, get_ticket_price, format_zk_input, request_attestation, prove_ticket_price,
+    verify_attestation, estimate_attestation,
+    probe_http_dependency, probe_rpc_dependency,
+    negotiate_protocol_version, SUPPORTED_PROTOCOL_VERSIONS,
+    ledger::{self, Ledger, LedgerEntry},
+    proof_view::{self, ProofStatus},
+    proofs::{self, SessionProofs},
+    sessions::{self, SessionActivity, SessionSummary},
+    ConfigCheck,
 };
 
 /// Agent A Server - holds tool implementations
@@ -31,32 +50,53 @@ use agent_a_mcp::{
 struct AgentAMcp {
     agent_b_url: Arc<String>,
     attester_url: Arc<String>,
+    zkfetch_url: Arc<String>,
+    payment_agent_url: Option<Arc<String>>,
     zeroproof_addr: Arc<String>,
     rpc_url: Arc<String>,
 }
 
 impl AgentAMcp {
     fn new() -> Self {
+        let settings = zp_config::get();
         Self {
-            agent_b_url: Arc::new(
-                std::env::var("AGENT_B_URL")
-                    .unwrap_or_else(|_| "http://localhost:8001".to_string()),
-            ),
-            attester_url: Arc::new(
-                std::env::var("ATTESTER_URL")
-                    .unwrap_or_else(|_| "http://localhost:8000".to_string()),
-            ),
-            zeroproof_addr: Arc::new(
-                std::env::var("ZEROPROOF_ADDRESS")
-                    .unwrap_or_else(|_| "0x9C33252D29B41Fe2706704a8Ca99E8731B58af41".to_string()),
-            ),
-            rpc_url: Arc::new(
-                std::env::var("RPC_URL")
-                    .unwrap_or_else(|_| "https://sepolia.infura.io/v3/abc123".to_string()),
-            ),
+            agent_b_url: Arc::new(settings.agent_a.agent_b_url.clone()),
+            attester_url: Arc::new(settings.network.attester_url.clone()),
+            zkfetch_url: Arc::new(settings.network.zkfetch_url.clone()),
+            payment_agent_url: settings.agent_a.payment_agent_url.clone().map(Arc::new),
+            zeroproof_addr: Arc::new(settings.network.zeroproof_address.clone()),
+            rpc_url: Arc::new(settings.network.rpc_url.clone()),
         }
     }
 
+    /// Check a proof with the ZeroProof contract and audit the outcome
+    async fn verify_on_chain(&self, proof: &str, public_values: &str, vk_hash: &str) -> Result<bool> {
+        let result = verify_on_chain(&self.zeroproof_addr, &self.rpc_url, proof, public_values, vk_hash).await;
+        let detail = match &result {
+            Ok(true) => format!("verified by ZeroProof at {}", self.zeroproof_addr),
+            Ok(false) => format!("rejected by ZeroProof at {}", self.zeroproof_addr),
+            Err(e) => format!("verification error: {}", e),
+        };
+        audit::record(AuditKind::OnChainVerification, matches!(result, Ok(true)), vk_hash, &detail);
+        result
+    }
+
+    /// Probe every configured peer concurrently
+    async fn probe_dependencies(&self) -> Vec<DependencyStatus> {
+        let payment_agent = async {
+            let url = format!("{}/health", self.payment_agent_url.as_deref()?);
+            Some(probe_http_dependency("payment_agent", "PAYMENT_AGENT_URL", "PAYMENT_AGENT", &url).await)
+        };
+        let (agent_b, attester, zkfetch, payment_agent, rpc) = tokio::join!(
+            probe_http_dependency("agent_b", "AGENT_B_URL", "AGENT_B", &self.agent_b_url),
+            probe_http_dependency("attester", "ATTESTER_URL", "ATTESTER", &self.attester_url),
+            probe_http_dependency("zkfetch", "ZKFETCH_URL", "ZKFETCH", &self.zkfetch_url),
+            payment_agent,
+            probe_rpc_dependency(&self.rpc_url),
+        );
+        [agent_b, attester, zkfetch].into_iter().chain(payment_agent).chain([rpc]).collect()
+    }
+
     /// List all available tools
     fn list_tools(&self) -> Value {
         json!({
@@ -69,7 +109,11 @@ impl AgentAMcp {
                         "properties": {
                             "from": {"type": "string"},
                             "to": {"type": "string"},
-                            "vip": {"type": "boolean"}
+                            "vip": {"type": "boolean"},
+                            "travel_date": {"type": "string", "description": "YYYY-MM-DD"},
+                            "cabin": {"type": "string", "enum": ["economy", "premium", "business", "first"]},
+                            "promo_code": {"type": "string"},
+                            "ancillaries": {"type": "array", "items": {"type": "string"}}
                         }
                     }
                 },
@@ -92,10 +136,29 @@ impl AgentAMcp {
                         "properties": {
                             "program_id": {"type": "string"},
                             "input_hex": {"type": "string"},
-                            "claimed_output": {"type": "string"}
+                            "input_segments_hex": {"type": "array", "items": {"type": "string"}, "description": "Further stdin segments, read by the guest after input_hex"},
+                            "claimed_output": {"type": "string"},
+                            "proof_mode": {"type": "string", "enum": ["groth16", "compressed"], "description": "compressed is faster but cannot be verified on-chain"}
                         }
                     }
                 },
+                {
+                    "name": "prove_ticket_price",
+                    "description": "Quote a fare from Agent B and request a Groth16 proof that the price follows its committed fare table",
+                    "inputSchema": {
+                        "type": "object",
+                        "properties": {
+                            "from": {"type": "string"},
+                            "to": {"type": "string"},
+                            "vip": {"type": "boolean"},
+                            "travel_date": {"type": "string", "description": "YYYY-MM-DD"},
+                            "cabin": {"type": "string", "enum": ["economy", "premium", "business", "first"]},
+                            "promo_code": {"type": "string"},
+                            "ancillaries": {"type": "array", "items": {"type": "string"}}
+                        },
+                        "required": ["from", "to", "vip"]
+                    }
+                },
                 {
                     "name": "verify_on_chain",
                     "description": "Verify ZK proof on Sepolia blockchain",
@@ -107,6 +170,31 @@ impl AgentAMcp {
                             "vk_hash": {"type": "string"}
                         }
                     }
+                },
+                {
+                    "name": "verify_attestation",
+                    "description": "Verify a ZK proof off-chain via the attester (no gas, no RPC)",
+                    "inputSchema": {
+                        "type": "object",
+                        "properties": {
+                            "proof": {"type": "string"},
+                            "public_values": {"type": "string"},
+                            "vk_hash": {"type": "string"}
+                        },
+                        "required": ["proof", "public_values", "vk_hash"]
+                    }
+                },
+                {
+                    "name": "estimate_attestation",
+                    "description": "Expected queue wait and proving time for a new proof, from the attester's recent history",
+                    "inputSchema": {
+                        "type": "object",
+                        "properties": {
+                            "program_id": {"type": "string", "description": "program_id or pricing_program_id from get_ticket_price"},
+                            "proof_mode": {"type": "string", "enum": ["groth16", "compressed"]}
+                        },
+                        "required": ["program_id"]
+                    }
                 }
             ]
         })
@@ -128,23 +216,57 @@ impl AgentAMcp {
                     .get("vip")
                     .and_then(|v| v.as_bool())
                     .unwrap_or(false);
+                let travel_date = arguments
+                    .get("travel_date")
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.to_string());
+                let cabin = arguments
+                    .get("cabin")
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.to_string());
+                let promo_code = arguments
+                    .get("promo_code")
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.to_string());
+                let ancillaries = arguments
+                    .get("ancillaries")
+                    .and_then(|v| v.as_array())
+                    .map(|a| a.iter().filter_map(|v| v.as_str().map(|s| s.to_string())).collect())
+                    .unwrap_or_default();
 
                 let input = PricingInput {
                     from: from.to_string(),
                     to: to.to_string(),
                     vip,
+                    travel_date,
+                    cabin,
+                    promo_code,
+                    ancillaries,
                 };
 
                 match get_ticket_price(&self.agent_b_url, &input).await {
                     Ok(response) => Ok(json!({
                         "price": response.price,
+                        "amount_minor": response.amount_minor,
                         "program_id": response.program_id,
-                        "elf_hash": response.elf_hash
+                        "elf_hash": response.elf_hash,
+                        "pricing_program_id": response.pricing_program_id,
+                        "fare_table_digest": response.fare_table_digest
                     })),
                     Err(e) => Err(anyhow!("Agent B call failed: {}", e)),
                 }
             }
 
+            "prove_ticket_price" => {
+                let input: PricingInput = serde_json::from_value(arguments)
+                    .map_err(|e| anyhow!("Invalid pricing input: {}", e))?;
+
+                match prove_ticket_price(&self.agent_b_url, &self.attester_url, &input).await {
+                    Ok(proof) => Ok(serde_json::to_value(proof)?),
+                    Err(e) => Err(anyhow!("Price proof failed: {}", e)),
+                }
+            }
+
             "format_zk_input" => {
                 let endpoint = arguments
                     .get("endpoint")
@@ -171,22 +293,36 @@ impl AgentAMcp {
                     .and_then(|v| v.as_str())
                     .unwrap_or("0x");
 
-                let input_bytes = hex::decode(input_hex.strip_prefix("0x").unwrap_or(input_hex))
+                let extra: Vec<String> = arguments
+                    .get("input_segments_hex")
+                    .and_then(|v| serde_json::from_value(v.clone()).ok())
+                    .unwrap_or_default();
+                let input_segments = decode_segments(input_hex, &extra)
                     .map_err(|e| anyhow!("Invalid hex: {}", e))?;
                 let claimed_output = arguments.get("claimed_output").cloned();
+                let proof_mode: ProofMode = arguments
+                    .get("proof_mode")
+                    .map(|v| serde_json::from_value(v.clone()))
+                    .transpose()
+                    .map_err(|e| anyhow!("Invalid proof_mode: {}", e))?
+                    .unwrap_or_default();
 
                 match request_attestation(
                     &self.attester_url,
                     program_id,
-                    input_bytes,
+                    input_segments,
                     claimed_output,
                     true,
+                    proof_mode,
                 )
                 .await
                 {
                     Ok(response) => Ok(json!({
                         "verified_output": response.verified_output,
-                        "vk_hash": response.vk_hash
+                        "vk_hash": response.vk_hash,
+                        "on_chain_compatible": response.on_chain_compatible,
+                        "cached": response.cached,
+                        "execution": response.execution
                     })),
                     Err(e) => Err(anyhow!("Attestation request failed: {}", e)),
                 }
@@ -206,7 +342,7 @@ impl AgentAMcp {
                     .and_then(|v| v.as_str())
                     .unwrap_or("0x");
 
-                match verify_on_chain(&self.zeroproof_addr, &self.rpc_url, proof, public_values, vk_hash).await {
+                match self.verify_on_chain(proof, public_values, vk_hash).await {
                     Ok(verified) => Ok(json!({
                         "verified": verified,
                         "message": if verified {
@@ -219,6 +355,23 @@ impl AgentAMcp {
                 }
             }
 
+            "verify_attestation" => {
+                let arg = |key: &str| arguments.get(key).and_then(|v| v.as_str()).unwrap_or("0x").to_string();
+                match verify_attestation(&self.attester_url, &arg("proof"), &arg("public_values"), &arg("vk_hash")).await {
+                    Ok(result) => Ok(json!(result)),
+                    Err(e) => Err(anyhow!("Attester verification error: {}", e)),
+                }
+            }
+
+            "estimate_attestation" => {
+                let req: EstimateAttestationRequest = serde_json::from_value(arguments)
+                    .map_err(|e| anyhow!("Invalid estimate input: {}", e))?;
+                match estimate_attestation(&self.attester_url, &req.program_id, req.proof_mode).await {
+                    Ok(estimate) => Ok(json!(estimate)),
+                    Err(e) => Err(anyhow!("Attester estimate error: {}", e)),
+                }
+            }
+
             _ => Err(anyhow!("Unknown tool: {}", name)),
         }
     }
@@ -226,12 +379,21 @@ impl AgentAMcp {
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    // Logs go to stderr, leaving stdout to JSON-RPC
+    let _telemetry = telemetry::init("agent-a");
+
+    let settings = zp_config::init()?;
+    if zp_config::print_config_requested() {
+        print!("{}", settings.to_toml());
+        return Ok(());
+    }
+    let audit_log = audit::init("agent-a").context("Failed to open the audit log (AUDIT_LOG_PATH)")?;
+    tracing::info!("Audit log: {}", audit_log.path().display());
+
     // Check if running in HTTP mode or JSON-RPC mode
-    let mode = std::env::var("AGENT_A_MODE").unwrap_or_else(|_| "http".to_string());
-    
-    match mode.as_str() {
-        "jsonrpc" => run_jsonrpc_server().await,
-        "http" | _ => start_http_server().await,
+    match settings.agent_a.mode {
+        AgentAMode::JsonRpc => run_jsonrpc_server().await,
+        AgentAMode::Http => start_http_server().await,
     }
 }
 
@@ -250,93 +412,185 @@ async fn run_jsonrpc_server() -> Result<()> {
         let request: Value = match serde_json::from_str(&line) {
             Ok(v) => v,
             Err(e) => {
-                eprintln!("Parse error: {}", e);
+                tracing::warn!(error = %e, "JSON-RPC parse error");
                 continue;
             }
         };
 
-        let id = request.get("id").cloned().unwrap_or(json!(null));
-        let method = match request.get("method").and_then(|v| v.as_str()) {
-            Some(m) => m,
-            None => continue,
-        };
+        // Send response
+        if let Some(response) = handle_jsonrpc(&server, &request, false).await {
+            println!("{}", response);
+        }
+    }
 
-        let response = match method {
-            "initialize" => {
-                json!({
-                    "jsonrpc": "2.0",
-                    "id": id,
-                    "result": {
-                        "protocolVersion": "2024-11",
-                        "capabilities": {"tools": {}},
-                        "serverInfo": {
-                            "name": "Agent A",
-                            "version": "0.1.0"
-                        }
-                    }
-                })
-            }
+    Ok(())
+}
 
-            "tools/list" => {
-                let tools = server.list_tools();
-                json!({
-                    "jsonrpc": "2.0",
-                    "id": id,
-                    "result": tools
-                })
-            }
+/// Answer one MCP JSON-RPC request; `None` for messages without a method.
+/// Proof resources are served only with `resources`: clients store proofs
+/// with the HTTP server, so a `jsonrpc` process never has any.
+async fn handle_jsonrpc(server: &AgentAMcp, request: &Value, resources: bool) -> Option<Value> {
+    let id = request.get("id").cloned().unwrap_or(json!(null));
+    let method = request.get("method").and_then(|v| v.as_str())?;
+    let params = request.get("params").cloned().unwrap_or(json!({}));
 
-            "tools/call" => {
-                let params = request.get("params").cloned().unwrap_or(json!({}));
-                let tool_name = params
-                    .get("name")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("unknown");
-                let arguments = params.get("arguments").cloned().unwrap_or(json!({}));
-
-                match server.call_tool(tool_name, arguments).await {
-                    Ok(result) => {
-                        json!({
-                            "jsonrpc": "2.0",
-                            "id": id,
-                            "result": {
-                                "content": [{
-                                    "type": "text",
-                                    "text": result.to_string()
-                                }]
+    let response = match method {
+        "initialize" => {
+            let requested = params.get("protocolVersion").and_then(|v| v.as_str());
+            match negotiate_protocol_version(requested) {
+                Ok(version) => {
+                    json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "result": {
+                            "protocolVersion": version,
+                            "capabilities": if resources {
+                                json!({"tools": {}, "resources": {}})
+                            } else {
+                                json!({"tools": {}})
+                            },
+                            "serverInfo": {
+                                "name": "Agent A",
+                                "version": "0.1.0"
                             }
-                        })
-                    }
-                    Err(e) => {
-                        json!({
-                            "jsonrpc": "2.0",
-                            "id": id,
-                            "error": {
-                                "code": -32603,
-                                "message": e.to_string()
+                        }
+                    })
+                }
+                Err(message) => {
+                    tracing::warn!(requested = ?requested, "rejected MCP client: {}", message);
+                    json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": {
+                            "code": -32602,
+                            "message": message,
+                            "data": {
+                                "supported": SUPPORTED_PROTOCOL_VERSIONS,
+                                "requested": requested
                             }
-                        })
-                    }
+                        }
+                    })
                 }
             }
+        }
 
-            _ => {
-                json!({
-                    "jsonrpc": "2.0",
-                    "id": id,
-                    "error": {
-                        "code": -32601,
-                        "message": format!("Method not found: {}", method)
-                    }
+        "tools/list" => {
+            let tools = server.list_tools();
+            json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "result": tools
+            })
+        }
+
+        "tools/call" => {
+            let tool_name = params
+                .get("name")
+                .and_then(|v| v.as_str())
+                .unwrap_or("unknown");
+            let arguments = params.get("arguments").cloned().unwrap_or(json!({}));
+
+            match server.call_tool(tool_name, arguments).await {
+                Ok(result) => {
+                    json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "result": {
+                            "content": [{
+                                "type": "text",
+                                "text": result.to_string()
+                            }]
+                        }
+                    })
+                }
+                Err(e) => {
+                    json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": {
+                            "code": -32603,
+                            "message": e.to_string()
+                        }
+                    })
+                }
+            }
+        }
+
+        // Session proofs as proof://{session_id}/{proof_id}
+        "resources/list" if resources => {
+            let resources: Vec<Value> = SESSION_PROOFS
+                .all()
+                .into_iter()
+                .map(|(session_id, proof)| {
+                    json!({
+                        "uri": proofs::uri(&session_id, &proof.proof_id),
+                        "name": format!("{} ({})", proof.proof_id, session_id),
+                        "description": format!("{} proof recorded at {}", proof.verification.scheme, proof.recorded_at),
+                        "mimeType": "application/json"
+                    })
                 })
+                .collect();
+            json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "result": {"resources": resources}
+            })
+        }
+
+        "resources/read" if resources => {
+            let uri = params.get("uri").and_then(|v| v.as_str()).unwrap_or_default();
+            let proof = proofs::parse_uri(uri)
+                .and_then(|(session_id, proof_id)| SESSION_PROOFS.get(session_id, proof_id));
+            match proof {
+                Some(proof) => {
+                    json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "result": {
+                            "contents": [{
+                                "uri": uri,
+                                "mimeType": "application/json",
+                                "text": serde_json::to_string_pretty(&proof).unwrap_or_default()
+                            }]
+                        }
+                    })
+                }
+                None => {
+                    json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": {
+                            "code": -32002,
+                            "message": format!("Resource not found: {}", uri)
+                        }
+                    })
+                }
             }
-        };
+        }
 
-        // Send response
-        println!("{}", response.to_string());
-    }
+        _ => {
+            json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": {
+                    "code": -32601,
+                    "message": format!("Method not found: {}", method)
+                }
+            })
+        }
+    };
 
-    Ok(())
+    Some(response)
+}
+
+/// MCP JSON-RPC over HTTP (`POST /mcp`), for hosts that reach the HTTP
+/// server rather than spawning `jsonrpc` mode; only this transport sees the
+/// proofs clients store
+async fn http_mcp(Json(request): Json<Value>) -> impl IntoResponse {
+    match handle_jsonrpc(&AgentAMcp::new(), &request, true).await {
+        Some(response) => (StatusCode::OK, Json(response)).into_response(),
+        None => StatusCode::ACCEPTED.into_response(),
+    }
 }
 
 /// HTTP Response wrapper
@@ -371,6 +625,14 @@ struct CallAgentBRequest {
     from: String,
     to: String,
     vip: bool,
+    #[serde(default)]
+    travel_date: Option<String>,
+    #[serde(default)]
+    cabin: Option<String>,
+    #[serde(default)]
+    promo_code: Option<String>,
+    #[serde(default)]
+    ancillaries: Vec<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -383,8 +645,21 @@ struct FormatZkInputRequest {
 struct RequestAttestationRequest {
     program_id: String,
     input_hex: String,
+    /// Further stdin segments, read by the guest after `input_hex`
+    #[serde(default)]
+    input_segments_hex: Vec<String>,
     #[serde(default)]
     claimed_output: Option<String>,
+    #[serde(default)]
+    proof_mode: ProofMode,
+}
+
+/// Decode the first stdin segment and any further ones from (0x-)hex
+fn decode_segments(first: &str, rest: &[String]) -> Result<Vec<Vec<u8>>, hex::FromHexError> {
+    std::iter::once(first)
+        .chain(rest.iter().map(String::as_str))
+        .map(|h| hex::decode(h.strip_prefix("0x").unwrap_or(h)))
+        .collect()
 }
 
 #[derive(Debug, Deserialize)]
@@ -394,6 +669,13 @@ struct VerifyOnChainRequest {
     vk_hash: String,
 }
 
+#[derive(Debug, Deserialize)]
+struct EstimateAttestationRequest {
+    program_id: String,
+    #[serde(default)]
+    proof_mode: ProofMode,
+}
+
 // HTTP Handlers
 async fn health() -> Json<serde_json::Value> {
     Json(json!({
@@ -404,6 +686,53 @@ async fn health() -> Json<serde_json::Value> {
     }))
 }
 
+/// Per-dependency reachability; 503 when any peer is down
+async fn health_dependencies() -> impl IntoResponse {
+    let dependencies = AgentAMcp::new().probe_dependencies().await;
+    let healthy = dependencies.iter().all(|d| d.reachable);
+    let status = if healthy { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+
+    (
+        status,
+        Json(json!({
+            "status": if healthy { "ok" } else { "degraded" },
+            "dependencies": dependencies,
+        })),
+    )
+}
+
+/// Readiness: configuration that can only fail at runtime (certificates
+/// on disk), then every peer; 503 until all of them pass
+async fn health_ready() -> impl IntoResponse {
+    let configuration = [ConfigCheck::new("mtls", check_tls())];
+    let dependencies = AgentAMcp::new().probe_dependencies().await;
+    let ready = configuration.iter().all(|c| c.ok) && dependencies.iter().all(|d| d.reachable);
+    let status = if ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+
+    (
+        status,
+        Json(json!({
+            "ready": ready,
+            "profile": zp_config::get().profile,
+            "configuration": configuration,
+            "dependencies": dependencies,
+        })),
+    )
+}
+
+/// The server certificate and each peer's client certificate load, if configured
+fn check_tls() -> Result<()> {
+    if let Some(tls) = MtlsConfig::from_env() {
+        tls.server_config().context("TLS_CERT / TLS_KEY / TLS_CA")?;
+    }
+    for peer in ["AGENT_B", "ATTESTER", "ZKFETCH", "PAYMENT_AGENT"] {
+        if let Some(tls) = MtlsConfig::for_peer(peer) {
+            tls.client_config().with_context(|| format!("{}_TLS_* / TLS_*", peer))?;
+        }
+    }
+    Ok(())
+}
+
 async fn list_tools_http(
 ) -> Json<serde_json::Value> {
     let server = AgentAMcp::new();
@@ -418,6 +747,10 @@ async fn http_get_ticket_price(
         from: req.from,
         to: req.to,
         vip: req.vip,
+        travel_date: req.travel_date,
+        cabin: req.cabin,
+        promo_code: req.promo_code,
+        ancillaries: req.ancillaries,
     };
 
     match get_ticket_price(&server.agent_b_url, &input).await {
@@ -426,8 +759,11 @@ async fn http_get_ticket_price(
                 StatusCode::OK,
                 Json(HttpResponse::ok(json!({
                     "price": response.price,
+                    "amount_minor": response.amount_minor,
                     "program_id": response.program_id,
-                    "elf_hash": response.elf_hash
+                    "elf_hash": response.elf_hash,
+                    "pricing_program_id": response.pricing_program_id,
+                    "fare_table_digest": response.fare_table_digest
                 }))),
             )
                 .into_response()
@@ -443,6 +779,23 @@ async fn http_get_ticket_price(
     }
 }
 
+async fn http_prove_ticket_price(
+    Json(input): Json<PricingInput>,
+) -> impl IntoResponse {
+    let server = AgentAMcp::new();
+
+    match prove_ticket_price(&server.agent_b_url, &server.attester_url, &input).await {
+        Ok(proof) => (StatusCode::OK, Json(HttpResponse::ok(proof))).into_response(),
+        Err(e) => {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(HttpResponse::<()>::err(e.to_string())),
+            )
+                .into_response()
+        }
+    }
+}
+
 async fn http_format_zk_input(
     Json(req): Json<FormatZkInputRequest>,
 ) -> impl IntoResponse {
@@ -474,8 +827,7 @@ async fn http_request_attestation(
 ) -> impl IntoResponse {
     let server = AgentAMcp::new();
     
-    let input_bytes = match hex::decode(req.input_hex.strip_prefix("0x").unwrap_or(&req.input_hex))
-    {
+    let input_segments = match decode_segments(&req.input_hex, &req.input_segments_hex) {
         Ok(bytes) => bytes,
         Err(e) => {
             return (
@@ -489,9 +841,13 @@ async fn http_request_attestation(
     match request_attestation(
         &server.attester_url,
         &req.program_id,
-        input_bytes,
-        req.claimed_output.as_deref().map(|s| serde_json::json!(s)),
+        input_segments,
+        // The attester only accepts object claims, so send JSON text as JSON
+        req.claimed_output
+            .as_deref()
+            .map(|s| serde_json::from_str(s).unwrap_or_else(|_| serde_json::json!(s))),
         true,
+        req.proof_mode,
     )
     .await
     {
@@ -500,7 +856,10 @@ async fn http_request_attestation(
                 StatusCode::OK,
                 Json(HttpResponse::ok(json!({
                     "verified_output": response.verified_output,
-                    "vk_hash": response.vk_hash
+                    "vk_hash": response.vk_hash,
+                    "on_chain_compatible": response.on_chain_compatible,
+                    "cached": response.cached,
+                    "execution": response.execution
                 }))),
             )
                 .into_response()
@@ -520,15 +879,7 @@ async fn http_verify_on_chain(
 ) -> impl IntoResponse {
     let server = AgentAMcp::new();
 
-    match verify_on_chain(
-        &server.zeroproof_addr,
-        &server.rpc_url,
-        &req.proof,
-        &req.public_values,
-        &req.vk_hash,
-    )
-    .await
-    {
+    match server.verify_on_chain(&req.proof, &req.public_values, &req.vk_hash).await {
         Ok(verified) => {
             (
                 StatusCode::OK,
@@ -553,37 +904,793 @@ async fn http_verify_on_chain(
     }
 }
 
+async fn http_verify_attestation(
+    Json(req): Json<VerifyOnChainRequest>,
+) -> impl IntoResponse {
+    let server = AgentAMcp::new();
+
+    match verify_attestation(&server.attester_url, &req.proof, &req.public_values, &req.vk_hash).await {
+        Ok(result) => (StatusCode::OK, Json(HttpResponse::ok(result))).into_response(),
+        Err(e) => {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(HttpResponse::<()>::err(e.to_string())),
+            )
+                .into_response()
+        }
+    }
+}
+
+async fn http_estimate_attestation(
+    Json(req): Json<EstimateAttestationRequest>,
+) -> impl IntoResponse {
+    let server = AgentAMcp::new();
+
+    match estimate_attestation(&server.attester_url, &req.program_id, req.proof_mode).await {
+        Ok(estimate) => (StatusCode::OK, Json(HttpResponse::ok(estimate))).into_response(),
+        Err(e) => {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(HttpResponse::<()>::err(e.to_string())),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Payment receipts by session ID, kept for the life of the server
+static PAYMENT_RECEIPTS: LazyLock<RwLock<HashMap<String, Vec<PaymentReceipt>>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+async fn http_store_payment_receipt(
+    Path(session_id): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> impl IntoResponse {
+    if let Some(rejection) = session_record_rejection(&session_id, &headers, &body) {
+        return rejection;
+    }
+    let Json(receipt) = match Json::<PaymentReceipt>::from_bytes(&body) {
+        Ok(json) => json,
+        Err(rejection) => return rejection.into_response(),
+    };
+    tracing::info!("[PAYMENT-RECEIPT] {} {} {} for session {}", receipt.instruction_id, receipt.amount, receipt.currency, session_id);
+    let mut receipts = PAYMENT_RECEIPTS.write().unwrap();
+    let session = receipts.entry(session_id).or_default();
+    // The same instruction may be reported again on retry
+    session.retain(|r| r.instruction_id != receipt.instruction_id);
+    session.push(receipt.clone());
+    (StatusCode::CREATED, Json(HttpResponse::ok(receipt))).into_response()
+}
+
+async fn http_payment_receipts(
+    Path(session_id): Path<String>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if let Some(rejection) = admin_rejection(&headers) {
+        return rejection;
+    }
+    match PAYMENT_RECEIPTS.read().unwrap().get(&session_id) {
+        Some(receipts) => (StatusCode::OK, Json(HttpResponse::ok(receipts.clone()))).into_response(),
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(HttpResponse::<()>::err(format!("No payment receipts for session {}", session_id))),
+        )
+            .into_response(),
+    }
+}
+
+/// Conversation transcripts by session ID, kept for the life of the server
+static MESSAGES: LazyLock<RwLock<HashMap<String, Vec<ClaudeMessage>>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// Page size of `GET /session[s]/{id}/messages` when `limit` is not given, and its cap
+const DEFAULT_MESSAGE_PAGE: usize = 50;
+const MAX_MESSAGE_PAGE: usize = 200;
+
+async fn http_store_message(
+    Path(session_id): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> impl IntoResponse {
+    if let Some(rejection) = session_record_rejection(&session_id, &headers, &body) {
+        return rejection;
+    }
+    let Json(mut message) = match Json::<ClaudeMessage>::from_bytes(&body) {
+        Ok(json) => json,
+        Err(rejection) => return rejection.into_response(),
+    };
+    if !matches!(message.role.as_str(), "user" | "assistant") {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(HttpResponse::<()>::err(format!("role must be user or assistant, got {}", message.role))),
+        )
+            .into_response();
+    }
+    message.timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    MESSAGES.write().unwrap().entry(session_id).or_default().push(message.clone());
+    (StatusCode::CREATED, Json(HttpResponse::ok(message))).into_response()
+}
+
+#[derive(Debug, Deserialize)]
+struct MessagesQuery {
+    #[serde(default)]
+    offset: usize,
+    limit: Option<usize>,
+}
+
+/// One page of a session's transcript, oldest first
+#[derive(Debug, Serialize)]
+struct MessagesPage {
+    messages: Vec<ClaudeMessage>,
+    total: usize,
+    offset: usize,
+    /// Offset of the next page, if there is one
+    next_offset: Option<usize>,
+}
+
+async fn http_messages(
+    Path(session_id): Path<String>,
+    headers: HeaderMap,
+    Query(query): Query<MessagesQuery>,
+) -> impl IntoResponse {
+    if let Some(rejection) = admin_rejection(&headers) {
+        return rejection;
+    }
+    let conversations = MESSAGES.read().unwrap();
+    let Some(messages) = conversations.get(&session_id) else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(HttpResponse::<()>::err(format!("No messages for session {}", session_id))),
+        )
+            .into_response();
+    };
+    let limit = query.limit.unwrap_or(DEFAULT_MESSAGE_PAGE).clamp(1, MAX_MESSAGE_PAGE);
+    let page: Vec<ClaudeMessage> = messages.iter().skip(query.offset).take(limit).cloned().collect();
+    let end = query.offset + page.len();
+    let page = MessagesPage {
+        next_offset: (end < messages.len()).then_some(end),
+        total: messages.len(),
+        offset: query.offset,
+        messages: page,
+    };
+    (StatusCode::OK, Json(HttpResponse::ok(page))).into_response()
+}
+
+/// Claude usage reported by the CLI, totalled per session
+static CLAUDE_USAGE: LazyLock<RwLock<HashMap<String, ClaudeUsage>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// Add one report (usually one call) to the session's total
+async fn http_record_usage(
+    Path(session_id): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> impl IntoResponse {
+    if let Some(rejection) = session_record_rejection(&session_id, &headers, &body) {
+        return rejection;
+    }
+    let Json(usage) = match Json::<ClaudeUsage>::from_bytes(&body) {
+        Ok(json) => json,
+        Err(rejection) => return rejection.into_response(),
+    };
+    let mut sessions = CLAUDE_USAGE.write().unwrap();
+    let total = sessions.entry(session_id).or_default();
+    total.add(&usage);
+    (StatusCode::CREATED, Json(HttpResponse::ok(*total))).into_response()
+}
+
+async fn http_usage(
+    Path(session_id): Path<String>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if let Some(rejection) = admin_rejection(&headers) {
+        return rejection;
+    }
+    match CLAUDE_USAGE.read().unwrap().get(&session_id) {
+        Some(usage) => (StatusCode::OK, Json(HttpResponse::ok(*usage))).into_response(),
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(HttpResponse::<()>::err(format!("No Claude usage for session {}", session_id))),
+        )
+            .into_response(),
+    }
+}
+
+/// Proof-bearing traces of every session, served as MCP resources
+static SESSION_PROOFS: LazyLock<SessionProofs> = LazyLock::new(SessionProofs::default);
+
+async fn http_store_proof(
+    Path(session_id): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> impl IntoResponse {
+    if let Some(rejection) = session_record_rejection(&session_id, &headers, &body) {
+        audit::record(AuditKind::ProofSubmission, false, &session_id, &format!("rejected proof ({})", rejection.status()));
+        return rejection;
+    }
+    let Json(trace) = match Json::<Value>::from_bytes(&body) {
+        Ok(json) => json,
+        Err(rejection) => return rejection.into_response(),
+    };
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let proof = SESSION_PROOFS.record(&session_id, trace, &zp_config::get().network.zeroproof_address, now);
+    tracing::info!("[PROOFS] {} for session {}", proof.proof_id, session_id);
+    audit::record(AuditKind::ProofSubmission, true, &session_id, &format!("stored {} ({})", proof.proof_id, proof.verification.scheme));
+    (StatusCode::CREATED, Json(HttpResponse::ok(proof))).into_response()
+}
+
+async fn http_session_proofs(
+    Path(session_id): Path<String>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if let Some(rejection) = admin_rejection(&headers) {
+        return rejection;
+    }
+    match SESSION_PROOFS.session(&session_id) {
+        Some(proofs) => (StatusCode::OK, Json(HttpResponse::ok(proofs))).into_response(),
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(HttpResponse::<()>::err(format!("No proofs for session {}", session_id))),
+        )
+            .into_response(),
+    }
+}
+
+/// Longest the proof viewer waits for the attester to check one proof
+const PROOF_VIEW_VERIFY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Default and longest lifetime of a proof viewer link
+const PROOF_LINK_TTL_SECS: u64 = 24 * 60 * 60;
+const MAX_PROOF_LINK_TTL_SECS: u64 = 30 * 24 * 60 * 60;
+
+/// Attester verdicts on viewed proofs, by (session ID, proof ID); stored
+/// proofs never change, so each is checked once
+static PROOF_VIEW_CHECKS: LazyLock<RwLock<HashMap<(String, String), ProofStatus>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+#[derive(Debug, Deserialize)]
+struct ProofLinkQuery {
+    expires: Option<String>,
+    sig: Option<String>,
+}
+
+fn proof_view_path(session_id: &str) -> String {
+    format!("/proofs/session/{}/view", session_id)
+}
+
+/// HTML audit page of a session's proofs; SP1 proofs are checked with the
+/// attester (off-chain, no gas) the first time the page shows them. Requires
+/// `Authorization: Bearer $ADMIN_TOKEN` or a link from
+/// `POST /admin/sessions/:id/proof-link`.
+async fn http_proof_view(
+    Path(session_id): Path<String>,
+    headers: HeaderMap,
+    Query(link): Query<ProofLinkQuery>,
+) -> impl IntoResponse {
+    let signed_link = SigningKey::from_env()
+        .is_some_and(|key| key.verify_link(&proof_view_path(&session_id), link.expires.as_deref(), link.sig.as_deref()).is_ok());
+    if !signed_link {
+        if let Some(rejection) = admin_rejection(&headers) {
+            return rejection;
+        }
+    }
+    let Some(stored) = SESSION_PROOFS.session(&session_id) else {
+        return (StatusCode::NOT_FOUND, format!("No proofs for session {}", session_id)).into_response();
+    };
+    let server = AgentAMcp::new();
+    let mut proofs = Vec::with_capacity(stored.len());
+    for proof in stored {
+        let key = (session_id.clone(), proof.proof_id.clone());
+        let cached = PROOF_VIEW_CHECKS.read().unwrap().get(&key).cloned();
+        let status = match (cached, proof_view::sp1_parts(&proof)) {
+            (Some(status), _) => status,
+            (None, Some((proof_hex, public_values, vk_hash))) => {
+                let check = verify_attestation(&server.attester_url, proof_hex, public_values, vk_hash);
+                let status = match tokio::time::timeout(PROOF_VIEW_VERIFY_TIMEOUT, check).await {
+                    Ok(Ok(result)) if result.valid => ProofStatus::Verified,
+                    Ok(Ok(result)) => ProofStatus::Rejected(result.error.unwrap_or_else(|| "invalid proof".to_string())),
+                    Ok(Err(e)) => ProofStatus::Unchecked(format!("attester unavailable ({})", e)),
+                    Err(_) => ProofStatus::Unchecked("attester timed out".to_string()),
+                };
+                // An unreachable attester is retried on the next view
+                if !matches!(status, ProofStatus::Unchecked(_)) {
+                    PROOF_VIEW_CHECKS.write().unwrap().insert(key, status.clone());
+                }
+                status
+            }
+            (None, None) if proof.verification.scheme == "sp1_groth16" => {
+                ProofStatus::Rejected("proof, public values or VK hash missing".to_string())
+            }
+            (None, None) => ProofStatus::Unchecked("issued by the Payment Agent".to_string()),
+        };
+        proofs.push((proof, status));
+    }
+    Html(proof_view::render(&session_id, &proofs)).into_response()
+}
+
+#[derive(Debug, Deserialize)]
+struct ProofLinkRequest {
+    /// Lifetime of the link; 24h by default, 30 days at most
+    #[serde(default)]
+    ttl_secs: Option<u64>,
+}
+
+/// Signed link to a session's proof viewer, for sharing the audit page with
+/// people who don't hold the admin token; requires
+/// `Authorization: Bearer $ADMIN_TOKEN` and `AGENT_HMAC_SECRET`
+async fn http_proof_link(
+    Path(session_id): Path<String>,
+    headers: HeaderMap,
+    Query(request): Query<ProofLinkRequest>,
+) -> impl IntoResponse {
+    if let Some(rejection) = admin_rejection(&headers) {
+        return rejection;
+    }
+    let Some(key) = SigningKey::from_env() else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(HttpResponse::<()>::err("Proof links are disabled: AGENT_HMAC_SECRET not set")),
+        )
+            .into_response();
+    };
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let expires = now + request.ttl_secs.unwrap_or(PROOF_LINK_TTL_SECS).min(MAX_PROOF_LINK_TTL_SECS);
+    let path = proof_view_path(&session_id);
+    let url = format!("{}?expires={}&sig={}", path, expires, key.link_signature(&path, expires));
+    tracing::info!("[PROOFS] Viewer link for session {} until {}", session_id, expires);
+    (StatusCode::CREATED, Json(HttpResponse::ok(json!({ "url": url, "expires": expires })))).into_response()
+}
+
+/// Payment events of every session, for reconciliation
+static LEDGER: LazyLock<Ledger> = LazyLock::new(Ledger::default);
+
+async fn http_record_ledger_entry(
+    Path(session_id): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> impl IntoResponse {
+    if let Some(rejection) = session_record_rejection(&session_id, &headers, &body) {
+        return rejection;
+    }
+    let Json(entry) = match Json::<LedgerEntry>::from_bytes(&body) {
+        Ok(json) => json,
+        Err(rejection) => return rejection.into_response(),
+    };
+    tracing::info!("[LEDGER] {:?} {} ({}) for session {}", entry.kind, entry.reference, entry.outcome, session_id);
+    LEDGER.record(&session_id, entry.clone());
+    (StatusCode::CREATED, Json(HttpResponse::ok(entry))).into_response()
+}
+
+#[derive(Debug, Deserialize)]
+struct LedgerQuery {
+    /// "json" (default) or "csv"
+    #[serde(default)]
+    format: Option<String>,
+}
+
+async fn http_ledger(
+    Path(session_id): Path<String>,
+    headers: HeaderMap,
+    Query(query): Query<LedgerQuery>,
+) -> impl IntoResponse {
+    if let Some(rejection) = admin_rejection(&headers) {
+        return rejection;
+    }
+    let Some(entries) = LEDGER.entries(&session_id) else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(HttpResponse::<()>::err(format!("No ledger entries for session {}", session_id))),
+        )
+            .into_response();
+    };
+    match query.format.as_deref() {
+        None | Some("json") => (StatusCode::OK, Json(HttpResponse::ok(entries))).into_response(),
+        Some("csv") => (
+            StatusCode::OK,
+            [
+                (header::CONTENT_TYPE, "text/csv".to_string()),
+                (header::CONTENT_DISPOSITION, format!("attachment; filename=\"ledger-{}.csv\"", session_id)),
+            ],
+            ledger::to_csv(&entries),
+        )
+            .into_response(),
+        Some(other) => (
+            StatusCode::BAD_REQUEST,
+            Json(HttpResponse::<()>::err(format!("Unsupported ledger format {} (use json or csv)", other))),
+        )
+            .into_response(),
+    }
+}
+
+/// Response refusing a request without `Authorization: Bearer $ADMIN_TOKEN`
+fn admin_rejection(headers: &HeaderMap) -> Option<Response> {
+    let Some(token) = AdminToken::from_env() else {
+        return Some(
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(HttpResponse::<()>::err("Admin endpoints are disabled: ADMIN_TOKEN not set")),
+            )
+                .into_response(),
+        );
+    };
+    if !token.verify(headers.get(header::AUTHORIZATION).and_then(|v| v.to_str().ok())) {
+        tracing::warn!("Rejected admin request: missing or wrong bearer token");
+        return Some((StatusCode::UNAUTHORIZED, Json(HttpResponse::<()>::err("invalid admin token"))).into_response());
+    }
+    None
+}
+
+/// Response refusing a session record the CLI POSTed without a valid HMAC
+/// signature (`AGENT_HMAC_SECRET`, as on its calls to Agent B and the
+/// attester). Without the secret session records are refused with 503.
+fn session_record_rejection(session_id: &str, headers: &HeaderMap, body: &[u8]) -> Option<Response> {
+    let Some(key) = SigningKey::from_env() else {
+        return Some(
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(HttpResponse::<()>::err("Session records are disabled: AGENT_HMAC_SECRET not set")),
+            )
+                .into_response(),
+        );
+    };
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+    if let Err(e) = key.verify(header(TIMESTAMP_HEADER), header(SIGNATURE_HEADER), body) {
+        tracing::warn!("Rejected session record for {}: {}", session_id, e);
+        return Some((StatusCode::UNAUTHORIZED, Json(HttpResponse::<()>::err(e))).into_response());
+    }
+    None
+}
+
+#[derive(Debug, Deserialize)]
+struct AdminSessionsQuery {
+    /// Only sessions idle for at least this many seconds
+    #[serde(default)]
+    idle_secs: Option<u64>,
+}
+
+/// Every session the server holds, most recently active first; requires
+/// `Authorization: Bearer $ADMIN_TOKEN`
+async fn http_admin_sessions(
+    headers: HeaderMap,
+    Query(query): Query<AdminSessionsQuery>,
+) -> impl IntoResponse {
+    if let Some(rejection) = admin_rejection(&headers) {
+        return rejection;
+    }
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let messages = MESSAGES.read().unwrap();
+    let receipts = PAYMENT_RECEIPTS.read().unwrap();
+    let usage = CLAUDE_USAGE.read().unwrap();
+    let mut session_ids: Vec<String> = messages
+        .keys()
+        .chain(receipts.keys())
+        .chain(usage.keys())
+        .cloned()
+        .chain(SESSION_PROOFS.session_ids())
+        .chain(LEDGER.session_ids())
+        .collect();
+    session_ids.sort();
+    session_ids.dedup();
+
+    let mut summaries: Vec<SessionSummary> = session_ids
+        .iter()
+        .map(|session_id| {
+            let proofs = SESSION_PROOFS.session(session_id).unwrap_or_default();
+            let ledger = LEDGER.entries(session_id).unwrap_or_default();
+            let activity = SessionActivity {
+                messages: messages.get(session_id).map(Vec::as_slice).unwrap_or_default(),
+                proofs: &proofs,
+                ledger: &ledger,
+                receipts: receipts.get(session_id).map(Vec::as_slice).unwrap_or_default(),
+                usage: usage.get(session_id),
+            };
+            sessions::summarize(session_id, activity)
+        })
+        .filter(|summary| query.idle_secs.is_none_or(|idle| now.saturating_sub(summary.last_activity) >= idle))
+        .collect();
+    summaries.sort_by_key(|summary| std::cmp::Reverse(summary.last_activity));
+    (StatusCode::OK, Json(HttpResponse::ok(summaries))).into_response()
+}
+
+/// Audit events, most recent `limit` matching `kind`, `subject`, `passed`
+/// and `since`, oldest first
+async fn http_audit(
+    headers: HeaderMap,
+    Query(query): Query<AuditQuery>,
+) -> impl IntoResponse {
+    if let Some(rejection) = admin_rejection(&headers) {
+        return rejection;
+    }
+    let Some(log) = audit::get() else {
+        return (StatusCode::SERVICE_UNAVAILABLE, Json(HttpResponse::<()>::err("Audit log is not open"))).into_response();
+    };
+    match log.query(&query) {
+        Ok(events) => (StatusCode::OK, Json(HttpResponse::ok(events))).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(HttpResponse::<()>::err(e))).into_response(),
+    }
+}
+
+/// Check the audit log's hash chain; 409 names the first broken entry
+async fn http_audit_verify(
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if let Some(rejection) = admin_rejection(&headers) {
+        return rejection;
+    }
+    let Some(log) = audit::get() else {
+        return (StatusCode::SERVICE_UNAVAILABLE, Json(HttpResponse::<()>::err("Audit log is not open"))).into_response();
+    };
+    match log.verify() {
+        Ok(events) => (StatusCode::OK, Json(HttpResponse::ok(json!({ "intact": true, "events": events })))).into_response(),
+        Err(e) => {
+            tracing::error!("{}", e);
+            (StatusCode::CONFLICT, Json(HttpResponse::<()>::err(e))).into_response()
+        }
+    }
+}
+
+/// Latest event the Payment Agent pushed for each purchase instruction, with
+/// when it arrived; removed once a client fetches it
+static PAYMENT_EVENTS: LazyLock<RwLock<HashMap<String, (u64, Value)>>> = LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// How long an event nobody fetched is kept
+const PAYMENT_EVENT_TTL_SECS: u64 = 10 * 60;
+
+/// Wakes clients waiting on `GET /webhooks/payment/:instruction_id`
+static PAYMENT_EVENT_ARRIVED: LazyLock<tokio::sync::Notify> = LazyLock::new(tokio::sync::Notify::new);
+
+/// Longest a client may wait for a payment event in one request
+const MAX_PAYMENT_EVENT_WAIT_SECS: u64 = 30;
+
+/// Secret the Payment Agent signs webhooks with (`PAYMENT_WEBHOOK_SECRET`,
+/// falling back to `AGENT_HMAC_SECRET`)
+fn payment_webhook_key() -> Option<SigningKey> {
+    std::env::var("PAYMENT_WEBHOOK_SECRET")
+        .ok()
+        .filter(|s| !s.is_empty())
+        .map(SigningKey::new)
+        .or_else(SigningKey::from_env)
+}
+
+/// Settlement or decline event from the Payment Agent, HMAC-signed like
+/// inter-agent calls (`X-ZP-Timestamp` / `X-ZP-Signature`)
+async fn http_payment_webhook(
+    headers: HeaderMap,
+    body: Bytes,
+) -> impl IntoResponse {
+    let Some(key) = payment_webhook_key() else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(HttpResponse::<()>::err("Payment webhooks are disabled: PAYMENT_WEBHOOK_SECRET not set")),
+        )
+            .into_response();
+    };
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+    if let Err(e) = key.verify(header(TIMESTAMP_HEADER), header(SIGNATURE_HEADER), &body) {
+        tracing::warn!("Rejected payment webhook: {}", e);
+        audit::record(AuditKind::PaymentVerification, false, "webhook", &format!("rejected payment webhook: {}", e));
+        return (StatusCode::UNAUTHORIZED, Json(HttpResponse::<()>::err(e))).into_response();
+    }
+
+    let event: Value = match serde_json::from_slice(&body) {
+        Ok(event) => event,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(HttpResponse::<()>::err(e))).into_response(),
+    };
+    let Some(instruction_id) = event["instructionId"].as_str().map(str::to_string) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(HttpResponse::<()>::err("Payment event has no instructionId")),
+        )
+            .into_response();
+    };
+
+    tracing::info!("[PAYMENT-WEBHOOK] {} for instruction {}", event["status"], instruction_id);
+    audit::record(AuditKind::PaymentVerification, true, &instruction_id, &format!("signed payment event {}", event["status"]));
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    {
+        let mut events = PAYMENT_EVENTS.write().unwrap();
+        events.retain(|_, (received_at, _)| now < *received_at + PAYMENT_EVENT_TTL_SECS);
+        events.insert(instruction_id, (now, event));
+    }
+    PAYMENT_EVENT_ARRIVED.notify_waiters();
+    (StatusCode::OK, Json(HttpResponse::ok(json!({ "received": true })))).into_response()
+}
+
+#[derive(Debug, Deserialize)]
+struct PaymentEventQuery {
+    /// Seconds to wait for an event before answering 204
+    #[serde(default)]
+    wait_secs: u64,
+}
+
+/// Latest pushed event for an instruction, waiting up to `wait_secs` for one
+/// to arrive (204 if none did). The client signs the instruction ID like a
+/// request body (`X-ZP-Timestamp` / `X-ZP-Signature` under
+/// `AGENT_HMAC_SECRET`); an event is handed out once.
+async fn http_payment_event(
+    Path(instruction_id): Path<String>,
+    headers: HeaderMap,
+    Query(query): Query<PaymentEventQuery>,
+) -> impl IntoResponse {
+    let Some(key) = SigningKey::from_env() else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(HttpResponse::<()>::err("Payment events are disabled: AGENT_HMAC_SECRET not set")),
+        )
+            .into_response();
+    };
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+    if let Err(e) = key.verify(header(TIMESTAMP_HEADER), header(SIGNATURE_HEADER), instruction_id.as_bytes()) {
+        tracing::warn!("Rejected payment event request for {}: {}", instruction_id, e);
+        return (StatusCode::UNAUTHORIZED, Json(HttpResponse::<()>::err(e))).into_response();
+    }
+
+    let deadline = tokio::time::Instant::now()
+        + std::time::Duration::from_secs(query.wait_secs.min(MAX_PAYMENT_EVENT_WAIT_SECS));
+    loop {
+        // Register for the wakeup before looking, so an event arriving in
+        // between isn't missed
+        let arrived = PAYMENT_EVENT_ARRIVED.notified();
+        tokio::pin!(arrived);
+        arrived.as_mut().enable();
+
+        if let Some((_, event)) = PAYMENT_EVENTS.write().unwrap().remove(&instruction_id) {
+            return (StatusCode::OK, Json(HttpResponse::ok(event))).into_response();
+        }
+        if tokio::time::timeout_at(deadline, arrived).await.is_err() {
+            return StatusCode::NO_CONTENT.into_response();
+        }
+    }
+}
+
+/// Run each request in a span continuing the caller's trace
+async fn trace_request(request: Request, next: Next) -> Response {
+    let span = telemetry::request_span(request.method(), request.uri().path(), request.headers());
+    next.run(request).instrument(span).await
+}
+
+/// Cross-origin policy for the HTTP API: any origin, or only `allowed_origins`
+fn cors_layer(network: &zp_config::NetworkSettings) -> CorsLayer {
+    if network.allows_any_origin() {
+        return CorsLayer::permissive();
+    }
+    let origins: Vec<HeaderValue> = network.allowed_origins.iter().filter_map(|o| o.parse().ok()).collect();
+    CorsLayer::new().allow_origin(origins).allow_methods(Any).allow_headers(Any)
+}
+
 /// Start HTTP server
 async fn start_http_server() -> Result<()> {
-    let port = std::env::var("AGENT_A_SERVER_PORT")
-        .unwrap_or_else(|_| "3001".to_string())
-        .parse::<u16>()
-        .unwrap_or(3001);
+    let port = zp_config::get().agent_a.port;
 
     let app = Router::new()
         .route("/health", get(health))
+        .route("/health/dependencies", get(health_dependencies))
+        .route("/health/ready", get(health_ready))
         .route("/tools", get(list_tools_http))
         .route("/tools/get_ticket_price", post(http_get_ticket_price))
         .route("/tools/format_zk_input", post(http_format_zk_input))
         .route("/tools/request_attestation", post(http_request_attestation))
+        .route("/tools/prove_ticket_price", post(http_prove_ticket_price))
         .route("/tools/verify_on_chain", post(http_verify_on_chain))
-        .layer(CorsLayer::permissive());
+        .route("/tools/verify_attestation", post(http_verify_attestation))
+        .route("/tools/estimate_attestation", post(http_estimate_attestation))
+        .route("/webhooks/payment", post(http_payment_webhook))
+        .route("/webhooks/payment/:instruction_id", get(http_payment_event))
+        .route(
+            "/session/:session_id/payment-receipt",
+            get(http_payment_receipts).post(http_store_payment_receipt),
+        )
+        .route(
+            "/session/:session_id/proofs",
+            get(http_session_proofs).post(http_store_proof),
+        )
+        .route(
+            "/session/:session_id/messages",
+            get(http_messages).post(http_store_message),
+        )
+        .route("/sessions/:session_id/messages", get(http_messages))
+        .route("/proofs/session/:session_id/view", get(http_proof_view))
+        .route("/admin/sessions/:session_id/proof-link", post(http_proof_link))
+        .route("/mcp", post(http_mcp))
+        .route("/admin/sessions", get(http_admin_sessions))
+        .route("/admin/audit", get(http_audit))
+        .route("/admin/audit/verify", get(http_audit_verify))
+        .route(
+            "/session/:session_id/ledger",
+            get(http_ledger).post(http_record_ledger_entry),
+        )
+        .route(
+            "/session/:session_id/usage",
+            get(http_usage).post(http_record_usage),
+        )
+        .layer(middleware::from_fn(trace_request))
+        .layer(cors_layer(&zp_config::get().network));
 
-    let listener = tokio::net::TcpListener::bind(format!("0.0.0.0:{}", port)).await?;
+    // Surface misconfigured peers before a user hits them mid-booking
+    println!("Checking dependencies...");
+    for dependency in AgentAMcp::new().probe_dependencies().await {
+        match &dependency.error {
+            None => println!("  ✓ {:<9} {} ({} ms)", dependency.name, dependency.url, dependency.latency_ms),
+            Some(e) => println!("  ✗ {:<9} {}: {}", dependency.name, dependency.url, e),
+        }
+    }
+
+    let tls = MtlsConfig::from_env();
+    let scheme = if tls.is_some() { "https" } else { "http" };
 
     println!("\n╔════════════════════════════════════════════════════════════╗");
     println!("║           Agent A - HTTP Server                            ║");
     println!("╚════════════════════════════════════════════════════════════╝\n");
-    println!("✓ Server listening on http://0.0.0.0:{}\n", port);
+    println!("✓ Server listening on {}://0.0.0.0:{}\n", scheme, port);
+    if tls.is_some() {
+        println!("  mTLS enabled: clients must present a certificate signed by TLS_CA\n");
+    }
     println!("Endpoints:");
     println!("  GET    http://localhost:{}/health", port);
+    println!("  GET    http://localhost:{}/health/dependencies", port);
+    println!("  GET    http://localhost:{}/health/ready", port);
     println!("  GET    http://localhost:{}/tools", port);
     println!("  POST   http://localhost:{}/tools/get_ticket_price", port);
     println!("  POST   http://localhost:{}/tools/format_zk_input", port);
     println!("  POST   http://localhost:{}/tools/request_attestation", port);
-    println!("  POST   http://localhost:{}/tools/verify_on_chain\n", port);
+    println!("  POST   http://localhost:{}/tools/prove_ticket_price", port);
+    println!("  POST   http://localhost:{}/tools/verify_on_chain", port);
+    println!("  POST   http://localhost:{}/session/:id/{{payment-receipt,ledger,proofs,messages,usage}}  (HMAC-signed)", port);
+    println!("  GET    http://localhost:{}/session/:id/payment-receipt  (Bearer $ADMIN_TOKEN)", port);
+    println!("  GET    http://localhost:{}/session/:id/ledger[?format=csv]  (Bearer $ADMIN_TOKEN)", port);
+    println!("  GET    http://localhost:{}/session/:id/proofs  (Bearer $ADMIN_TOKEN)", port);
+    println!("  GET    http://localhost:{}/proofs/session/:id/view  (HTML, Bearer $ADMIN_TOKEN or signed link)", port);
+    println!("  POST   http://localhost:{}/admin/sessions/:id/proof-link[?ttl_secs=]  (Bearer $ADMIN_TOKEN)", port);
+    println!("  GET    http://localhost:{}/session[s]/:id/messages[?offset=&limit=]  (Bearer $ADMIN_TOKEN)", port);
+    println!("  GET    http://localhost:{}/session/:id/usage  (Claude tokens, Bearer $ADMIN_TOKEN)", port);
+    println!("  POST   http://localhost:{}/mcp  (MCP JSON-RPC: tools, proof:// resources)", port);
+    println!("  POST   http://localhost:{}/webhooks/payment", port);
+    println!("  GET    http://localhost:{}/webhooks/payment/:instruction_id[?wait_secs=]  (HMAC-signed)", port);
+    println!("  GET    http://localhost:{}/admin/sessions[?idle_secs=]  (Bearer $ADMIN_TOKEN)", port);
+    println!("  GET    http://localhost:{}/admin/audit[?kind=&subject=&passed=&since=&limit=]", port);
+    println!("  GET    http://localhost:{}/admin/audit/verify\n", port);
 
-    axum::serve(listener, app).await?;
+    match tls {
+        Some(tls) => {
+            let config = RustlsConfig::from_config(Arc::new(tls.server_config()?));
+            let handle = axum_server::Handle::new();
+            tokio::spawn(drain_on_signal(handle.clone()));
+            axum_server::bind_rustls(([0, 0, 0, 0], port).into(), config)
+                .handle(handle)
+                .serve(app.into_make_service())
+                .await?;
+        }
+        None => {
+            let listener = tokio::net::TcpListener::bind(format!("0.0.0.0:{}", port)).await?;
+            axum::serve(listener, app)
+                .with_graceful_shutdown(shutdown::signal())
+                .await?;
+        }
+    }
 
+    println!("✓ Agent A stopped");
     Ok(())
 }
+
+/// Stop accepting connections on SIGINT/SIGTERM and let in-flight requests finish
+async fn drain_on_signal(handle: axum_server::Handle) {
+    shutdown::signal().await;
+    handle.graceful_shutdown(Some(shutdown::DRAIN_TIMEOUT));
+}
@@ -0,0 +1,226 @@
+//! Proof viewer page
+//!
+//! `GET /proofs/session/{id}/view` renders a session's stored proofs as a
+//! standalone HTML page: workflow stage, the trace with identifiers masked
+//! and proof bytes shortened, whether the proof verifies, and whether the
+//! ZeroProof contract could verify it on-chain. Meant to be shared as an
+//! audit record with people who don't run the frontend.
+
+use serde_json::Value;
+
+use crate::proofs::StoredProof;
+
+/// Outcome of checking a stored proof when the page is rendered
+#[derive(Debug, Clone)]
+pub enum ProofStatus {
+    Verified,
+    Rejected(String),
+    /// Not checked, and why
+    Unchecked(String),
+}
+
+/// Trace keys whose values are masked (case-insensitive, ignoring `_`)
+const SENSITIVE_KEYS: &[&str] = &[
+    "sessionid",
+    "tokenid",
+    "instructionid",
+    "transactionreferenceid",
+    "enrollmentreferenceid",
+    "paymentreference",
+    "refundreference",
+    "consumerid",
+    "email",
+    "passengeremail",
+    "credentials",
+];
+
+/// Hex values longer than this are shortened on the page
+const MAX_HEX_CHARS: usize = 66;
+
+/// Proof, public values and VK hash of an SP1 proof, if the trace has all three
+pub fn sp1_parts(proof: &StoredProof) -> Option<(&str, &str, &str)> {
+    if proof.verification.scheme != "sp1_groth16" {
+        return None;
+    }
+    let field = |key: &str| proof.trace[key].as_str().filter(|s| !s.is_empty());
+    Some((field("proof")?, field("public_values")?, proof.verification.vk_hash.as_deref()?))
+}
+
+/// How the proof can be verified on-chain, or why it can't
+pub fn on_chain_compatibility(proof: &StoredProof) -> Result<String, String> {
+    if proof.verification.scheme != "sp1_groth16" {
+        return Err("Off-chain only: issued by the Payment Agent".to_string());
+    }
+    let (proof_hex, public_values_hex, vk_hash) = sp1_parts(proof).ok_or("Proof, public values or VK hash missing")?;
+    let bytes = |s: &str| hex::decode(s.strip_prefix("0x").unwrap_or(s));
+    if bytes(vk_hash).map(|b| b.len()) != Ok(32) {
+        return Err(format!("VK hash {} is not 32 bytes", vk_hash));
+    }
+    if bytes(proof_hex).is_err() || bytes(public_values_hex).is_err() {
+        return Err("Proof or public values are not hex".to_string());
+    }
+    match &proof.verification.verifier_address {
+        Some(address) => Ok(format!("ZeroProof.verifyProof (sp1-zkvm, Groth16) at {}", address)),
+        None => Err("No ZeroProof verifier configured".to_string()),
+    }
+}
+
+/// Copy of a trace with identifiers masked and long hex shortened
+pub fn redact(trace: &Value) -> Value {
+    match trace {
+        Value::Object(map) => map
+            .iter()
+            .map(|(key, v)| {
+                let normalized: String = key.chars().filter(|c| *c != '_').flat_map(char::to_lowercase).collect();
+                let v = if SENSITIVE_KEYS.contains(&normalized.as_str()) { mask(v) } else { redact(v) };
+                (key.clone(), v)
+            })
+            .collect(),
+        Value::Array(items) => items.iter().map(redact).collect(),
+        Value::String(s) if s.len() > MAX_HEX_CHARS && s.trim_start_matches("0x").chars().all(|c| c.is_ascii_hexdigit()) => {
+            Value::String(format!("{}… ({} bytes)", &s[..MAX_HEX_CHARS / 2], s.trim_start_matches("0x").len() / 2))
+        }
+        other => other.clone(),
+    }
+}
+
+/// Strings keep their last 4 characters; anything structured is replaced
+fn mask(v: &Value) -> Value {
+    match v {
+        Value::Null => Value::Null,
+        Value::String(s) if s.chars().count() > 8 => {
+            let tail: String = s.chars().skip(s.chars().count() - 4).collect();
+            Value::String(format!("****{}", tail))
+        }
+        _ => Value::String("****".to_string()),
+    }
+}
+
+/// The page for `session_id`, one section per proof in recorded order
+pub fn render(session_id: &str, proofs: &[(StoredProof, ProofStatus)]) -> String {
+    let mut sections = String::new();
+    for (proof, status) in proofs {
+        let (status_class, status_text) = match status {
+            ProofStatus::Verified => ("ok", "Verified".to_string()),
+            ProofStatus::Rejected(reason) => ("bad", format!("Rejected: {}", reason)),
+            ProofStatus::Unchecked(reason) => ("muted", format!("Not checked: {}", reason)),
+        };
+        let (chain_class, chain_text) = match on_chain_compatibility(proof) {
+            Ok(how) => ("ok", format!("Compatible: {}", how)),
+            Err(why) => ("muted", why),
+        };
+        let trace = serde_json::to_string_pretty(&redact(&proof.trace)).unwrap_or_default();
+        sections.push_str(&format!(
+            r#"<section>
+  <h2>{proof_id}</h2>
+  <table>
+    <tr><th>Workflow stage</th><td>{stage}</td></tr>
+    <tr><th>Recorded</th><td>{recorded}</td></tr>
+    <tr><th>Scheme</th><td>{scheme}</td></tr>
+    <tr><th>Verification</th><td class="{status_class}">{status}</td></tr>
+    <tr><th>On-chain</th><td class="{chain_class}">{chain}</td></tr>
+  </table>
+  <pre>{trace}</pre>
+</section>
+"#,
+            proof_id = escape(&proof.proof_id),
+            stage = escape(proof.workflow_stage.as_deref().unwrap_or("-")),
+            recorded = utc(proof.recorded_at),
+            scheme = escape(&proof.verification.scheme),
+            status = escape(&status_text),
+            chain = escape(&chain_text),
+            trace = escape(&trace),
+        ));
+    }
+
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>Proofs for session {session}</title>
+<style>
+  body {{ font-family: system-ui, sans-serif; max-width: 60rem; margin: 2rem auto; padding: 0 1rem; color: #222; }}
+  section {{ border: 1px solid #ddd; border-radius: 6px; padding: 0 1rem 1rem; margin-bottom: 1.5rem; }}
+  th {{ text-align: left; padding-right: 1.5rem; font-weight: 600; }}
+  pre {{ background: #f6f8fa; padding: 0.75rem; overflow-x: auto; font-size: 0.85rem; }}
+  .ok {{ color: #1a7f37; }}
+  .bad {{ color: #cf222e; }}
+  .muted {{ color: #666; }}
+</style>
+</head>
+<body>
+<h1>Proofs for session {session}</h1>
+<p class="muted">{count} proof(s). Identifiers are masked and proof bytes shortened.</p>
+{sections}</body>
+</html>
+"#,
+        session = escape(session_id),
+        count = proofs.len(),
+        sections = sections,
+    )
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Unix seconds as `YYYY-MM-DD HH:MM:SS UTC`
+fn utc(secs: u64) -> String {
+    // Civil date from days since the epoch (Howard Hinnant's algorithm)
+    let days = (secs / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let doe = days.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    let time = secs % 86_400;
+    format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC", year, month, day, time / 3600, time / 60 % 60, time % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proofs::SessionProofs;
+    use serde_json::json;
+
+    #[test]
+    fn test_render_masks_and_checks_compatibility() {
+        let proofs = SessionProofs::default();
+        let sp1 = proofs.record(
+            "sess_1",
+            json!({
+                "kind": "sp1_pricing_proof",
+                "workflow_stage": "pricing_zk",
+                "vk_hash": format!("0x{}", "ab".repeat(32)),
+                "proof": "11".repeat(200),
+                "public_values": "0x22",
+            }),
+            "0xverifier",
+            1760600000,
+        );
+        let payment = proofs.record(
+            "sess_1",
+            json!({ "kind": "payment_credentials", "instruction_id": "instr_0123456789", "proof": "<script>" }),
+            "0xverifier",
+            1760600001,
+        );
+
+        assert!(on_chain_compatibility(&sp1).unwrap().contains("0xverifier"));
+        assert!(on_chain_compatibility(&payment).is_err());
+        assert_eq!(redact(&payment.trace)["instruction_id"], "****6789");
+        assert_eq!(redact(&sp1.trace)["proof"], format!("{}… (200 bytes)", "1".repeat(33)));
+
+        let page = render("sess_1", &[(sp1, ProofStatus::Verified), (payment, ProofStatus::Unchecked("issued by the Payment Agent".into()))]);
+        assert!(page.contains("2025-10-16 07:33:20 UTC"));
+        assert!(page.contains("&lt;script&gt;"));
+        assert!(!page.contains("instr_0123456789"));
+    }
+}
@@ -0,0 +1,154 @@
+//! Session proof store
+//!
+//! Every cryptographic trace of a session that carries a proof (card
+//! enrollment, payment credentials, refunds, card removal, the SP1 pricing
+//! proof), as reported by the client. Exposed over MCP as resources named
+//! `proof://{session_id}/{proof_id}`, so hosts can inspect the trail without
+//! the client's traces file.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+
+/// URI scheme of proof resources
+pub const URI_SCHEME: &str = "proof://";
+
+/// How a stored proof can be checked
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ProofVerification {
+    /// `sp1_groth16` for zkVM proofs, `payment_agent` for proofs the Payment
+    /// Agent issued
+    pub scheme: String,
+    /// VK hash the on-chain verifier checks the proof against
+    pub vk_hash: Option<String>,
+    /// Guest program that produced the proof
+    pub program_id: Option<String>,
+    /// ZeroProof contract that verifies SP1 proofs
+    pub verifier_address: Option<String>,
+}
+
+/// One proof-bearing trace of a session
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct StoredProof {
+    /// `<kind>-<n>`, unique within the session
+    pub proof_id: String,
+    /// Trace kind, e.g. sp1_pricing_proof or payment_credentials
+    pub kind: String,
+    pub workflow_stage: Option<String>,
+    /// Unix time the server received it
+    pub recorded_at: u64,
+    pub verification: ProofVerification,
+    /// The trace as the client recorded it, proof included
+    pub trace: Value,
+}
+
+/// Proofs by session ID, kept for the life of the server
+#[derive(Debug, Default)]
+pub struct SessionProofs {
+    sessions: RwLock<BTreeMap<String, Vec<StoredProof>>>,
+}
+
+impl SessionProofs {
+    /// Store a trace under the next ID for its kind; SP1 proofs point at
+    /// `verifier_address`
+    pub fn record(&self, session_id: &str, trace: Value, verifier_address: &str, now: u64) -> StoredProof {
+        let kind = trace["kind"].as_str().unwrap_or("proof").to_string();
+        let mut sessions = self.sessions.write().unwrap();
+        let proofs = sessions.entry(session_id.to_string()).or_default();
+        let n = proofs.iter().filter(|p| p.kind == kind).count() + 1;
+
+        let verification = if kind.starts_with("sp1_") {
+            ProofVerification {
+                scheme: "sp1_groth16".to_string(),
+                vk_hash: trace["vk_hash"].as_str().map(str::to_string),
+                program_id: trace["program_id"].as_str().map(str::to_string),
+                verifier_address: Some(verifier_address.to_string()),
+            }
+        } else {
+            ProofVerification {
+                scheme: "payment_agent".to_string(),
+                vk_hash: None,
+                program_id: None,
+                verifier_address: None,
+            }
+        };
+        let proof = StoredProof {
+            proof_id: format!("{}-{}", kind, n),
+            workflow_stage: trace["workflow_stage"].as_str().map(str::to_string),
+            kind,
+            recorded_at: now,
+            verification,
+            trace,
+        };
+        proofs.push(proof.clone());
+        proof
+    }
+
+    /// The session's proofs in the order they were recorded, if it has any
+    pub fn session(&self, session_id: &str) -> Option<Vec<StoredProof>> {
+        self.sessions.read().unwrap().get(session_id).cloned()
+    }
+
+    pub fn session_ids(&self) -> Vec<String> {
+        self.sessions.read().unwrap().keys().cloned().collect()
+    }
+
+    /// Every stored proof as (session ID, proof), by session
+    pub fn all(&self) -> Vec<(String, StoredProof)> {
+        self.sessions
+            .read()
+            .unwrap()
+            .iter()
+            .flat_map(|(session_id, proofs)| proofs.iter().map(move |p| (session_id.clone(), p.clone())))
+            .collect()
+    }
+
+    pub fn get(&self, session_id: &str, proof_id: &str) -> Option<StoredProof> {
+        self.sessions
+            .read()
+            .unwrap()
+            .get(session_id)?
+            .iter()
+            .find(|p| p.proof_id == proof_id)
+            .cloned()
+    }
+}
+
+/// `proof://{session_id}/{proof_id}`
+pub fn uri(session_id: &str, proof_id: &str) -> String {
+    format!("{}{}/{}", URI_SCHEME, session_id, proof_id)
+}
+
+/// Session and proof ID named by a proof URI
+pub fn parse_uri(uri: &str) -> Option<(&str, &str)> {
+    uri.strip_prefix(URI_SCHEME)?
+        .split_once('/')
+        .filter(|(session_id, proof_id)| !session_id.is_empty() && !proof_id.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_record_and_address_proofs() {
+        let proofs = SessionProofs::default();
+        proofs.record("sess_1", json!({ "kind": "payment_credentials", "proof": "p1" }), "0xabc", 1);
+        let second = proofs.record("sess_1", json!({ "kind": "payment_credentials", "proof": "p2" }), "0xabc", 2);
+        let sp1 = proofs.record("sess_1", json!({ "kind": "sp1_pricing_proof", "vk_hash": "0x01" }), "0xabc", 3);
+
+        assert_eq!(second.proof_id, "payment_credentials-2");
+        assert_eq!(second.verification.scheme, "payment_agent");
+        assert_eq!(sp1.verification.verifier_address.as_deref(), Some("0xabc"));
+        assert_eq!(proofs.all().len(), 3);
+
+        let uri = uri("sess_1", &sp1.proof_id);
+        assert_eq!(uri, "proof://sess_1/sp1_pricing_proof-1");
+        let (session_id, proof_id) = parse_uri(&uri).unwrap();
+        assert_eq!(proofs.get(session_id, proof_id).unwrap().trace["vk_hash"], "0x01");
+        assert!(parse_uri("proof://sess_1/").is_none());
+    }
+}
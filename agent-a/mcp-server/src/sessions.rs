@@ -0,0 +1,150 @@
+//! Session overview for operators
+//!
+//! Combines what the server holds for a session (transcript, proofs, ledger,
+//! payment receipts) into one summary: the step it last reached, when, and
+//! what it has collected and spent, including the Claude tokens the CLI
+//! reported for it. Served by `GET /admin/sessions` to spot stuck bookings
+//! and abandoned sessions.
+
+use pricing_core::money::{minor_unit_exponent, Money};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::ledger::LedgerEntry;
+use crate::proofs::StoredProof;
+use crate::{ClaudeMessage, ClaudeUsage, PaymentReceipt};
+
+/// Where a session stands
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SessionSummary {
+    pub session_id: String,
+    /// Latest event: a proof's workflow stage, a payment step (`instruction`,
+    /// `credentials failed`, `payment_settled`, ...) or `conversation`
+    pub current_step: String,
+    /// Unix time of the latest event
+    pub last_activity: u64,
+    pub proof_count: usize,
+    pub message_count: usize,
+    /// Settled payments per currency, e.g. {"USD": "450.00"}
+    pub total_spend: BTreeMap<String, String>,
+    /// Claude calls and tokens reported for the session
+    pub claude_usage: ClaudeUsage,
+}
+
+/// Everything recorded for one session
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SessionActivity<'a> {
+    pub messages: &'a [ClaudeMessage],
+    pub proofs: &'a [StoredProof],
+    pub ledger: &'a [LedgerEntry],
+    pub receipts: &'a [PaymentReceipt],
+    pub usage: Option<&'a ClaudeUsage>,
+}
+
+pub fn summarize(session_id: &str, activity: SessionActivity) -> SessionSummary {
+    // Later sources win ties, so a payment step outranks the message that asked for it
+    let events = activity
+        .messages
+        .iter()
+        .map(|m| (m.timestamp, "conversation".to_string()))
+        .chain(activity.proofs.iter().map(|p| (p.recorded_at, p.workflow_stage.clone().unwrap_or_else(|| p.kind.clone()))))
+        .chain(activity.ledger.iter().map(|e| {
+            let step = if e.outcome == "success" {
+                e.kind.as_str().to_string()
+            } else {
+                format!("{} failed", e.kind.as_str())
+            };
+            (e.timestamp, step)
+        }))
+        .chain(activity.receipts.iter().map(|r| (r.timestamp, "payment_settled".to_string())));
+    let (last_activity, current_step) = events.max_by_key(|(timestamp, _)| *timestamp).unwrap_or_default();
+
+    let mut spend: BTreeMap<String, i64> = BTreeMap::new();
+    for receipt in activity.receipts {
+        match amount_minor(&receipt.amount, &receipt.currency) {
+            Some(amount) => *spend.entry(receipt.currency.clone()).or_default() += amount,
+            None => tracing::warn!("Receipt {} has an unreadable amount {}", receipt.instruction_id, receipt.amount),
+        }
+    }
+
+    SessionSummary {
+        session_id: session_id.to_string(),
+        current_step,
+        last_activity,
+        proof_count: activity.proofs.len(),
+        message_count: activity.messages.len(),
+        total_spend: spend
+            .into_iter()
+            .map(|(currency, minor)| {
+                let amount = Money::new(minor, &currency).to_string();
+                (currency, amount)
+            })
+            .collect(),
+        claude_usage: activity.usage.copied().unwrap_or_default(),
+    }
+}
+
+/// Decimal amount ("450", "450.5", "450.50" USD) in minor units of `currency`
+fn amount_minor(amount: &str, currency: &str) -> Option<i64> {
+    let exponent = minor_unit_exponent(currency) as usize;
+    let (units, fraction) = amount.trim().split_once('.').unwrap_or((amount.trim(), ""));
+    if fraction.len() > exponent || !fraction.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let fraction: i64 = if exponent == 0 { 0 } else { format!("{:0<width$}", fraction, width = exponent).parse().ok()? };
+    units.parse::<i64>().ok()?.checked_mul(10i64.pow(exponent as u32))?.checked_add(fraction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ledger::LedgerEventKind;
+
+    fn receipt(amount: &str, currency: &str, timestamp: u64) -> PaymentReceipt {
+        PaymentReceipt {
+            instruction_id: format!("instr_{}", timestamp),
+            payment_method: "•••• 4242".to_string(),
+            amount: amount.to_string(),
+            currency: currency.to_string(),
+            transaction_reference_id: None,
+            proof_id: None,
+            timestamp,
+            settlement_amount: None,
+            settlement_currency: None,
+            fx_rate: None,
+        }
+    }
+
+    #[test]
+    fn test_summarize_session() {
+        let messages = [ClaudeMessage { role: "user".to_string(), content: "Book NYC to LON".to_string(), timestamp: 100 }];
+        let ledger = [LedgerEntry {
+            kind: LedgerEventKind::Credentials,
+            reference: "txn_1".to_string(),
+            external_id: None,
+            amount: Some("120.00".to_string()),
+            currency: Some("EUR".to_string()),
+            proof_id: None,
+            outcome: "timed out".to_string(),
+            timestamp: 300,
+        }];
+        let receipts = [
+            receipt("450.5", "USD", 200),
+            receipt("49.50", "USD", 250),
+            receipt("120", "EUR", 290),
+            receipt("45000", "JPY", 295),
+        ];
+
+        let usage = ClaudeUsage { calls: 2, input_tokens: 6_000, output_tokens: 900, ..Default::default() };
+        let activity = SessionActivity { messages: &messages, ledger: &ledger, receipts: &receipts, usage: Some(&usage), ..Default::default() };
+        let summary = summarize("sess_1", activity);
+        assert_eq!(summary.current_step, "credentials failed");
+        assert_eq!(summary.last_activity, 300);
+        assert_eq!(summary.message_count, 1);
+        assert_eq!(summary.total_spend["USD"], "500.00");
+        assert_eq!(summary.total_spend["EUR"], "120.00");
+        assert_eq!(summary.total_spend["JPY"], "45000");
+        assert_eq!(summary.claude_usage, usage);
+    }
+}
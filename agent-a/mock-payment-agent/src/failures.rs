@@ -0,0 +1,95 @@
+//! Failure injection for the mock Payment Agent
+//!
+//! `MOCK_PAYMENT_FAILURES` lists `<tool>=<failure>` pairs, e.g.
+//! `get-payment-status=declined,retrieve-payment-credentials=expired`.
+//! `health` stands for `GET /health`. The set can be replaced at runtime with
+//! `PUT /failures` and a `{ "<tool>": "<failure>" }` body.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{LazyLock, RwLock};
+
+/// How a tool (or the health check) misbehaves
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Failure {
+    /// Tool error in the response envelope
+    Error,
+    /// HTTP 503
+    Unavailable,
+    /// No answer for longer than the client waits
+    Timeout,
+    /// Payment status: declined by the issuer
+    Declined,
+    /// Payment status: OTP challenge until `complete-payment-challenge`
+    Challenge,
+    /// Credentials: the proof backing them has expired
+    Expired,
+    /// Health check: down for maintenance
+    Maintenance,
+}
+
+impl FromStr for Failure {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "error" => Ok(Failure::Error),
+            "unavailable" => Ok(Failure::Unavailable),
+            "timeout" => Ok(Failure::Timeout),
+            "declined" => Ok(Failure::Declined),
+            "challenge" => Ok(Failure::Challenge),
+            "expired" => Ok(Failure::Expired),
+            "maintenance" => Ok(Failure::Maintenance),
+            other => Err(format!("unknown failure '{}'", other)),
+        }
+    }
+}
+
+/// Parse a `<tool>=<failure>,...` list
+pub fn parse(spec: &str) -> Result<HashMap<String, Failure>, String> {
+    spec.split(',')
+        .map(str::trim)
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (tool, failure) = pair
+                .split_once('=')
+                .ok_or_else(|| format!("expected <tool>=<failure>, got '{}'", pair))?;
+            Ok((tool.trim().to_string(), failure.parse()?))
+        })
+        .collect()
+}
+
+static FAILURES: LazyLock<RwLock<HashMap<String, Failure>>> = LazyLock::new(|| {
+    let spec = std::env::var("MOCK_PAYMENT_FAILURES").unwrap_or_default();
+    RwLock::new(parse(&spec).unwrap_or_else(|e| panic!("invalid MOCK_PAYMENT_FAILURES: {}", e)))
+});
+
+/// Failure injected for `tool`, if any
+pub fn for_tool(tool: &str) -> Option<Failure> {
+    FAILURES.read().unwrap().get(tool).copied()
+}
+
+pub fn all() -> HashMap<String, Failure> {
+    FAILURES.read().unwrap().clone()
+}
+
+pub fn replace(failures: HashMap<String, Failure>) {
+    *FAILURES.write().unwrap() = failures;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_failures() {
+        let failures = parse("get-payment-status=declined, retrieve-payment-credentials=EXPIRED,").unwrap();
+        assert_eq!(failures["get-payment-status"], Failure::Declined);
+        assert_eq!(failures["retrieve-payment-credentials"], Failure::Expired);
+        assert!(parse("").unwrap().is_empty());
+        assert!(parse("enroll-card").is_err());
+        assert!(parse("enroll-card=sometimes").is_err());
+    }
+}
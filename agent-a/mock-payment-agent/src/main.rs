@@ -0,0 +1,374 @@
+//! Mock Payment Agent
+//!
+//! Stands in for the external Payment Agent (`PAYMENT_AGENT_URL`) during local
+//! development and integration tests. Serves the same HTTP surface Agent A's
+//! client uses: `GET /tools`, `GET /health`, `POST /tools/{name}` for the
+//! payment tools, and `GET /session/{id}` with the session's enrolled cards
+//! and purchase instructions. Everything is kept in memory; payments confirm
+//! straight away unless a failure is injected (see `failures`).
+//!
+//! ```text
+//! MOCK_PAYMENT_AGENT_PORT=3002 cargo run -p mock-payment-agent
+//! PAYMENT_AGENT_URL=http://localhost:3002 cargo run -p mcp-client
+//! ```
+
+mod failures;
+
+use axum::{
+    extract::{Json, Path},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Router,
+};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{LazyLock, RwLock};
+use std::time::Duration;
+use uuid::Uuid;
+use zk_protocol::{shutdown, telemetry};
+
+use failures::Failure;
+
+/// One-time code that completes an injected challenge
+const CHALLENGE_OTP: &str = "123456";
+
+/// Tool response envelope, as the real Payment Agent sends it
+#[derive(Serialize)]
+struct HttpResponse<T> {
+    success: bool,
+    data: Option<T>,
+    error: Option<String>,
+}
+
+impl<T> HttpResponse<T> {
+    fn ok(data: T) -> Self {
+        Self { success: true, data: Some(data), error: None }
+    }
+
+    fn err(error: impl std::fmt::Display) -> Self {
+        Self { success: false, data: None, error: Some(error.to_string()) }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Card {
+    token_id: String,
+    enrollment_reference: String,
+    last4: String,
+}
+
+#[derive(Debug, Clone)]
+struct Instruction {
+    instruction_id: String,
+    client_reference_id: String,
+    token_id: String,
+    amount: String,
+    currency: String,
+    status: &'static str,
+    challenge_completed: bool,
+}
+
+#[derive(Debug, Default)]
+struct Session {
+    consumer_id: String,
+    cards: Vec<Card>,
+    instructions: Vec<Instruction>,
+}
+
+/// Payment Agent sessions by session ID
+static SESSIONS: LazyLock<RwLock<HashMap<String, Session>>> = LazyLock::new(|| RwLock::new(HashMap::new()));
+
+const TOOLS: &[(&str, &str)] = &[
+    ("enroll-card", "Enroll the consumer's card (sessionId, consumerId, enrollmentReferenceId)"),
+    ("unenroll-card", "Remove an enrolled card (sessionId, consumerId, tokenId)"),
+    ("initiate-purchase-instruction", "Create a purchase instruction on an enrolled card (sessionId, consumerId, tokenId, amount, currency, merchant)"),
+    ("get-payment-status", "Settlement status of a purchase instruction (sessionId, instructionId)"),
+    ("complete-payment-challenge", "Complete a step-up challenge (sessionId, instructionId, challengeId, otp)"),
+    ("retrieve-payment-credentials", "Retrieve credentials of a confirmed instruction (sessionId, instructionId, transactionReferenceId)"),
+    ("confirm-transaction", "Confirm a completed transaction (sessionId, instructionId)"),
+    ("refund-payment", "Refund part or all of a purchase instruction (sessionId, instructionId, amount, currency)"),
+];
+
+fn new_id(prefix: &str) -> String {
+    format!("{}_{}", prefix, &Uuid::new_v4().simple().to_string()[..16])
+}
+
+fn proof(kind: &str, subject: Value) -> Value {
+    json!({ "id": new_id("prf"), "type": kind, "subject": subject, "issuer": "mock-payment-agent" })
+}
+
+fn arg<'a>(args: &'a Value, key: &str) -> Result<&'a str, String> {
+    args[key].as_str().ok_or_else(|| format!("missing {}", key))
+}
+
+async fn list_tools() -> impl IntoResponse {
+    let tools: Vec<Value> = TOOLS
+        .iter()
+        .map(|(name, description)| json!({ "name": name, "description": description }))
+        .collect();
+    Json(HttpResponse::ok(json!({ "tools": tools })))
+}
+
+async fn health() -> Response {
+    match failures::for_tool("health") {
+        Some(Failure::Maintenance) => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({ "status": "maintenance", "message": "Scheduled maintenance (injected)" })),
+        )
+            .into_response(),
+        Some(Failure::Unavailable | Failure::Error) => StatusCode::SERVICE_UNAVAILABLE.into_response(),
+        _ => Json(json!({ "status": "ok" })).into_response(),
+    }
+}
+
+async fn call_tool(Path(tool): Path<String>, Json(args): Json<Value>) -> Response {
+    let failure = failures::for_tool(&tool);
+    tracing::info!(tool_name = %tool, failure = ?failure, "tool call");
+    match failure {
+        Some(Failure::Error) => {
+            return (StatusCode::OK, Json(HttpResponse::<()>::err(format!("{} failed (injected)", tool)))).into_response()
+        }
+        Some(Failure::Unavailable) => {
+            return (StatusCode::SERVICE_UNAVAILABLE, Json(HttpResponse::<()>::err("Payment Agent unavailable (injected)")))
+                .into_response()
+        }
+        Some(Failure::Timeout) => tokio::time::sleep(Duration::from_secs(300)).await,
+        _ => {}
+    }
+
+    let result = match tool.as_str() {
+        "enroll-card" => enroll_card(&args),
+        "unenroll-card" => unenroll_card(&args),
+        "initiate-purchase-instruction" => initiate_purchase(&args),
+        "get-payment-status" => payment_status(&args, failure),
+        "complete-payment-challenge" => complete_challenge(&args),
+        "retrieve-payment-credentials" => retrieve_credentials(&args, failure),
+        "confirm-transaction" => with_instruction(&args, |instruction| Ok(json!({ "status": instruction.status }))),
+        "refund-payment" => refund_payment(&args),
+        _ => {
+            return (StatusCode::NOT_FOUND, Json(HttpResponse::<()>::err(format!("Unknown tool: {}", tool)))).into_response()
+        }
+    };
+    match result {
+        Ok(data) => (StatusCode::OK, Json(HttpResponse::ok(data))).into_response(),
+        Err(e) => (StatusCode::OK, Json(HttpResponse::<()>::err(e))).into_response(),
+    }
+}
+
+fn enroll_card(args: &Value) -> Result<Value, String> {
+    let session_id = arg(args, "sessionId")?;
+    let reference = arg(args, "enrollmentReferenceId")?;
+    let mut sessions = SESSIONS.write().unwrap();
+    let session = sessions.entry(session_id.to_string()).or_default();
+    session.consumer_id = arg(args, "consumerId")?.to_string();
+
+    // Enrolling again under the same reference returns the same card
+    let card = match session.cards.iter().find(|card| card.enrollment_reference == reference) {
+        Some(card) => card.clone(),
+        None => {
+            let card = Card {
+                token_id: new_id("tok"),
+                enrollment_reference: reference.to_string(),
+                last4: format!("{:04}", 4242 + session.cards.len()),
+            };
+            session.cards.push(card.clone());
+            card
+        }
+    };
+    Ok(json!({
+        "success": true,
+        "status": "SUCCESS",
+        "tokenId": card.token_id,
+        "proof": proof("card_enrollment", json!({ "tokenId": card.token_id, "enrollmentReferenceId": reference })),
+    }))
+}
+
+fn unenroll_card(args: &Value) -> Result<Value, String> {
+    let token_id = arg(args, "tokenId")?;
+    let mut sessions = SESSIONS.write().unwrap();
+    let session = sessions.get_mut(arg(args, "sessionId")?).ok_or("unknown session")?;
+    let before = session.cards.len();
+    session.cards.retain(|card| card.token_id != token_id);
+    if session.cards.len() == before {
+        return Err(format!("unknown token {}", token_id));
+    }
+    Ok(json!({ "tokenId": token_id, "status": "REMOVED", "proof": proof("card_unenrollment", json!({ "tokenId": token_id })) }))
+}
+
+fn initiate_purchase(args: &Value) -> Result<Value, String> {
+    let token_id = arg(args, "tokenId")?;
+    let reference = arg(args, "clientReferenceId")?;
+    let mut sessions = SESSIONS.write().unwrap();
+    let session = sessions.get_mut(arg(args, "sessionId")?).ok_or("unknown session")?;
+    if !session.cards.iter().any(|card| card.token_id == token_id) {
+        return Err(format!("unknown token {}", token_id));
+    }
+
+    // The client reference deduplicates retried requests
+    if let Some(existing) = session.instructions.iter().find(|i| i.client_reference_id == reference) {
+        return Ok(json!({ "instructionId": existing.instruction_id, "status": existing.status }));
+    }
+    let instruction = Instruction {
+        instruction_id: new_id("ins"),
+        client_reference_id: reference.to_string(),
+        token_id: token_id.to_string(),
+        amount: arg(args, "amount")?.to_string(),
+        currency: args["currency"].as_str().unwrap_or("USD").to_string(),
+        status: "PENDING",
+        challenge_completed: false,
+    };
+    let response = json!({ "instructionId": instruction.instruction_id, "status": instruction.status });
+    session.instructions.push(instruction);
+    Ok(response)
+}
+
+/// Run `f` on the instruction named by the request's `sessionId` and `instructionId`
+fn with_instruction(args: &Value, f: impl FnOnce(&mut Instruction) -> Result<Value, String>) -> Result<Value, String> {
+    let instruction_id = arg(args, "instructionId")?;
+    let mut sessions = SESSIONS.write().unwrap();
+    let session = sessions.get_mut(arg(args, "sessionId")?).ok_or("unknown session")?;
+    let instruction = session
+        .instructions
+        .iter_mut()
+        .find(|i| i.instruction_id == instruction_id)
+        .ok_or_else(|| format!("unknown instruction {}", instruction_id))?;
+    f(instruction)
+}
+
+fn payment_status(args: &Value, failure: Option<Failure>) -> Result<Value, String> {
+    with_instruction(args, |instruction| {
+        match failure {
+            Some(Failure::Declined) => {
+                instruction.status = "DECLINED";
+                return Ok(json!({ "status": "DECLINED", "reason": "insufficient funds" }));
+            }
+            Some(Failure::Challenge) if !instruction.challenge_completed => {
+                return Ok(json!({
+                    "status": "CHALLENGE_REQUIRED",
+                    "challenge": {
+                        "challengeId": format!("chl_{}", instruction.instruction_id),
+                        "type": "otp",
+                        "message": format!("Enter the code sent to your phone ({} in this mock).", CHALLENGE_OTP),
+                    }
+                }));
+            }
+            _ => {}
+        }
+        if instruction.status == "PENDING" {
+            instruction.status = "CONFIRMED";
+        }
+        Ok(json!({ "instructionId": instruction.instruction_id, "status": instruction.status }))
+    })
+}
+
+fn complete_challenge(args: &Value) -> Result<Value, String> {
+    if args["otp"].as_str().is_some_and(|otp| otp != CHALLENGE_OTP) {
+        return Err("invalid one-time code".to_string());
+    }
+    with_instruction(args, |instruction| {
+        instruction.challenge_completed = true;
+        Ok(json!({ "instructionId": instruction.instruction_id, "status": "AUTHENTICATED" }))
+    })
+}
+
+fn retrieve_credentials(args: &Value, failure: Option<Failure>) -> Result<Value, String> {
+    if failure == Some(Failure::Expired) {
+        return Err("proof expired".to_string());
+    }
+    let transaction_reference = arg(args, "transactionReferenceId")?.to_string();
+    with_instruction(args, |instruction| {
+        if instruction.status != "CONFIRMED" {
+            return Err(format!("instruction {} is {}", instruction.instruction_id, instruction.status));
+        }
+        let proof = proof(
+            "payment_credentials",
+            json!({
+                "instructionId": instruction.instruction_id,
+                "tokenId": instruction.token_id,
+                "transactionReferenceId": transaction_reference,
+                "amount": instruction.amount,
+                "currency": instruction.currency,
+            }),
+        );
+        Ok(json!({ "proofId": proof["id"], "proof": proof }))
+    })
+}
+
+fn refund_payment(args: &Value) -> Result<Value, String> {
+    let amount = arg(args, "amount")?.to_string();
+    with_instruction(args, |instruction| {
+        if instruction.status != "CONFIRMED" {
+            return Err(format!("instruction {} is {}", instruction.instruction_id, instruction.status));
+        }
+        let refund_id = new_id("rfd");
+        Ok(json!({
+            "refundId": refund_id,
+            "status": "REFUNDED",
+            "amount": amount,
+            "currency": instruction.currency,
+            "proof": proof("refund", json!({ "refundId": refund_id, "instructionId": instruction.instruction_id })),
+        }))
+    })
+}
+
+async fn session(Path(session_id): Path<String>) -> impl IntoResponse {
+    let sessions = SESSIONS.read().unwrap();
+    let session = sessions.get(&session_id);
+    let cards: Vec<Value> = session
+        .map(|s| s.cards.iter().map(|card| json!({ "tokenId": card.token_id, "brand": "Visa", "last4": card.last4 })).collect())
+        .unwrap_or_default();
+    let instructions: Vec<Value> = session
+        .map(|s| {
+            s.instructions
+                .iter()
+                .map(|i| json!({ "instructionId": i.instruction_id, "clientReferenceId": i.client_reference_id, "status": i.status }))
+                .collect()
+        })
+        .unwrap_or_default();
+    Json(HttpResponse::ok(json!({
+        "sessionId": session_id,
+        "consumerId": session.map(|s| s.consumer_id.clone()),
+        "enrolledCards": cards,
+        "enrolledTokenIds": session.map(|s| s.cards.iter().map(|c| c.token_id.clone()).collect::<Vec<_>>()).unwrap_or_default(),
+        "instructions": instructions,
+    })))
+}
+
+async fn get_failures() -> impl IntoResponse {
+    Json(HttpResponse::ok(failures::all()))
+}
+
+async fn put_failures(Json(injected): Json<HashMap<String, Failure>>) -> impl IntoResponse {
+    tracing::info!(failures = ?injected, "failure injection replaced");
+    failures::replace(injected.clone());
+    Json(HttpResponse::ok(injected))
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let _telemetry = telemetry::init("mock-payment-agent");
+    let port: u16 = std::env::var("MOCK_PAYMENT_AGENT_PORT")
+        .ok()
+        .and_then(|p| p.parse().ok())
+        .unwrap_or(3002);
+
+    let injected = failures::all();
+    let app = Router::new()
+        .route("/health", get(health))
+        .route("/tools", get(list_tools))
+        .route("/tools/:name", post(call_tool))
+        .route("/session/:session_id", get(session))
+        .route("/failures", get(get_failures).put(put_failures));
+
+    let listener = tokio::net::TcpListener::bind(("0.0.0.0", port)).await?;
+    println!("✓ Mock Payment Agent on http://localhost:{}", port);
+    if !injected.is_empty() {
+        println!("  Injected failures: {:?}", injected);
+    }
+    axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown::signal())
+        .await?;
+    Ok(())
+}
@@ -182,10 +182,16 @@ async fn main() -> anyhow::Result<()> {
         .collect();
     
     let attest_req = AttestRequest {
+        // v1 returns the proof inline, which the on-chain check below reads
+        protocol_version: 1,
         program_id: price_resp.program_id.clone(),
         input_bytes,
+        input_segments: Vec::new(),
         claimed_output: Some(price_resp.data.clone()),
         verify_locally: true,
+        proof_mode: Default::default(),
+        hints: Default::default(),
+        job_id: None,
     };
 
     let attest_resp = client
@@ -0,0 +1,119 @@
+//! zp-dev: the whole demo stack in one command
+//!
+//! Starts a mock attester in-process, then Agent B, the Agent A HTTP server
+//! and the mock Payment Agent as child processes (`cargo run`), all wired
+//! together from the shared configuration (`zeroproof.toml` / env). Ctrl-C,
+//! SIGTERM, or any service exiting stops everything: each service gets a
+//! SIGTERM and `shutdown::DRAIN_TIMEOUT` to finish in-flight requests.
+//!
+//! ```text
+//! cargo run -p zp-dev
+//! ```
+
+mod mock_attester;
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use tokio::process::{Child, Command};
+use zk_protocol::shutdown;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let settings = zp_config::init()?;
+    if zp_config::print_config_requested() {
+        print!("{}", settings.to_toml());
+        return Ok(());
+    }
+
+    let agent_a_port = settings.agent_a.port;
+    let agent_b_port = settings.agent_b.port;
+    let attester_port = settings.attester.port;
+    let attester_url = format!("http://localhost:{}", attester_port);
+    let agent_b_url = format!("http://localhost:{}", agent_b_port);
+    let payment_agent_port = std::env::var("MOCK_PAYMENT_AGENT_PORT").unwrap_or_else(|_| "3002".to_string());
+
+    let listener = tokio::net::TcpListener::bind(("0.0.0.0", attester_port))
+        .await
+        .with_context(|| format!("mock attester: port {} unavailable", attester_port))?;
+    tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, mock_attester::router()).await {
+            eprintln!("[mock-attester] stopped: {}", e);
+        }
+    });
+    println!("✓ Mock attester on {}", attester_url);
+
+    let root = repo_root();
+    let mut agent_b = cargo_run(&root.join("agent-b/Cargo.toml"), &["-p", "agent-b-mcp-server"])
+        .env("AGENT_B_PORT", agent_b_port.to_string())
+        .env("ATTESTER_URL", &attester_url)
+        .spawn()
+        .context("failed to start Agent B")?;
+    println!("✓ Agent B starting on {}", agent_b_url);
+
+    let mut agent_a = cargo_run(
+        &root.join("agent-a/Cargo.toml"),
+        &["-p", "agent-a-mcp-server", "--bin", "agent-a-mcp"],
+    )
+    .env("AGENT_A_SERVER_PORT", agent_a_port.to_string())
+    .env("AGENT_A_MODE", "http")
+    .env("AGENT_B_URL", &agent_b_url)
+    .env("ATTESTER_URL", &attester_url)
+    .spawn()
+    .context("failed to start Agent A")?;
+    println!("✓ Agent A starting on http://localhost:{}", agent_a_port);
+
+    let mut payment_agent = cargo_run(&root.join("agent-a/Cargo.toml"), &["-p", "mock-payment-agent"])
+        .env("MOCK_PAYMENT_AGENT_PORT", &payment_agent_port)
+        .spawn()
+        .context("failed to start the mock Payment Agent")?;
+    println!("✓ Mock Payment Agent starting on http://localhost:{}", payment_agent_port);
+    println!("\n  Client: PAYMENT_AGENT_URL=http://localhost:{} cargo run -p mcp-client", payment_agent_port);
+    println!("  Press Ctrl-C to stop\n");
+
+    tokio::select! {
+        _ = shutdown::signal() => {}
+        status = agent_b.wait() => report_exit("Agent B", status),
+        status = agent_a.wait() => report_exit("Agent A", status),
+        status = payment_agent.wait() => report_exit("Mock Payment Agent", status),
+    }
+
+    stop(&mut payment_agent).await;
+    stop(&mut agent_a).await;
+    stop(&mut agent_b).await;
+    Ok(())
+}
+
+/// The checkout this binary was built from
+fn repo_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../..")
+}
+
+/// `cargo run --manifest-path <manifest> <args>`, killed if we exit first
+fn cargo_run(manifest: &Path, args: &[&str]) -> Command {
+    let mut command = Command::new(std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string()));
+    command
+        .arg("run")
+        .arg("--manifest-path")
+        .arg(manifest)
+        .args(args)
+        .kill_on_drop(true);
+    command
+}
+
+fn report_exit(name: &str, status: std::io::Result<std::process::ExitStatus>) {
+    match status {
+        Ok(status) => eprintln!("✗ {} exited ({}); shutting down", name, status),
+        Err(e) => eprintln!("✗ {} could not be waited on ({}); shutting down", name, e),
+    }
+}
+
+/// SIGTERM, then SIGKILL if the service has not drained in time
+async fn stop(child: &mut Child) {
+    if let Some(pid) = child.id() {
+        let _ = Command::new("kill").arg("-TERM").arg(pid.to_string()).status().await;
+        if tokio::time::timeout(shutdown::DRAIN_TIMEOUT, child.wait()).await.is_ok() {
+            return;
+        }
+    }
+    let _ = child.kill().await;
+}
@@ -0,0 +1,111 @@
+//! Stand-in for the attester: answers the endpoints Agent A and Agent B call
+//! without generating proofs, so the demo runs without SP1 or a GPU.
+//! Attestations come back with an empty proof, `backend: mock` and
+//! `output_verified: false`.
+
+use axum::{
+    extract::{Json, Query},
+    routing::{get, post},
+    Router,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicU64, Ordering};
+use zk_protocol::{
+    AttestRequest, AttestResponse, EstimateResponse, ProgramInfo, ProofMode, ProverBackend, VerifyRequest,
+    VerifyResponse, PROTOCOL_VERSION,
+};
+
+static NEXT_JOB: AtomicU64 = AtomicU64::new(1);
+
+pub fn router() -> Router {
+    Router::new()
+        .route("/register-elf", post(register_elf))
+        .route("/programs", get(list_programs))
+        .route("/attest", post(attest))
+        .route("/attest/estimate", get(estimate))
+        .route("/verify-sp1", post(verify))
+}
+
+async fn register_elf() -> Json<Value> {
+    Json(json!({ "program_id": "mock-program", "message": "mock attester: ELF not stored" }))
+}
+
+async fn list_programs() -> Json<Vec<ProgramInfo>> {
+    Json(Vec::new())
+}
+
+async fn attest(Json(request): Json<AttestRequest>) -> Json<AttestResponse> {
+    let job_id = format!("mock-{}", NEXT_JOB.fetch_add(1, Ordering::Relaxed));
+    println!("[mock-attester] {} for {} ({:?})", job_id, request.program_id, request.proof_mode);
+    Json(AttestResponse {
+        protocol_version: PROTOCOL_VERSION,
+        job_id,
+        proof: String::new(),
+        public_values: String::new(),
+        vk_hash: format!("0x{}", "00".repeat(32)),
+        verified_output: request.claimed_output.unwrap_or(Value::Null),
+        output_verified: false,
+        proof_mode: request.proof_mode,
+        on_chain_compatible: false,
+        backend: Some(ProverBackend::Mock),
+        cached: false,
+        execution: None,
+    })
+}
+
+#[derive(Deserialize)]
+struct EstimateQuery {
+    program_id: String,
+    #[serde(default)]
+    proof_mode: ProofMode,
+}
+
+async fn estimate(Query(query): Query<EstimateQuery>) -> Json<EstimateResponse> {
+    Json(EstimateResponse {
+        program_id: query.program_id,
+        proof_mode: query.proof_mode,
+        queue_wait_secs: 0,
+        proving_secs: 0,
+        samples: 0,
+    })
+}
+
+async fn verify(Json(request): Json<VerifyRequest>) -> Json<VerifyResponse> {
+    Json(VerifyResponse {
+        valid: false,
+        vk_hash: request.vk_hash.unwrap_or_default(),
+        error: Some("mock attester does not verify proofs".to_string()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_mock_attestation_is_never_verified() {
+        let request = || -> AttestRequest {
+            serde_json::from_value(json!({
+                "program_id": "pricing",
+                "claimed_output": { "price": 450 },
+                "proof_mode": "compressed",
+            }))
+            .unwrap()
+        };
+        let Json(first) = attest(Json(request())).await;
+        let Json(second) = attest(Json(request())).await;
+
+        assert_ne!(first.job_id, second.job_id);
+        assert_eq!(first.verified_output, json!({ "price": 450 }));
+        assert!(!first.output_verified && !first.on_chain_compatible);
+        assert_eq!(first.proof_mode, ProofMode::Compressed);
+        assert_eq!(first.backend, Some(ProverBackend::Mock));
+
+        let verify_request: VerifyRequest =
+            serde_json::from_value(json!({ "proof": first.proof, "public_values": first.public_values, "vk_hash": first.vk_hash })).unwrap();
+        let Json(verified) = verify(Json(verify_request)).await;
+        assert!(!verified.valid);
+        assert_eq!(verified.vk_hash, first.vk_hash);
+    }
+}
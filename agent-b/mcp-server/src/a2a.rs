@@ -0,0 +1,302 @@
+//! Agent-to-Agent (A2A) protocol binding for Agent B
+//!
+//! Orchestrators that don't speak MCP discover Agent B from its agent card
+//! (`GET /.well-known/agent.json`) and send JSON-RPC 2.0 requests to
+//! `POST /a2a`:
+//!
+//! - `message/send`: run a skill; the message carries a data part
+//!   `{ "skill": "<id>", "arguments": { ... } }`, with the same arguments as
+//!   the MCP tool of that name (see `GET /tools`)
+//! - `tasks/get`: fetch a task by ID
+//! - `tasks/cancel`: always refused; skills finish before `message/send`
+//!   returns
+//!
+//! Every skill runs synchronously, so the returned task is already
+//! `completed` (result in its artifact) or `failed` (reason in its status
+//! message). `/a2a` is signed like the tool calls when `AGENT_HMAC_SECRET`
+//! is set.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use axum::{
+    extract::{Json, State},
+    http::StatusCode,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::{AppState, ToolResponse};
+
+/// A2A specification version the agent card and tasks follow
+const PROTOCOL_VERSION: &str = "0.3.0";
+
+/// Skills in the agent card, as (id, name, description, tags); each is an MCP
+/// tool of the same name
+const SKILLS: &[(&str, &str, &str, &[&str])] = &[
+    ("get-ticket-price", "Flight pricing", "Price a flight between two IATA city codes for a cabin, tier, travel date, promo code and add-ons", &["pricing", "flights"]),
+    ("get-ticket-price-v2", "Flight quote", "Structured quote with a quote ID, expiry, currency and fare breakdown", &["pricing", "flights", "quote"]),
+    ("book-flight", "Book a flight", "Book a flight for a passenger; returns the booking ID and confirmation code", &["booking", "flights"]),
+    ("get-booking", "Look up a booking", "Fetch a booking by its booking ID", &["booking"]),
+    ("cancel-booking", "Cancel a booking", "Cancel a confirmed booking and calculate the refund due", &["booking", "refund"]),
+];
+
+/// How long a finished task can be fetched with `tasks/get`
+const TASK_TTL_SECS: u64 = 60 * 60;
+
+/// Tasks kept at most; the oldest are dropped first
+const MAX_TASKS: usize = 10_000;
+
+/// Tasks created by `message/send`, by task ID, with their creation time
+#[derive(Default)]
+pub struct TaskStore {
+    tasks: RwLock<HashMap<String, (u64, Task)>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Task {
+    id: String,
+    context_id: String,
+    status: TaskStatus,
+    artifacts: Vec<Value>,
+    kind: &'static str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct TaskStatus {
+    /// `completed` or `failed`
+    state: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<Value>,
+    timestamp: String,
+}
+
+/// JSON-RPC 2.0 request envelope
+#[derive(Debug, Deserialize)]
+pub struct RpcRequest {
+    #[serde(default)]
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+/// Skill invocation carried in a message's data part
+#[derive(Debug, Deserialize)]
+struct SkillCall {
+    skill: String,
+    #[serde(default)]
+    arguments: Value,
+}
+
+/// JSON-RPC error codes used by A2A
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const TASK_NOT_FOUND: i64 = -32001;
+const TASK_NOT_CANCELABLE: i64 = -32002;
+
+type RpcError = (i64, String);
+
+/// Agent card describing Agent B's skills and where to call them. The URL
+/// comes from configuration (`AGENT_B_PUBLIC_URL`, else `AGENT_B_URL`), never
+/// from the request's Host header.
+pub async fn agent_card() -> Json<Value> {
+    let settings = zp_config::get();
+    let base_url = settings.agent_b.public_url.as_deref().unwrap_or(&settings.agent_a.agent_b_url);
+
+    let skills: Vec<Value> = SKILLS
+        .iter()
+        .map(|(id, name, description, tags)| {
+            json!({
+                "id": id,
+                "name": name,
+                "description": description,
+                "tags": tags,
+                "inputModes": ["application/json"],
+                "outputModes": ["application/json"],
+            })
+        })
+        .collect();
+
+    Json(json!({
+        "protocolVersion": PROTOCOL_VERSION,
+        "name": "Agent B - Flight Pricing & Booking",
+        "description": "Prices and books flights. Send a data part { \"skill\", \"arguments\" }; arguments follow the MCP tool schemas at GET /tools.",
+        "url": format!("{}/a2a", base_url.trim_end_matches('/')),
+        "preferredTransport": "JSONRPC",
+        "version": env!("CARGO_PKG_VERSION"),
+        "capabilities": { "streaming": false, "pushNotifications": false, "stateTransitionHistory": false },
+        "defaultInputModes": ["application/json"],
+        "defaultOutputModes": ["application/json"],
+        "skills": skills,
+    }))
+}
+
+/// JSON-RPC endpoint for `message/send`, `tasks/get` and `tasks/cancel`
+pub async fn rpc(State(state): State<Arc<AppState>>, Json(request): Json<RpcRequest>) -> (StatusCode, Json<Value>) {
+    tracing::info!("[A2A] {} received", request.method);
+    let result = match request.method.as_str() {
+        "message/send" => send_message(&state, &request.params).await,
+        "tasks/get" => task_id(&request.params).and_then(|id| {
+            state.tasks.get(id).ok_or((TASK_NOT_FOUND, format!("Task {} not found or expired", id)))
+        }),
+        "tasks/cancel" => task_id(&request.params).and_then(|id| match state.tasks.get(id) {
+            Some(task) => Err((TASK_NOT_CANCELABLE, format!("Task {} already {}", id, task.status.state))),
+            None => Err((TASK_NOT_FOUND, format!("Task {} not found or expired", id))),
+        }),
+        other => Err((METHOD_NOT_FOUND, format!("Unsupported method {}", other))),
+    };
+
+    let body = match result {
+        Ok(task) => json!({ "jsonrpc": "2.0", "id": request.id, "result": task }),
+        Err((code, message)) => {
+            tracing::warn!("[A2A] {} rejected: {}", request.method, message);
+            json!({ "jsonrpc": "2.0", "id": request.id, "error": { "code": code, "message": message } })
+        }
+    };
+    // JSON-RPC reports errors in the body
+    (StatusCode::OK, Json(body))
+}
+
+/// Run the skill named in the message and record the task
+async fn send_message(state: &Arc<AppState>, params: &Value) -> Result<Task, RpcError> {
+    let message = &params["message"];
+    let call = skill_call(message)?;
+    let outcome = run_skill(state, &call).await?;
+
+    let id = TaskStore::new_id();
+    let context_id = message["contextId"].as_str().map_or_else(|| id.clone(), str::to_string);
+    let timestamp = timestamp();
+    let task = match outcome {
+        Ok(data) => Task {
+            artifacts: vec![json!({
+                "artifactId": format!("{}-result", id),
+                "name": call.skill,
+                "parts": [{ "kind": "data", "data": data }],
+            })],
+            status: TaskStatus { state: "completed", message: None, timestamp },
+            id,
+            context_id,
+            kind: "task",
+        },
+        Err(error) => Task {
+            artifacts: Vec::new(),
+            status: TaskStatus {
+                state: "failed",
+                message: Some(json!({
+                    "kind": "message",
+                    "role": "agent",
+                    "messageId": format!("{}-status", id),
+                    "parts": [{ "kind": "text", "text": error }],
+                })),
+                timestamp,
+            },
+            id,
+            context_id,
+            kind: "task",
+        },
+    };
+    tracing::info!("[A2A] Task {} ({}) {}", task.id, call.skill, task.status.state);
+    state.tasks.insert(task.clone());
+    Ok(task)
+}
+
+/// The `{ skill, arguments }` data part of an A2A message
+fn skill_call(message: &Value) -> Result<SkillCall, RpcError> {
+    let data = message["parts"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find(|part| part["kind"] == "data")
+        .map(|part| part["data"].clone())
+        .ok_or((INVALID_PARAMS, "message needs a data part { \"skill\", \"arguments\" }".to_string()))?;
+    serde_json::from_value(data).map_err(|e| (INVALID_PARAMS, format!("invalid skill call: {}", e)))
+}
+
+/// Call the MCP tool behind a skill: `Ok(data)` or `Err(tool error)`;
+/// malformed arguments are a JSON-RPC error rather than a failed task
+async fn run_skill(state: &Arc<AppState>, call: &SkillCall) -> Result<Result<Value, String>, RpcError> {
+    let state = State(state.clone());
+    let outcome = match call.skill.as_str() {
+        "get-ticket-price" => tool_outcome(super::get_ticket_price(state, Json(arguments(call)?)).await),
+        "get-ticket-price-v2" => tool_outcome(super::get_ticket_price_v2(state, Json(arguments(call)?)).await),
+        "book-flight" => tool_outcome(super::book_flight(state, Json(arguments(call)?)).await),
+        "get-booking" => tool_outcome(super::get_booking(state, Json(arguments(call)?)).await),
+        "cancel-booking" => tool_outcome(super::cancel_booking(state, Json(arguments(call)?)).await),
+        other => return Err((INVALID_PARAMS, format!("Unknown skill {} (see /.well-known/agent.json)", other))),
+    };
+    Ok(outcome)
+}
+
+fn arguments<T: serde::de::DeserializeOwned>(call: &SkillCall) -> Result<T, RpcError> {
+    serde_json::from_value(call.arguments.clone())
+        .map_err(|e| (INVALID_PARAMS, format!("invalid arguments for {}: {}", call.skill, e)))
+}
+
+type ToolResult<T> = Result<Json<ToolResponse<T>>, (StatusCode, Json<ToolResponse<()>>)>;
+
+fn tool_outcome<T: Serialize>(result: ToolResult<T>) -> Result<Value, String> {
+    match result {
+        Ok(Json(response)) => Ok(serde_json::to_value(response.data).unwrap_or_default()),
+        Err((_, Json(response))) => Err(response.error.unwrap_or_default()),
+    }
+}
+
+fn task_id(params: &Value) -> Result<&str, RpcError> {
+    params["id"].as_str().ok_or((INVALID_PARAMS, "params.id (task ID) is required".to_string()))
+}
+
+/// Current UTC time as RFC 3339
+fn timestamp() -> String {
+    super::rfc3339(super::now_secs())
+}
+
+impl TaskStore {
+    /// Unguessable, so one caller cannot read another's tasks
+    fn new_id() -> String {
+        format!("task_{}", uuid::Uuid::new_v4().simple())
+    }
+
+    fn get(&self, id: &str) -> Option<Task> {
+        let now = super::now_secs();
+        let tasks = self.tasks.read().unwrap();
+        tasks.get(id).filter(|(created, _)| now < created + TASK_TTL_SECS).map(|(_, task)| task.clone())
+    }
+
+    /// Store `task`, dropping expired tasks and, past MAX_TASKS, the oldest
+    fn insert(&self, task: Task) {
+        let now = super::now_secs();
+        let mut tasks = self.tasks.write().unwrap();
+        tasks.retain(|_, (created, _)| now < *created + TASK_TTL_SECS);
+        while tasks.len() >= MAX_TASKS {
+            let Some(oldest) = tasks.iter().min_by_key(|(_, (created, _))| *created).map(|(id, _)| id.clone()) else {
+                break;
+            };
+            tasks.remove(&oldest);
+        }
+        tasks.insert(task.id.clone(), (now, task));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_skill_call_from_data_part() {
+        let message = json!({
+            "role": "user",
+            "parts": [
+                { "kind": "text", "text": "Price NYC to LON" },
+                { "kind": "data", "data": { "skill": "get-ticket-price", "arguments": { "from": "NYC", "to": "LON" } } },
+            ],
+        });
+        let call = skill_call(&message).unwrap();
+        assert_eq!(call.skill, "get-ticket-price");
+        assert_eq!(call.arguments["to"], "LON");
+
+        let text_only = json!({ "parts": [{ "kind": "text", "text": "hi" }] });
+        assert_eq!(skill_call(&text_only).unwrap_err().0, INVALID_PARAMS);
+    }
+}
@@ -0,0 +1,66 @@
+//! Persistent booking store for Agent B
+//!
+//! Keeps every booking in memory and rewrites a JSON file after each change,
+//! so bookings survive restarts without a database.
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+use pricing_core::{
+    booking::Booking,
+    store::{BookingStore, InMemoryBookingStore, StoreError},
+};
+
+/// `BookingStore` persisted to a JSON file (`BOOKINGS_PATH`)
+pub struct FileBookingStore {
+    path: PathBuf,
+    bookings: InMemoryBookingStore,
+}
+
+impl FileBookingStore {
+    /// Load bookings from `path`, starting empty if the file does not exist yet
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let bookings = match std::fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => InMemoryBookingStore::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self { path, bookings })
+    }
+
+    pub fn len(&self) -> usize {
+        self.bookings.len()
+    }
+
+    /// Write all bookings to a temporary file and move it over the old one
+    fn flush(&self) -> Result<(), StoreError> {
+        let backend = |e: std::io::Error| StoreError::Backend(e.to_string());
+        let json = serde_json::to_vec_pretty(&self.bookings)
+            .map_err(|e| StoreError::Backend(e.to_string()))?;
+        let tmp = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp, json).map_err(backend)?;
+        std::fs::rename(&tmp, &self.path).map_err(backend)
+    }
+}
+
+impl BookingStore for FileBookingStore {
+    fn save(&mut self, booking: Booking) -> Result<(), StoreError> {
+        self.bookings.save(booking)?;
+        self.flush()
+    }
+
+    fn get(&self, booking_id: &str) -> Option<Booking> {
+        self.bookings.get(booking_id)
+    }
+
+    fn cancel(&mut self, booking_id: &str) -> Result<Booking, StoreError> {
+        let booking = self.bookings.cancel(booking_id)?;
+        self.flush()?;
+        Ok(booking)
+    }
+
+    fn list(&self) -> Vec<Booking> {
+        self.bookings.list()
+    }
+}
@@ -0,0 +1,233 @@
+//! Booking export for finance and reporting
+//!
+//! `GET /admin/bookings/export?format=csv|json&from=&to=&route=` returns the
+//! stored bookings, oldest first, with the Payment Agent proof IDs that back
+//! each payment. `from`/`to` bound the booking date (UTC, `YYYY-MM-DD`,
+//! inclusive) and `route` picks one origin-destination pair (`NYC-LON`).
+//! Requires `Authorization: Bearer $ADMIN_TOKEN`.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Json, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use pricing_core::{booking::Booking, date::Date};
+use serde::{Deserialize, Serialize};
+use zk_protocol::signing::AdminToken;
+
+use super::{tool_error, AppState, ToolResponse};
+
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery {
+    /// "json" (default) or "csv"
+    #[serde(default)]
+    format: Option<String>,
+    /// First booking date included
+    #[serde(default)]
+    from: Option<String>,
+    /// Last booking date included
+    #[serde(default)]
+    to: Option<String>,
+    /// `<from>-<to>` city codes
+    #[serde(default)]
+    route: Option<String>,
+}
+
+/// One exported booking
+#[derive(Debug, Serialize)]
+pub struct ExportRow {
+    booking_id: String,
+    confirmation_code: String,
+    status: String,
+    /// RFC 3339 booking time
+    booked_at: String,
+    from: String,
+    to: String,
+    cabin: String,
+    travel_date: Option<String>,
+    /// Decimal amount paid, e.g. "450.00"
+    amount: Option<String>,
+    currency: Option<String>,
+    session_id: Option<String>,
+    proof_ids: Vec<String>,
+}
+
+/// Bookings matching the query's filters
+struct Filter {
+    from_day: Option<i64>,
+    to_day: Option<i64>,
+    route: Option<(String, String)>,
+}
+
+impl Filter {
+    fn parse(query: &ExportQuery) -> Result<Self, String> {
+        let day = |value: &Option<String>, name: &str| {
+            value
+                .as_deref()
+                .map(|s| Date::parse(s).map(|d| d.days_since_epoch()).ok_or(format!("{} must be a YYYY-MM-DD date, got {}", name, s)))
+                .transpose()
+        };
+        let route = query
+            .route
+            .as_deref()
+            .map(|r| {
+                r.split_once('-')
+                    .map(|(from, to)| (from.to_uppercase(), to.to_uppercase()))
+                    .ok_or(format!("route must look like NYC-LON, got {}", r))
+            })
+            .transpose()?;
+        Ok(Self { from_day: day(&query.from, "from")?, to_day: day(&query.to, "to")?, route })
+    }
+
+    fn matches(&self, booking: &Booking) -> bool {
+        let day = (booking.booked_at / 86_400) as i64;
+        self.from_day.is_none_or(|from| day >= from)
+            && self.to_day.is_none_or(|to| day <= to)
+            && self.route.as_ref().is_none_or(|(from, to)| {
+                booking.request.from.eq_ignore_ascii_case(from) && booking.request.to.eq_ignore_ascii_case(to)
+            })
+    }
+}
+
+pub async fn export_bookings(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<ExportQuery>,
+) -> Response {
+    let Some(token) = AdminToken::from_env() else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(tool_error("Admin endpoints are disabled: ADMIN_TOKEN not set".to_string())),
+        )
+            .into_response();
+    };
+    if !token.verify(headers.get(header::AUTHORIZATION).and_then(|v| v.to_str().ok())) {
+        tracing::warn!("Rejected admin request: missing or wrong bearer token");
+        return (StatusCode::UNAUTHORIZED, Json(tool_error("invalid admin token".to_string()))).into_response();
+    }
+
+    let filter = match Filter::parse(&query) {
+        Ok(filter) => filter,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(tool_error(e))).into_response(),
+    };
+    let mut bookings: Vec<Booking> = state.bookings.lock().unwrap().list().into_iter().filter(|b| filter.matches(b)).collect();
+    bookings.sort_by(|a, b| a.booked_at.cmp(&b.booked_at).then_with(|| a.response.booking_id.cmp(&b.response.booking_id)));
+    let rows: Vec<ExportRow> = bookings.iter().map(row).collect();
+    tracing::info!("[EXPORT] {} booking(s) as {}", rows.len(), query.format.as_deref().unwrap_or("json"));
+
+    match query.format.as_deref() {
+        None | Some("json") => (StatusCode::OK, Json(ToolResponse::ok(rows))).into_response(),
+        Some("csv") => (
+            StatusCode::OK,
+            [
+                (header::CONTENT_TYPE, "text/csv"),
+                (header::CONTENT_DISPOSITION, "attachment; filename=\"bookings.csv\""),
+            ],
+            to_csv(&rows),
+        )
+            .into_response(),
+        Some(other) => (
+            StatusCode::BAD_REQUEST,
+            Json(tool_error(format!("Unsupported export format {} (use json or csv)", other))),
+        )
+            .into_response(),
+    }
+}
+
+fn row(booking: &Booking) -> ExportRow {
+    let request = &booking.request;
+    ExportRow {
+        booking_id: booking.response.booking_id.clone(),
+        confirmation_code: booking.response.confirmation_code.clone(),
+        status: booking.response.status.clone(),
+        booked_at: super::rfc3339(booking.booked_at),
+        from: request.from.clone(),
+        to: request.to.clone(),
+        cabin: serde_json::to_value(request.cabin).ok().and_then(|c| c.as_str().map(str::to_string)).unwrap_or_default(),
+        travel_date: request.travel_date.clone(),
+        amount: request.amount.as_ref().map(|m| m.to_string()),
+        currency: request.amount.as_ref().map(|m| m.currency.clone()),
+        session_id: request.session_id.clone(),
+        proof_ids: request.proof_ids.clone(),
+    }
+}
+
+const CSV_HEADER: &str = "booking_id,confirmation_code,status,booked_at,from,to,cabin,travel_date,amount,currency,session_id,proof_ids";
+
+/// Rows as CSV under a header row; proof IDs are separated by `;`
+fn to_csv(rows: &[ExportRow]) -> String {
+    let mut csv = String::from(CSV_HEADER);
+    csv.push('\n');
+    for row in rows {
+        let fields = [
+            row.booking_id.clone(),
+            row.confirmation_code.clone(),
+            row.status.clone(),
+            row.booked_at.clone(),
+            row.from.clone(),
+            row.to.clone(),
+            row.cabin.clone(),
+            row.travel_date.clone().unwrap_or_default(),
+            row.amount.clone().unwrap_or_default(),
+            row.currency.clone().unwrap_or_default(),
+            row.session_id.clone().unwrap_or_default(),
+            row.proof_ids.join(";"),
+        ];
+        let fields: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// Quote a field containing a separator, quote or line break
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pricing_core::{booking, fares::Cabin, money::Money};
+
+    fn booking(from: &str, to: &str, booked_at: u64) -> Booking {
+        let request = booking::Request {
+            from: from.to_string(),
+            to: to.to_string(),
+            passenger_name: "Jane Doe".to_string(),
+            passenger_email: "jane@example.com".to_string(),
+            cabin: Cabin::Business,
+            session_id: Some("sess_1".to_string()),
+            travel_date: Some("2025-12-01".to_string()),
+            amount: Some(Money::new(45_000, "USD")),
+            proof_ids: vec!["prf_1".to_string(), "prf_2".to_string()],
+        };
+        Booking { response: booking::handle(request.clone()), request, booked_at }
+    }
+
+    #[test]
+    fn test_filter_and_csv_export() {
+        let query = ExportQuery {
+            format: Some("csv".to_string()),
+            from: Some("2025-10-16".to_string()),
+            to: Some("2025-10-16".to_string()),
+            route: Some("nyc-lon".to_string()),
+        };
+        let filter = Filter::parse(&query).unwrap();
+        assert!(filter.matches(&booking("NYC", "LON", 1_760_600_000)));
+        assert!(!filter.matches(&booking("NYC", "PAR", 1_760_600_000)));
+        assert!(!filter.matches(&booking("NYC", "LON", 1_760_600_000 + 86_400)));
+        assert!(Filter::parse(&ExportQuery { from: Some("16/10/2025".to_string()), format: None, to: None, route: None }).is_err());
+
+        let csv = to_csv(&[row(&booking("NYC", "LON", 1_760_600_000))]);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], CSV_HEADER);
+        assert!(lines[1].ends_with(",2025-10-16T07:33:20Z,NYC,LON,business,2025-12-01,450.00,USD,sess_1,prf_1;prf_2"));
+    }
+}
@@ -1,24 +1,68 @@
-/// Agent B MCP Server - Pricing & Booking Service
-///
-/// Exposes pricing and booking operations as MCP tools over HTTP API
-/// - POST /tools/get-ticket-price
-/// - POST /tools/book-flight
-/// - GET /tools - List all tools
+//! Agent B MCP Server - Pricing & Booking Service
+//!
+//! Exposes pricing and booking operations as MCP tools over HTTP API
+//! - POST /tools/get-ticket-price
+//! - POST /tools/get-ticket-price-v2
+//! - POST /tools/book-flight
+//! - POST /tools/list-destinations
+//! - POST /tools/list-ancillaries
+//! - POST /tools/get-booking
+//! - POST /tools/cancel-booking
+//! - GET /tools - List all tools
+//!
+//! and as A2A skills (`GET /.well-known/agent.json`, `POST /a2a`). Operators
+//! export bookings with `GET /admin/bookings/export`.
 
 use anyhow::Result;
 use axum::{
-    extract::Json,
-    http::StatusCode,
-    response::IntoResponse,
+    body::{self, Body},
+    extract::{Json, Request, State},
+    http::{HeaderValue, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
     routing::{get, post},
     Router,
 };
+use axum_server::tls_rustls::RustlsConfig;
 use serde::{Deserialize, Serialize};
-use serde_json::{json, Value};
-use std::sync::Arc;
-use tower_http::cors::CorsLayer;
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+use tower_http::cors::{Any, CorsLayer};
+use tracing::Instrument;
+use zk_protocol::{
+    shutdown,
+    signing::{SigningKey, SIGNATURE_HEADER, TIMESTAMP_HEADER},
+    telemetry,
+    tls::MtlsConfig,
+};
+use zp_config::AgentBSettings;
+
+use pricing_core::{
+    booking::Booking,
+    date::Date,
+    fares::{Cabin, RouteTable},
+    money::Money,
+    pricing, promo,
+    refund,
+    store::{BookingStore, StoreError},
+    ancillary,
+    destinations::{self, Destination},
+    error::CoreError,
+};
+use std::time::{SystemTime, UNIX_EPOCH};
 
-use pricing_core::pricing;
+mod a2a;
+mod bookings;
+mod export;
+
+use bookings::FileBookingStore;
+
+/// Shared server state: the fare table and the injected booking store
+struct AppState {
+    fares: RouteTable,
+    bookings: Mutex<Box<dyn BookingStore + Send>>,
+    tasks: a2a::TaskStore,
+}
 
 /// Pricing Tool Request
 #[derive(Debug, Deserialize)]
@@ -26,16 +70,32 @@ struct PriceRequest {
     from: String,
     to: String,
     vip: Option<bool>,
+    travel_date: Option<String>,
+    #[serde(default)]
+    cabin: Cabin,
+    promo_code: Option<String>,
+    /// Add-on codes from list-ancillaries
+    #[serde(default)]
+    ancillaries: Vec<String>,
 }
 
 /// Pricing Tool Response
 #[derive(Debug, Serialize)]
 struct PriceResponse {
+    /// Display value of `amount`; pay `amount`, not this
     price: f64,
+    /// Exact quote in minor units
+    amount: Money,
     from: String,
     to: String,
     vip: bool,
+    travel_date: Option<String>,
+    cabin: Cabin,
+    promo_code: Option<String>,
+    quote_date: String,
     currency: String,
+    /// Fare, promo discount and add-ons making up `amount`
+    breakdown: pricing::Breakdown,
 }
 
 /// Booking Tool Request
@@ -45,6 +105,27 @@ struct BookRequest {
     to: String,
     passenger_name: String,
     passenger_email: String,
+    #[serde(default)]
+    cabin: Cabin,
+    /// Booking/payment session, mixed into the generated booking codes
+    #[serde(default)]
+    session_id: Option<String>,
+    /// Departure date from the quote, kept for refunds
+    #[serde(default)]
+    travel_date: Option<String>,
+    /// Amount paid, kept for refunds; must match the re-priced fare
+    #[serde(default)]
+    amount: Option<Money>,
+    /// Quote inputs, used to re-price `amount`
+    #[serde(default)]
+    vip: Option<bool>,
+    #[serde(default)]
+    promo_code: Option<String>,
+    #[serde(default)]
+    ancillaries: Vec<String>,
+    /// Payment Agent proofs backing the payment, kept for reporting
+    #[serde(default)]
+    proof_ids: Vec<String>,
 }
 
 /// Booking Tool Response
@@ -56,6 +137,38 @@ struct BookResponse {
     from: String,
     to: String,
     passenger_name: String,
+    cabin: Cabin,
+}
+
+/// Ancillary catalog request
+#[derive(Debug, Deserialize)]
+struct AncillariesRequest {
+    #[serde(default)]
+    cabin: Cabin,
+}
+
+/// One add-on as offered for a cabin
+#[derive(Debug, Serialize)]
+struct AncillaryOffer {
+    code: String,
+    name: String,
+    /// Price in this cabin (zero when included)
+    amount: Money,
+    included: bool,
+}
+
+/// Booking lookup / cancellation request
+#[derive(Debug, Deserialize)]
+struct BookingIdRequest {
+    booking_id: String,
+}
+
+/// Cancellation Tool Response
+#[derive(Debug, Serialize)]
+struct CancelResponse {
+    booking: Booking,
+    /// Refund due, when the booking recorded the amount paid
+    refund: Option<refund::Response>,
 }
 
 /// Tool Definition
@@ -63,7 +176,8 @@ struct BookResponse {
 struct ToolDefinition {
     name: String,
     description: String,
-    inputSchema: serde_json::Value,
+    #[serde(rename = "inputSchema")]
+    input_schema: serde_json::Value,
 }
 
 /// Tools List Response
@@ -98,6 +212,108 @@ fn tool_error(error: String) -> ToolResponse<()> {
     }
 }
 
+/// Input schema shared by get-ticket-price and get-ticket-price-v2
+fn price_input_schema() -> serde_json::Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "from": {
+                "type": "string",
+                "description": "Departure IATA city code from list-destinations (e.g., NYC)"
+            },
+            "to": {
+                "type": "string",
+                "description": "Destination IATA city code from list-destinations (e.g., LON)"
+            },
+            "vip": {
+                "type": "boolean",
+                "description": "Whether passenger is VIP (optional, default false)"
+            },
+            "travel_date": {
+                "type": "string",
+                "description": "Departure date as YYYY-MM-DD (optional); seasonal and weekend pricing applies"
+            },
+            "cabin": {
+                "type": "string",
+                "enum": ["economy", "premium", "business", "first"],
+                "description": "Cabin class (optional, default economy)"
+            },
+            "promo_code": {
+                "type": "string",
+                "description": "Promo/discount code the user provided (optional, e.g. SAVE20)"
+            },
+            "ancillaries": {
+                "type": "array",
+                "items": {"type": "string"},
+                "description": "Add-on codes from list-ancillaries (optional, e.g. checked_bag)"
+            }
+        },
+        "required": ["from", "to"]
+    })
+}
+
+/// Liveness: the process is up and serving
+async fn health() -> Json<serde_json::Value> {
+    Json(json!({
+        "status": "ok",
+        "service": "Agent B MCP Server",
+        "version": "0.1.0"
+    }))
+}
+
+/// Readiness: the fare table, the booking store and the TLS certificates
+/// are usable. Agent B calls no peers, so there are no dependencies to probe.
+async fn health_ready(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let bookings_path = &zp_config::get().agent_b.bookings_path;
+    let check = |name: &str, result: Result<(), String>| {
+        json!({ "name": name, "ok": result.is_ok(), "error": result.err() })
+    };
+    let configuration = [
+        check(
+            "fare_table",
+            if state.fares.routes.is_empty() {
+                Err("no routes loaded (check FARE_TABLE_PATH)".to_string())
+            } else {
+                Ok(())
+            },
+        ),
+        check("bookings_store", writable_dir(bookings_path)),
+        check(
+            "mtls",
+            MtlsConfig::from_env().map_or(Ok(()), |tls| {
+                tls.server_config().map(|_| ()).map_err(|e| format!("{} (check TLS_CERT / TLS_KEY / TLS_CA)", e))
+            }),
+        ),
+    ];
+    let ready = configuration.iter().all(|c| c["ok"] == true);
+    let status = if ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+
+    (
+        status,
+        Json(json!({
+            "ready": ready,
+            "profile": zp_config::get().profile,
+            "configuration": configuration,
+            "dependencies": [],
+        })),
+    )
+}
+
+/// The directory holding `path` exists and accepts writes
+fn writable_dir(path: &std::path::Path) -> Result<(), String> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => std::path::Path::new("."),
+    };
+    match std::fs::metadata(dir) {
+        Ok(meta) if meta.permissions().readonly() => {
+            Err(format!("{} is read-only (check BOOKINGS_PATH)", dir.display()))
+        }
+        Ok(_) => Ok(()),
+        Err(e) => Err(format!("{}: {} (check BOOKINGS_PATH)", dir.display(), e)),
+    }
+}
+
 /// List all available tools
 async fn list_tools() -> Json<ToolsResponse> {
     tracing::info!("[LIST TOOLS] Received request to list available tools");
@@ -106,29 +322,39 @@ async fn list_tools() -> Json<ToolsResponse> {
             ToolDefinition {
                 name: "get-ticket-price".to_string(),
                 description: "Get flight ticket pricing based on route and passenger tier".to_string(),
-                inputSchema: json!({
+                input_schema: price_input_schema(),
+            },
+            ToolDefinition {
+                name: "get-ticket-price-v2".to_string(),
+                description: "Get a structured quote (quote_id, expiry, currency and fare breakdown) for a route and passenger tier".to_string(),
+                input_schema: price_input_schema(),
+            },
+            ToolDefinition {
+                name: "list-destinations".to_string(),
+                description: "List the supported IATA city/airport codes with city names, for from/to".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {}
+                }),
+            },
+            ToolDefinition {
+                name: "list-ancillaries".to_string(),
+                description: "List add-ons (bags, seats, meals) and their prices for a cabin".to_string(),
+                input_schema: json!({
                     "type": "object",
                     "properties": {
-                        "from": {
-                            "type": "string",
-                            "description": "Departure city code (e.g., NYC)"
-                        },
-                        "to": {
+                        "cabin": {
                             "type": "string",
-                            "description": "Destination city code (e.g., LON)"
-                        },
-                        "vip": {
-                            "type": "boolean",
-                            "description": "Whether passenger is VIP (optional, default false)"
+                            "enum": ["economy", "premium", "business", "first"],
+                            "description": "Cabin class (optional, default economy)"
                         }
-                    },
-                    "required": ["from", "to"]
+                    }
                 }),
             },
             ToolDefinition {
                 name: "book-flight".to_string(),
                 description: "Book a flight and generate confirmation".to_string(),
-                inputSchema: json!({
+                input_schema: json!({
                     "type": "object",
                     "properties": {
                         "from": {
@@ -146,54 +372,207 @@ async fn list_tools() -> Json<ToolsResponse> {
                         "passenger_email": {
                             "type": "string",
                             "description": "Email address of passenger"
+                        },
+                        "cabin": {
+                            "type": "string",
+                            "enum": ["economy", "premium", "business", "first"],
+                            "description": "Cabin class that was priced and paid for (optional, default economy)"
+                        },
+                        "session_id": {
+                            "type": "string",
+                            "description": "Payment session ID (optional)"
+                        },
+                        "travel_date": {
+                            "type": "string",
+                            "description": "Departure date from the quote, YYYY-MM-DD (optional, used for refunds)"
+                        },
+                        "amount": {
+                            "type": "object",
+                            "description": "Amount paid, as returned by get-ticket-price (optional, used for refunds)",
+                            "properties": {
+                                "amount_minor": {"type": "integer"},
+                                "currency": {"type": "string"}
+                            }
+                        },
+                        "proof_ids": {
+                            "type": "array",
+                            "items": {"type": "string"},
+                            "description": "Payment Agent proof IDs backing the payment (optional, included in booking exports)"
                         }
                     },
                     "required": ["from", "to", "passenger_name", "passenger_email"]
                 }),
             },
+            ToolDefinition {
+                name: "get-booking".to_string(),
+                description: "Look up a booking by its ID".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "booking_id": {
+                            "type": "string",
+                            "description": "Booking ID returned by book-flight"
+                        }
+                    },
+                    "required": ["booking_id"]
+                }),
+            },
+            ToolDefinition {
+                name: "cancel-booking".to_string(),
+                description: "Cancel a confirmed booking and calculate the refund".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "booking_id": {
+                            "type": "string",
+                            "description": "Booking ID returned by book-flight"
+                        }
+                    },
+                    "required": ["booking_id"]
+                }),
+            },
         ],
     })
 }
 
 /// Get ticket pricing
 async fn get_ticket_price(
+    State(state): State<Arc<AppState>>,
     Json(req): Json<PriceRequest>,
 ) -> Result<Json<ToolResponse<PriceResponse>>, (StatusCode, Json<ToolResponse<()>>)> {
     tracing::info!("[GET-TICKET-PRICE] Tool call received: from={}, to={}, vip={:?}", req.from, req.to, req.vip);
     
-    // Validate input
-    if req.from.is_empty() || req.to.is_empty() {
-        tracing::warn!("[GET-TICKET-PRICE] Validation failed: missing required fields");
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(tool_error(
-                "from and to fields are required".to_string(),
-            )),
-        ));
-    }
+    // Promo codes are checked against today's date, which becomes part of the quote
+    let quote_date = today();
+    let promo_code = validate_price_request(&state.fares, &req, &quote_date)?;
 
     // Use pricing-core to calculate price
     let core_req = pricing::Request {
         from: req.from.clone(),
         to: req.to.clone(),
         vip: req.vip.unwrap_or(false),
+        travel_date: req.travel_date.clone(),
+        cabin: req.cabin,
+        promo_code: promo_code.clone(),
+        quote_date: Some(quote_date.clone()),
+        ancillaries: req.ancillaries.clone(),
     };
 
-    let core_resp = pricing::handle(core_req);
+    let core_resp = pricing::handle_with_table(core_req, &state.fares).map_err(core_error)?;
     
-    tracing::info!("[GET-TICKET-PRICE] Successfully calculated price: ${} (vip={})", core_resp.price, req.vip.unwrap_or(false));
+    tracing::info!("[GET-TICKET-PRICE] Successfully calculated price: {} {} (vip={}, cabin={:?})", core_resp.price, core_resp.price.currency, req.vip.unwrap_or(false), req.cabin);
 
     Ok(Json(ToolResponse::ok(PriceResponse {
-        price: core_resp.price,
+        price: core_resp.price.as_major(),
+        currency: core_resp.price.currency.clone(),
+        amount: core_resp.price,
         from: req.from,
         to: req.to,
         vip: req.vip.unwrap_or(false),
-        currency: "USD".to_string(),
+        travel_date: req.travel_date,
+        cabin: req.cabin,
+        promo_code,
+        quote_date,
+        breakdown: core_resp.breakdown,
     })))
 }
 
+/// Get a structured v2 quote with an ID and expiry
+async fn get_ticket_price_v2(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<PriceRequest>,
+) -> Result<Json<ToolResponse<pricing::v2::Response>>, (StatusCode, Json<ToolResponse<()>>)> {
+    tracing::info!("[GET-TICKET-PRICE-V2] Tool call received: from={}, to={}, vip={:?}", req.from, req.to, req.vip);
+
+    let quoted_at = now_secs();
+    let quote_date = Date::from_days_since_epoch((quoted_at / 86_400) as i64).to_string();
+    let promo_code = validate_price_request(&state.fares, &req, &quote_date)?;
+
+    let core_req = pricing::v2::Request {
+        from: req.from,
+        to: req.to,
+        vip: req.vip.unwrap_or(false),
+        travel_date: req.travel_date,
+        cabin: req.cabin,
+        promo_code,
+        ancillaries: req.ancillaries,
+        quoted_at,
+    };
+
+    let quote = pricing::v2::handle_with_table(core_req, &state.fares).map_err(core_error)?;
+
+    tracing::info!("[GET-TICKET-PRICE-V2] Quote {}: {} {} (expires_at={})", quote.quote_id, quote.price, quote.currency, quote.expires_at);
+    Ok(Json(ToolResponse::ok(quote)))
+}
+
+/// Check a pricing request against the fare table; returns the canonical
+/// promo code when one was given and is valid on `quote_date`
+fn validate_price_request(
+    fares: &RouteTable,
+    req: &PriceRequest,
+    quote_date: &str,
+) -> Result<Option<String>, (StatusCode, Json<ToolResponse<()>>)> {
+    let bad_request = |message: String| (StatusCode::BAD_REQUEST, Json(tool_error(message)));
+
+    if req.from.is_empty() || req.to.is_empty() {
+        tracing::warn!("[GET-TICKET-PRICE] Validation failed: missing required fields");
+        return Err(bad_request("from and to fields are required".to_string()));
+    }
+
+    if let Some(date) = &req.travel_date {
+        if Date::parse(date).is_none() {
+            tracing::warn!("[GET-TICKET-PRICE] Validation failed: invalid travel_date {}", date);
+            return Err(bad_request("travel_date must be a valid YYYY-MM-DD date".to_string()));
+        }
+    }
+
+    if let Some(code) = req.ancillaries.iter().find(|c| ancillary::find(fares, c).is_none()) {
+        tracing::warn!("[GET-TICKET-PRICE] Unknown ancillary {}", code);
+        return Err(bad_request(format!("unknown ancillary: {}", code)));
+    }
+
+    match &req.promo_code {
+        Some(code) => match promo::validate(fares, code, Some(quote_date)) {
+            Ok(promo) => Ok(Some(promo.code.clone())),
+            Err(e) => {
+                tracing::warn!("[GET-TICKET-PRICE] Promo code {} rejected: {}", code, e.message());
+                Err(bad_request(format!("{}: {}", e.message(), code)))
+            }
+        },
+        None => Ok(None),
+    }
+}
+
+/// List the supported IATA city/airport codes
+async fn list_destinations() -> Json<ToolResponse<&'static [Destination]>> {
+    tracing::info!("[LIST-DESTINATIONS] Tool call received");
+    Json(ToolResponse::ok(destinations::DESTINATIONS))
+}
+
+/// List the ancillary catalog priced for a cabin
+async fn list_ancillaries(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<AncillariesRequest>,
+) -> Json<ToolResponse<Vec<AncillaryOffer>>> {
+    tracing::info!("[LIST-ANCILLARIES] Tool call received: cabin={:?}", req.cabin);
+
+    let offers = state
+        .fares
+        .ancillaries
+        .iter()
+        .map(|a| AncillaryOffer {
+            code: a.code.clone(),
+            name: a.name.clone(),
+            amount: Money::new(a.price_for(req.cabin), &state.fares.currency),
+            included: a.included_in.contains(&req.cabin),
+        })
+        .collect();
+    Json(ToolResponse::ok(offers))
+}
+
 /// Book a flight
 async fn book_flight(
+    State(state): State<Arc<AppState>>,
     Json(req): Json<BookRequest>,
 ) -> Result<Json<ToolResponse<BookResponse>>, (StatusCode, Json<ToolResponse<()>>)> {
     tracing::info!("[BOOK-FLIGHT] Tool call received: from={}, to={}, passenger={}, email={}", req.from, req.to, req.passenger_name, req.passenger_email);
@@ -209,15 +588,33 @@ async fn book_flight(
         ));
     }
 
+    destinations::validate(&req.from).map_err(core_error)?;
+    destinations::validate(&req.to).map_err(core_error)?;
+
+    // The amount drives refunds, so it has to be what this fare actually costs
+    if let Some(paid) = &req.amount {
+        check_booked_amount(&state.fares, &req, paid)?;
+    }
+
     // Use pricing-core to generate booking
     let core_req = pricing_core::booking::Request {
         from: req.from.clone(),
         to: req.to.clone(),
         passenger_name: req.passenger_name.clone(),
         passenger_email: req.passenger_email.clone(),
+        cabin: req.cabin,
+        session_id: req.session_id.clone(),
+        travel_date: req.travel_date.clone(),
+        amount: req.amount.clone(),
+        proof_ids: req.proof_ids.clone(),
     };
 
-    let core_resp = pricing_core::booking::handle(core_req);
+    let core_resp = pricing_core::booking::handle_with_store(
+        core_req,
+        now_secs(),
+        state.bookings.lock().unwrap().as_mut(),
+    )
+    .map_err(store_error)?;
     
     tracing::info!("[BOOK-FLIGHT] Successfully booked flight: booking_id={}, confirmation_code={}, status={}", core_resp.booking_id, core_resp.confirmation_code, core_resp.status);
 
@@ -228,37 +625,295 @@ async fn book_flight(
         from: req.from,
         to: req.to,
         passenger_name: req.passenger_name,
+        cabin: req.cabin,
     })))
 }
 
+/// Re-price the booked trip and make sure `paid` is that fare in the
+/// table's currency
+fn check_booked_amount(
+    fares: &RouteTable,
+    req: &BookRequest,
+    paid: &Money,
+) -> Result<(), (StatusCode, Json<ToolResponse<()>>)> {
+    let bad_request = |message: String| (StatusCode::BAD_REQUEST, Json(tool_error(message)));
+
+    if !paid.currency.eq_ignore_ascii_case(&fares.currency) {
+        tracing::warn!("[BOOK-FLIGHT] Amount currency {} does not match fares in {}", paid.currency, fares.currency);
+        return Err(bad_request(format!("amount must be in {}", fares.currency)));
+    }
+    if paid.amount_minor <= 0 {
+        tracing::warn!("[BOOK-FLIGHT] Non-positive amount {}", paid.amount_minor);
+        return Err(bad_request("amount must be positive".to_string()));
+    }
+
+    let quote = pricing::handle_with_table(
+        pricing::Request {
+            from: req.from.clone(),
+            to: req.to.clone(),
+            vip: req.vip.unwrap_or(false),
+            travel_date: req.travel_date.clone(),
+            cabin: req.cabin,
+            promo_code: req.promo_code.clone(),
+            quote_date: Some(today()),
+            ancillaries: req.ancillaries.clone(),
+        },
+        fares,
+    )
+    .map_err(core_error)?;
+
+    if quote.price.amount_minor != paid.amount_minor {
+        tracing::warn!("[BOOK-FLIGHT] Amount {} does not match the fare {}", paid, quote.price);
+        return Err(bad_request(format!("amount {} {} does not match the fare {} {}", paid, paid.currency, quote.price, quote.price.currency)));
+    }
+    Ok(())
+}
+
+/// Look up a stored booking
+async fn get_booking(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<BookingIdRequest>,
+) -> Result<Json<ToolResponse<Booking>>, (StatusCode, Json<ToolResponse<()>>)> {
+    tracing::info!("[GET-BOOKING] Tool call received: booking_id={}", req.booking_id);
+
+    let booking = state.bookings.lock().unwrap().get(&req.booking_id);
+    booking
+        .map(|b| Json(ToolResponse::ok(b)))
+        .ok_or_else(|| store_error(StoreError::NotFound))
+}
+
+/// Cancel a confirmed booking and work out the refund
+async fn cancel_booking(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<BookingIdRequest>,
+) -> Result<Json<ToolResponse<CancelResponse>>, (StatusCode, Json<ToolResponse<()>>)> {
+    tracing::info!("[CANCEL-BOOKING] Tool call received: booking_id={}", req.booking_id);
+
+    let booking = state
+        .bookings
+        .lock()
+        .unwrap()
+        .cancel(&req.booking_id)
+        .map_err(store_error)?;
+
+    let refund = booking.request.amount.clone().map(|paid| {
+        refund::handle(refund::Request {
+            paid,
+            cabin: booking.request.cabin,
+            travel_date: booking.request.travel_date.clone(),
+            booked_at: booking.booked_at,
+            cancelled_at: now_secs(),
+        })
+    });
+
+    match &refund {
+        Some(r) => tracing::info!("[CANCEL-BOOKING] Cancelled booking {}: refund {} ({:?})", booking.response.booking_id, r.refund, r.rule),
+        None => tracing::info!("[CANCEL-BOOKING] Cancelled booking {}: no amount recorded, refund not calculated", booking.response.booking_id),
+    }
+    Ok(Json(ToolResponse::ok(CancelResponse { booking, refund })))
+}
+
+/// Map a pricing-core rejection to an HTTP status and tool error. A bad tier
+/// multiplier comes from the fare table, not the caller.
+fn core_error(e: impl Into<CoreError>) -> (StatusCode, Json<ToolResponse<()>>) {
+    let e = e.into();
+    tracing::warn!("Pricing rejected request: {}", e);
+    match e {
+        CoreError::UnknownRoute(_) => (
+            StatusCode::BAD_REQUEST,
+            Json(tool_error(format!("{} (see list-destinations)", e))),
+        ),
+        CoreError::InvalidDate(_) | CoreError::PromoRejected(..) | CoreError::UnknownAncillary(_) => {
+            (StatusCode::BAD_REQUEST, Json(tool_error(e.to_string())))
+        }
+        CoreError::InvalidTier(_) => (StatusCode::INTERNAL_SERVER_ERROR, Json(tool_error(e.to_string()))),
+    }
+}
+
+/// Map a booking store failure to an HTTP status and tool error
+fn store_error(e: StoreError) -> (StatusCode, Json<ToolResponse<()>>) {
+    let status = match e {
+        StoreError::NotFound => StatusCode::NOT_FOUND,
+        StoreError::AlreadyCancelled => StatusCode::CONFLICT,
+        StoreError::Backend(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    tracing::warn!("Booking store: {}", e);
+    (status, Json(tool_error(e.to_string())))
+}
+
+/// Current unix time in seconds
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Unix seconds as RFC 3339 UTC, e.g. `2025-10-16T07:33:20Z`
+fn rfc3339(secs: u64) -> String {
+    let date = Date::from_days_since_epoch((secs / 86_400) as i64);
+    let time = secs % 86_400;
+    format!("{}T{:02}:{:02}:{:02}Z", date, time / 3600, time / 60 % 60, time % 60)
+}
+
+/// Today's UTC date (YYYY-MM-DD), used as the quote date for promo expiry
+fn today() -> String {
+    Date::from_days_since_epoch((now_secs() / 86_400) as i64).to_string()
+}
+
+/// Load the fare table override from `fare_table_path`, or use the embedded defaults
+fn load_fare_table(settings: &AgentBSettings) -> Result<RouteTable> {
+    match &settings.fare_table_path {
+        Some(path) => {
+            let table = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+            println!("✓ Loaded fare table from {}", path.display());
+            Ok(table)
+        }
+        None => Ok(RouteTable::embedded()),
+    }
+}
+
+/// Largest request body accepted for signature verification
+const MAX_SIGNED_BODY_BYTES: usize = 1024 * 1024;
+
+/// Reject tool calls whose HMAC signature does not match the shared secret
+async fn verify_signature(
+    State(key): State<Arc<SigningKey>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let (parts, body) = request.into_parts();
+    let bytes = match body::to_bytes(body, MAX_SIGNED_BODY_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(_) => return StatusCode::PAYLOAD_TOO_LARGE.into_response(),
+    };
+
+    let header = |name: &str| parts.headers.get(name).and_then(|v| v.to_str().ok());
+    if let Err(e) = key.verify(header(TIMESTAMP_HEADER), header(SIGNATURE_HEADER), &bytes) {
+        tracing::warn!("Rejected unsigned or tampered request to {}: {}", parts.uri, e);
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(json!({ "success": false, "error": e.to_string() })),
+        )
+            .into_response();
+    }
+
+    next.run(Request::from_parts(parts, Body::from(bytes))).await
+}
+
+/// Run each request in a span continuing Agent A's trace
+async fn trace_request(request: Request, next: Next) -> Response {
+    let span = telemetry::request_span(request.method(), request.uri().path(), request.headers());
+    next.run(request).instrument(span).await
+}
+
+/// Cross-origin policy for the HTTP API: any origin, or only `allowed_origins`
+fn cors_layer(network: &zp_config::NetworkSettings) -> CorsLayer {
+    if network.allows_any_origin() {
+        return CorsLayer::permissive();
+    }
+    let origins: Vec<HeaderValue> = network.allowed_origins.iter().filter_map(|o| o.parse().ok()).collect();
+    CorsLayer::new().allow_origin(origins).allow_methods(Any).allow_headers(Any)
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Initialize logging
-    tracing_subscriber::fmt()
-        .with_max_level(tracing::Level::INFO)
-        .init();
+    // Initialize logging and trace export
+    let _telemetry = telemetry::init("agent-b");
+
+    let config = zp_config::init()?;
+    if zp_config::print_config_requested() {
+        print!("{}", config.to_toml());
+        return Ok(());
+    }
+    let settings = &config.agent_b;
 
     println!("\n╔════════════════════════════════════════════════════════════╗");
     println!("║          Agent B - MCP Server (Pricing & Booking)          ║");
     println!("╚════════════════════════════════════════════════════════════╝\n");
 
+    let bookings = FileBookingStore::open(&settings.bookings_path)?;
+    println!("✓ Loaded {} booking(s) from {}", bookings.len(), settings.bookings_path.display());
+
+    let state = Arc::new(AppState {
+        fares: load_fare_table(settings)?,
+        bookings: Mutex::new(Box::new(bookings)),
+        tasks: a2a::TaskStore::default(),
+    });
+
+    // Tool calls must be signed when AGENT_HMAC_SECRET is set
+    let mut tool_calls = Router::new()
+        .route("/tools/get-ticket-price", post(get_ticket_price))
+        .route("/tools/book-flight", post(book_flight))
+        .route("/tools/get-ticket-price-v2", post(get_ticket_price_v2))
+        .route("/tools/list-destinations", post(list_destinations))
+        .route("/tools/list-ancillaries", post(list_ancillaries))
+        .route("/tools/get-booking", post(get_booking))
+        .route("/tools/cancel-booking", post(cancel_booking))
+        .route("/a2a", post(a2a::rpc));
+    if let Some(key) = SigningKey::from_env() {
+        println!("✓ HMAC request signing enforced on tool calls");
+        tool_calls = tool_calls.route_layer(middleware::from_fn_with_state(Arc::new(key), verify_signature));
+    }
+
     // Build router
     let app = Router::new()
+        .route("/health", get(health))
+        .route("/health/ready", get(health_ready))
         .route("/tools", get(list_tools))
-        .route("/tools/get-ticket-price", post(get_ticket_price))
-        .route("/tools/book-flight", post(book_flight))
-        .layer(CorsLayer::permissive());
+        .route("/.well-known/agent.json", get(a2a::agent_card))
+        .route("/admin/bookings/export", get(export::export_bookings))
+        .merge(tool_calls)
+        .with_state(state)
+        .layer(middleware::from_fn(trace_request))
+        .layer(cors_layer(&config.network));
 
-    // Bind and serve
-    let listener = tokio::net::TcpListener::bind("0.0.0.0:8001")
-        .await?;
+    // Optional mutual TLS (TLS_CERT / TLS_KEY / TLS_CA)
+    let tls = MtlsConfig::from_env();
+    let scheme = if tls.is_some() { "https" } else { "http" };
 
-    println!("✓ Agent B MCP Server running on http://0.0.0.0:8001");
+    println!("✓ Agent B MCP Server running on {}://0.0.0.0:{}", scheme, settings.port);
+    println!("  GET  /health                    — Liveness");
+    println!("  GET  /health/ready              — Readiness (fare table, booking store, TLS)");
     println!("  GET  /tools                     — List all tools");
     println!("  POST /tools/get-ticket-price    — Get flight pricing");
-    println!("  POST /tools/book-flight         — Book a flight\n");
+    println!("  POST /tools/book-flight         — Book a flight");
+    println!("  POST /tools/get-ticket-price-v2 — Get a structured quote (ID, expiry, breakdown)");
+    println!("  POST /tools/list-destinations   — List supported IATA codes");
+    println!("  POST /tools/list-ancillaries    — List add-ons for a cabin");
+    println!("  POST /tools/get-booking         — Look up a booking");
+    println!("  POST /tools/cancel-booking      — Cancel a booking");
+    println!("  GET  /.well-known/agent.json    — A2A agent card");
+    println!("  POST /a2a                       — A2A JSON-RPC (message/send, tasks/get)");
+    println!("  GET  /admin/bookings/export     — Bookings as JSON or CSV (Bearer $ADMIN_TOKEN)\n");
 
-    axum::serve(listener, app).await?;
+    // Bind and serve
+    match tls {
+        Some(tls) => {
+            let config = RustlsConfig::from_config(Arc::new(tls.server_config()?));
+            let handle = axum_server::Handle::new();
+            tokio::spawn(drain_on_signal(handle.clone()));
+            axum_server::bind_rustls(([0, 0, 0, 0], settings.port).into(), config)
+                .handle(handle)
+                .serve(app.into_make_service())
+                .await?;
+        }
+        None => {
+            let listener = tokio::net::TcpListener::bind(("0.0.0.0", settings.port))
+                .await?;
+            axum::serve(listener, app)
+                .with_graceful_shutdown(shutdown::signal())
+                .await?;
+        }
+    }
 
+    // Every booking change is already on disk; dropping the router closes the store
+    println!("✓ Agent B stopped");
     Ok(())
 }
+
+/// Stop accepting connections on SIGINT/SIGTERM and let in-flight requests finish
+async fn drain_on_signal(handle: axum_server::Handle) {
+    shutdown::signal().await;
+    handle.graceful_shutdown(Some(shutdown::DRAIN_TIMEOUT));
+}
@@ -0,0 +1,50 @@
+use alloc::{string::String, vec::Vec};
+use serde::{Deserialize, Serialize};
+
+use crate::fares::{Cabin, RouteTable};
+
+/// Optional extra sold with a ticket
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Ancillary {
+    /// Identifier sent in pricing requests, e.g. `checked_bag`
+    pub code: String,
+    /// Human-readable name
+    pub name: String,
+    /// Price in minor units of the table currency
+    pub price: i64,
+    /// Cabins where the extra is already part of the fare
+    pub included_in: Vec<Cabin>,
+}
+
+impl Ancillary {
+    /// Price in `cabin`: free where included, otherwise the list price
+    pub fn price_for(&self, cabin: Cabin) -> i64 {
+        if self.included_in.contains(&cabin) {
+            0
+        } else {
+            self.price
+        }
+    }
+}
+
+/// One ancillary line on a quote
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct AncillaryCharge {
+    pub code: String,
+    pub amount_minor: i64,
+}
+
+/// Built-in catalog (code, name, price, cabins that include it)
+pub(crate) const DEFAULT_ANCILLARIES: &[(&str, &str, i64, &[Cabin])] = &[
+    ("checked_bag", "Checked bag (23 kg)", 3_500, &[Cabin::Business, Cabin::First]),
+    ("extra_legroom", "Extra legroom seat", 4_900, &[Cabin::Premium, Cabin::Business, Cabin::First]),
+    ("meal", "Hot meal", 1_500, &[Cabin::Business, Cabin::First]),
+];
+
+/// Look up `code` (case-insensitive) in the table's catalog
+pub fn find<'a>(table: &'a RouteTable, code: &str) -> Option<&'a Ancillary> {
+    table
+        .ancillaries
+        .iter()
+        .find(|a| a.code.eq_ignore_ascii_case(code))
+}
@@ -1,21 +1,56 @@
-use alloc::string::String;
+use alloc::{string::String, vec::Vec};
+use core::fmt::Write;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
-#[derive(Serialize, Deserialize)]
+use crate::{
+    fares::Cabin,
+    money::Money,
+    store::{BookingStore, StoreError},
+};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Request {
     pub from: String,
     pub to: String,
     pub passenger_name: String,
     pub passenger_email: String,
+    /// Cabin class (defaults to economy)
+    #[serde(default)]
+    pub cabin: Cabin,
+    /// Booking session (e.g. the payment session ID), mixed into the
+    /// generated codes so repeat bookings in different sessions differ
+    #[serde(default)]
+    pub session_id: Option<String>,
+    /// Departure date (`YYYY-MM-DD`), used for refunds
+    #[serde(default)]
+    pub travel_date: Option<String>,
+    /// Amount paid, used for refunds
+    #[serde(default)]
+    pub amount: Option<Money>,
+    /// Payment Agent proofs backing the payment, kept for reporting; not
+    /// part of the booking codes
+    #[serde(default)]
+    pub proof_ids: Vec<String>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Response {
     pub booking_id: String,
     pub status: String,
     pub confirmation_code: String,
 }
 
+/// A booking as kept by a `BookingStore`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Booking {
+    pub request: Request,
+    pub response: Response,
+    /// When the booking was made (unix seconds)
+    #[serde(default)]
+    pub booked_at: u64,
+}
+
 /// Booking logic that runs both on server and inside SP1
 /// NOTE: Inside SP1, external HTTP calls are not possible, so this will
 /// return a deterministic result based on input. The server implementation
@@ -24,16 +59,11 @@ pub fn handle(req: Request) -> Response {
     // Deterministic booking logic for ZK proof
     // In SP1: generates deterministic booking based on inputs
     // On server: this can be overridden to call real booking API
-    
-    // Generate deterministic booking ID from request data
-    let booking_data = alloc::format!(
-        "{}-{}-{}-{}",
-        req.from, req.to, req.passenger_name, req.passenger_email
-    );
-    
-    // Simple hash-like transformation (deterministic)
-    let booking_id = alloc::format!("BK{:08X}", booking_data.len() * 12345);
-    let confirmation_code = alloc::format!("CONF{:06X}", booking_data.len() * 67890);
+
+    // booking_id and confirmation_code come from disjoint bytes of one digest
+    let digest = booking_digest(&req);
+    let booking_id = alloc::format!("BK{}", hex_upper(&digest[..8]));
+    let confirmation_code = alloc::format!("CONF{}", hex_upper(&digest[8..13]));
 
     Response {
         booking_id,
@@ -41,3 +71,47 @@ pub fn handle(req: Request) -> Response {
         confirmation_code,
     }
 }
+
+/// Book at `booked_at` (unix seconds) and persist the result in `store`.
+/// Booking the same request in the same session again returns the stored
+/// booking unless it was cancelled.
+pub fn handle_with_store(
+    req: Request,
+    booked_at: u64,
+    store: &mut dyn BookingStore,
+) -> Result<Response, StoreError> {
+    let response = handle(req.clone());
+    if let Some(existing) = store.get(&response.booking_id) {
+        if existing.response.status != "cancelled" {
+            return Ok(existing.response);
+        }
+    }
+    store.save(Booking {
+        request: req,
+        response: response.clone(),
+        booked_at,
+    })?;
+    Ok(response)
+}
+
+/// SHA-256 over the length-prefixed booking fields and session
+fn booking_digest(req: &Request) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    let session = req.session_id.as_deref().unwrap_or("");
+    for field in [&req.from, &req.to, &req.passenger_name, &req.passenger_email] {
+        hasher.update((field.len() as u64).to_le_bytes());
+        hasher.update(field.as_bytes());
+    }
+    hasher.update([req.cabin as u8]);
+    hasher.update((session.len() as u64).to_le_bytes());
+    hasher.update(session.as_bytes());
+    hasher.finalize().into()
+}
+
+fn hex_upper(bytes: &[u8]) -> String {
+    let mut s = String::with_capacity(bytes.len() * 2);
+    for b in bytes {
+        let _ = write!(s, "{:02X}", b);
+    }
+    s
+}
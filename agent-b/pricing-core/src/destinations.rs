@@ -0,0 +1,56 @@
+use alloc::string::String;
+use core::fmt;
+use serde::Serialize;
+
+/// IATA city or airport code accepted as `from`/`to`
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Destination {
+    pub code: &'static str,
+    pub city: &'static str,
+    pub country: &'static str,
+}
+
+const fn dest(code: &'static str, city: &'static str, country: &'static str) -> Destination {
+    Destination { code, city, country }
+}
+
+/// Supported destinations. Pairs without a great-circle entry in the fare
+/// table are priced at its `default_fare`.
+pub const DESTINATIONS: &[Destination] = &[
+    dest("NYC", "New York", "US"),
+    dest("LAX", "Los Angeles", "US"),
+    dest("SFO", "San Francisco", "US"),
+    dest("CHI", "Chicago", "US"),
+    dest("MIA", "Miami", "US"),
+    dest("YTO", "Toronto", "CA"),
+    dest("LON", "London", "GB"),
+    dest("PAR", "Paris", "FR"),
+    dest("BER", "Berlin", "DE"),
+    dest("ROM", "Rome", "IT"),
+    dest("MAD", "Madrid", "ES"),
+    dest("AMS", "Amsterdam", "NL"),
+    dest("DXB", "Dubai", "AE"),
+    dest("SIN", "Singapore", "SG"),
+    dest("HKG", "Hong Kong", "HK"),
+    dest("TYO", "Tokyo", "JP"),
+    dest("SYD", "Sydney", "AU"),
+];
+
+/// `from`/`to` value that is not a supported IATA code
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnknownCode(pub String);
+
+impl fmt::Display for UnknownCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unsupported IATA code: {}", self.0)
+    }
+}
+
+/// Look up an exact (upper-case) IATA code
+pub fn find(code: &str) -> Option<&'static Destination> {
+    DESTINATIONS.iter().find(|d| d.code == code)
+}
+
+pub fn validate(code: &str) -> Result<&'static Destination, UnknownCode> {
+    find(code).ok_or_else(|| UnknownCode(String::from(code)))
+}
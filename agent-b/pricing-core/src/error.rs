@@ -0,0 +1,39 @@
+use alloc::string::String;
+use core::fmt;
+use serde::{Deserialize, Serialize};
+
+use crate::{destinations::UnknownCode, promo::PromoError};
+
+/// Why pricing-core rejected a request. Serializable so the guest can commit
+/// it alongside a failed quote instead of panicking.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum CoreError {
+    /// `from` or `to` is not a supported IATA code
+    UnknownRoute(String),
+    /// `travel_date` is not a valid `YYYY-MM-DD` date
+    InvalidDate(String),
+    /// Tier multiplier outside 1..=10_000 bps (tiers may only discount)
+    InvalidTier(u32),
+    /// `promo_code` is unknown or has expired on the quote date
+    PromoRejected(String, PromoError),
+    /// An ancillary code is not in the fare table's catalog
+    UnknownAncillary(String),
+}
+
+impl fmt::Display for CoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CoreError::UnknownRoute(code) => write!(f, "unsupported IATA code: {}", code),
+            CoreError::InvalidDate(date) => write!(f, "invalid date: {}", date),
+            CoreError::InvalidTier(bps) => write!(f, "invalid tier multiplier: {} bps", bps),
+            CoreError::PromoRejected(code, e) => write!(f, "{}: {}", e.message(), code),
+            CoreError::UnknownAncillary(code) => write!(f, "unknown ancillary: {}", code),
+        }
+    }
+}
+
+impl From<UnknownCode> for CoreError {
+    fn from(e: UnknownCode) -> Self {
+        CoreError::UnknownRoute(e.0)
+    }
+}
@@ -0,0 +1,339 @@
+use alloc::{string::String, vec::Vec};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    ancillary::{Ancillary, DEFAULT_ANCILLARIES},
+    date::Date,
+    money::{apply_bps, BPS},
+    promo::{Discount, PromoCode, DEFAULT_PROMOS},
+};
+
+/// Fare multiplier per passenger tier, in basis points
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TierMultipliers {
+    pub standard: u32,
+    pub vip: u32,
+}
+
+impl TierMultipliers {
+    pub fn get(&self, vip: bool) -> u32 {
+        if vip {
+            self.vip
+        } else {
+            self.standard
+        }
+    }
+}
+
+/// Great-circle distance between two cities (either direction)
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Route {
+    pub from: String,
+    pub to: String,
+    pub distance_km: u32,
+}
+
+/// Cabin class, priced as a multiple of the economy fare
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Cabin {
+    #[default]
+    Economy,
+    Premium,
+    Business,
+    First,
+}
+
+/// Fare multiplier per cabin class, in basis points
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CabinMultipliers {
+    pub economy: u32,
+    pub premium: u32,
+    pub business: u32,
+    pub first: u32,
+}
+
+impl CabinMultipliers {
+    pub fn get(&self, cabin: Cabin) -> u32 {
+        match cabin {
+            Cabin::Economy => self.economy,
+            Cabin::Premium => self.premium,
+            Cabin::Business => self.business,
+            Cabin::First => self.first,
+        }
+    }
+}
+
+/// Date range (inclusive, may wrap over New Year) with a fare multiplier
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Season {
+    pub name: String,
+    pub start_month: u8,
+    pub start_day: u8,
+    pub end_month: u8,
+    pub end_day: u8,
+    /// Fare multiplier in basis points (12_500 = ×1.25)
+    pub multiplier_bps: u32,
+}
+
+impl Season {
+    pub fn contains(&self, date: &Date) -> bool {
+        let day = date.month as u16 * 100 + date.day as u16;
+        let start = self.start_month as u16 * 100 + self.start_day as u16;
+        let end = self.end_month as u16 * 100 + self.end_day as u16;
+
+        if start <= end {
+            (start..=end).contains(&day)
+        } else {
+            day >= start || day <= end
+        }
+    }
+}
+
+/// Route fare table used by pricing — the same table must be handed to the
+/// SP1 guest so the proven price matches the one Agent B quoted
+///
+/// fare = (base_fare + per_km_rate × distance_km) × season × weekend × cabin × tier
+///
+/// All amounts are in minor units of `currency` and all multipliers in basis
+/// points, so the guest and the server round identically.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RouteTable {
+    pub currency: String,
+    pub routes: Vec<Route>,
+    /// Fixed part of every fare (taxes, handling)
+    pub base_fare: i64,
+    /// Price per great-circle kilometre
+    pub per_km_rate: i64,
+    /// Fare for city pairs not listed in `routes`
+    pub default_fare: i64,
+    /// Seasonal multipliers; the first season containing the travel date applies
+    pub seasons: Vec<Season>,
+    /// Uplift for Saturday and Sunday departures, in basis points
+    pub weekend_multiplier_bps: u32,
+    /// Cabin class multipliers
+    pub cabins: CabinMultipliers,
+    /// Passenger tier multipliers (VIP discount)
+    pub tiers: TierMultipliers,
+    /// Promo codes accepted on quotes
+    pub promo_codes: Vec<PromoCode>,
+    /// Add-ons (bags, seats, meals) that can be bought with a ticket
+    pub ancillaries: Vec<Ancillary>,
+}
+
+/// Built-in great-circle distances (from, to, km), used unless the server
+/// loads an override
+const DEFAULT_ROUTES: &[(&str, &str, u32)] = &[
+    ("NYC", "LON", 5570),
+    ("NYC", "PAR", 5837),
+    ("NYC", "SFO", 4130),
+    ("NYC", "LAX", 3944),
+    ("NYC", "TYO", 10838),
+    ("LON", "PAR", 344),
+    ("LON", "SFO", 8616),
+    ("LON", "LAX", 8756),
+    ("LON", "TYO", 9559),
+    ("PAR", "SFO", 8958),
+    ("PAR", "LAX", 9085),
+    ("PAR", "TYO", 9714),
+    ("SFO", "LAX", 543),
+    ("SFO", "TYO", 8277),
+    ("LAX", "TYO", 8815),
+];
+
+/// Built-in seasons (name, start month/day, end month/day, multiplier bps)
+const DEFAULT_SEASONS: &[(&str, u8, u8, u8, u8, u32)] = &[
+    ("winter-holidays", 12, 18, 1, 6, 13_500),
+    ("summer-peak", 6, 15, 8, 31, 12_500),
+    ("thanksgiving", 11, 22, 11, 30, 12_000),
+    ("winter-low", 1, 15, 3, 15, 9_000),
+];
+
+const DEFAULT_CURRENCY: &str = "USD";
+const DEFAULT_WEEKEND_MULTIPLIER_BPS: u32 = 11_000;
+const DEFAULT_BASE_FARE: i64 = 12_300;
+const DEFAULT_PER_KM_RATE: i64 = 10;
+const DEFAULT_FARE: i64 = 45_000;
+
+impl RouteTable {
+    /// Static table compiled into both the server and the guest program
+    pub fn embedded() -> Self {
+        Self {
+            currency: String::from(DEFAULT_CURRENCY),
+            routes: DEFAULT_ROUTES
+                .iter()
+                .map(|(from, to, distance_km)| Route {
+                    from: String::from(*from),
+                    to: String::from(*to),
+                    distance_km: *distance_km,
+                })
+                .collect(),
+            base_fare: DEFAULT_BASE_FARE,
+            per_km_rate: DEFAULT_PER_KM_RATE,
+            default_fare: DEFAULT_FARE,
+            seasons: DEFAULT_SEASONS
+                .iter()
+                .map(|(name, start_month, start_day, end_month, end_day, multiplier_bps)| Season {
+                    name: String::from(*name),
+                    start_month: *start_month,
+                    start_day: *start_day,
+                    end_month: *end_month,
+                    end_day: *end_day,
+                    multiplier_bps: *multiplier_bps,
+                })
+                .collect(),
+            weekend_multiplier_bps: DEFAULT_WEEKEND_MULTIPLIER_BPS,
+            cabins: CabinMultipliers {
+                economy: BPS,
+                premium: 16_000,
+                business: 32_000,
+                first: 55_000,
+            },
+            tiers: TierMultipliers {
+                standard: BPS,
+                // VIP passengers pay 85% of the fare
+                vip: 8_500,
+            },
+            promo_codes: DEFAULT_PROMOS
+                .iter()
+                .map(|(code, discount, expires)| PromoCode {
+                    code: String::from(*code),
+                    discount: discount.clone(),
+                    expires: expires.map(String::from),
+                })
+                .collect(),
+            ancillaries: DEFAULT_ANCILLARIES
+                .iter()
+                .map(|(code, name, price, included_in)| Ancillary {
+                    code: String::from(*code),
+                    name: String::from(*name),
+                    price: *price,
+                    included_in: included_in.to_vec(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Distance between two cities, in either direction
+    pub fn distance_km(&self, from: &str, to: &str) -> Option<u32> {
+        self.routes
+            .iter()
+            .find(|r| (r.from == from && r.to == to) || (r.from == to && r.to == from))
+            .map(|r| r.distance_km)
+    }
+
+    /// Fare in minor units for `from` → `to` before tier adjustments,
+    /// falling back to `default_fare` for unknown city pairs
+    pub fn fare(&self, from: &str, to: &str) -> i64 {
+        match self.distance_km(from, to) {
+            Some(km) => self.base_fare + self.per_km_rate * km as i64,
+            None => self.default_fare,
+        }
+    }
+
+    /// Apply the seasonal and weekday multipliers for a travel date
+    pub fn apply_date(&self, fare: i64, date: &Date) -> i64 {
+        let fare = match self.seasons.iter().find(|s| s.contains(date)) {
+            Some(season) => apply_bps(fare, season.multiplier_bps),
+            None => fare,
+        };
+        if date.is_weekend() {
+            apply_bps(fare, self.weekend_multiplier_bps)
+        } else {
+            fare
+        }
+    }
+
+    /// SHA-256 of a canonical encoding of the whole table. The pricing guest
+    /// commits it, so a proof pins the exact fares a quote was priced against.
+    pub fn digest(&self) -> [u8; 32] {
+        let mut h = TableHasher(Sha256::new());
+        h.str(&self.currency);
+        h.len(self.routes.len());
+        for route in &self.routes {
+            h.str(&route.from);
+            h.str(&route.to);
+            h.u64(route.distance_km as u64);
+        }
+        h.i64(self.base_fare);
+        h.i64(self.per_km_rate);
+        h.i64(self.default_fare);
+        h.len(self.seasons.len());
+        for season in &self.seasons {
+            h.str(&season.name);
+            h.bytes(&[season.start_month, season.start_day, season.end_month, season.end_day]);
+            h.u64(season.multiplier_bps as u64);
+        }
+        h.u64(self.weekend_multiplier_bps as u64);
+        for bps in [self.cabins.economy, self.cabins.premium, self.cabins.business, self.cabins.first] {
+            h.u64(bps as u64);
+        }
+        h.u64(self.tiers.standard as u64);
+        h.u64(self.tiers.vip as u64);
+        h.len(self.promo_codes.len());
+        for promo in &self.promo_codes {
+            h.str(&promo.code);
+            match promo.discount {
+                Discount::PercentBps(bps) => {
+                    h.bytes(&[0]);
+                    h.u64(bps as u64);
+                }
+                Discount::Fixed(amount) => {
+                    h.bytes(&[1]);
+                    h.i64(amount);
+                }
+            }
+            match &promo.expires {
+                Some(date) => {
+                    h.bytes(&[1]);
+                    h.str(date);
+                }
+                None => h.bytes(&[0]),
+            }
+        }
+        h.len(self.ancillaries.len());
+        for ancillary in &self.ancillaries {
+            h.str(&ancillary.code);
+            h.str(&ancillary.name);
+            h.i64(ancillary.price);
+            h.len(ancillary.included_in.len());
+            for cabin in &ancillary.included_in {
+                h.bytes(&[*cabin as u8]);
+            }
+        }
+        h.0.finalize().into()
+    }
+}
+
+/// Length-prefixed, little-endian field encoding for `RouteTable::digest`
+struct TableHasher(Sha256);
+
+impl TableHasher {
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
+    }
+
+    fn u64(&mut self, n: u64) {
+        self.0.update(n.to_le_bytes());
+    }
+
+    fn i64(&mut self, n: i64) {
+        self.0.update(n.to_le_bytes());
+    }
+
+    fn len(&mut self, n: usize) {
+        self.u64(n as u64);
+    }
+
+    fn str(&mut self, s: &str) {
+        self.len(s.len());
+        self.0.update(s.as_bytes());
+    }
+}
+
+impl Default for RouteTable {
+    fn default() -> Self {
+        Self::embedded()
+    }
+}
@@ -1,17 +1,30 @@
 #![no_std]   // Required: must work inside SP1 too
 
 extern crate alloc;
-use alloc::string::String;
+use alloc::string::{String, ToString};
 use serde::{Deserialize, Serialize};
 
 pub mod pricing;
 pub mod booking;
+pub mod fares;
+pub mod date;
+pub mod promo;
+pub mod money;
+pub mod workflow;
+pub mod store;
+pub mod refund;
+pub mod ancillary;
+pub mod destinations;
+pub mod error;
 
 /// Single enum — one input type for the entire backend
 #[derive(Serialize, Deserialize)]
 pub enum RpcCall {
     GetPrice(pricing::Request),
     BookFlight(booking::Request),
+    /// Pricing against a server-supplied fare table
+    GetPriceWithTable(pricing::Request, fares::RouteTable),
+    Refund(refund::Request),
 }
 
 /// Single enum — one output type
@@ -20,12 +33,23 @@ pub enum RpcResult {
     Price(pricing::Response),
     Booking(booking::Response),
     Error(String),
+    Refund(refund::Response),
 }
 
 /// Main dispatcher — runs both on server and inside SP1
 pub fn handle_call(call: RpcCall) -> RpcResult {
     match call {
-        RpcCall::GetPrice(req)   => RpcResult::Price(pricing::handle(req)),
+        RpcCall::GetPrice(req)   => price_result(pricing::handle(req)),
         RpcCall::BookFlight(req) => RpcResult::Booking(booking::handle(req)),
+        RpcCall::GetPriceWithTable(req, table) => {
+            price_result(pricing::handle_with_table(req, &table))
+        }
+        RpcCall::Refund(req) => RpcResult::Refund(refund::handle(req)),
     }
-}
\ No newline at end of file
+}
+fn price_result(result: Result<pricing::Response, error::CoreError>) -> RpcResult {
+    match result {
+        Ok(resp) => RpcResult::Price(resp),
+        Err(e) => RpcResult::Error(e.to_string()),
+    }
+}
@@ -0,0 +1,76 @@
+use alloc::string::String;
+use core::fmt;
+use serde::{Deserialize, Serialize};
+
+/// Basis points in 1.0 — multipliers and percentages are stored as bps so
+/// pricing never touches floating point
+pub const BPS: u32 = 10_000;
+
+/// ISO 4217 currencies without a minor unit (yen, won, ...)
+const ZERO_DECIMAL_CURRENCIES: &[&str] = &[
+    "BIF", "CLP", "DJF", "GNF", "ISK", "JPY", "KMF", "KRW", "PYG", "RWF", "UGX", "UYI", "VND", "VUV", "XAF", "XOF", "XPF",
+];
+
+/// ISO 4217 currencies whose minor unit is a thousandth
+const THREE_DECIMAL_CURRENCIES: &[&str] = &["BHD", "IQD", "JOD", "KWD", "LYD", "OMR", "TND"];
+
+/// Decimal places of `currency`'s minor unit (2 unless ISO 4217 says otherwise)
+pub fn minor_unit_exponent(currency: &str) -> u32 {
+    let listed = |codes: &[&str]| codes.iter().any(|code| code.eq_ignore_ascii_case(currency));
+    if listed(ZERO_DECIMAL_CURRENCIES) {
+        0
+    } else if listed(THREE_DECIMAL_CURRENCIES) {
+        3
+    } else {
+        2
+    }
+}
+
+/// Amount in the currency's minor unit (cents for USD, yen for JPY)
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Money {
+    pub amount_minor: i64,
+    pub currency: String,
+}
+
+impl Money {
+    pub fn new(amount_minor: i64, currency: &str) -> Self {
+        Self {
+            amount_minor,
+            currency: String::from(currency),
+        }
+    }
+
+    /// Decimal places of this amount's minor unit
+    pub fn exponent(&self) -> u32 {
+        minor_unit_exponent(&self.currency)
+    }
+
+    /// Major-unit value for display only — never feed this back into pricing
+    pub fn as_major(&self) -> f64 {
+        self.amount_minor as f64 / 10u64.pow(self.exponent()) as f64
+    }
+}
+
+/// Decimal string with the currency's minor-unit places, as expected by
+/// payment APIs (`450.00` USD, `45000` JPY)
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.amount_minor < 0 { "-" } else { "" };
+        let abs = self.amount_minor.unsigned_abs();
+        let exponent = self.exponent();
+        if exponent == 0 {
+            return write!(f, "{}{}", sign, abs);
+        }
+        let scale = 10u64.pow(exponent);
+        write!(f, "{}{}.{:0width$}", sign, abs / scale, abs % scale, width = exponent as usize)
+    }
+}
+
+/// Multiply `amount_minor` by `bps / 10_000`, rounding half away from zero
+pub fn apply_bps(amount_minor: i64, bps: u32) -> i64 {
+    let scaled = amount_minor as i128 * bps as i128;
+    let half = BPS as i128 / 2;
+    let rounded = if scaled >= 0 { scaled + half } else { scaled - half };
+    (rounded / BPS as i128) as i64
+}
@@ -1,38 +1,155 @@
-use alloc::string::String;
+use alloc::{string::String, vec::Vec};
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize)]
+use crate::{
+    ancillary::{self, AncillaryCharge},
+    date::Date,
+    destinations,
+    error::CoreError,
+    fares::{Cabin, RouteTable},
+    money::{apply_bps, Money, BPS},
+    promo,
+};
+
+pub mod v2;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Request {
     pub from: String,
     pub to: String,
     pub vip: bool,
+    /// Departure date (`YYYY-MM-DD`); seasonal and weekend multipliers apply when set
+    #[serde(default)]
+    pub travel_date: Option<String>,
+    /// Cabin class (defaults to economy)
+    #[serde(default)]
+    pub cabin: Cabin,
+    /// Promo code to apply; an unknown or expired code rejects the request
+    #[serde(default)]
+    pub promo_code: Option<String>,
+    /// Date the quote is made (`YYYY-MM-DD`), used for promo code expiry
+    #[serde(default)]
+    pub quote_date: Option<String>,
+    /// Ancillary codes to add; a code not in the catalog rejects the request
+    #[serde(default)]
+    pub ancillaries: Vec<String>,
+}
+
+/// How a quote's price is made up, in minor units
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Breakdown {
+    /// Ticket fare after route, date, cabin and tier adjustments
+    pub fare: i64,
+    /// Tier multiplier applied to the fare, in basis points
+    pub tier_multiplier_bps: u32,
+    /// Amount taken off the fare by the promo code
+    pub promo_discount: i64,
+    pub ancillaries: Vec<AncillaryCharge>,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct Response {
-    pub price: f64,
+    /// Total: fare minus promo discount plus ancillaries
+    pub price: Money,
+    pub breakdown: Breakdown,
 }
 
 /// This function runs both on your server and inside SP1
 /// → Zero duplication, 100% guaranteed correctness
-pub fn handle(req: Request) -> Response {
+pub fn handle(req: Request) -> Result<Response, CoreError> {
+    handle_with_table(req, &RouteTable::embedded())
+}
+
+/// Price `req` against an explicit fare table (e.g. one loaded by the server).
+/// `from` and `to` must be supported IATA codes, `travel_date`, if set, a
+/// valid date, and the promo and ancillary codes known to the table.
+pub fn handle_with_table(req: Request, table: &RouteTable) -> Result<Response, CoreError> {
+    destinations::validate(&req.from)?;
+    destinations::validate(&req.to)?;
+
     // ←←← YOUR REAL SECRET PRICING LOGIC (edit only here!) ←←←
-    let base = if req.from == "NYC" && req.to == "LON" {
-        680.0
-    } else if req.from == "LON" && req.to == "NYC" {
-        675.0
-    } else {
-        450.0
+    let base = table.fare(&req.from, &req.to);
+    let base = match req.travel_date.as_deref() {
+        Some(s) => {
+            let date = Date::parse(s).ok_or_else(|| CoreError::InvalidDate(String::from(s)))?;
+            table.apply_date(base, &date)
+        }
+        None => base,
     };
+    let base = apply_bps(base, table.cabins.get(req.cabin));
+
+    let tier_multiplier_bps = table.tiers.get(req.vip);
+    if tier_multiplier_bps == 0 || tier_multiplier_bps > BPS {
+        return Err(CoreError::InvalidTier(tier_multiplier_bps));
+    }
+    let fare = apply_bps(base, tier_multiplier_bps);
 
-    let price = if req.vip {
-        base * 0.85
-    } else {
-        base
+    let discounted = match req.promo_code.as_deref() {
+        Some(code) => promo::validate(table, code, req.quote_date.as_deref())
+            .map_err(|e| CoreError::PromoRejected(String::from(code), e))?
+            .discount
+            .apply(fare),
+        None => fare,
     };
 
+    let ancillaries = req
+        .ancillaries
+        .iter()
+        .map(|code| {
+            let a = ancillary::find(table, code).ok_or_else(|| CoreError::UnknownAncillary(code.clone()))?;
+            Ok(AncillaryCharge {
+                code: a.code.clone(),
+                amount_minor: a.price_for(req.cabin),
+            })
+        })
+        .collect::<Result<Vec<AncillaryCharge>, CoreError>>()?;
+    let price = discounted + ancillaries.iter().map(|a| a.amount_minor).sum::<i64>();
+
     // You can add arbitrage checks, signature verification, etc.
     // As long as it uses only no_std-compatible code
 
-    Response { price }
+    Ok(Response {
+        price: Money::new(price, &table.currency),
+        breakdown: Breakdown {
+            fare,
+            tier_multiplier_bps,
+            promo_discount: fare - discounted,
+            ancillaries,
+        },
+    })
+}
+
+/// Public values of the pricing guest program
+#[derive(Serialize, Deserialize)]
+pub struct PriceCommitment {
+    /// The quoted request, including its travel and quote dates
+    pub request: Request,
+    /// `RouteTable::digest` of the table the price was computed from
+    pub fare_table_digest: [u8; 32],
+    /// Zero when `error` is set
+    pub price: Money,
+    /// Why the request could not be priced; `None` for a valid quote
+    pub error: Option<CoreError>,
+}
+
+impl PriceCommitment {
+    pub fn is_valid(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Price `req` against `table` and bind the result to the table's digest.
+/// Invalid requests still produce a commitment, flagged by `error`, so the
+/// guest proves the rejection instead of panicking.
+pub fn commit(req: Request, table: &RouteTable) -> PriceCommitment {
+    let (price, error) = match handle_with_table(req.clone(), table) {
+        Ok(resp) => (resp.price, None),
+        Err(e) => (Money::new(0, &table.currency), Some(e)),
+    };
+    PriceCommitment {
+        request: req,
+        fare_table_digest: table.digest(),
+        price,
+        error,
+    }
 }
@@ -0,0 +1,115 @@
+//! Versioned pricing API: a structured quote with an ID and an expiry.
+//!
+//! Wraps the v1 computation, so v1 and v2 always agree on the price.
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::Breakdown;
+use crate::{
+    date::Date,
+    error::CoreError,
+    fares::{Cabin, RouteTable},
+    money::Money,
+};
+
+/// How long a quote can be booked at its price
+pub const QUOTE_TTL_SECS: u64 = 15 * 60;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Request {
+    pub from: String,
+    pub to: String,
+    #[serde(default)]
+    pub vip: bool,
+    /// Departure date (`YYYY-MM-DD`)
+    #[serde(default)]
+    pub travel_date: Option<String>,
+    #[serde(default)]
+    pub cabin: Cabin,
+    #[serde(default)]
+    pub promo_code: Option<String>,
+    /// Ancillary codes to add
+    #[serde(default)]
+    pub ancillaries: Vec<String>,
+    /// When the quote is made (unix seconds); sets the quote date and expiry
+    pub quoted_at: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Response {
+    /// Stable ID of this quote (same request, table and time → same ID)
+    pub quote_id: String,
+    pub price: Money,
+    pub currency: String,
+    pub breakdown: Breakdown,
+    pub quoted_at: u64,
+    /// Unix seconds after which the quote must be refreshed
+    pub expires_at: u64,
+}
+
+/// Quote `req` against the embedded fare table
+pub fn handle(req: Request) -> Result<Response, CoreError> {
+    handle_with_table(req, &RouteTable::embedded())
+}
+
+/// Quote `req` against an explicit fare table
+pub fn handle_with_table(req: Request, table: &RouteTable) -> Result<Response, CoreError> {
+    let quote_date = Date::from_days_since_epoch((req.quoted_at / 86_400) as i64);
+    let quote_id = quote_id(&req, table);
+    let v1 = super::handle_with_table(
+        super::Request {
+            from: req.from,
+            to: req.to,
+            vip: req.vip,
+            travel_date: req.travel_date,
+            cabin: req.cabin,
+            promo_code: req.promo_code,
+            quote_date: Some(quote_date.to_string()),
+            ancillaries: req.ancillaries,
+        },
+        table,
+    )?;
+
+    Ok(Response {
+        quote_id,
+        currency: v1.price.currency.clone(),
+        price: v1.price,
+        breakdown: v1.breakdown,
+        quoted_at: req.quoted_at,
+        expires_at: req.quoted_at + QUOTE_TTL_SECS,
+    })
+}
+
+/// `Q` + 16 hex digits of SHA-256 over the request fields, the fare table
+/// digest and the quote time
+fn quote_id(req: &Request, table: &RouteTable) -> String {
+    let mut hasher = Sha256::new();
+    let mut put = |field: &str| {
+        hasher.update((field.len() as u64).to_le_bytes());
+        hasher.update(field.as_bytes());
+    };
+    put(&req.from);
+    put(&req.to);
+    put(req.travel_date.as_deref().unwrap_or(""));
+    put(req.promo_code.as_deref().unwrap_or(""));
+    for code in &req.ancillaries {
+        put(code);
+    }
+    hasher.update([req.vip as u8, req.cabin as u8]);
+    hasher.update((req.ancillaries.len() as u64).to_le_bytes());
+    hasher.update(req.quoted_at.to_le_bytes());
+    hasher.update(table.digest());
+
+    let digest = hasher.finalize();
+    let mut id = String::from("Q");
+    for b in &digest[..8] {
+        id.push_str(&format!("{:02X}", b));
+    }
+    id
+}
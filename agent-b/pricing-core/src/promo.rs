@@ -0,0 +1,85 @@
+use alloc::string::String;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    date::Date,
+    fares::RouteTable,
+    money::{apply_bps, BPS},
+};
+
+/// Amount taken off a quote
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Discount {
+    /// Share of the fare in basis points, e.g. 2_000 for 20%
+    PercentBps(u32),
+    /// Fixed amount in minor units of the quote currency
+    Fixed(i64),
+}
+
+impl Discount {
+    /// Apply the discount to a minor-unit price, never going below zero
+    pub fn apply(&self, price: i64) -> i64 {
+        let discounted = match self {
+            Discount::PercentBps(bps) => apply_bps(price, BPS.saturating_sub(*bps)),
+            Discount::Fixed(amount) => price - amount,
+        };
+        discounted.max(0)
+    }
+}
+
+/// Promo code accepted by get-ticket-price
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PromoCode {
+    pub code: String,
+    pub discount: Discount,
+    /// Last day (YYYY-MM-DD, inclusive) the code can be quoted; `None` never expires
+    pub expires: Option<String>,
+}
+
+/// Why a promo code was not applied
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PromoError {
+    Unknown,
+    Expired,
+}
+
+impl PromoError {
+    pub fn message(&self) -> &'static str {
+        match self {
+            PromoError::Unknown => "unknown promo code",
+            PromoError::Expired => "promo code has expired",
+        }
+    }
+}
+
+/// Built-in promo codes (code, discount, expiry)
+pub(crate) const DEFAULT_PROMOS: &[(&str, Discount, Option<&str>)] = &[
+    ("SAVE20", Discount::PercentBps(2_000), Some("2026-12-31")),
+    ("SUMMER10", Discount::PercentBps(1_000), Some("2026-09-30")),
+    ("WELCOME50", Discount::Fixed(5_000), None),
+];
+
+/// Look up `code` (case-insensitive) and check it is still valid on
+/// `quote_date`. Codes with an expiry need a quote date, so the result
+/// stays deterministic inside SP1 where there is no clock.
+pub fn validate<'a>(
+    table: &'a RouteTable,
+    code: &str,
+    quote_date: Option<&str>,
+) -> Result<&'a PromoCode, PromoError> {
+    let promo = table
+        .promo_codes
+        .iter()
+        .find(|p| p.code.eq_ignore_ascii_case(code))
+        .ok_or(PromoError::Unknown)?;
+
+    if let Some(expires) = promo.expires.as_deref().and_then(Date::parse) {
+        let today = quote_date.and_then(Date::parse).ok_or(PromoError::Expired)?;
+        if today.days_since_epoch() > expires.days_since_epoch() {
+            return Err(PromoError::Expired);
+        }
+    }
+
+    Ok(promo)
+}
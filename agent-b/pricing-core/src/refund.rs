@@ -0,0 +1,103 @@
+use alloc::string::String;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    date::Date,
+    fares::Cabin,
+    money::{apply_bps, Money},
+};
+
+/// Cancellations this soon after booking are refunded in full
+const GRACE_PERIOD_SECS: u64 = 24 * 60 * 60;
+/// Departures at least this many days away get the lowest penalty
+const ADVANCE_NOTICE_DAYS: i64 = 30;
+/// Departures at least this many days away get the middle penalty
+const SHORT_NOTICE_DAYS: i64 = 7;
+
+/// Penalty in basis points per cabin for advance, short and last-minute
+/// notice; higher fare classes are more flexible
+const fn penalty_bps(cabin: Cabin) -> [u32; 3] {
+    match cabin {
+        Cabin::Economy => [2_000, 5_000, 10_000],
+        Cabin::Premium => [1_000, 3_000, 6_000],
+        Cabin::Business => [500, 1_500, 3_000],
+        Cabin::First => [0, 1_000, 2_000],
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Request {
+    /// Amount paid for the booking
+    pub paid: Money,
+    #[serde(default)]
+    pub cabin: Cabin,
+    /// Departure date (`YYYY-MM-DD`); unknown departures get the last-minute penalty
+    #[serde(default)]
+    pub travel_date: Option<String>,
+    /// When the booking was made (unix seconds)
+    pub booked_at: u64,
+    /// When the cancellation was requested (unix seconds)
+    pub cancelled_at: u64,
+}
+
+/// Which refund rule applied
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RefundRule {
+    /// Cancelled within 24 hours of booking
+    GracePeriod,
+    /// 30 or more days before departure
+    AdvanceNotice,
+    /// 7 to 29 days before departure
+    ShortNotice,
+    /// Less than 7 days before departure
+    LastMinute,
+    /// On or after the departure date
+    AfterDeparture,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Response {
+    pub refund: Money,
+    pub penalty: Money,
+    pub penalty_bps: u32,
+    pub rule: RefundRule,
+}
+
+/// Refund due on cancellation. Pure function of the request, so it runs
+/// unchanged on the server and inside SP1.
+pub fn handle(req: Request) -> Response {
+    let cancel_day = (req.cancelled_at / 86_400) as i64;
+    let days_to_departure = req
+        .travel_date
+        .as_deref()
+        .and_then(Date::parse)
+        .map(|d| d.days_since_epoch() - cancel_day);
+
+    let rule = match days_to_departure {
+        Some(days) if days <= 0 => RefundRule::AfterDeparture,
+        _ if req.cancelled_at.saturating_sub(req.booked_at) <= GRACE_PERIOD_SECS => {
+            RefundRule::GracePeriod
+        }
+        Some(days) if days >= ADVANCE_NOTICE_DAYS => RefundRule::AdvanceNotice,
+        Some(days) if days >= SHORT_NOTICE_DAYS => RefundRule::ShortNotice,
+        _ => RefundRule::LastMinute,
+    };
+
+    let [advance, short, last_minute] = penalty_bps(req.cabin);
+    let penalty_bps = match rule {
+        RefundRule::GracePeriod => 0,
+        RefundRule::AdvanceNotice => advance,
+        RefundRule::ShortNotice => short,
+        RefundRule::LastMinute => last_minute,
+        RefundRule::AfterDeparture => 10_000,
+    };
+
+    let penalty = apply_bps(req.paid.amount_minor, penalty_bps);
+    Response {
+        refund: Money::new(req.paid.amount_minor - penalty, &req.paid.currency),
+        penalty: Money::new(penalty, &req.paid.currency),
+        penalty_bps,
+        rule,
+    }
+}
@@ -0,0 +1,80 @@
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+use core::fmt;
+use serde::{Deserialize, Serialize};
+
+use crate::booking::Booking;
+
+/// Why a store operation failed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StoreError {
+    NotFound,
+    AlreadyCancelled,
+    /// The backing storage failed (I/O, serialization, ...)
+    Backend(String),
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreError::NotFound => write!(f, "booking not found"),
+            StoreError::AlreadyCancelled => write!(f, "booking is already cancelled"),
+            StoreError::Backend(e) => write!(f, "booking store error: {}", e),
+        }
+    }
+}
+
+/// Where bookings live between calls. The core only needs this trait, so
+/// servers can inject persistent storage while the guest uses memory.
+pub trait BookingStore {
+    /// Insert or replace the booking under its `booking_id`
+    fn save(&mut self, booking: Booking) -> Result<(), StoreError>;
+    fn get(&self, booking_id: &str) -> Option<Booking>;
+    /// Mark a confirmed booking as cancelled and return it
+    fn cancel(&mut self, booking_id: &str) -> Result<Booking, StoreError>;
+    /// Every booking, by booking ID
+    fn list(&self) -> Vec<Booking>;
+}
+
+/// Bookings keyed by ID, kept in memory
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct InMemoryBookingStore {
+    bookings: BTreeMap<String, Booking>,
+}
+
+impl InMemoryBookingStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.bookings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bookings.is_empty()
+    }
+}
+
+impl BookingStore for InMemoryBookingStore {
+    fn save(&mut self, booking: Booking) -> Result<(), StoreError> {
+        self.bookings.insert(booking.response.booking_id.clone(), booking);
+        Ok(())
+    }
+
+    fn get(&self, booking_id: &str) -> Option<Booking> {
+        self.bookings.get(booking_id).cloned()
+    }
+
+    fn cancel(&mut self, booking_id: &str) -> Result<Booking, StoreError> {
+        let booking = self.bookings.get_mut(booking_id).ok_or(StoreError::NotFound)?;
+        if booking.response.status == "cancelled" {
+            return Err(StoreError::AlreadyCancelled);
+        }
+        booking.response.status = String::from("cancelled");
+        Ok(booking.clone())
+    }
+
+    fn list(&self) -> Vec<Booking> {
+        self.bookings.values().cloned().collect()
+    }
+}
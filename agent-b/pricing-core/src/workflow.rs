@@ -0,0 +1,94 @@
+use alloc::{string::String, vec::Vec};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{money::Money, pricing::PriceCommitment};
+
+/// Public values committed by the payment agent's proof of a charge
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PaymentCommitment {
+    pub session_id: String,
+    pub amount: Money,
+}
+
+/// Booking as recorded by Agent B
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct BookingClaim {
+    pub session_id: String,
+    pub booking_id: String,
+    pub amount: Money,
+}
+
+/// Input to the workflow guest program. The pricing and payment public
+/// values are passed as raw bytes so their digests match the standalone proofs.
+#[derive(Serialize, Deserialize)]
+pub struct WorkflowInput {
+    /// bincode `PriceCommitment` from the pricing guest
+    pub price_public_values: Vec<u8>,
+    /// bincode `PaymentCommitment` from the payment agent's proof
+    pub payment_public_values: Vec<u8>,
+    pub booking: BookingClaim,
+}
+
+/// Public values of the workflow guest program
+#[derive(Serialize, Deserialize)]
+pub struct WorkflowCommitment {
+    /// SHA-256 of the pricing proof's public values
+    pub price_public_values_digest: [u8; 32],
+    /// SHA-256 of the payment proof's public values
+    pub payment_public_values_digest: [u8; 32],
+    pub session_id: String,
+    pub booking_id: String,
+    pub amount: Money,
+}
+
+/// Inconsistency between the quote, the booking and the payment
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WorkflowError {
+    InvalidQuote,
+    BookedAmountMismatch,
+    PaidAmountMismatch,
+    SessionMismatch,
+}
+
+impl WorkflowError {
+    pub fn message(&self) -> &'static str {
+        match self {
+            WorkflowError::InvalidQuote => "pricing proof flags the quote as invalid",
+            WorkflowError::BookedAmountMismatch => "booked amount differs from the quoted price",
+            WorkflowError::PaidAmountMismatch => "paid amount differs from the quoted price",
+            WorkflowError::SessionMismatch => "payment and booking belong to different sessions",
+        }
+    }
+}
+
+/// Check that the booking and the payment both match the quote and each
+/// other. `input` supplies the raw public values; `quote` and `payment` are
+/// their decoded forms.
+pub fn check(
+    input: &WorkflowInput,
+    quote: &PriceCommitment,
+    payment: &PaymentCommitment,
+) -> Result<WorkflowCommitment, WorkflowError> {
+    let booking = &input.booking;
+    if !quote.is_valid() {
+        return Err(WorkflowError::InvalidQuote);
+    }
+    if booking.amount != quote.price {
+        return Err(WorkflowError::BookedAmountMismatch);
+    }
+    if payment.amount != quote.price {
+        return Err(WorkflowError::PaidAmountMismatch);
+    }
+    if payment.session_id != booking.session_id {
+        return Err(WorkflowError::SessionMismatch);
+    }
+
+    Ok(WorkflowCommitment {
+        price_public_values_digest: Sha256::digest(&input.price_public_values).into(),
+        payment_public_values_digest: Sha256::digest(&input.payment_public_values).into(),
+        session_id: booking.session_id.clone(),
+        booking_id: booking.booking_id.clone(),
+        amount: booking.amount.clone(),
+    })
+}
@@ -0,0 +1,154 @@
+//! Property tests for the invariants the SP1 guest programs rely on: pricing
+//! is a pure, non-negative function that never charges a higher tier less,
+//! rejects codes it can't apply, and distinct bookings never share an ID.
+
+use pricing_core::{booking, destinations::DESTINATIONS, error::CoreError, fares::Cabin, pricing, promo::PromoError};
+use proptest::prelude::*;
+
+/// Supported IATA code
+fn city() -> impl Strategy<Value = String> {
+    prop::sample::select(DESTINATIONS.iter().map(|d| d.code).collect::<Vec<_>>()).prop_map(String::from)
+}
+
+/// Any three-letter code, supported or not
+fn any_code() -> impl Strategy<Value = String> {
+    prop_oneof![city(), "[A-Z]{3}"]
+}
+
+fn date() -> impl Strategy<Value = String> {
+    (2024u16..2031, 1u8..=12, 1u8..=28).prop_map(|(y, m, d)| format!("{:04}-{:02}-{:02}", y, m, d))
+}
+
+fn cabin() -> impl Strategy<Value = Cabin> {
+    prop::sample::select(vec![Cabin::Economy, Cabin::Premium, Cabin::Business, Cabin::First])
+}
+
+/// Promo code valid on the quote date, if any, and the quote date
+fn promo_code() -> impl Strategy<Value = (Option<String>, Option<String>)> {
+    // Well before SUMMER10 and SAVE20 expire
+    let before_expiry = (2024u16..=2025, 1u8..=12, 1u8..=28).prop_map(|(y, m, d)| format!("{:04}-{:02}-{:02}", y, m, d));
+    prop_oneof![
+        prop::option::of(date()).prop_map(|quote_date| (None, quote_date)),
+        prop::option::of(date()).prop_map(|quote_date| (Some("WELCOME50".to_string()), quote_date)),
+        (prop::sample::select(vec!["SAVE20", "summer10"]), before_expiry)
+            .prop_map(|(code, quote_date)| (Some(code.to_string()), Some(quote_date))),
+    ]
+}
+
+/// Code of no built-in promo
+fn unknown_promo_code() -> impl Strategy<Value = String> {
+    "[A-Z0-9]{4,8}".prop_filter("built-in code", |code| !["SAVE20", "SUMMER10", "WELCOME50"].contains(&code.as_str()))
+}
+
+fn ancillaries() -> impl Strategy<Value = Vec<String>> {
+    prop::collection::vec(
+        prop::sample::select(vec!["checked_bag", "extra_legroom", "meal"]).prop_map(String::from),
+        0..4,
+    )
+}
+
+prop_compose! {
+    fn price_request()(
+        from in city(),
+        to in city(),
+        vip in any::<bool>(),
+        travel_date in prop::option::of(date()),
+        cabin in cabin(),
+        (promo_code, quote_date) in promo_code(),
+        ancillaries in ancillaries(),
+    ) -> pricing::Request {
+        pricing::Request { from, to, vip, travel_date, cabin, promo_code, quote_date, ancillaries }
+    }
+}
+
+prop_compose! {
+    fn booking_request()(
+        from in any_code(),
+        to in any_code(),
+        passenger_name in "[A-Za-z ]{1,20}",
+        passenger_email in "[a-z]{1,8}@[a-z]{1,8}\\.com",
+        cabin in cabin(),
+        session_id in prop::option::of("sess_[a-z0-9]{1,8}"),
+    ) -> booking::Request {
+        booking::Request {
+            from,
+            to,
+            passenger_name,
+            passenger_email,
+            cabin,
+            session_id,
+            travel_date: None,
+            amount: None,
+            proof_ids: Vec::new(),
+        }
+    }
+}
+
+proptest! {
+    #[test]
+    fn pricing_is_deterministic(req in price_request()) {
+        let first = pricing::handle(req.clone()).unwrap();
+        let second = pricing::handle(req).unwrap();
+        prop_assert_eq!(first.price, second.price);
+        prop_assert_eq!(first.breakdown.fare, second.breakdown.fare);
+        prop_assert_eq!(first.breakdown.ancillaries, second.breakdown.ancillaries);
+    }
+
+    #[test]
+    fn price_is_never_negative(req in price_request()) {
+        let resp = pricing::handle(req).unwrap();
+        prop_assert!(resp.price.amount_minor >= 0);
+        prop_assert!(resp.breakdown.promo_discount >= 0);
+    }
+
+    #[test]
+    fn vip_never_pays_more(req in price_request()) {
+        let vip = pricing::handle(pricing::Request { vip: true, ..req.clone() }).unwrap();
+        let regular = pricing::handle(pricing::Request { vip: false, ..req }).unwrap();
+        prop_assert!(vip.price.amount_minor <= regular.price.amount_minor);
+    }
+
+    #[test]
+    fn fare_rises_with_cabin(req in price_request()) {
+        let fares: Vec<i64> = [Cabin::Economy, Cabin::Premium, Cabin::Business, Cabin::First]
+            .into_iter()
+            .map(|cabin| pricing::handle(pricing::Request { cabin, ..req.clone() }).unwrap().breakdown.fare)
+            .collect();
+        prop_assert!(fares.windows(2).all(|w| w[0] <= w[1]), "fares by cabin: {:?}", fares);
+    }
+
+    #[test]
+    fn only_supported_codes_are_priced(req in price_request(), from in any_code()) {
+        let supported = DESTINATIONS.iter().any(|d| d.code == from);
+        prop_assert_eq!(supported, pricing::handle(pricing::Request { from, ..req }).is_ok());
+    }
+
+    #[test]
+    fn promo_codes_that_cannot_apply_are_rejected(req in price_request(), promo_code in unknown_promo_code()) {
+        let unknown = pricing::handle(pricing::Request { promo_code: Some(promo_code.clone()), ..req.clone() });
+        prop_assert_eq!(unknown.err(), Some(CoreError::PromoRejected(promo_code, PromoError::Unknown)));
+
+        // SAVE20 runs to 2026-12-31, and can't be checked without a quote date
+        for quote_date in [Some("2027-01-01".to_string()), None] {
+            let expired = pricing::handle(pricing::Request { promo_code: Some("SAVE20".to_string()), quote_date, ..req.clone() });
+            prop_assert_eq!(expired.err(), Some(CoreError::PromoRejected("SAVE20".to_string(), PromoError::Expired)));
+        }
+    }
+
+    #[test]
+    fn unknown_ancillaries_are_rejected(req in price_request(), at in any::<prop::sample::Index>()) {
+        let mut ancillaries = req.ancillaries.clone();
+        ancillaries.insert(at.index(ancillaries.len() + 1), "lounge".to_string());
+        let unknown = pricing::handle(pricing::Request { ancillaries, ..req });
+        prop_assert_eq!(unknown.err(), Some(CoreError::UnknownAncillary("lounge".to_string())));
+    }
+
+    #[test]
+    fn distinct_bookings_get_distinct_ids(a in booking_request(), b in booking_request()) {
+        let same = (&a.from, &a.to, &a.passenger_name, &a.passenger_email, a.cabin, &a.session_id)
+            == (&b.from, &b.to, &b.passenger_name, &b.passenger_email, b.cabin, &b.session_id);
+        let (a, b) = (booking::handle(a), booking::handle(b));
+        prop_assert_eq!(same, a.booking_id == b.booking_id);
+        prop_assert_eq!(same, a.confirmation_code == b.confirmation_code);
+    }
+}
@@ -0,0 +1,15 @@
+//! Money amounts follow each currency's ISO 4217 minor unit.
+
+use pricing_core::money::{minor_unit_exponent, Money};
+
+#[test]
+fn test_display_uses_the_currency_exponent() {
+    assert_eq!(Money::new(45_000, "USD").to_string(), "450.00");
+    assert_eq!(Money::new(-5, "EUR").to_string(), "-0.05");
+    assert_eq!(Money::new(45_000, "JPY").to_string(), "45000");
+    assert_eq!(Money::new(1_234, "KWD").to_string(), "1.234");
+
+    assert_eq!(Money::new(45_000, "JPY").as_major(), 45_000.0);
+    assert_eq!(Money::new(1_500, "BHD").as_major(), 1.5);
+    assert_eq!((minor_unit_exponent("jpy"), minor_unit_exponent("GBP")), (0, 2));
+}
@@ -0,0 +1,82 @@
+//! Boundaries of the cancellation refund rules: the 24-hour grace period,
+//! 30 and 7 days' notice, departure day and bookings without a travel date.
+
+use pricing_core::{
+    date::Date,
+    fares::Cabin,
+    money::Money,
+    refund::{self, RefundRule},
+};
+
+/// Day the cancellation is requested (2024-10-04), an hour after midnight UTC
+const CANCEL_DAY: i64 = 20_000;
+const CANCELLED_AT: u64 = CANCEL_DAY as u64 * 86_400 + 3_600;
+const DAY: u64 = 86_400;
+
+/// Cancel an economy booking paid 450.00, made `booked_ago` seconds before
+/// the cancellation, departing `days_out` days after the cancellation day
+fn cancel(booked_ago: u64, days_out: Option<i64>) -> refund::Response {
+    refund::handle(refund::Request {
+        paid: Money::new(45_000, "USD"),
+        cabin: Cabin::Economy,
+        travel_date: days_out.map(|days| Date::from_days_since_epoch(CANCEL_DAY + days).to_string()),
+        booked_at: CANCELLED_AT - booked_ago,
+        cancelled_at: CANCELLED_AT,
+    })
+}
+
+#[test]
+fn test_grace_period_boundary() {
+    let full = cancel(DAY, Some(3));
+    assert_eq!(full.rule, RefundRule::GracePeriod);
+    assert_eq!((full.refund.amount_minor, full.penalty.amount_minor, full.penalty_bps), (45_000, 0, 0));
+
+    // One second past 24 hours the notice rules apply
+    assert_eq!(cancel(DAY + 1, Some(3)).rule, RefundRule::LastMinute);
+    assert_eq!(cancel(DAY + 1, Some(60)).rule, RefundRule::AdvanceNotice);
+
+    // The grace period does not reach past departure
+    assert_eq!(cancel(60, Some(0)).rule, RefundRule::AfterDeparture);
+}
+
+#[test]
+fn test_notice_boundaries() {
+    let booked_ago = 90 * DAY;
+    let cases = [
+        (365, RefundRule::AdvanceNotice, 2_000),
+        (30, RefundRule::AdvanceNotice, 2_000),
+        (29, RefundRule::ShortNotice, 5_000),
+        (7, RefundRule::ShortNotice, 5_000),
+        (6, RefundRule::LastMinute, 10_000),
+        (1, RefundRule::LastMinute, 10_000),
+        (0, RefundRule::AfterDeparture, 10_000),
+        (-5, RefundRule::AfterDeparture, 10_000),
+    ];
+    for (days_out, rule, penalty_bps) in cases {
+        let response = cancel(booked_ago, Some(days_out));
+        assert_eq!((response.rule, response.penalty_bps), (rule, penalty_bps), "{} days out", days_out);
+        assert_eq!(response.refund.amount_minor + response.penalty.amount_minor, 45_000);
+        assert_eq!(response.refund.currency, "USD");
+    }
+
+    // 20% of 450.00
+    let advance = cancel(booked_ago, Some(30));
+    assert_eq!((advance.refund.amount_minor, advance.penalty.amount_minor), (36_000, 9_000));
+}
+
+#[test]
+fn test_missing_or_invalid_travel_date_is_last_minute() {
+    assert_eq!(cancel(10 * DAY, None).rule, RefundRule::LastMinute);
+    assert_eq!(cancel(10 * DAY, None).refund.amount_minor, 0);
+    // Still refunded in full during the grace period
+    assert_eq!(cancel(DAY / 2, None).rule, RefundRule::GracePeriod);
+
+    let invalid = refund::handle(refund::Request {
+        paid: Money::new(45_000, "USD"),
+        cabin: Cabin::First,
+        travel_date: Some("next tuesday".to_string()),
+        booked_at: CANCELLED_AT - 10 * DAY,
+        cancelled_at: CANCELLED_AT,
+    });
+    assert_eq!((invalid.rule, invalid.penalty_bps), (RefundRule::LastMinute, 2_000));
+}
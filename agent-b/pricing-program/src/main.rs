@@ -0,0 +1,17 @@
+//! Pricing guest: proves `price = f(route, tier, date)` for one quote.
+//!
+//! Reads a `(pricing::Request, RouteTable)` pair and commits a
+//! `pricing::PriceCommitment`, so the proof pins the request, the digest of
+//! the fare table it was priced against and the resulting price. Invalid
+//! requests commit with `error` set rather than aborting the proof.
+
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+
+use pricing_core::{fares::RouteTable, pricing};
+
+pub fn main() {
+    let (req, table): (pricing::Request, RouteTable) = sp1_zkvm::io::read();
+    let commitment = pricing::commit(req, &table);
+    sp1_zkvm::io::commit(&commitment);
+}
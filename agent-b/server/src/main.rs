@@ -1,12 +1,26 @@
 use axum::{
-    extract::State,
+    body::{self, Body},
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
     routing::post,
     Router, Json,
 };
+use axum_server::tls_rustls::RustlsConfig;
 use serde::{Deserialize, Serialize};
 use sha2::{Sha256, Digest};
 use std::sync::Arc;
-use pricing_core::{pricing, booking};
+use pricing_core::{date::Date, error::CoreError, fares::{Cabin, RouteTable}, money::Money, pricing, booking};
+use std::time::{SystemTime, UNIX_EPOCH};
+use zk_protocol::{
+    shutdown,
+    signing::{SigningKey, SIGNATURE_HEADER, TIMESTAMP_HEADER},
+    tls::MtlsConfig,
+};
+
+/// Largest request body accepted for signature verification
+const MAX_SIGNED_BODY_BYTES: usize = 1024 * 1024;
 
 mod zk_adapter;
 
@@ -15,15 +29,31 @@ struct PriceRequest {
     from: String,
     to: String,
     vip: bool,
+    #[serde(default)]
+    travel_date: Option<String>,
+    #[serde(default)]
+    cabin: Cabin,
+    #[serde(default)]
+    promo_code: Option<String>,
+    #[serde(default)]
+    ancillaries: Vec<String>,
 }
 
 #[derive(Serialize)]
 struct PriceResponse {
     // Agent-specific data
     price: f64,
+    /// Exact quote in minor units (`price` is for display)
+    amount: Money,
+    breakdown: pricing::Breakdown,
     // ZK verification metadata
     program_id: String,
     elf_hash: String,
+    /// Pricing guest to attest this quote with (`endpoint: "price-proof"`),
+    /// absent when its ELF was not built
+    pricing_program_id: Option<String>,
+    /// Hex SHA-256 of the fare table, as committed by the pricing guest
+    fare_table_digest: String,
 }
 
 #[derive(Serialize)]
@@ -35,6 +65,9 @@ struct BookResponse {
     // ZK verification metadata
     program_id: String,
     elf_hash: String,
+    /// Workflow guest proving the booking matches its quote and payment,
+    /// absent when its ELF was not built
+    workflow_program_id: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -43,6 +76,14 @@ struct BookRequest {
     to: String,
     passenger_name: String,
     passenger_email: String,
+    #[serde(default)]
+    cabin: Cabin,
+    #[serde(default)]
+    session_id: Option<String>,
+    #[serde(default)]
+    travel_date: Option<String>,
+    #[serde(default)]
+    amount: Option<Money>,
 }
 
 #[derive(Clone)]
@@ -50,26 +91,51 @@ struct AppState {
     program_id: String,
     elf_hash: String,
     booking_api_url: Option<String>,
+    fare_table: RouteTable,
+    pricing_program_id: Option<String>,
+    workflow_program_id: Option<String>,
 }
 
 async fn price_handler(
     State(state): State<Arc<AppState>>,
     Json(req): Json<PriceRequest>,
-) -> Json<PriceResponse> {
+) -> Result<Json<PriceResponse>, (StatusCode, String)> {
     // Use pricing-core logic
     let core_req = pricing::Request {
         from: req.from,
         to: req.to,
         vip: req.vip,
+        travel_date: req.travel_date,
+        cabin: req.cabin,
+        promo_code: req.promo_code,
+        quote_date: Some(today()),
+        ancillaries: req.ancillaries,
     };
     
-    let core_resp = pricing::handle(core_req);
+    let core_resp = pricing::handle_with_table(core_req, &state.fare_table)
+        .map_err(core_error_status)?;
 
-    Json(PriceResponse {
-        price: core_resp.price,
+    Ok(Json(PriceResponse {
+        price: core_resp.price.as_major(),
+        amount: core_resp.price,
+        breakdown: core_resp.breakdown,
         program_id: state.program_id.clone(),
         elf_hash: state.elf_hash.clone(),
-    })
+        pricing_program_id: state.pricing_program_id.clone(),
+        fare_table_digest: hex::encode(state.fare_table.digest()),
+    }))
+}
+
+/// Caller mistakes are 400s; a bad tier multiplier is a fare table problem
+fn core_error_status(e: CoreError) -> (StatusCode, String) {
+    let status = match e {
+        CoreError::UnknownRoute(_)
+        | CoreError::InvalidDate(_)
+        | CoreError::PromoRejected(..)
+        | CoreError::UnknownAncillary(_) => StatusCode::BAD_REQUEST,
+        CoreError::InvalidTier(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    (status, e.to_string())
 }
 
 async fn book_handler(
@@ -88,6 +154,11 @@ async fn book_handler(
                     to: req.to.clone(),
                     passenger_name: req.passenger_name.clone(),
                     passenger_email: req.passenger_email.clone(),
+                    cabin: req.cabin,
+                    session_id: req.session_id.clone(),
+                    travel_date: req.travel_date.clone(),
+                    amount: req.amount.clone(),
+                    proof_ids: Vec::new(),
                 };
                 booking::handle(core_req)
             }
@@ -99,6 +170,11 @@ async fn book_handler(
             to: req.to,
             passenger_name: req.passenger_name,
             passenger_email: req.passenger_email,
+            cabin: req.cabin,
+            session_id: req.session_id,
+            travel_date: req.travel_date,
+            amount: req.amount,
+            proof_ids: Vec::new(),
         };
         booking::handle(core_req)
     };
@@ -109,9 +185,19 @@ async fn book_handler(
         confirmation_code: core_resp.confirmation_code,
         program_id: state.program_id.clone(),
         elf_hash: state.elf_hash.clone(),
+        workflow_program_id: state.workflow_program_id.clone(),
     })
 }
 
+/// Today's UTC date (YYYY-MM-DD), used as the quote date for promo expiry
+pub(crate) fn today() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    Date::from_days_since_epoch((secs / 86_400) as i64).to_string()
+}
+
 async fn call_booking_api(
     api_url: &str,
     req: &BookRequest,
@@ -124,6 +210,7 @@ async fn call_booking_api(
         to: String,
         passenger_name: String,
         passenger_email: String,
+        cabin: Cabin,
     }
     
     let api_req = ApiRequest {
@@ -131,6 +218,7 @@ async fn call_booking_api(
         to: req.to.clone(),
         passenger_name: req.passenger_name.clone(),
         passenger_email: req.passenger_email.clone(),
+        cabin: req.cabin,
     };
     
     let response = client
@@ -159,21 +247,50 @@ async fn call_booking_api(
     })
 }
 
+/// Path of a guest ELF built with `cargo prove build`
+fn elf_path(program: &str) -> std::path::PathBuf {
+    std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("../target/elf-compilation/riscv32im-succinct-zkvm-elf/release")
+        .join(program)
+}
+
+/// Register an ELF under `file_name` with a description for the attester's
+/// registry; `output_schema` (JSON) lets the attester decode its public
+/// values and check claimed outputs against them
 async fn register_elf_with_attester(
     elf_bytes: Vec<u8>,
+    file_name: &str,
+    description: &str,
+    output_schema: Option<String>,
     attester_url: &str,
 ) -> Result<String, String> {
     let part = reqwest::multipart::Part::bytes(elf_bytes)
-        .file_name("agent-b-program.elf")
+        .file_name(format!("{}.elf", file_name))
         .mime_str("application/octet-stream")
         .map_err(|e| format!("Failed to create multipart: {}", e))?;
     
-    let form = reqwest::multipart::Form::new()
-        .part("elf", part);
+    // Guests are versioned with the rest of the Agent B workspace
+    let mut form = reqwest::multipart::Form::new()
+        .part("elf", part)
+        .text("name", file_name.to_string())
+        .text("version", env!("CARGO_PKG_VERSION"))
+        .text("description", description.to_string());
+    if let Some(schema) = output_schema {
+        form = form.text("output_schema", schema);
+    }
 
-    let client = reqwest::Client::new();
+    let mut builder = reqwest::Client::builder();
+    if let Some(tls) = MtlsConfig::for_peer("ATTESTER") {
+        let config = tls
+            .client_config()
+            .map_err(|e| format!("Failed to load attester TLS config: {}", e))?;
+        builder = builder.use_preconfigured_tls(config);
+    }
+    let client = builder
+        .build()
+        .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
     let response = client
-        .post(&format!("{}/register-elf", attester_url))
+        .post(format!("{}/register-elf", attester_url))
         .multipart(form)
         .send()
         .await
@@ -190,11 +307,34 @@ async fn register_elf_with_attester(
         .ok_or_else(|| "No program_id in response".to_string())
 }
 
+/// Register an optional guest ELF if it has been built; `dir` is its crate,
+/// which may hold an `output-schema.json` describing its public values
+async fn register_optional_elf(program: &str, dir: &str, description: &str, attester_url: &str) -> Option<String> {
+    match std::fs::read(elf_path(program)) {
+        Ok(bytes) => {
+            let schema_path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                .join("..")
+                .join(dir)
+                .join("output-schema.json");
+            let schema = std::fs::read_to_string(schema_path).ok();
+            let id = register_elf_with_attester(bytes, program, description, schema, attester_url)
+                .await
+                .unwrap_or_else(|e| panic!("Failed to register {} with attester: {}", program, e));
+            println!("  {}: {}", program, id);
+            Some(id)
+        }
+        Err(_) => {
+            println!("  {}: (not built, run 'cd {} && cargo prove build')", program, dir);
+            None
+        }
+    }
+}
+
 // POST /zk-input - Helper endpoint for external agents
 // Returns properly formatted zkVM input bytes
 #[derive(Deserialize)]
 struct ZkInputRequest {
-    endpoint: String,  // "price" or "book"
+    endpoint: String,  // "price", "book", "refund", "price-proof" or "workflow-proof"
     input: serde_json::Value,
 }
 
@@ -204,24 +344,52 @@ struct ZkInputResponse {
 }
 
 async fn zk_input_handler(
+    State(state): State<Arc<AppState>>,
     Json(req): Json<ZkInputRequest>,
 ) -> Json<ZkInputResponse> {
-    let rpc_call = zk_adapter::json_to_rpc_call(&req.endpoint, &req.input)
-        .expect("Failed to convert to RpcCall");
-    
-    let input_bytes = zk_adapter::rpc_call_to_bytes(&rpc_call);
+    let input_bytes = match req.endpoint.as_str() {
+        "price-proof" => zk_adapter::json_to_price_proof_input(&req.input, &state.fare_table)
+            .expect("Failed to build pricing proof input"),
+        "workflow-proof" => zk_adapter::json_to_workflow_proof_input(&req.input)
+            .expect("Failed to build workflow proof input"),
+        _ => {
+            let rpc_call = zk_adapter::json_to_rpc_call(&req.endpoint, &req.input, &state.fare_table)
+                .expect("Failed to convert to RpcCall");
+            zk_adapter::rpc_call_to_bytes(&rpc_call)
+        }
+    };
     
     Json(ZkInputResponse { input_bytes })
 }
 
+/// Reject requests whose HMAC signature does not match the shared secret
+async fn verify_signature(
+    State(key): State<Arc<SigningKey>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let (parts, body) = request.into_parts();
+    let bytes = match body::to_bytes(body, MAX_SIGNED_BODY_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(_) => return StatusCode::PAYLOAD_TOO_LARGE.into_response(),
+    };
+
+    let header = |name: &str| parts.headers.get(name).and_then(|v| v.to_str().ok());
+    if let Err(e) = key.verify(header(TIMESTAMP_HEADER), header(SIGNATURE_HEADER), &bytes) {
+        eprintln!("⚠ Rejected unsigned or tampered request to {}: {}", parts.uri, e);
+        return (StatusCode::UNAUTHORIZED, e.to_string()).into_response();
+    }
+
+    next.run(Request::from_parts(parts, Body::from(bytes))).await
+}
+
 #[tokio::main]
 async fn main() {
-    let attester_url = std::env::var("ATTESTER_URL")
-        .unwrap_or_else(|_| "http://localhost:8000".to_string());
+    let config = zp_config::init().expect("Invalid configuration");
+    let attester_url = config.network.attester_url.clone();
 
     // Read the proper ELF binary (not .a archive)
-    let elf_path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
-        .join("../target/elf-compilation/riscv32im-succinct-zkvm-elf/release/agent-b-program");
+    let elf_path = elf_path("agent-b-program");
     
     println!("Loading ELF from: {:?}", elf_path);
     let elf_bytes = std::fs::read(&elf_path)
@@ -233,7 +401,13 @@ async fn main() {
     let elf_hash = format!("0x{}", hex::encode(hasher.finalize()));
 
     // Register with attester
-    let program_id = register_elf_with_attester(elf_bytes, &attester_url)
+    let program_id = register_elf_with_attester(
+        elf_bytes,
+        "agent-b-program",
+        "Agent B RPC dispatcher (pricing, booking, refunds)",
+        None,
+        &attester_url,
+    )
         .await
         .expect("Failed to register ELF with attester");
 
@@ -242,6 +416,23 @@ async fn main() {
     println!("  elf_hash: {}", elf_hash);
     println!("  attester_url: {}", attester_url);
 
+    // Optional: the pricing guest, for on-demand proofs of individual quotes,
+    // and the workflow guest tying a booking to its quote and payment
+    let pricing_program_id = register_optional_elf(
+        "agent-b-pricing-program",
+        "pricing-program",
+        "Proves a quote's price against a committed fare table",
+        &attester_url,
+    )
+    .await;
+    let workflow_program_id = register_optional_elf(
+        "agent-b-workflow-program",
+        "workflow-program",
+        "Proves a booking matches its quote and payment",
+        &attester_url,
+    )
+    .await;
+
     // Optional: External booking API URL
     let booking_api_url = std::env::var("BOOKING_API_URL").ok();
     if let Some(ref url) = booking_api_url {
@@ -250,27 +441,76 @@ async fn main() {
         println!("  booking_api_url: (not set, using deterministic logic)");
     }
 
+    // Optional: fare table override (JSON), otherwise the embedded defaults
+    let fare_table = match std::env::var("FARE_TABLE_PATH") {
+        Ok(path) => {
+            let json = std::fs::read_to_string(&path)
+                .unwrap_or_else(|e| panic!("Failed to read fare table {}: {}", path, e));
+            println!("  fare_table: {}", path);
+            serde_json::from_str(&json).expect("Invalid fare table JSON")
+        }
+        Err(_) => {
+            println!("  fare_table: (embedded defaults)");
+            RouteTable::embedded()
+        }
+    };
+
     let state = Arc::new(AppState {
         program_id,
         elf_hash,
         booking_api_url,
+        fare_table,
+        pricing_program_id,
+        workflow_program_id,
     });
 
-    let app = Router::new()
+    let mut app = Router::new()
         .route("/price", post(price_handler))
         .route("/book", post(book_handler))
         .route("/zk-input", post(zk_input_handler))
         .with_state(state);
 
-    let listener = tokio::net::TcpListener::bind("0.0.0.0:8001")
-        .await
-        .expect("Failed to bind to 0.0.0.0:8001");
+    // Callers must sign requests when AGENT_HMAC_SECRET is set
+    if let Some(key) = SigningKey::from_env() {
+        println!("✓ HMAC request signing enforced");
+        app = app.route_layer(middleware::from_fn_with_state(Arc::new(key), verify_signature));
+    }
+
+    let tls = MtlsConfig::from_env();
+    let scheme = if tls.is_some() { "https" } else { "http" };
 
-    println!("✓ Agent B running on http://0.0.0.0:8001");
+    println!("✓ Agent B running on {}://0.0.0.0:8001", scheme);
     println!("  POST /price  — Get flight pricing");
     println!("  POST /book   — Book a flight");
 
-    axum::serve(listener, app)
-        .await
-        .expect("Server error");
+    match tls {
+        Some(tls) => {
+            let config = tls.server_config().expect("Failed to load TLS config");
+            let handle = axum_server::Handle::new();
+            tokio::spawn(drain_on_signal(handle.clone()));
+            axum_server::bind_rustls(([0, 0, 0, 0], 8001).into(), RustlsConfig::from_config(Arc::new(config)))
+                .handle(handle)
+                .serve(app.into_make_service())
+                .await
+                .expect("Server error");
+        }
+        None => {
+            let listener = tokio::net::TcpListener::bind("0.0.0.0:8001")
+                .await
+                .expect("Failed to bind to 0.0.0.0:8001");
+
+            axum::serve(listener, app)
+                .with_graceful_shutdown(shutdown::signal())
+                .await
+                .expect("Server error");
+        }
+    }
+
+    println!("✓ Agent B stopped");
+}
+
+/// Stop accepting connections on SIGINT/SIGTERM and let in-flight requests finish
+async fn drain_on_signal(handle: axum_server::Handle) {
+    shutdown::signal().await;
+    handle.graceful_shutdown(Some(shutdown::DRAIN_TIMEOUT));
 }
\ No newline at end of file
@@ -6,27 +6,109 @@
 
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use pricing_core::{pricing, booking, RpcCall};
+use pricing_core::{fares::RouteTable, pricing, booking, refund, workflow, RpcCall};
 
 /// Convert generic JSON input to Agent B's internal RpcCall format
-/// This allows Agent A to send simple JSON without knowing RpcCall structure
-pub fn json_to_rpc_call(endpoint: &str, input: &Value) -> Result<RpcCall, String> {
+/// This allows Agent A to send simple JSON without knowing RpcCall structure.
+/// Pricing calls carry `fares` so the guest prices against the server's table,
+/// and default `quote_date` to today like /price does.
+pub fn json_to_rpc_call(endpoint: &str, input: &Value, fares: &RouteTable) -> Result<RpcCall, String> {
     match endpoint {
         "price" => {
-            let req: pricing::Request = serde_json::from_value(input.clone())
+            let mut req: pricing::Request = serde_json::from_value(input.clone())
                 .map_err(|e| format!("Invalid pricing input: {}", e))?;
-            Ok(RpcCall::GetPrice(req))
+            req.quote_date.get_or_insert_with(crate::today);
+            Ok(RpcCall::GetPriceWithTable(req, fares.clone()))
         }
         "book" => {
             let req: booking::Request = serde_json::from_value(input.clone())
                 .map_err(|e| format!("Invalid booking input: {}", e))?;
             Ok(RpcCall::BookFlight(req))
         }
+        "refund" => {
+            let req: refund::Request = serde_json::from_value(input.clone())
+                .map_err(|e| format!("Invalid refund input: {}", e))?;
+            Ok(RpcCall::Refund(req))
+        }
         _ => Err(format!("Unknown endpoint: {}", endpoint))
     }
 }
 
+/// Convert a JSON pricing request into input bytes for the pricing guest,
+/// a bincode `(pricing::Request, RouteTable)` pair priced against `fares`
+pub fn json_to_price_proof_input(input: &Value, fares: &RouteTable) -> Result<Vec<u8>, String> {
+    let mut req: pricing::Request = serde_json::from_value(input.clone())
+        .map_err(|e| format!("Invalid pricing input: {}", e))?;
+    req.quote_date.get_or_insert_with(crate::today);
+    bincode::serialize(&(req, fares)).map_err(|e| format!("Failed to serialize proof input: {}", e))
+}
+
+/// Convert `{ price_public_values, payment_public_values, booking }` (public
+/// values as hex, as returned by the attester) into workflow guest input bytes
+pub fn json_to_workflow_proof_input(input: &Value) -> Result<Vec<u8>, String> {
+    let public_values = |field: &str| {
+        let hex_str = input[field]
+            .as_str()
+            .ok_or_else(|| format!("Missing {}", field))?;
+        hex::decode(hex_str.strip_prefix("0x").unwrap_or(hex_str))
+            .map_err(|e| format!("Invalid {}: {}", field, e))
+    };
+    let booking: workflow::BookingClaim = serde_json::from_value(input["booking"].clone())
+        .map_err(|e| format!("Invalid booking claim: {}", e))?;
+
+    let workflow_input = workflow::WorkflowInput {
+        price_public_values: public_values("price_public_values")?,
+        payment_public_values: public_values("payment_public_values")?,
+        booking,
+    };
+    bincode::serialize(&workflow_input).map_err(|e| format!("Failed to serialize workflow input: {}", e))
+}
+
 /// Helper to serialize RpcCall to bincode bytes for zkVM
 pub fn rpc_call_to_bytes(call: &RpcCall) -> Vec<u8> {
     bincode::serialize(call).expect("Failed to serialize RpcCall")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_json_to_rpc_call() {
+        let fares = RouteTable::embedded();
+        let call = json_to_rpc_call("price", &json!({ "from": "NYC", "to": "LON", "vip": false }), &fares).unwrap();
+        let RpcCall::GetPriceWithTable(req, table) = call else {
+            panic!("expected a pricing call");
+        };
+        assert_eq!(req.quote_date, Some(crate::today()));
+        assert_eq!(table.digest(), fares.digest());
+
+        let invalid = json_to_rpc_call("price", &json!({ "from": "NYC" }), &fares).err().unwrap();
+        assert!(invalid.starts_with("Invalid pricing input"));
+        assert_eq!(json_to_rpc_call("cancel", &json!({}), &fares).err(), Some("Unknown endpoint: cancel".to_string()));
+    }
+
+    #[test]
+    fn test_json_to_workflow_proof_input() {
+        let booking = json!({
+            "session_id": "sess_1",
+            "booking_id": "BK1",
+            "amount": { "amount_minor": 45_000, "currency": "USD" },
+        });
+        let bytes = json_to_workflow_proof_input(&json!({
+            "price_public_values": "0x0102",
+            "payment_public_values": "03",
+            "booking": booking.clone(),
+        }))
+        .unwrap();
+        let input: workflow::WorkflowInput = bincode::deserialize(&bytes).unwrap();
+        assert_eq!((input.price_public_values, input.payment_public_values), (vec![1, 2], vec![3]));
+        assert_eq!(input.booking.amount.amount_minor, 45_000);
+
+        let missing = json_to_workflow_proof_input(&json!({ "price_public_values": "0x01", "booking": booking.clone() }));
+        assert_eq!(missing.err(), Some("Missing payment_public_values".to_string()));
+        let not_hex = json_to_workflow_proof_input(&json!({ "price_public_values": "0xzz", "payment_public_values": "03", "booking": booking }));
+        assert!(not_hex.unwrap_err().starts_with("Invalid price_public_values"));
+    }
+}
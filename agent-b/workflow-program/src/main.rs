@@ -0,0 +1,27 @@
+//! Workflow guest: proves a booking is consistent with its quote and payment.
+//!
+//! Reads a `workflow::WorkflowInput` carrying the public values of the pricing
+//! proof and the payment agent's proof, asserts that the booked and paid
+//! amounts equal the quoted price and that the session IDs match, then commits
+//! a `workflow::WorkflowCommitment` binding the digests of both.
+
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+
+use pricing_core::{
+    pricing::PriceCommitment,
+    workflow::{self, PaymentCommitment, WorkflowInput},
+};
+
+pub fn main() {
+    let input: WorkflowInput = sp1_zkvm::io::read();
+    let quote: PriceCommitment =
+        bincode::deserialize(&input.price_public_values).expect("invalid pricing public values");
+    let payment: PaymentCommitment =
+        bincode::deserialize(&input.payment_public_values).expect("invalid payment public values");
+
+    match workflow::check(&input, &quote, &payment) {
+        Ok(commitment) => sp1_zkvm::io::commit(&commitment),
+        Err(e) => panic!("{}", e.message()),
+    }
+}
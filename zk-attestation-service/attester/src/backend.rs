@@ -0,0 +1,59 @@
+//! Prover backend selection: the local backend (mock, CPU or CUDA, from
+//! `SP1_PROVER`) or the Succinct prover network, chosen per request from the
+//! caller's hints.
+
+use once_cell::sync::Lazy;
+use sp1_sdk::{network::NetworkProver, ProverClient};
+use zk_protocol::{ProverBackend, ProverHints};
+use zp_config::Prover;
+
+/// Operator configuration, read once from the `[attester]` settings
+pub struct BackendConfig {
+    /// Backend behind `ProverClient::from_env()`
+    pub local: ProverBackend,
+    /// Present when `NETWORK_PRIVATE_KEY` is set
+    pub network: Option<NetworkProver>,
+    /// Typical local proving time, compared against `max_latency_secs`
+    pub local_latency_secs: u64,
+}
+
+pub static BACKENDS: Lazy<BackendConfig> = Lazy::new(BackendConfig::from_env);
+
+impl BackendConfig {
+    fn from_env() -> Self {
+        let settings = &zp_config::get().attester;
+        let local = match settings.prover {
+            Prover::Mock => ProverBackend::Mock,
+            Prover::Cuda => ProverBackend::Cuda,
+            Prover::Network => ProverBackend::Network,
+            Prover::Cpu => ProverBackend::Cpu,
+        };
+        let network = std::env::var("NETWORK_PRIVATE_KEY")
+            .is_ok()
+            .then(|| ProverClient::builder().network().build());
+        // Groth16 on CPU takes ~20 minutes, CUDA a few
+        let default_latency = match local {
+            ProverBackend::Mock => 0,
+            ProverBackend::Cuda => 300,
+            ProverBackend::Cpu | ProverBackend::Network => 1200,
+        };
+        let local_latency_secs = settings.local_latency_secs.unwrap_or(default_latency);
+
+        Self { local, network, local_latency_secs }
+    }
+
+    /// Offload to the network only when it is configured, the caller is
+    /// willing to pay, and the local backend would miss the latency target
+    pub fn choose(&self, hints: &ProverHints) -> ProverBackend {
+        let willing_to_pay = hints.max_price_per_pgu != Some(0);
+        let too_slow = hints
+            .max_latency_secs
+            .is_some_and(|secs| secs < self.local_latency_secs);
+
+        if self.network.is_some() && willing_to_pay && too_slow {
+            ProverBackend::Network
+        } else {
+            self.local
+        }
+    }
+}
@@ -0,0 +1,72 @@
+//! Completed proofs keyed by program, proof mode and stdin, so identical
+//! attestations (e.g. re-proving the same pricing computation) skip the
+//! prover. Entries live for `ATTESTER_PROOF_CACHE_TTL_SECS` (default one
+//! hour; 0 disables the cache).
+
+use once_cell::sync::Lazy;
+use sha2::{Digest, Sha256};
+use sp1_sdk::SP1ProofWithPublicValues;
+use std::{
+    collections::HashMap,
+    sync::RwLock,
+    time::{Duration, Instant},
+};
+use zk_protocol::{ExecutionMetadata, ProofMode};
+
+pub type CacheKey = [u8; 32];
+
+#[derive(Clone)]
+pub struct CachedProof {
+    pub proof: SP1ProofWithPublicValues,
+    /// Cycles, proving time and backend of the original run
+    pub execution: ExecutionMetadata,
+    /// Checked with `prover.verify` when it was generated
+    pub verified: bool,
+    created: Instant,
+}
+
+static TTL: Lazy<Duration> = Lazy::new(|| Duration::from_secs(zp_config::get().attester.proof_cache_ttl_secs));
+
+static PROOFS: Lazy<RwLock<HashMap<CacheKey, CachedProof>>> = Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// Digest of everything that determines the proof: the program, the proof
+/// mode and each stdin segment (length-prefixed, so segment boundaries count)
+pub fn key(program_id: &str, proof_mode: ProofMode, segments: &[&[u8]]) -> CacheKey {
+    let mut hasher = Sha256::new();
+    hasher.update((program_id.len() as u64).to_le_bytes());
+    hasher.update(program_id.as_bytes());
+    hasher.update([proof_mode as u8]);
+    for segment in segments {
+        hasher.update((segment.len() as u64).to_le_bytes());
+        hasher.update(segment);
+    }
+    hasher.finalize().into()
+}
+
+/// Unexpired proof for `key`
+pub fn get(key: &CacheKey) -> Option<CachedProof> {
+    PROOFS
+        .read()
+        .unwrap()
+        .get(key)
+        .filter(|entry| entry.created.elapsed() < *TTL)
+        .cloned()
+}
+
+/// Remember a freshly generated proof, dropping expired entries
+pub fn insert(key: CacheKey, proof: SP1ProofWithPublicValues, execution: ExecutionMetadata, verified: bool) {
+    if TTL.is_zero() {
+        return;
+    }
+    let mut proofs = PROOFS.write().unwrap();
+    proofs.retain(|_, entry| entry.created.elapsed() < *TTL);
+    proofs.insert(
+        key,
+        CachedProof {
+            proof,
+            execution,
+            verified,
+            created: Instant::now(),
+        },
+    );
+}
@@ -0,0 +1,74 @@
+//! Proving-time history per program and proof mode, used to answer
+//! `GET /attest/estimate` with numbers from this machine rather than a
+//! hardcoded range.
+
+use once_cell::sync::Lazy;
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::RwLock,
+    time::Duration,
+};
+use zk_protocol::{EstimateResponse, ProofMode};
+
+use crate::{backend::BACKENDS, jobs};
+
+/// Durations kept per program and mode; older runs age out
+const HISTORY_LEN: usize = 20;
+
+static HISTORY: Lazy<RwLock<HashMap<(String, ProofMode), VecDeque<Duration>>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// Record how long a completed proof took
+pub fn record(program_id: &str, proof_mode: ProofMode, duration: Duration) {
+    let mut history = HISTORY.write().unwrap();
+    let runs = history.entry((program_id.to_string(), proof_mode)).or_default();
+    if runs.len() == HISTORY_LEN {
+        runs.pop_front();
+    }
+    runs.push_back(duration);
+}
+
+/// Mean recorded proving time, falling back to the backend's configured
+/// latency before the first proof. Queue wait assumes every job ahead takes
+/// as long as this one.
+pub fn estimate(program_id: &str, proof_mode: ProofMode) -> EstimateResponse {
+    let (proving_secs, samples) = match HISTORY.read().unwrap().get(&(program_id.to_string(), proof_mode)) {
+        Some(runs) if !runs.is_empty() => {
+            let total: Duration = runs.iter().sum();
+            ((total / runs.len() as u32).as_secs(), runs.len())
+        }
+        _ => (BACKENDS.local_latency_secs, 0),
+    };
+    let rounds_ahead = (jobs::pending() / jobs::workers()) as u64;
+
+    EstimateResponse {
+        program_id: program_id.to_string(),
+        proof_mode,
+        queue_wait_secs: rounds_ahead * proving_secs,
+        proving_secs,
+        samples,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_averages_recent_runs() {
+        let program_id = "estimate-test-program";
+        assert_eq!(estimate(program_id, ProofMode::Groth16).samples, 0);
+
+        record(program_id, ProofMode::Groth16, Duration::from_secs(10));
+        record(program_id, ProofMode::Groth16, Duration::from_secs(20));
+        let groth16 = estimate(program_id, ProofMode::Groth16);
+        assert_eq!((groth16.proving_secs, groth16.samples), (15, 2));
+
+        // Only the last HISTORY_LEN runs count
+        for _ in 0..HISTORY_LEN {
+            record(program_id, ProofMode::Groth16, Duration::from_secs(30));
+        }
+        let groth16 = estimate(program_id, ProofMode::Groth16);
+        assert_eq!((groth16.proving_secs, groth16.samples), (30, HISTORY_LEN));
+    }
+}
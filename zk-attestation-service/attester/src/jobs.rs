@@ -0,0 +1,140 @@
+//! Attestation jobs: a bounded pool of proving slots (`ATTESTER_WORKERS`,
+//! default 1) and cancellation of jobs that are queued for or holding one.
+//! Once the attester starts draining, new jobs are refused and queued ones
+//! cancelled; jobs already proving run to completion.
+
+use once_cell::sync::Lazy;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+};
+use tokio::sync::{Notify, OwnedSemaphorePermit, Semaphore};
+use zk_protocol::JobStatus;
+
+/// Proofs generated concurrently; one Groth16 proof already saturates a machine
+static WORKER_COUNT: Lazy<usize> = Lazy::new(|| zp_config::get().attester.workers);
+
+static WORKERS: Lazy<Arc<Semaphore>> = Lazy::new(|| Arc::new(Semaphore::new(*WORKER_COUNT)));
+
+static JOBS: Lazy<RwLock<HashMap<String, Arc<Job>>>> = Lazy::new(|| RwLock::new(HashMap::new()));
+
+static DRAINING: AtomicBool = AtomicBool::new(false);
+
+struct Job {
+    status: RwLock<JobStatus>,
+    cancelled: Notify,
+}
+
+/// An unfinished job; dropping it (on success, error or cancellation)
+/// removes it from the job table
+pub struct JobHandle {
+    id: String,
+    job: Arc<Job>,
+}
+
+/// Track a new job as queued. Fails if `id` belongs to an unfinished job.
+pub fn start(id: &str) -> Result<JobHandle, String> {
+    let mut jobs = JOBS.write().unwrap();
+    if DRAINING.load(Ordering::SeqCst) {
+        return Err("attester is shutting down".to_string());
+    }
+    if jobs.contains_key(id) {
+        return Err(format!("job_id {} is already in progress", id));
+    }
+    let job = Arc::new(Job {
+        status: RwLock::new(JobStatus::Queued),
+        cancelled: Notify::new(),
+    });
+    jobs.insert(id.to_string(), job.clone());
+    Ok(JobHandle { id: id.to_string(), job })
+}
+
+/// Cancel an unfinished job, returning the stage it was in
+pub fn cancel(id: &str) -> Option<JobStatus> {
+    let job = JOBS.write().unwrap().remove(id)?;
+    // notify_one keeps the wakeup if the job is between awaits
+    job.cancelled.notify_one();
+    let status = *job.status.read().unwrap();
+    Some(status)
+}
+
+/// Refuse new jobs and cancel those still waiting for a slot, returning
+/// how many were cancelled and how many are still proving
+pub fn drain() -> (usize, usize) {
+    let mut jobs = JOBS.write().unwrap();
+    DRAINING.store(true, Ordering::SeqCst);
+    let queued: Vec<String> = jobs
+        .iter()
+        .filter(|(_, job)| *job.status.read().unwrap() == JobStatus::Queued)
+        .map(|(id, _)| id.clone())
+        .collect();
+    for id in &queued {
+        if let Some(job) = jobs.remove(id) {
+            job.cancelled.notify_one();
+        }
+    }
+    (queued.len(), jobs.len())
+}
+
+/// Number of proving slots
+pub fn workers() -> usize {
+    *WORKER_COUNT
+}
+
+/// Jobs queued or running
+pub fn pending() -> usize {
+    JOBS.read().unwrap().len()
+}
+
+impl JobHandle {
+    /// Wait for a proving slot; `None` if the job was cancelled while queued
+    pub async fn acquire_slot(&self) -> Option<OwnedSemaphorePermit> {
+        tokio::select! {
+            biased;
+            _ = self.job.cancelled.notified() => None,
+            permit = WORKERS.clone().acquire_owned() => {
+                *self.job.status.write().unwrap() = JobStatus::Running;
+                Some(permit.expect("worker semaphore closed"))
+            }
+        }
+    }
+
+    /// Resolves when the job is cancelled
+    pub async fn cancelled(&self) {
+        self.job.cancelled.notified().await
+    }
+}
+
+impl Drop for JobHandle {
+    fn drop(&mut self) {
+        let mut jobs = JOBS.write().unwrap();
+        // A cancelled id may already be reused by a new job
+        if jobs.get(&self.id).is_some_and(|job| Arc::ptr_eq(job, &self.job)) {
+            jobs.remove(&self.id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancel_frees_the_job_id() {
+        let first = start("jobs-test-1").unwrap();
+        assert!(start("jobs-test-1").is_err());
+
+        assert_eq!(cancel("jobs-test-1"), Some(JobStatus::Queued));
+        assert_eq!(cancel("jobs-test-1"), None);
+
+        // The cancelled handle must not remove the job that reused its id
+        let second = start("jobs-test-1").unwrap();
+        drop(first);
+        assert!(start("jobs-test-1").is_err());
+        drop(second);
+        assert!(start("jobs-test-1").is_ok());
+    }
+}
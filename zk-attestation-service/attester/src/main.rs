@@ -1,25 +1,65 @@
 use axum::{
-    extract::{Multipart, DefaultBodyLimit},
-    routing::post,
+    body::{self, Body},
+    extract::{Multipart, DefaultBodyLimit, Path, Query, Request, State},
+    middleware::{self, Next},
+    routing::{delete, get, post},
     Json, Router,
-    http::StatusCode,
+    http::{header, HeaderMap, StatusCode},
     response::{IntoResponse, Response},
 };
+use axum_server::tls_rustls::RustlsConfig;
 use once_cell::sync::Lazy;
-use serde::Serialize;
-use sp1_sdk::{ProverClient, SP1ProvingKey, SP1VerifyingKey, SP1Stdin, HashableKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sp1_sdk::{
+    EnvProver, HashableKey, ProverClient, SP1ProofWithPublicValues, SP1ProvingKey, SP1Stdin, SP1VerifyingKey,
+    SP1_CIRCUIT_VERSION,
+};
+use sp1_verifier::Groth16Verifier;
 use std::{
     collections::HashMap,
     sync::{Arc, RwLock},
+    time::{Duration, Instant},
 };
+use tokio::sync::OwnedSemaphorePermit;
+use tracing::Instrument;
 use uuid::Uuid;
-use zk_protocol::{AttestRequest, AttestResponse};
+use zk_protocol::{
+    audit::{self, AuditKind, AuditQuery},
+    shutdown,
+    signing::{AdminToken, SigningKey, SIGNATURE_HEADER, TIMESTAMP_HEADER},
+    telemetry,
+    tls::MtlsConfig,
+    schema::claim_matches,
+    ArtifactPart, AttestRequest, AttestResponse, CancelResponse, EstimateResponse, ExecutionMetadata,
+    OutputSchema, ProgramInfo,
+    ProgramMetadata, ProofMode, ProverBackend, ProverHints, VerifyRequest, VerifyResponse,
+};
+
+mod backend;
+mod cache;
+mod estimate;
+mod jobs;
+use backend::BACKENDS;
 
 type ElfStore = HashMap<String, Vec<u8>>; // program_id → ELF bytes
 type KeyCache = HashMap<String, (SP1ProvingKey, SP1VerifyingKey)>; // program_id → (pk, vk)
+type SchemaStore = HashMap<String, OutputSchema>; // program_id → public values layout
+type ProgramRegistry = HashMap<String, ProgramInfo>; // program_id → registration metadata
+type ArtifactStore = HashMap<String, ProofArtifact>; // job_id → proof of a completed attestation
+
+/// Raw outputs of a completed attestation
+struct ProofArtifact {
+    proof: Vec<u8>,
+    public_values: Vec<u8>,
+    execution: ExecutionMetadata,
+}
 
 static STORE: Lazy<Arc<RwLock<ElfStore>>> = Lazy::new(|| Arc::new(RwLock::new(HashMap::new())));
 static KEY_CACHE: Lazy<Arc<RwLock<KeyCache>>> = Lazy::new(|| Arc::new(RwLock::new(HashMap::new())));
+static SCHEMAS: Lazy<Arc<RwLock<SchemaStore>>> = Lazy::new(|| Arc::new(RwLock::new(HashMap::new())));
+static PROGRAMS: Lazy<Arc<RwLock<ProgramRegistry>>> = Lazy::new(|| Arc::new(RwLock::new(HashMap::new())));
+static ARTIFACTS: Lazy<Arc<RwLock<ArtifactStore>>> = Lazy::new(|| Arc::new(RwLock::new(HashMap::new())));
 
 // Simple error wrapper for better error responses
 struct AppError(String);
@@ -39,12 +79,17 @@ impl From<String> for AppError {
 #[derive(Serialize)]
 struct RegisterResponse {
     program_id: String,
+    elf_hash: String,
     registered_at: String,
+    /// Proving keys already cached; false while background setup runs
+    keys_ready: bool,
 }
 
 // POST /register-elf  ← called by Agent B on startup
-async fn register_elf(mut multipart: Multipart) -> Result<Json<RegisterResponse>, AppError> {
+async fn register_elf(mut multipart: Multipart) -> Result<Response, AppError> {
     let mut elf_bytes: Option<Vec<u8>> = None;
+    let mut output_schema: Option<OutputSchema> = None;
+    let mut metadata = ProgramMetadata::default();
 
     // Read all multipart fields
     while let Some(field) = multipart.next_field().await.map_err(|e| {
@@ -65,7 +110,24 @@ async fn register_elf(mut multipart: Multipart) -> Result<Json<RegisterResponse>
             
             println!("✓ Read ELF file: {} bytes", bytes.len());
             elf_bytes = Some(bytes.to_vec());
-            break; // Got what we need, stop reading
+        } else if field_name.as_deref() == Some("output_schema") {
+            // Optional JSON layout of the program's public values
+            let text = field.text().await.map_err(|e| {
+                AppError(format!("Failed to read output_schema: {}", e))
+            })?;
+            let schema: OutputSchema = serde_json::from_str(&text)
+                .map_err(|e| AppError(format!("Invalid output_schema: {}", e)))?;
+            println!("✓ Read output schema");
+            output_schema = Some(schema);
+        } else if let Some(name @ ("name" | "elf_name" | "version" | "description")) = field_name.as_deref() {
+            let text = field.text().await.map_err(|e| {
+                AppError(format!("Failed to read {}: {}", name, e))
+            })?;
+            match name {
+                "version" => metadata.version = Some(text),
+                "description" => metadata.description = Some(text),
+                _ => metadata.name = Some(text),
+            }
         }
     }
 
@@ -74,53 +136,155 @@ async fn register_elf(mut multipart: Multipart) -> Result<Json<RegisterResponse>
         AppError("ELF file required but not found in request".to_string())
     })?;
     
+    let elf_hash = format!("0x{}", hex::encode(Sha256::digest(&elf)));
+
+    // Same ELF again (e.g. Agent B restarted): keep its program_id and keys
+    let existing = PROGRAMS
+        .read()
+        .unwrap()
+        .values()
+        .find(|p| p.elf_hash == elf_hash)
+        .cloned();
+    if let Some(existing) = existing {
+        // The schema decides what /attest accepts as verified, so an
+        // unauthenticated re-upload must not swap it
+        if let Some(schema) = &output_schema {
+            if SCHEMAS.read().unwrap().get(&existing.program_id) != Some(schema) {
+                eprintln!("✗ Rejected a different output_schema for {}", existing.program_id);
+                return Ok((
+                    StatusCode::CONFLICT,
+                    format!("ELF {} is already registered with a different output_schema", elf_hash),
+                )
+                    .into_response());
+            }
+        }
+        let keys_ready = KEY_CACHE.read().unwrap().contains_key(&existing.program_id);
+        println!("✓ ELF {} already registered as {} (keys_ready={})", elf_hash, existing.program_id, keys_ready);
+        return Ok(Json(RegisterResponse {
+            program_id: existing.program_id,
+            elf_hash,
+            registered_at: existing.registered_at,
+            keys_ready,
+        })
+        .into_response());
+    }
+
     let program_id = Uuid::new_v4().to_string();
+    let registered_at = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+    let info = ProgramInfo {
+        program_id: program_id.clone(),
+        metadata,
+        elf_hash: elf_hash.clone(),
+        registered_at: registered_at.clone(),
+        has_output_schema: output_schema.is_some(),
+        keys_ready: false,
+    };
 
     {
         let mut store = STORE.write().unwrap();
-        store.insert(program_id.clone(), elf);
+        store.insert(program_id.clone(), elf.clone());
+    }
+    if let Some(schema) = output_schema {
+        SCHEMAS.write().unwrap().insert(program_id.clone(), schema);
     }
+    PROGRAMS.write().unwrap().insert(program_id.clone(), info);
 
     println!("✓ ELF registered with program_id: {}", program_id);
 
+    // Warm the key cache now rather than on the first /attest
+    let setup_id = program_id.clone();
+    tokio::task::spawn_blocking(move || {
+        cached_keys(&ProverClient::from_env(), &setup_id, &elf);
+        println!("✓ Keys ready for program_id: {}", setup_id);
+    });
+
     Ok(Json(RegisterResponse {
-        program_id: program_id.clone(),
-        registered_at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
-    }))
+        program_id,
+        elf_hash,
+        registered_at,
+        keys_ready: false,
+    })
+    .into_response())
+}
+
+// GET /programs  ← registry listing, oldest first
+async fn list_programs() -> Json<Vec<ProgramInfo>> {
+    let keys = KEY_CACHE.read().unwrap();
+    let mut programs: Vec<ProgramInfo> = PROGRAMS
+        .read()
+        .unwrap()
+        .values()
+        .map(|p| ProgramInfo { keys_ready: keys.contains_key(&p.program_id), ..p.clone() })
+        .collect();
+    programs.sort_by(|a, b| a.registered_at.cmp(&b.registered_at).then_with(|| a.program_id.cmp(&b.program_id)));
+    Json(programs)
+}
+
+/// Proving and verifying keys for a program, computed on first use. Setup
+/// runs outside the lock so other programs are not blocked for minutes.
+fn cached_keys(prover: &EnvProver, program_id: &str, elf: &[u8]) -> (SP1ProvingKey, SP1VerifyingKey) {
+    if let Some((cached_pk, cached_vk)) = KEY_CACHE.read().unwrap().get(program_id) {
+        // Cache hit: use cached keys
+        println!("✓ Using cached keys for program_id: {}", program_id);
+        return (cached_pk.clone(), cached_vk.clone());
+    }
+
+    // Cache miss: compute keys and store in cache
+    println!("⚙ Computing keys for program_id: {} (will be cached)", program_id);
+    let (new_pk, new_vk) = prover.setup(elf);
+    KEY_CACHE
+        .write()
+        .unwrap()
+        .insert(program_id.to_string(), (new_pk.clone(), new_vk.clone()));
+    (new_pk, new_vk)
 }
 
 // POST /attest  ← called by Agent A
 async fn attest(
     Json(payload): Json<AttestRequest>,
-) -> Json<AttestResponse> {
+) -> Result<Json<AttestResponse>, AppError> {
+    // 0. Refuse wire formats this attester does not understand
+    let version = payload.negotiated_version().map_err(|e| {
+        eprintln!("✗ Rejected attest request: {}", e);
+        audit::record(AuditKind::ProofSubmission, false, &payload.program_id, &format!("rejected: {}", e));
+        AppError(e.to_string())
+    })?;
+    if payload.protocol_version == 0 {
+        println!("⚠ Unversioned attest request, reading as protocol v{}", version);
+    }
+
     let prover = ProverClient::from_env();
     let program_id = &payload.program_id;
 
-    // 1. Fetch the pre-registered ELF
-    let elf = {
-        let store = STORE.read().unwrap();
-        store.get(program_id)
-            .expect("Unknown program_id")
-            .clone()
+    // 1. Fetch the pre-registered ELF; only registered programs are proven
+    let Some(elf) = STORE.read().unwrap().get(program_id).cloned() else {
+        eprintln!("✗ Rejected attest request for unregistered program_id {}", program_id);
+        audit::record(AuditKind::WhitelistRejection, false, program_id, "program_id is not registered");
+        return Err(AppError(format!("Unknown program_id {} (register it with POST /register-elf)", program_id)));
     };
 
-    // 2. Get or compute pk and vk (cached after first setup)
-    let (pk, vk) = {
-        let mut cache = KEY_CACHE.write().unwrap();
-        
-        if let Some((cached_pk, cached_vk)) = cache.get(program_id) {
-            // Cache hit: use cached keys
-            println!("✓ Using cached keys for program_id: {}", program_id);
-            (cached_pk.clone(), cached_vk.clone())
-        } else {
-            // Cache miss: compute keys and store in cache
-            println!("⚙ Computing keys for program_id: {} (will be cached)", program_id);
-            let (new_pk, new_vk) = prover.setup(&elf);
-            cache.insert(program_id.clone(), (new_pk.clone(), new_vk.clone()));
-            (new_pk, new_vk)
-        }
+    // Queue for a proving slot; DELETE /attest/{job_id} can cancel from here on
+    let job_id = payload.job_id.clone().unwrap_or_else(|| Uuid::new_v4().to_string());
+    let job = jobs::start(&job_id).map_err(AppError)?;
+    audit::record(
+        AuditKind::ProofSubmission,
+        true,
+        &job_id,
+        &format!("program {} ({:?}, protocol v{})", program_id, payload.proof_mode, version),
+    );
+    let cancelled = || {
+        println!("⊘ Job {} cancelled", job_id);
+        AppError(format!("Job {} was cancelled", job_id))
     };
 
+    // Identical inputs were proven recently: skip the queue and the prover
+    let proof_mode = payload.proof_mode;
+    let cache_key = cache::key(program_id, proof_mode, &payload.stdin_segments());
+    let cached = cache::get(&cache_key);
+
+    // 2. Get or compute pk and vk (cached after first setup)
+    let (pk, vk) = cached_keys(&prover, program_id, &elf);
+
     // 3. Compute VK hash for on-chain verification (stateless universal verifier pattern)
     // SP1 uses bytes32() to hash the VK, which is passed to verifyProof() each time
     // NO storage on-chain needed - contracts are stateless!
@@ -130,65 +294,501 @@ async fn attest(
     println!("✓ Verifying Key Hash: {}", vk_hash_str);
     println!("  (Pass this to SP1VerifierGroth16.verifyProof() on-chain)");
 
-    // 4. Create stdin with the input
-    // Input is already bincode-serialized by the agent
-    let mut stdin = SP1Stdin::new();
-    stdin.write_vec(payload.input_bytes.clone());
+    let is_cached = cached.is_some();
+    let (proof, execution, verified) = match cached {
+        Some(hit) => {
+            println!("✓ Proof cache hit for program_id {} ({:?})", program_id, proof_mode);
+            (hit.proof, hit.execution, hit.verified)
+        }
+        None => {
+            let slot = job.acquire_slot().await.ok_or_else(cancelled)?;
+
+            // 4. Create stdin with the input, one write_vec per segment
+            // Input is already bincode-serialized by the agent
+            let mut stdin = SP1Stdin::new();
+            for segment in payload.stdin_segments() {
+                stdin.write_vec(segment.to_vec());
+            }
+
+            // Execute first: rejects bad input before proving and counts cycles
+            let cycles = {
+                let (elf, stdin) = (elf.clone(), stdin.clone());
+                tokio::task::spawn_blocking(move || {
+                    ProverClient::from_env()
+                        .execute(&elf, &stdin)
+                        .run()
+                        .map(|(_, report)| report.total_instruction_count())
+                })
+                .await
+                .expect("Execution task panicked")
+                .map_err(|e| AppError(format!("Execution failed: {}", e)))?
+            };
+            println!("✓ Executed in {} cycles", cycles);
 
-    // 5. Generate Groth16 proof (SNARK-wrapped for on-chain compatibility)
-    // Groth16: (~100k gas on-chain, uses GPU acceleration if available)
-    // Alternative: .plonk() (~300k gas, const-size proof)
-    let proof = prover
-        .prove(&pk, &stdin)
-        .groth16()  // Wraps STARK in Groth16 for on-chain verification
-        .run()
-        .expect("Proving failed");
+            // 5. Generate the proof
+            // Groth16: SNARK-wrapped for on-chain verification (~100k gas, uses GPU acceleration if available)
+            // Compressed: skips the SNARK wrap, much faster but off-chain only
+            // Alternative: .plonk() (~300k gas, const-size proof)
+            let backend = BACKENDS.choose(&payload.hints);
+            println!("⚙ Proving in {:?} mode on {:?} backend", proof_mode, backend);
+            let started = Instant::now();
+            let proof = tokio::select! {
+                proof = run_prover(backend, pk, stdin, proof_mode, &payload.hints, slot) => proof?,
+                _ = job.cancelled() => return Err(cancelled()),
+            };
+            let proving_time = started.elapsed();
+            estimate::record(program_id, proof_mode, proving_time);
+            let execution = ExecutionMetadata {
+                backend,
+                cycles,
+                proving_ms: proving_time.as_millis() as u64,
+                sp1_version: SP1_CIRCUIT_VERSION.to_string(),
+            };
+            (proof, execution, false)
+        }
+    };
 
     // 6. Optional: Verify proof locally before returning
     // - If verify_locally=true (default): Verify proof in attester (safe, adds 2-3s)
     // - If verify_locally=false: Skip verification (fast, Agent A verifies on-chain)
-    if payload.verify_locally {
+    // A cached proof that was already verified is not checked again
+    let verified = if payload.verify_locally && !verified {
         println!("⚙ Verifying proof locally in attester...");
         prover.verify(&proof, &vk)
             .expect("Verification failed");
         println!("✓ Local verification passed");
+        true
+    } else if verified {
+        println!("✓ Cached proof was verified when generated");
+        true
     } else {
         println!("⊘ Skipping local verification (Agent A will verify on-chain)");
+        false
+    };
+    if !is_cached {
+        cache::insert(cache_key, proof.clone(), execution.clone(), verified);
     }
 
-    // 7. Extract public values and proof bytes
-    let actual_output = payload.claimed_output.unwrap_or_else(|| serde_json::json!({}));
+    // 7. Extract public values and check the claim against what was proven.
+    // Only the schema registered with the ELF counts; without one nothing
+    // is verified.
     let public_values_bytes = proof.public_values.as_slice();
+    let schema = SCHEMAS.read().unwrap().get(program_id).cloned();
+    let (verified_output, output_verified) = match schema {
+        Some(schema) => {
+            let proven = schema
+                .decode(public_values_bytes)
+                .map_err(|e| AppError(format!("Public values do not match output schema: {}", e)))?;
+            if let Some(claimed) = &payload.claimed_output {
+                if !claim_matches(claimed, &proven) {
+                    eprintln!("✗ claimed_output {} does not match proven {}", claimed, proven);
+                    return Err(AppError(format!(
+                        "claimed_output must be a non-empty object matching the proven public values: {}",
+                        proven
+                    )));
+                }
+                println!("✓ claimed_output matches proven public values");
+            }
+            (proven, true)
+        }
+        None => {
+            println!("⚠ No output schema for program_id {}, claimed_output not checked", program_id);
+            (serde_json::Value::Null, false)
+        }
+    };
 
     // proof.bytes() returns [vkey_hash[..4], proof_bytes]
     // The contract expects proofBytes to START with the first 4 bytes of the verifier hash
     // So we use proof.bytes() as-is (it already has the correct format)
-    let proof_bytes = proof.bytes();
+    // Compressed proofs have no on-chain encoding, so ship the whole SP1 proof
+    let proof_bytes = match proof_mode {
+        ProofMode::Groth16 => proof.bytes(),
+        ProofMode::Compressed => bincode::serialize(&proof).expect("Failed to serialize compressed proof"),
+    };
+
+    // 8. Keep the raw artifacts; v2+ clients download them instead of
+    // receiving hex inside the JSON
+    let (proof_hex, public_values_hex) = if version < 2 {
+        (hex::encode(&proof_bytes), hex::encode(public_values_bytes))
+    } else {
+        (String::new(), String::new())
+    };
+    ARTIFACTS.write().unwrap().insert(
+        job_id.clone(),
+        ProofArtifact {
+            proof: proof_bytes,
+            public_values: public_values_bytes.to_vec(),
+            execution: execution.clone(),
+        },
+    );
+    println!("✓ Stored proof artifacts for job_id: {}", job_id);
+    drop(job);
 
-    Json(AttestResponse {
-        proof: hex::encode(proof_bytes),
-        public_values: hex::encode(public_values_bytes),
+    Ok(Json(AttestResponse {
+        protocol_version: version,
+        job_id,
+        proof: proof_hex,
+        public_values: public_values_hex,
         vk_hash: vk_hash_str,  // Include VK hash for on-chain verification
-        verified_output: actual_output,
+        verified_output,
+        output_verified,
+        proof_mode,
+        on_chain_compatible: proof_mode.on_chain_compatible(),
+        backend: Some(execution.backend),
+        cached: is_cached,
+        execution: Some(execution),
+    }))
+}
+
+/// Run the prover for one job. Local proofs cannot be interrupted, so their
+/// blocking thread keeps the worker slot until SP1 returns even if the job is
+/// cancelled; a cancelled network proof stops polling and frees it at once.
+async fn run_prover(
+    backend: ProverBackend,
+    pk: SP1ProvingKey,
+    stdin: SP1Stdin,
+    proof_mode: ProofMode,
+    hints: &ProverHints,
+    slot: OwnedSemaphorePermit,
+) -> Result<SP1ProofWithPublicValues, AppError> {
+    let result = match (backend, &BACKENDS.network) {
+        (ProverBackend::Network, Some(network)) => {
+            let _slot = slot;
+            let mut builder = network.prove(&pk, &stdin);
+            builder = match proof_mode {
+                ProofMode::Groth16 => builder.groth16(),
+                ProofMode::Compressed => builder.compressed(),
+            };
+            if let Some(secs) = hints.max_latency_secs {
+                builder = builder.timeout(Duration::from_secs(secs));
+            }
+            if let Some(price) = hints.max_price_per_pgu {
+                builder = builder.max_price_per_pgu(price);
+            }
+            builder.run_async().await
+        }
+        _ => tokio::task::spawn_blocking(move || {
+            let _slot = slot;
+            let prover = ProverClient::from_env();
+            let builder = prover.prove(&pk, &stdin);
+            match proof_mode {
+                ProofMode::Groth16 => builder.groth16().run(),  // Wraps STARK in Groth16 for on-chain verification
+                ProofMode::Compressed => builder.compressed().run(),
+            }
+        })
+        .await
+        .expect("Proving task panicked"),
+    };
+    result.map_err(|e| AppError(format!("Proving failed: {}", e)))
+}
+
+#[derive(Deserialize)]
+struct EstimateQuery {
+    program_id: String,
+    #[serde(default)]
+    proof_mode: ProofMode,
+}
+
+// GET /attest/estimate?program_id=...&proof_mode=...  ← Agent A tells the user how long to wait
+async fn estimate_attest(Query(query): Query<EstimateQuery>) -> Result<Json<EstimateResponse>, AppError> {
+    if !STORE.read().unwrap().contains_key(&query.program_id) {
+        return Err(AppError(format!("Unknown program_id: {}", query.program_id)));
+    }
+    Ok(Json(estimate::estimate(&query.program_id, query.proof_mode)))
+}
+
+// DELETE /attest/{job_id}  ← Agent A abandons a booking mid-proof
+async fn cancel_attest(Path(job_id): Path<String>) -> Response {
+    match jobs::cancel(&job_id) {
+        Some(previous_status) => {
+            println!("⊘ Cancelling job {} ({:?})", job_id, previous_status);
+            Json(CancelResponse { job_id, previous_status }).into_response()
+        }
+        None if ARTIFACTS.read().unwrap().contains_key(&job_id) => {
+            (StatusCode::CONFLICT, format!("Job {} already completed", job_id)).into_response()
+        }
+        None => (StatusCode::NOT_FOUND, format!("No pending job {}", job_id)).into_response(),
+    }
+}
+
+// POST /verify-sp1  ← agents and the payment service check a proof without sp1-sdk
+async fn verify_sp1(Json(req): Json<VerifyRequest>) -> Result<Json<VerifyResponse>, AppError> {
+    let decode = |name: &str, h: &str| {
+        hex::decode(h.strip_prefix("0x").unwrap_or(h)).map_err(|e| AppError(format!("Invalid {} hex: {}", name, e)))
+    };
+    let proof = decode("proof", &req.proof)?;
+    let public_values = decode("public_values", &req.public_values)?;
+
+    if req.proof_mode == ProofMode::Compressed {
+        return verify_compressed(&proof, &public_values, req.program_id).map(Json);
+    }
+
+    // An explicit vk_hash wins; otherwise use the registered program's VK
+    let vk_hash = match (req.vk_hash, req.program_id) {
+        (Some(vk_hash), _) => vk_hash,
+        (None, Some(program_id)) => {
+            let elf = STORE
+                .read()
+                .unwrap()
+                .get(&program_id)
+                .cloned()
+                .ok_or_else(|| AppError(format!("Unknown program_id: {}", program_id)))?;
+            let (_, vk) = cached_keys(&ProverClient::from_env(), &program_id, &elf);
+            vk.bytes32()
+        }
+        (None, None) => return Err(AppError("vk_hash or program_id required".to_string())),
+    };
+
+    let result = Groth16Verifier::verify(&proof, &public_values, &vk_hash, *sp1_verifier::GROTH16_VK_BYTES);
+    match &result {
+        Ok(()) => println!("✓ /verify-sp1: proof valid for {}", vk_hash),
+        Err(e) => println!("✗ /verify-sp1: proof invalid for {}: {}", vk_hash, e),
+    }
+    Ok(Json(VerifyResponse {
+        valid: result.is_ok(),
+        vk_hash,
+        error: result.err().map(|e| e.to_string()),
+    }))
+}
+
+/// Compressed STARKs carry no VK hash prefix; check the bincode SP1 proof
+/// against the registered program's VK with the full SDK verifier
+fn verify_compressed(
+    proof: &[u8],
+    public_values: &[u8],
+    program_id: Option<String>,
+) -> Result<VerifyResponse, AppError> {
+    let program_id = program_id.ok_or_else(|| AppError("Compressed proofs need a program_id".to_string()))?;
+    let elf = STORE
+        .read()
+        .unwrap()
+        .get(&program_id)
+        .cloned()
+        .ok_or_else(|| AppError(format!("Unknown program_id: {}", program_id)))?;
+    let proof: SP1ProofWithPublicValues =
+        bincode::deserialize(proof).map_err(|e| AppError(format!("Invalid compressed proof: {}", e)))?;
+
+    let prover = ProverClient::from_env();
+    let (_, vk) = cached_keys(&prover, &program_id, &elf);
+    let result = if proof.public_values.as_slice() != public_values {
+        Err("public_values differ from those inside the proof".to_string())
+    } else {
+        prover.verify(&proof, &vk).map_err(|e| e.to_string())
+    };
+    let vk_hash = vk.bytes32();
+    match &result {
+        Ok(()) => println!("✓ /verify-sp1: compressed proof valid for {}", vk_hash),
+        Err(e) => println!("✗ /verify-sp1: compressed proof invalid for {}: {}", vk_hash, e),
+    }
+    Ok(VerifyResponse {
+        valid: result.is_ok(),
+        vk_hash,
+        error: result.err(),
     })
 }
 
+#[derive(Deserialize)]
+struct ArtifactQuery {
+    #[serde(default)]
+    part: ArtifactPart,
+}
+
+// GET /attest/{job_id}/artifact?part=proof|public_values  ← raw bytes of a completed job
+async fn get_artifact(
+    Path(job_id): Path<String>,
+    Query(query): Query<ArtifactQuery>,
+) -> Response {
+    let artifacts = ARTIFACTS.read().unwrap();
+    let Some(artifact) = artifacts.get(&job_id) else {
+        return (StatusCode::NOT_FOUND, format!("No artifacts for job_id {}", job_id)).into_response();
+    };
+    let (bytes, name, content_type) = match query.part {
+        ArtifactPart::Proof => (artifact.proof.clone(), "proof.bin", "application/octet-stream"),
+        ArtifactPart::PublicValues => (artifact.public_values.clone(), "public_values.bin", "application/octet-stream"),
+        ArtifactPart::Metadata => (
+            serde_json::to_vec(&artifact.execution).expect("Failed to serialize metadata"),
+            "metadata.json",
+            "application/json",
+        ),
+    };
+    (
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}-{}\"", job_id, name)),
+        ],
+        bytes,
+    )
+        .into_response()
+}
+
+/// Response refusing a request without `Authorization: Bearer $ADMIN_TOKEN`
+fn admin_rejection(headers: &HeaderMap) -> Option<Response> {
+    let Some(token) = AdminToken::from_env() else {
+        return Some((StatusCode::SERVICE_UNAVAILABLE, "Admin endpoints are disabled: ADMIN_TOKEN not set").into_response());
+    };
+    if !token.verify(headers.get(header::AUTHORIZATION).and_then(|v| v.to_str().ok())) {
+        eprintln!("⚠ Rejected admin request: missing or wrong bearer token");
+        return Some((StatusCode::UNAUTHORIZED, "invalid admin token").into_response());
+    }
+    None
+}
+
+/// Audit events, most recent `limit` matching `kind`, `subject`, `passed`
+/// and `since`, oldest first
+async fn audit_events(headers: HeaderMap, Query(query): Query<AuditQuery>) -> Response {
+    if let Some(rejection) = admin_rejection(&headers) {
+        return rejection;
+    }
+    let Some(log) = audit::get() else {
+        return (StatusCode::SERVICE_UNAVAILABLE, "Audit log is not open").into_response();
+    };
+    match log.query(&query) {
+        Ok(events) => Json(events).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// Check the audit log's hash chain; 409 names the first broken entry
+async fn verify_audit_log(headers: HeaderMap) -> Response {
+    if let Some(rejection) = admin_rejection(&headers) {
+        return rejection;
+    }
+    let Some(log) = audit::get() else {
+        return (StatusCode::SERVICE_UNAVAILABLE, "Audit log is not open").into_response();
+    };
+    match log.verify() {
+        Ok(events) => Json(serde_json::json!({ "intact": true, "events": events })).into_response(),
+        Err(e) => {
+            eprintln!("✗ {}", e);
+            (StatusCode::CONFLICT, e.to_string()).into_response()
+        }
+    }
+}
+
+/// Largest /attest body accepted for signature verification
+const MAX_SIGNED_BODY_BYTES: usize = 20 * 1024 * 1024;
+
+/// Reject proof requests whose HMAC signature does not match the shared secret
+async fn verify_signature(
+    State(key): State<Arc<SigningKey>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let (parts, body) = request.into_parts();
+    let bytes = match body::to_bytes(body, MAX_SIGNED_BODY_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(_) => return StatusCode::PAYLOAD_TOO_LARGE.into_response(),
+    };
+
+    let header = |name: &str| parts.headers.get(name).and_then(|v| v.to_str().ok());
+    if let Err(e) = key.verify(header(TIMESTAMP_HEADER), header(SIGNATURE_HEADER), &bytes) {
+        eprintln!("⚠ Rejected unsigned or tampered request to {}: {}", parts.uri, e);
+        return (StatusCode::UNAUTHORIZED, e.to_string()).into_response();
+    }
+
+    next.run(Request::from_parts(parts, Body::from(bytes))).await
+}
+
+/// Run each request in a span continuing the caller's trace
+async fn trace_request(request: Request, next: Next) -> Response {
+    let span = telemetry::request_span(request.method(), request.uri().path(), request.headers());
+    next.run(request).instrument(span).await
+}
+
 #[tokio::main]
 async fn main() {
+    let _telemetry = telemetry::init("attester");
+
+    let config = zp_config::init().unwrap_or_else(|e| {
+        eprintln!("✗ {}", e);
+        std::process::exit(1);
+    });
+    if zp_config::print_config_requested() {
+        print!("{}", config.to_toml());
+        return;
+    }
+    let port = config.attester.port;
+    let audit_log = audit::init("attester").unwrap_or_else(|e| {
+        eprintln!("✗ Failed to open the audit log (AUDIT_LOG_PATH): {}", e);
+        std::process::exit(1);
+    });
+
+    // Proof requests and cancellations must be signed when AGENT_HMAC_SECRET is set
+    let mut attest_routes = Router::new()
+        .route("/attest", post(attest))
+        .route("/attest/:job_id", delete(cancel_attest));
+    if let Some(key) = SigningKey::from_env() {
+        println!("✓ HMAC request signing enforced on /attest");
+        attest_routes = attest_routes.route_layer(middleware::from_fn_with_state(Arc::new(key), verify_signature));
+    }
+
     let app = Router::new()
         .route("/register-elf", post(register_elf))
-        .route("/attest", post(attest))
-        .layer(DefaultBodyLimit::max(20 * 1024 * 1024)); // 20MB limit for ELF files
+        .route("/programs", get(list_programs))
+        .route("/attest/estimate", get(estimate_attest))
+        .route("/attest/:job_id/artifact", get(get_artifact))
+        .route("/verify-sp1", post(verify_sp1))
+        .route("/admin/audit", get(audit_events))
+        .route("/admin/audit/verify", get(verify_audit_log))
+        .merge(attest_routes)
+        .layer(DefaultBodyLimit::max(20 * 1024 * 1024)) // 20MB limit for ELF files
+        .layer(middleware::from_fn(trace_request));
 
-    println!("ZK Attester running → http://0.0.0.0:8000");
+    // Optional mutual TLS: only Agent A / Agent B certificates signed by TLS_CA get through
+    let tls = MtlsConfig::from_env();
+    let scheme = if tls.is_some() { "https" } else { "http" };
+
+    println!("ZK Attester running → {}://0.0.0.0:{}", scheme, port);
+    println!(
+        "   Prover: {:?} locally (~{}s), network offload {}",
+        BACKENDS.local,
+        BACKENDS.local_latency_secs,
+        if BACKENDS.network.is_some() { "enabled" } else { "disabled (set NETWORK_PRIVATE_KEY)" }
+    );
     println!("   POST /register-elf   ← Agent B calls this once");
+    println!("   GET  /programs       ← registered guest programs");
     println!("   POST /attest        ← Agent A calls this");
+    println!("   GET  /attest/estimate ← expected queue wait and proving time");
+    println!("   DELETE /attest/:job_id ← cancel a queued or running job");
+    println!("   GET  /attest/:job_id/artifact ← raw proof / public values");
+    println!("   POST /verify-sp1    ← server-side Groth16 verification");
+    println!("   GET  /admin/audit[/verify] ← audit events / chain check (Bearer $ADMIN_TOKEN)");
+    println!("   Audit log: {}", audit_log.path().display());
 
-    let listener = tokio::net::TcpListener::bind("0.0.0.0:8000")
-        .await
-        .expect("Failed to bind to 0.0.0.0:8000");
+    match tls {
+        Some(tls) => {
+            let config = tls.server_config().expect("Failed to load TLS config");
+            let handle = axum_server::Handle::new();
+            let on_signal = handle.clone();
+            tokio::spawn(async move {
+                shutdown_signal().await;
+                on_signal.graceful_shutdown(Some(shutdown::DRAIN_TIMEOUT));
+            });
+            axum_server::bind_rustls(([0, 0, 0, 0], port).into(), RustlsConfig::from_config(Arc::new(config)))
+                .handle(handle)
+                .serve(app.into_make_service())
+                .await
+                .expect("Server error");
+        }
+        None => {
+            let listener = tokio::net::TcpListener::bind(("0.0.0.0", port))
+                .await
+                .expect("Failed to bind the attester port");
 
-    axum::serve(listener, app)
-        .await
-        .expect("Server error");
+            axum::serve(listener, app)
+                .with_graceful_shutdown(shutdown_signal())
+                .await
+                .expect("Server error");
+        }
+    }
+
+    println!("✓ Attester stopped");
+}
+
+/// SIGINT/SIGTERM: stop taking jobs and cancel queued ones so only running
+/// proofs hold up the exit
+async fn shutdown_signal() {
+    shutdown::signal().await;
+    let (cancelled, running) = jobs::drain();
+    println!("   Cancelled {} queued job(s); waiting for {} running proof(s)", cancelled, running);
 }
\ No newline at end of file
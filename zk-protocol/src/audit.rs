@@ -0,0 +1,295 @@
+//! Append-only audit log (feature `audit`)
+//!
+//! Security-relevant events (payment verification, proof submission,
+//! whitelist rejection, on-chain verification) are appended as JSON lines to
+//! `AUDIT_LOG_PATH` (default `<service>-audit.jsonl`). Every event carries
+//! the SHA-256 hash of the one before it, so editing or deleting a line
+//! breaks the chain from that point on and `AuditLog::verify` reports where.
+//!
+//! Services call `init` once at startup and `record` wherever an event
+//! happens; `record` does nothing before `init`, e.g. in tests.
+
+use std::{
+    fmt,
+    fs::{File, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// `prev_hash` of the first event
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Events returned by a query when no `limit` is given, and its cap
+pub const DEFAULT_QUERY_LIMIT: usize = 100;
+pub const MAX_QUERY_LIMIT: usize = 1000;
+
+/// What an audit event records
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditKind {
+    /// A payment event from the Payment Agent was checked
+    PaymentVerification,
+    /// A proof was submitted for proving or storage
+    ProofSubmission,
+    /// A request named something that is not on an allow list (unregistered
+    /// program, ...)
+    WhitelistRejection,
+    /// A proof was checked by the ZeroProof contract
+    OnChainVerification,
+}
+
+/// One entry of the log
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AuditEvent {
+    /// Position in the log, from 1
+    pub seq: u64,
+    /// Unix seconds
+    pub timestamp: u64,
+    /// Service that recorded it (agent-a, attester, ...)
+    pub service: String,
+    pub kind: AuditKind,
+    /// Whether the check passed or the submission was accepted
+    pub passed: bool,
+    /// What the event is about: a session, job, program or instruction ID
+    pub subject: String,
+    pub detail: String,
+    /// `hash` of the previous event, `GENESIS_HASH` for the first
+    pub prev_hash: String,
+    /// Hex SHA-256 over every other field
+    pub hash: String,
+}
+
+impl AuditEvent {
+    fn digest(&self) -> String {
+        let fields = (
+            self.seq,
+            self.timestamp,
+            &self.service,
+            self.kind,
+            self.passed,
+            &self.subject,
+            &self.detail,
+            &self.prev_hash,
+        );
+        hex::encode(Sha256::digest(serde_json::to_vec(&fields).expect("audit fields serialize")))
+    }
+}
+
+/// Filters for `AuditLog::query`, usable as an axum `Query`
+#[derive(Deserialize, Debug, Default)]
+pub struct AuditQuery {
+    #[serde(default)]
+    pub kind: Option<AuditKind>,
+    #[serde(default)]
+    pub subject: Option<String>,
+    #[serde(default)]
+    pub passed: Option<bool>,
+    /// Only events at or after this Unix time
+    #[serde(default)]
+    pub since: Option<u64>,
+    /// Most recent events to return
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// Where the chain stops holding
+#[derive(Debug, PartialEq, Eq)]
+pub struct ChainError {
+    /// Line (from 1) of the first bad entry
+    pub line: u64,
+    pub reason: String,
+}
+
+impl fmt::Display for ChainError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "audit chain broken at line {}: {}", self.line, self.reason)
+    }
+}
+
+impl std::error::Error for ChainError {}
+
+struct Tail {
+    file: File,
+    seq: u64,
+    hash: String,
+}
+
+/// Append-only, hash-chained event log backed by a JSON lines file
+pub struct AuditLog {
+    service: String,
+    path: PathBuf,
+    tail: Mutex<Tail>,
+}
+
+impl AuditLog {
+    /// Open (or create) the log at `path`, continuing its chain. A broken
+    /// chain is reported on stderr and left for `verify` to point at.
+    pub fn open(service: &str, path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        let events = read_events(&path)?;
+        let (seq, hash) = match events.last() {
+            Some(Ok(last)) => (last.seq, last.hash.clone()),
+            Some(Err(_)) | None => (events.len() as u64, GENESIS_HASH.to_string()),
+        };
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let log = Self { service: service.to_string(), path, tail: Mutex::new(Tail { file, seq, hash }) };
+        if let Err(e) = log.verify() {
+            eprintln!("⚠ {} ({})", e, log.path.display());
+        }
+        Ok(log)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append an event and sync it to disk
+    pub fn append(&self, kind: AuditKind, passed: bool, subject: &str, detail: &str) -> io::Result<AuditEvent> {
+        let mut tail = self.tail.lock().unwrap();
+        let mut event = AuditEvent {
+            seq: tail.seq + 1,
+            timestamp: now(),
+            service: self.service.clone(),
+            kind,
+            passed,
+            subject: subject.to_string(),
+            detail: detail.to_string(),
+            prev_hash: tail.hash.clone(),
+            hash: String::new(),
+        };
+        event.hash = event.digest();
+
+        let mut line = serde_json::to_vec(&event).map_err(io::Error::other)?;
+        line.push(b'\n');
+        tail.file.write_all(&line)?;
+        tail.file.sync_data()?;
+        tail.seq = event.seq;
+        tail.hash = event.hash.clone();
+        Ok(event)
+    }
+
+    /// The most recent events matching `query`, oldest first
+    pub fn query(&self, query: &AuditQuery) -> io::Result<Vec<AuditEvent>> {
+        let _tail = self.tail.lock().unwrap();
+        let limit = query.limit.unwrap_or(DEFAULT_QUERY_LIMIT).clamp(1, MAX_QUERY_LIMIT);
+        let matching: Vec<AuditEvent> = read_events(&self.path)?
+            .into_iter()
+            .filter_map(Result::ok)
+            .filter(|e| query.kind.is_none_or(|kind| e.kind == kind))
+            .filter(|e| query.subject.as_ref().is_none_or(|subject| &e.subject == subject))
+            .filter(|e| query.passed.is_none_or(|passed| e.passed == passed))
+            .filter(|e| query.since.is_none_or(|since| e.timestamp >= since))
+            .collect();
+        let skip = matching.len().saturating_sub(limit);
+        Ok(matching.into_iter().skip(skip).collect())
+    }
+
+    /// Check every link of the chain, returning the number of events
+    pub fn verify(&self) -> Result<u64, ChainError> {
+        let _tail = self.tail.lock().unwrap();
+        let events = read_events(&self.path).map_err(|e| ChainError { line: 0, reason: e.to_string() })?;
+        let count = events.len() as u64;
+        let mut prev_hash = GENESIS_HASH.to_string();
+        for (i, event) in events.into_iter().enumerate() {
+            let line = i as u64 + 1;
+            let broken = |reason: String| ChainError { line, reason };
+            let event = event.map_err(|e| broken(format!("unreadable entry: {}", e)))?;
+            if event.seq != line {
+                return Err(broken(format!("seq {} out of order", event.seq)));
+            }
+            if event.prev_hash != prev_hash {
+                return Err(broken("prev_hash does not match the previous entry".to_string()));
+            }
+            if event.digest() != event.hash {
+                return Err(broken("hash does not match the entry".to_string()));
+            }
+            prev_hash = event.hash;
+        }
+        Ok(count)
+    }
+}
+
+fn read_events(path: &Path) -> io::Result<Vec<Result<AuditEvent, serde_json::Error>>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut events = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        if !line.trim().is_empty() {
+            events.push(serde_json::from_str(&line));
+        }
+    }
+    Ok(events)
+}
+
+static LOG: OnceLock<AuditLog> = OnceLock::new();
+
+/// Open the service's log at `AUDIT_LOG_PATH` (default `<service>-audit.jsonl`)
+pub fn init(service: &str) -> io::Result<&'static AuditLog> {
+    let path = std::env::var("AUDIT_LOG_PATH")
+        .ok()
+        .filter(|p| !p.is_empty())
+        .unwrap_or_else(|| format!("{}-audit.jsonl", service));
+    let log = AuditLog::open(service, path)?;
+    Ok(LOG.get_or_init(|| log))
+}
+
+/// The log opened by `init`, if any
+pub fn get() -> Option<&'static AuditLog> {
+    LOG.get()
+}
+
+/// Append to the process log; failures are reported on stderr so they never
+/// fail the request being audited
+pub fn record(kind: AuditKind, passed: bool, subject: &str, detail: &str) {
+    if let Some(log) = LOG.get() {
+        if let Err(e) = log.append(kind, passed, subject, detail) {
+            eprintln!("⚠ Could not write audit event {:?} for {}: {}", kind, subject, e);
+        }
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chain_detects_tampering() {
+        let path = std::env::temp_dir().join(format!("zp-audit-test-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let log = AuditLog::open("attester", &path).unwrap();
+        log.append(AuditKind::ProofSubmission, true, "job_1", "program pricing").unwrap();
+        log.append(AuditKind::WhitelistRejection, false, "evil", "program not registered").unwrap();
+        assert_eq!(log.verify(), Ok(2));
+        drop(log);
+
+        // Reopening continues the chain
+        let log = AuditLog::open("attester", &path).unwrap();
+        let third = log.append(AuditKind::ProofSubmission, true, "job_2", "program pricing").unwrap();
+        assert_eq!(third.seq, 3);
+        let rejected = log.query(&AuditQuery { passed: Some(false), ..Default::default() }).unwrap();
+        assert_eq!(rejected.len(), 1);
+        assert_eq!(rejected[0].subject, "evil");
+
+        let text = std::fs::read_to_string(&path).unwrap().replacen("\"evil\"", "\"good\"", 1);
+        std::fs::write(&path, text).unwrap();
+        assert_eq!(log.verify().unwrap_err().line, 2);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
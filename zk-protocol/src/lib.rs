@@ -1,39 +1,332 @@
-/// General protocol for ZK attestation between agents
-/// This library provides common types and serialization helpers
-/// that any agent can use without depending on other agents' code.
+//! General protocol for ZK attestation between agents
+//! This library provides common types and serialization helpers
+//! that any agent can use without depending on other agents' code.
+
+#[cfg(feature = "audit")]
+pub mod audit;
+pub mod schema;
+#[cfg(feature = "shutdown")]
+pub mod shutdown;
+#[cfg(feature = "signing")]
+pub mod signing;
+#[cfg(feature = "telemetry")]
+pub mod telemetry;
+#[cfg(feature = "mtls")]
+pub mod tls;
 
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::fmt;
+
+pub use schema::OutputSchema;
+
+/// Version of the `AttestRequest`/`AttestResponse` wire format.
+///
+/// Compatibility policy:
+/// - Adding an optional field (with a serde default) keeps the version.
+/// - Renaming, removing or changing the meaning of a field bumps it.
+/// - The attester accepts `MIN_PROTOCOL_VERSION..=PROTOCOL_VERSION` and
+///   rejects anything else with a `VersionError` naming the supported range.
+/// - Requests without `protocol_version` predate versioning (`0`) and are
+///   read as version 1, which has the same shape.
+///
+/// Version 2 drops `proof` and `public_values` from the JSON response; fetch
+/// them from `GET /attest/{job_id}/artifact` instead. Version 1 requests
+/// still get them inline.
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// Oldest wire format the attester still accepts
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
 
 /// Request to the attester service to generate a ZK proof
 #[derive(Serialize, Deserialize, Debug)]
 pub struct AttestRequest {
+    /// Wire format version of the sender (0 = sent before versioning)
+    #[serde(default)]
+    pub protocol_version: u32,
     pub program_id: String,
     /// Input data as raw bytes (bincode-serialized)
     /// Will be passed to the zkVM program via stdin
+    #[serde(default)]
     pub input_bytes: Vec<u8>,
-    /// Expected output for verification (optional, format defined by agent)
+    /// Further stdin segments, one `write_vec` each, for guests that call
+    /// `sp1_zkvm::io::read` several times. Written after `input_bytes`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub input_segments: Vec<Vec<u8>>,
+    /// Expected output, a JSON object that may list a subset of fields;
+    /// checked against the public values decoded with the schema registered
+    /// for the program, and the attestation rejected on mismatch
     pub claimed_output: Option<Value>,
     /// Whether to verify the proof locally before returning
     #[serde(default = "default_verify")]
     pub verify_locally: bool,
+    /// Groth16 (default) for on-chain use, or a faster compressed STARK
+    #[serde(default)]
+    pub proof_mode: ProofMode,
+    /// Cost / latency limits the attester uses to pick a prover backend
+    #[serde(default)]
+    pub hints: ProverHints,
+    /// Caller-chosen job id, so the job can be cancelled with
+    /// `DELETE /attest/{job_id}` while this request is still pending
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub job_id: Option<String>,
 }
 
 fn default_verify() -> bool {
     true
 }
 
+fn default_on_chain() -> bool {
+    true
+}
+
+/// Kind of proof the attester produces
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ProofMode {
+    /// STARK wrapped in Groth16; verifiable by the on-chain SP1 verifier
+    #[default]
+    Groth16,
+    /// Compressed STARK without the SNARK wrap: minutes faster, but only
+    /// verifiable off-chain (e.g. `POST /verify-sp1` with a program_id)
+    Compressed,
+}
+
+/// Where a proof is generated
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ProverBackend {
+    /// No real proof; for development only
+    Mock,
+    Cpu,
+    Cuda,
+    /// Succinct prover network
+    Network,
+}
+
+/// Per-request limits for backend selection. Unset fields leave the choice
+/// to the attester's configuration.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProverHints {
+    /// Proof needed within this many seconds; routes to the prover network
+    /// when the local backend is expected to be slower
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_latency_secs: Option<u64>,
+    /// Highest network price per prover gas unit the caller accepts;
+    /// 0 keeps the proof on the local backend
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_price_per_pgu: Option<u64>,
+}
+
+impl ProofMode {
+    pub fn on_chain_compatible(self) -> bool {
+        matches!(self, ProofMode::Groth16)
+    }
+}
+
+/// Requested wire format the attester cannot serve
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VersionError {
+    pub requested: u32,
+}
+
+impl fmt::Display for VersionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unsupported protocol_version {} (attester supports {}..={})",
+            self.requested, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION
+        )
+    }
+}
+
+impl std::error::Error for VersionError {}
+
+impl AttestRequest {
+    /// Wire format this request should be read as, after mapping
+    /// unversioned (legacy) requests to version 1
+    pub fn negotiated_version(&self) -> Result<u32, VersionError> {
+        let version = if self.protocol_version == 0 { 1 } else { self.protocol_version };
+        if (MIN_PROTOCOL_VERSION..=PROTOCOL_VERSION).contains(&version) {
+            Ok(version)
+        } else {
+            Err(VersionError { requested: self.protocol_version })
+        }
+    }
+
+    /// Stdin segments in the order the guest reads them: `input_bytes` (if
+    /// non-empty, or if it is the only input) followed by `input_segments`
+    pub fn stdin_segments(&self) -> Vec<&[u8]> {
+        let mut segments = Vec::with_capacity(1 + self.input_segments.len());
+        if !self.input_bytes.is_empty() || self.input_segments.is_empty() {
+            segments.push(self.input_bytes.as_slice());
+        }
+        segments.extend(self.input_segments.iter().map(Vec::as_slice));
+        segments
+    }
+}
+
 /// Response from the attester service
 #[derive(Serialize, Deserialize, Debug)]
 pub struct AttestResponse {
-    /// Hex-encoded Groth16 proof for on-chain verification
+    /// Wire format version the attester answered with
+    #[serde(default)]
+    pub protocol_version: u32,
+    /// ID under which the proof artifacts are stored
+    #[serde(default)]
+    pub job_id: String,
+    /// Hex-encoded proof (version 1 only): Groth16 bytes for on-chain
+    /// verification, or a bincode `SP1ProofWithPublicValues` when compressed
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub proof: String,
-    /// Public values committed by the zkVM program (hex-encoded)
+    /// Public values committed by the zkVM program, hex-encoded (version 1 only)
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub public_values: String,
     /// VK hash for on-chain verifier (bytes32)
     pub vk_hash: String,
-    /// Output from the zkVM program
+    /// Public values decoded with the program's output schema, or null when
+    /// no schema is known
     pub verified_output: Value,
+    /// Whether `verified_output` was decoded from the proof (and any claim
+    /// checked against it)
+    #[serde(default)]
+    pub output_verified: bool,
+    #[serde(default)]
+    pub proof_mode: ProofMode,
+    /// False for compressed proofs, which the on-chain verifier rejects
+    #[serde(default = "default_on_chain")]
+    pub on_chain_compatible: bool,
+    /// Backend that generated the proof
+    #[serde(default)]
+    pub backend: Option<ProverBackend>,
+    /// Served from the proof cache: the same program and input were proven
+    /// recently, so no new proof was generated
+    #[serde(default)]
+    pub cached: bool,
+    /// How the proof was produced (for cached proofs, the original run)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub execution: Option<ExecutionMetadata>,
+}
+
+/// Cost and environment of a proof, for auditing and for reproducing its
+/// verification with the same SP1 circuits
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ExecutionMetadata {
+    pub backend: ProverBackend,
+    /// RISC-V cycles the guest executed
+    pub cycles: u64,
+    /// Wall-clock proof generation time
+    pub proving_ms: u64,
+    /// SP1 circuit version the proof was generated with, e.g. `v5.0.0`
+    pub sp1_version: String,
+}
+
+/// Request to the attester to check a Groth16 proof server-side
+/// (`POST /verify-sp1`), so callers need not depend on sp1-sdk
+#[derive(Serialize, Deserialize, Debug)]
+pub struct VerifyRequest {
+    /// Hex-encoded proof bytes, as returned by `/attest`
+    pub proof: String,
+    /// Hex-encoded public values
+    pub public_values: String,
+    /// VK hash (bytes32) the proof must verify against
+    #[serde(default)]
+    pub vk_hash: Option<String>,
+    /// Registered program whose VK to use when `vk_hash` is not given
+    #[serde(default)]
+    pub program_id: Option<String>,
+    /// Compressed proofs can only be checked against a registered program_id
+    #[serde(default)]
+    pub proof_mode: ProofMode,
+}
+
+/// Outcome of `POST /verify-sp1`
+#[derive(Serialize, Deserialize, Debug)]
+pub struct VerifyResponse {
+    pub valid: bool,
+    /// VK hash the proof was checked against
+    pub vk_hash: String,
+    /// Why verification failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Part of a stored proof served by `GET /attest/{job_id}/artifact?part=`
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ArtifactPart {
+    /// Proof bytes as passed to the on-chain verifier (or the bincode SP1
+    /// proof for compressed jobs)
+    #[default]
+    Proof,
+    /// Public values committed by the guest
+    PublicValues,
+    /// `ExecutionMetadata` of the job, as JSON
+    Metadata,
+}
+
+/// Stage of an attestation job that has not finished yet
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    /// Waiting for a free proving slot
+    Queued,
+    /// Proof generation in progress
+    Running,
+}
+
+/// Response to `DELETE /attest/{job_id}`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CancelResponse {
+    pub job_id: String,
+    /// Stage the job was in when cancelled; a running local proof may keep
+    /// its slot until the prover returns
+    pub previous_status: JobStatus,
+}
+
+/// Response to `GET /attest/estimate`: expected wait for a new job, from the
+/// attester's recent proving times for the program
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EstimateResponse {
+    pub program_id: String,
+    pub proof_mode: ProofMode,
+    /// Time until a proving slot frees up for a job submitted now
+    pub queue_wait_secs: u64,
+    /// Expected proof generation time once the job runs
+    pub proving_secs: u64,
+    /// Completed proofs the estimate is based on; 0 means the configured
+    /// default latency was used
+    pub samples: usize,
+}
+
+/// Descriptive fields sent with `POST /register-elf`
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProgramMetadata {
+    /// Short name, e.g. `agent-b-pricing-program`
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Semantic version of the guest
+    #[serde(default)]
+    pub version: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// Entry of the attester's program registry (`GET /programs`)
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProgramInfo {
+    pub program_id: String,
+    #[serde(flatten)]
+    pub metadata: ProgramMetadata,
+    /// 0x-hex SHA-256 of the ELF
+    pub elf_hash: String,
+    /// RFC 3339 registration time
+    pub registered_at: String,
+    /// Whether claimed outputs can be checked against the public values
+    pub has_output_schema: bool,
+    /// Whether proving keys are set up, so the first attestation skips setup
+    #[serde(default)]
+    pub keys_ready: bool,
 }
 
 /// Response from an agent's pricing/booking endpoint
@@ -0,0 +1,250 @@
+//! Output schemas: describe the bincode layout of a guest's public values so
+//! the attester can decode them to JSON without depending on agent crates.
+//!
+//! A schema mirrors the Rust type the guest commits, field by field:
+//!
+//! ```json
+//! {"type": "struct", "fields": [
+//!     {"name": "price", "type": "struct", "fields": [
+//!         {"name": "amount_minor", "type": "i64"},
+//!         {"name": "currency", "type": "string"}
+//!     ]},
+//!     {"name": "digest", "type": "bytes32"}
+//! ]}
+//! ```
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fmt;
+
+/// Shape of a value committed with `sp1_zkvm::io::commit` (bincode, fixed-width
+/// little-endian integers, u64 length prefixes)
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OutputSchema {
+    Bool,
+    U8,
+    U32,
+    U64,
+    I64,
+    String,
+    /// `Vec<u8>`, decoded as 0x-hex
+    Bytes,
+    /// `[u8; 32]`, decoded as 0x-hex
+    Bytes32,
+    Option { of: Box<OutputSchema> },
+    Vec { of: Box<OutputSchema> },
+    Struct { fields: Vec<OutputField> },
+    /// Variants in declaration order; unit variants decode to their name,
+    /// others to `{name: {fields}}`
+    Enum { variants: Vec<OutputVariant> },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct OutputField {
+    pub name: String,
+    #[serde(flatten)]
+    pub schema: OutputSchema,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct OutputVariant {
+    pub name: String,
+    #[serde(default)]
+    pub fields: Vec<OutputField>,
+}
+
+/// Public values that do not fit the schema
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaError {
+    UnexpectedEnd,
+    TrailingBytes(usize),
+    InvalidBool(u8),
+    InvalidOptionTag(u8),
+    UnknownVariant(u32),
+    InvalidUtf8,
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaError::UnexpectedEnd => write!(f, "public values end before the schema does"),
+            SchemaError::TrailingBytes(n) => write!(f, "{} bytes of public values left after decoding", n),
+            SchemaError::InvalidBool(b) => write!(f, "invalid bool byte {}", b),
+            SchemaError::InvalidOptionTag(b) => write!(f, "invalid option tag {}", b),
+            SchemaError::UnknownVariant(i) => write!(f, "enum variant {} not in schema", i),
+            SchemaError::InvalidUtf8 => write!(f, "string is not valid UTF-8"),
+        }
+    }
+}
+
+impl std::error::Error for SchemaError {}
+
+impl OutputSchema {
+    /// Decode `bytes` completely; leftover bytes mean the schema is wrong
+    pub fn decode(&self, bytes: &[u8]) -> Result<Value, SchemaError> {
+        let mut reader = Reader(bytes);
+        let value = reader.value(self)?;
+        match reader.0.len() {
+            0 => Ok(value),
+            n => Err(SchemaError::TrailingBytes(n)),
+        }
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], SchemaError> {
+        if self.0.len() < n {
+            return Err(SchemaError::UnexpectedEnd);
+        }
+        let (head, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Ok(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], SchemaError> {
+        let mut out = [0u8; N];
+        out.copy_from_slice(self.take(N)?);
+        Ok(out)
+    }
+
+    fn len(&mut self) -> Result<usize, SchemaError> {
+        Ok(u64::from_le_bytes(self.array()?) as usize)
+    }
+
+    fn value(&mut self, schema: &OutputSchema) -> Result<Value, SchemaError> {
+        Ok(match schema {
+            OutputSchema::Bool => match self.array::<1>()?[0] {
+                0 => Value::Bool(false),
+                1 => Value::Bool(true),
+                b => return Err(SchemaError::InvalidBool(b)),
+            },
+            OutputSchema::U8 => Value::from(self.array::<1>()?[0]),
+            OutputSchema::U32 => Value::from(u32::from_le_bytes(self.array()?)),
+            OutputSchema::U64 => Value::from(u64::from_le_bytes(self.array()?)),
+            OutputSchema::I64 => Value::from(i64::from_le_bytes(self.array()?)),
+            OutputSchema::String => {
+                let len = self.len()?;
+                let s = std::str::from_utf8(self.take(len)?).map_err(|_| SchemaError::InvalidUtf8)?;
+                Value::String(s.to_string())
+            }
+            OutputSchema::Bytes => {
+                let len = self.len()?;
+                Value::String(to_hex(self.take(len)?))
+            }
+            OutputSchema::Bytes32 => Value::String(to_hex(self.take(32)?)),
+            OutputSchema::Option { of } => match self.array::<1>()?[0] {
+                0 => Value::Null,
+                1 => self.value(of)?,
+                b => return Err(SchemaError::InvalidOptionTag(b)),
+            },
+            OutputSchema::Vec { of } => {
+                let len = self.len()?;
+                Value::Array((0..len).map(|_| self.value(of)).collect::<Result<_, _>>()?)
+            }
+            OutputSchema::Struct { fields } => self.fields(fields)?,
+            OutputSchema::Enum { variants } => {
+                let index = u32::from_le_bytes(self.array()?);
+                let variant = variants
+                    .get(index as usize)
+                    .ok_or(SchemaError::UnknownVariant(index))?;
+                if variant.fields.is_empty() {
+                    Value::String(variant.name.clone())
+                } else {
+                    let mut map = Map::new();
+                    map.insert(variant.name.clone(), self.fields(&variant.fields)?);
+                    Value::Object(map)
+                }
+            }
+        })
+    }
+
+    fn fields(&mut self, fields: &[OutputField]) -> Result<Value, SchemaError> {
+        let mut map = Map::new();
+        for field in fields {
+            map.insert(field.name.clone(), self.value(&field.schema)?);
+        }
+        Ok(Value::Object(map))
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    let mut s = String::with_capacity(2 + bytes.len() * 2);
+    s.push_str("0x");
+    for b in bytes {
+        s.push_str(&format!("{:02x}", b));
+    }
+    s
+}
+
+/// Whether `claimed` agrees with the decoded public values. A claim must be
+/// a non-empty object; it matches when every claimed key matches, so it may
+/// cover only the fields the caller cares about. Nested objects follow the
+/// same rule and everything else must be equal.
+pub fn claim_matches(claimed: &Value, proven: &Value) -> bool {
+    matches!(claimed, Value::Object(fields) if !fields.is_empty()) && value_matches(claimed, proven)
+}
+
+fn value_matches(claimed: &Value, proven: &Value) -> bool {
+    match (claimed, proven) {
+        (Value::Object(claimed), Value::Object(proven)) => claimed
+            .iter()
+            .all(|(key, value)| proven.get(key).is_some_and(|p| value_matches(value, p))),
+        _ => claimed == proven,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[derive(Serialize)]
+    enum Tier {
+        Standard,
+        Vip { discount_bps: u32 },
+    }
+
+    #[derive(Serialize)]
+    struct Commitment {
+        amount: i64,
+        currency: String,
+        digest: [u8; 32],
+        promo: Option<String>,
+        tiers: Vec<Tier>,
+    }
+
+    #[test]
+    fn decodes_bincode_public_values() {
+        let schema: OutputSchema = serde_json::from_value(json!({"type": "struct", "fields": [
+            {"name": "amount", "type": "i64"},
+            {"name": "currency", "type": "string"},
+            {"name": "digest", "type": "bytes32"},
+            {"name": "promo", "type": "option", "of": {"type": "string"}},
+            {"name": "tiers", "type": "vec", "of": {"type": "enum", "variants": [
+                {"name": "standard"},
+                {"name": "vip", "fields": [{"name": "discount_bps", "type": "u32"}]}
+            ]}}
+        ]}))
+        .unwrap();
+        let bytes = bincode::serialize(&Commitment {
+            amount: -5,
+            currency: "USD".into(),
+            digest: [0xab; 32],
+            promo: None,
+            tiers: vec![Tier::Standard, Tier::Vip { discount_bps: 8_500 }],
+        })
+        .unwrap();
+
+        let proven = schema.decode(&bytes).unwrap();
+        assert_eq!(proven["tiers"], json!(["standard", {"vip": {"discount_bps": 8500}}]));
+        assert_eq!(proven["digest"], json!(format!("0x{}", "ab".repeat(32))));
+        assert!(claim_matches(&json!({"amount": -5, "promo": null}), &proven));
+        assert!(!claim_matches(&json!({"amount": 5}), &proven));
+        assert!(!claim_matches(&json!({}), &proven));
+        assert!(!claim_matches(&proven["amount"], &proven["amount"]));
+        assert_eq!(schema.decode(&bytes[..bytes.len() - 1]), Err(SchemaError::UnexpectedEnd));
+    }
+}
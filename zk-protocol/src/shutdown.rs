@@ -0,0 +1,40 @@
+//! Graceful shutdown for the agents' and attester's HTTP servers
+//!
+//! `signal()` resolves on Ctrl-C (SIGINT) or SIGTERM (`docker stop`,
+//! Kubernetes). Servers pass it to `axum::serve(..).with_graceful_shutdown`,
+//! or to an `axum_server::Handle` with `DRAIN_TIMEOUT` when serving TLS, so
+//! they stop accepting connections and finish in-flight requests before
+//! `main` returns and drops its state (and flushes buffered trace spans).
+
+use std::time::Duration;
+
+/// How long a TLS server waits for in-flight requests once a signal arrives
+pub const DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Resolves once the process is asked to stop
+pub async fn signal() {
+    let interrupt = async {
+        if tokio::signal::ctrl_c().await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(_) => std::future::pending::<()>().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    let name = tokio::select! {
+        _ = interrupt => "SIGINT",
+        _ = terminate => "SIGTERM",
+    };
+    println!("\n{} received: finishing in-flight requests, then shutting down", name);
+}
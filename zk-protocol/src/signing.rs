@@ -0,0 +1,164 @@
+//! HMAC request signing between agents (feature `signing`)
+//!
+//! The caller sends `X-ZP-Timestamp` (unix seconds) and `X-ZP-Signature`,
+//! the hex HMAC-SHA256 of `"<timestamp>.<hex sha256(body)>"` under the shared
+//! secret in `AGENT_HMAC_SECRET`. The receiver recomputes it and rejects
+//! requests that are unsigned, tampered with, or outside the allowed clock skew.
+//!
+//! Operator endpoints (`/admin/...`) take a static bearer token instead, from
+//! `ADMIN_TOKEN`. Pages shared with people who hold neither use signed links:
+//! `?expires=<unix seconds>&sig=<hex HMAC of the path and expiry>`.
+
+use std::{
+    fmt,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+/// Header carrying the signing timestamp (unix seconds)
+pub const TIMESTAMP_HEADER: &str = "x-zp-timestamp";
+/// Header carrying the hex HMAC-SHA256 signature
+pub const SIGNATURE_HEADER: &str = "x-zp-signature";
+/// Maximum accepted difference between the signer's and verifier's clocks
+pub const MAX_CLOCK_SKEW_SECS: u64 = 300;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Reason a signed request was rejected
+#[derive(Debug, PartialEq, Eq)]
+pub enum SignatureError {
+    MissingHeaders,
+    InvalidTimestamp,
+    Expired,
+    Mismatch,
+}
+
+impl fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignatureError::MissingHeaders => write!(f, "missing request signature"),
+            SignatureError::InvalidTimestamp => write!(f, "invalid signature timestamp"),
+            SignatureError::Expired => write!(f, "request signature expired"),
+            SignatureError::Mismatch => write!(f, "request signature mismatch"),
+        }
+    }
+}
+
+impl std::error::Error for SignatureError {}
+
+/// Shared secret used to sign and verify inter-agent requests
+#[derive(Clone)]
+pub struct SigningKey {
+    secret: Vec<u8>,
+}
+
+impl SigningKey {
+    pub fn new(secret: impl Into<Vec<u8>>) -> Self {
+        Self { secret: secret.into() }
+    }
+
+    /// Load the shared secret from `AGENT_HMAC_SECRET`; signing is off when unset
+    pub fn from_env() -> Option<Self> {
+        std::env::var("AGENT_HMAC_SECRET")
+            .ok()
+            .filter(|s| !s.is_empty())
+            .map(Self::new)
+    }
+
+    /// Header name/value pairs to attach to a request carrying `body`
+    pub fn headers(&self, body: &[u8]) -> [(&'static str, String); 2] {
+        let timestamp = now();
+        [
+            (TIMESTAMP_HEADER, timestamp.to_string()),
+            (SIGNATURE_HEADER, hex::encode(self.mac(timestamp, body).finalize().into_bytes())),
+        ]
+    }
+
+    /// Check the header values sent alongside `body`
+    pub fn verify(
+        &self,
+        timestamp: Option<&str>,
+        signature: Option<&str>,
+        body: &[u8],
+    ) -> Result<(), SignatureError> {
+        let (timestamp, signature) = timestamp.zip(signature).ok_or(SignatureError::MissingHeaders)?;
+        let timestamp: u64 = timestamp.parse().map_err(|_| SignatureError::InvalidTimestamp)?;
+
+        if now().abs_diff(timestamp) > MAX_CLOCK_SKEW_SECS {
+            return Err(SignatureError::Expired);
+        }
+
+        let signature = hex::decode(signature).map_err(|_| SignatureError::Mismatch)?;
+        self.mac(timestamp, body)
+            .verify_slice(&signature)
+            .map_err(|_| SignatureError::Mismatch)
+    }
+
+    /// `sig` query value of a link to `path` that works until `expires`
+    /// (unix seconds)
+    pub fn link_signature(&self, path: &str, expires: u64) -> String {
+        hex::encode(self.link_mac(path, expires).finalize().into_bytes())
+    }
+
+    /// Check the `expires` and `sig` query values of a link to `path`
+    pub fn verify_link(&self, path: &str, expires: Option<&str>, signature: Option<&str>) -> Result<(), SignatureError> {
+        let (expires, signature) = expires.zip(signature).ok_or(SignatureError::MissingHeaders)?;
+        let expires: u64 = expires.parse().map_err(|_| SignatureError::InvalidTimestamp)?;
+        if now() > expires {
+            return Err(SignatureError::Expired);
+        }
+        let signature = hex::decode(signature).map_err(|_| SignatureError::Mismatch)?;
+        self.link_mac(path, expires)
+            .verify_slice(&signature)
+            .map_err(|_| SignatureError::Mismatch)
+    }
+
+    fn link_mac(&self, path: &str, expires: u64) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(&self.secret).expect("HMAC accepts any key length");
+        mac.update(format!("link.{}.{}", expires, path).as_bytes());
+        mac
+    }
+
+    fn mac(&self, timestamp: u64, body: &[u8]) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(&self.secret).expect("HMAC accepts any key length");
+        mac.update(format!("{}.{}", timestamp, hex::encode(Sha256::digest(body))).as_bytes());
+        mac
+    }
+}
+
+/// Bearer token guarding operator endpoints
+#[derive(Clone)]
+pub struct AdminToken {
+    digest: [u8; 32],
+}
+
+impl AdminToken {
+    pub fn new(token: &str) -> Self {
+        Self { digest: Sha256::digest(token.as_bytes()).into() }
+    }
+
+    /// Load the token from `ADMIN_TOKEN`; admin endpoints are off when unset
+    pub fn from_env() -> Option<Self> {
+        std::env::var("ADMIN_TOKEN")
+            .ok()
+            .filter(|s| !s.is_empty())
+            .map(|token| Self::new(&token))
+    }
+
+    /// Check an `Authorization: Bearer <token>` header value. Digests are
+    /// compared so the comparison time says nothing about the token.
+    pub fn verify(&self, authorization: Option<&str>) -> bool {
+        authorization
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .is_some_and(|token| Self::new(token.trim()).digest == self.digest)
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
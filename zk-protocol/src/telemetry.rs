@@ -0,0 +1,203 @@
+//! Distributed tracing across agents (feature `telemetry`)
+//!
+//! Every service logs through `tracing` and, when `OTEL_EXPORTER_OTLP_ENDPOINT`
+//! is set, also exports its spans over OTLP/HTTP (Jaeger, Tempo, ...). Trace
+//! context travels on inter-agent calls as W3C `traceparent` / `baggage`
+//! headers. The booking's session ID, taken from `X-ZP-Session-Id` or a
+//! `/session/{id}` path, rides along in the baggage and tags each service's
+//! request span as `session_id`, so a booking can be followed end to end.
+//!
+//! Log lines go to stderr, as text or, with `LOG_FORMAT=json`, as one JSON
+//! object per event carrying the fields of the spans it happened in.
+
+use http::{HeaderMap, HeaderName, HeaderValue, Method};
+use opentelemetry::{
+    baggage::BaggageExt,
+    global,
+    propagation::{Extractor, Injector, TextMapCompositePropagator},
+    trace::TracerProvider as _,
+    Context, KeyValue,
+};
+use opentelemetry_sdk::{
+    propagation::{BaggagePropagator, TraceContextPropagator},
+    trace::SdkTracerProvider,
+    Resource,
+};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+
+/// Header carrying the booking's session ID between services
+pub const SESSION_HEADER: &str = "x-zp-session-id";
+const SESSION_BAGGAGE_KEY: &str = "session_id";
+
+/// Keeps the span exporter alive; dropping it flushes pending spans
+pub struct Telemetry {
+    provider: Option<SdkTracerProvider>,
+}
+
+impl Drop for Telemetry {
+    fn drop(&mut self) {
+        if let Some(provider) = self.provider.take() {
+            let _ = provider.shutdown();
+        }
+    }
+}
+
+/// Install the global subscriber for `service_name`: log lines to stderr
+/// (filtered by `RUST_LOG`, default `info`; JSON with `LOG_FORMAT=json`) plus
+/// OTLP export when configured. Hold on to the returned guard until shutdown.
+pub fn init(service_name: &'static str) -> Telemetry {
+    global::set_text_map_propagator(TextMapCompositePropagator::new(vec![
+        Box::new(TraceContextPropagator::new()),
+        Box::new(BaggagePropagator::new()),
+    ]));
+
+    let provider = std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT")
+        .ok()
+        .filter(|endpoint| !endpoint.is_empty())
+        .and_then(|endpoint| match otlp_provider(service_name) {
+            Ok(provider) => {
+                eprintln!("✓ Exporting traces to {}", endpoint);
+                Some(provider)
+            }
+            Err(e) => {
+                eprintln!("⚠ Trace export disabled: {}", e);
+                None
+            }
+        });
+    let otel_layer = provider
+        .as_ref()
+        .map(|provider| tracing_opentelemetry::layer().with_tracer(provider.tracer(service_name)));
+
+    let json = std::env::var("LOG_FORMAT").is_ok_and(|format| format.eq_ignore_ascii_case("json"));
+    let json_layer = json.then(|| {
+        tracing_subscriber::fmt::layer()
+            .json()
+            .flatten_event(true)
+            .with_current_span(true)
+            .with_span_list(true)
+            .with_writer(std::io::stderr)
+    });
+    let text_layer = (!json).then(|| tracing_subscriber::fmt::layer().with_writer(std::io::stderr));
+
+    let _ = tracing_subscriber::registry()
+        .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
+        .with(json_layer)
+        .with(text_layer)
+        .with(otel_layer)
+        .try_init();
+
+    Telemetry { provider }
+}
+
+fn otlp_provider(service_name: &'static str) -> Result<SdkTracerProvider, opentelemetry_otlp::ExporterBuildError> {
+    // Endpoint, headers and timeout come from the standard OTEL_EXPORTER_OTLP_* variables
+    let exporter = opentelemetry_otlp::SpanExporter::builder().with_http().build()?;
+    Ok(SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(Resource::builder().with_service_name(service_name).build())
+        .build())
+}
+
+/// Span for an incoming request, continuing the caller's trace and tagged
+/// with the booking's session ID when the request carries one (and with the
+/// tool called, for `/tools/{name}`)
+pub fn request_span(method: &Method, path: &str, headers: &HeaderMap) -> tracing::Span {
+    let mut cx = global::get_text_map_propagator(|propagator| propagator.extract(&HeaderExtractor(headers)));
+    let session_id = headers
+        .get(SESSION_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+        .or_else(|| session_from_path(path))
+        .or_else(|| cx.baggage().get(SESSION_BAGGAGE_KEY).map(|v| v.to_string()));
+    if let Some(session_id) = &session_id {
+        cx = cx.with_baggage(vec![KeyValue::new(SESSION_BAGGAGE_KEY, session_id.clone())]);
+    }
+
+    let span = tracing::info_span!(
+        "request",
+        otel.name = %format!("{} {}", method, path),
+        http.method = %method,
+        http.target = %path,
+        session_id = tracing::field::Empty,
+        tool_name = tracing::field::Empty,
+    );
+    if let Some(session_id) = &session_id {
+        span.record("session_id", session_id.as_str());
+    }
+    if let Some(tool_name) = path.strip_prefix("/tools/") {
+        span.record("tool_name", tool_name);
+    }
+    let _ = span.set_parent(cx);
+    span
+}
+
+/// Headers carrying the current span's trace context (and session ID) on an
+/// outbound call to another agent
+pub fn propagation_headers() -> HeaderMap {
+    let cx = tracing::Span::current().context();
+    let mut headers = HeaderMap::new();
+    global::get_text_map_propagator(|propagator| propagator.inject_context(&cx, &mut HeaderInjector(&mut headers)));
+    if let Some(value) = session_of(&cx).and_then(|id| HeaderValue::from_str(&id).ok()) {
+        headers.insert(SESSION_HEADER, value);
+    }
+    headers
+}
+
+fn session_of(cx: &Context) -> Option<String> {
+    cx.baggage().get(SESSION_BAGGAGE_KEY).map(|v| v.to_string())
+}
+
+/// `abc` from `/session/abc/...`
+fn session_from_path(path: &str) -> Option<String> {
+    let rest = path.strip_prefix("/session/")?;
+    let id = rest.split('/').next()?;
+    (!id.is_empty()).then(|| id.to_string())
+}
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|v| v.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(HeaderName::as_str).collect()
+    }
+}
+
+struct HeaderInjector<'a>(&'a mut HeaderMap);
+
+impl Injector for HeaderInjector<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        if let (Ok(name), Ok(value)) = (HeaderName::from_bytes(key.as_bytes()), HeaderValue::from_str(&value)) {
+            self.0.insert(name, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trace_context_round_trip() {
+        global::set_text_map_propagator(TextMapCompositePropagator::new(vec![
+            Box::new(TraceContextPropagator::new()),
+            Box::new(BaggagePropagator::new()),
+        ]));
+        let mut incoming = HeaderMap::new();
+        incoming.insert("traceparent", HeaderValue::from_static("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"));
+
+        let cx = global::get_text_map_propagator(|p| p.extract(&HeaderExtractor(&incoming)))
+            .with_baggage(vec![KeyValue::new(SESSION_BAGGAGE_KEY, "sess_1")]);
+        let mut outgoing = HeaderMap::new();
+        global::get_text_map_propagator(|p| p.inject_context(&cx, &mut HeaderInjector(&mut outgoing)));
+
+        assert_eq!(outgoing["traceparent"], incoming["traceparent"]);
+        assert_eq!(outgoing["baggage"], "session_id=sess_1");
+        assert_eq!(session_from_path("/session/sess_1/ledger").as_deref(), Some("sess_1"));
+        assert_eq!(session_from_path("/tools"), None);
+    }
+}
@@ -0,0 +1,150 @@
+//! Optional mutual TLS between agents (feature `mtls`)
+//!
+//! Every service loads its own certificate, private key and the CA bundle it
+//! trusts for peers from PEM files. Paths are looked up per peer first
+//! (`ATTESTER_TLS_CERT`, `AGENT_B_TLS_CA`, ...) and fall back to the
+//! service-wide `TLS_CERT`, `TLS_KEY` and `TLS_CA`. When they are not set the
+//! caller keeps talking plain HTTP, so localhost setups are unchanged.
+
+use std::{
+    fmt,
+    fs::File,
+    io::{self, BufReader},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use rustls::{
+    crypto::{ring, CryptoProvider},
+    pki_types::{CertificateDer, PrivateKeyDer},
+    server::WebPkiClientVerifier,
+    ClientConfig, RootCertStore, ServerConfig,
+};
+
+/// Error loading certificates or building a TLS config
+#[derive(Debug)]
+pub enum TlsError {
+    Io(PathBuf, io::Error),
+    MissingKey(PathBuf),
+    Rustls(rustls::Error),
+    Verifier(String),
+}
+
+impl fmt::Display for TlsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TlsError::Io(path, e) => write!(f, "failed to read {}: {}", path.display(), e),
+            TlsError::MissingKey(path) => write!(f, "no private key found in {}", path.display()),
+            TlsError::Rustls(e) => write!(f, "TLS error: {}", e),
+            TlsError::Verifier(e) => write!(f, "invalid client verifier: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for TlsError {}
+
+impl From<rustls::Error> for TlsError {
+    fn from(e: rustls::Error) -> Self {
+        TlsError::Rustls(e)
+    }
+}
+
+/// PEM files making up one side of a mutually authenticated connection
+#[derive(Debug, Clone)]
+pub struct MtlsConfig {
+    /// Certificate chain presented to the peer
+    pub cert_path: PathBuf,
+    /// Private key matching `cert_path`
+    pub key_path: PathBuf,
+    /// CA bundle used to verify the peer's certificate
+    pub ca_path: PathBuf,
+}
+
+impl MtlsConfig {
+    /// Service-wide identity from `TLS_CERT`, `TLS_KEY` and `TLS_CA`
+    pub fn from_env() -> Option<Self> {
+        Self::lookup(None)
+    }
+
+    /// Identity used when calling `peer`, e.g. `for_peer("ATTESTER")` reads
+    /// `ATTESTER_TLS_CERT` and falls back to `TLS_CERT`
+    pub fn for_peer(peer: &str) -> Option<Self> {
+        Self::lookup(Some(peer))
+    }
+
+    fn lookup(peer: Option<&str>) -> Option<Self> {
+        let var = |name: &str| {
+            peer.and_then(|p| std::env::var(format!("{}_{}", p, name)).ok())
+                .or_else(|| std::env::var(name).ok())
+                .filter(|v| !v.is_empty())
+                .map(PathBuf::from)
+        };
+
+        Some(Self {
+            cert_path: var("TLS_CERT")?,
+            key_path: var("TLS_KEY")?,
+            ca_path: var("TLS_CA")?,
+        })
+    }
+
+    /// Server config that requires clients to present a certificate signed by `ca_path`
+    pub fn server_config(&self) -> Result<ServerConfig, TlsError> {
+        let provider = provider();
+        let verifier =
+            WebPkiClientVerifier::builder_with_provider(Arc::new(self.roots()?), provider.clone())
+                .build()
+                .map_err(|e| TlsError::Verifier(e.to_string()))?;
+
+        let mut config = ServerConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()?
+            .with_client_cert_verifier(verifier)
+            .with_single_cert(self.certs()?, self.key()?)?;
+        config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+        Ok(config)
+    }
+
+    /// Client config that presents `cert_path` and only trusts servers signed by `ca_path`
+    pub fn client_config(&self) -> Result<ClientConfig, TlsError> {
+        let config = ClientConfig::builder_with_provider(provider())
+            .with_safe_default_protocol_versions()?
+            .with_root_certificates(self.roots()?)
+            .with_client_auth_cert(self.certs()?, self.key()?)?;
+        Ok(config)
+    }
+
+    fn certs(&self) -> Result<Vec<CertificateDer<'static>>, TlsError> {
+        read_certs(&self.cert_path)
+    }
+
+    fn key(&self) -> Result<PrivateKeyDer<'static>, TlsError> {
+        let mut reader = open(&self.key_path)?;
+        rustls_pemfile::private_key(&mut reader)
+            .map_err(|e| TlsError::Io(self.key_path.clone(), e))?
+            .ok_or_else(|| TlsError::MissingKey(self.key_path.clone()))
+    }
+
+    fn roots(&self) -> Result<RootCertStore, TlsError> {
+        let mut roots = RootCertStore::empty();
+        for cert in read_certs(&self.ca_path)? {
+            roots.add(cert)?;
+        }
+        Ok(roots)
+    }
+}
+
+fn provider() -> Arc<CryptoProvider> {
+    Arc::new(ring::default_provider())
+}
+
+fn open(path: &Path) -> Result<BufReader<File>, TlsError> {
+    File::open(path)
+        .map(BufReader::new)
+        .map_err(|e| TlsError::Io(path.to_path_buf(), e))
+}
+
+fn read_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>, TlsError> {
+    let mut reader = open(path)?;
+    rustls_pemfile::certs(&mut reader)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| TlsError::Io(path.to_path_buf(), e))
+}
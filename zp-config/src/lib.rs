@@ -0,0 +1,587 @@
+//! Typed settings for Agent A, Agent B and the attester
+//!
+//! Each service reads the same layered configuration: the defaults of the
+//! selected profile, then an optional TOML file (`ZP_CONFIG`, or
+//! `zeroproof.toml` in the working directory when present), then environment
+//! variables. The result is validated once at startup, so a bad URL or port
+//! fails fast instead of on the first request. Run any service with
+//! `--print-config` to see what it resolved.
+//!
+//! The profile (`ZP_PROFILE`, or `profile` in the file; `local` by default)
+//! picks the chain and service endpoints every service agrees on:
+//!
+//! | Profile | Services | Chain | Cross-origin callers |
+//! |---------|----------|-------|----------------------|
+//! | `local` | `localhost` | Sepolia | any |
+//! | `dev` | container names (`attester`, `agent-b`, `zkfetch`) | Sepolia | any |
+//! | `staging` | container names | Sepolia | `allowed_origins` only |
+//! | `prod` | container names | must be configured | `allowed_origins` only |
+//!
+//! Secrets (`AGENT_HMAC_SECRET`, TLS keys, wallet keys) stay in their own
+//! environment variables and never appear here.
+//!
+//! ```toml
+//! profile = "staging"
+//!
+//! [network]
+//! allowed_origins = ["https://app.example.com"]
+//!
+//! [agent_a]
+//! port = 3001
+//!
+//! [attester]
+//! workers = 2
+//! prover = "cuda"
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::OnceLock,
+};
+
+/// File read when `ZP_CONFIG` is not set, if it exists
+pub const DEFAULT_CONFIG_FILE: &str = "zeroproof.toml";
+
+/// Public Sepolia JSON-RPC endpoint
+const SEPOLIA_RPC_URL: &str = "https://ethereum-sepolia-rpc.publicnode.com";
+/// ZeroProof entry point deployed on Sepolia (see `contracts/README.md`)
+const SEPOLIA_ZEROPROOF_ADDRESS: &str = "0x9C33252D29B41Fe2706704a8Ca99E8731B58af41";
+/// Reclaim zkTLS verifier on Sepolia
+const SEPOLIA_RECLAIM_ADDRESS: &str = "0xAe94FB09711e1c6B057853a515483792d8e474d0";
+
+/// Settings for every service; each reads its own section and `network`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub profile: Profile,
+    pub network: NetworkSettings,
+    pub agent_a: AgentASettings,
+    pub agent_b: AgentBSettings,
+    pub attester: AttesterSettings,
+}
+
+/// Deployment environment, selecting the default endpoints
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Profile {
+    /// Every service on this machine
+    #[default]
+    Local,
+    /// Services reached by container name
+    Dev,
+    /// As `dev`, with cross-origin calls limited to `allowed_origins`
+    Staging,
+    /// No testnet defaults: the chain endpoints must be configured
+    Prod,
+}
+
+impl Profile {
+    fn as_str(self) -> &'static str {
+        match self {
+            Profile::Local => "local",
+            Profile::Dev => "dev",
+            Profile::Staging => "staging",
+            Profile::Prod => "prod",
+        }
+    }
+}
+
+impl FromStr for Profile {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "local" => Ok(Profile::Local),
+            "dev" => Ok(Profile::Dev),
+            "staging" => Ok(Profile::Staging),
+            "prod" => Ok(Profile::Prod),
+            other => Err(format!("expected local, dev, staging or prod, got {}", other)),
+        }
+    }
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Chain and shared service endpoints, the same for Agent A and Agent B
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NetworkSettings {
+    /// `RPC_URL`: JSON-RPC endpoint of the chain the verifiers are on
+    pub rpc_url: String,
+    /// `ZEROPROOF_ADDRESS`: ZeroProof verifier entry point
+    pub zeroproof_address: String,
+    /// `RECLAIM_ADDRESS`: Reclaim zkTLS verifier
+    pub reclaim_address: String,
+    /// `ATTESTER_URL`
+    pub attester_url: String,
+    /// `ZKFETCH_URL`: zkfetch wrapper producing zkTLS proofs
+    pub zkfetch_url: String,
+    /// `ALLOWED_ORIGINS` (comma-separated): browser origins allowed to call
+    /// the agents' HTTP APIs; `*` allows any
+    pub allowed_origins: Vec<String>,
+}
+
+impl NetworkSettings {
+    fn for_profile(profile: Profile) -> Self {
+        let (attester_url, zkfetch_url) = match profile {
+            Profile::Local => ("http://localhost:8000", "http://localhost:8003"),
+            Profile::Dev | Profile::Staging | Profile::Prod => ("http://attester:8000", "http://zkfetch:8003"),
+        };
+        let (rpc_url, zeroproof_address, reclaim_address) = match profile {
+            Profile::Prod => ("", "", ""),
+            _ => (SEPOLIA_RPC_URL, SEPOLIA_ZEROPROOF_ADDRESS, SEPOLIA_RECLAIM_ADDRESS),
+        };
+        let allowed_origins = match profile {
+            Profile::Local | Profile::Dev => vec!["*".to_string()],
+            Profile::Staging | Profile::Prod => Vec::new(),
+        };
+        Self {
+            rpc_url: rpc_url.to_string(),
+            zeroproof_address: zeroproof_address.to_string(),
+            reclaim_address: reclaim_address.to_string(),
+            attester_url: attester_url.to_string(),
+            zkfetch_url: zkfetch_url.to_string(),
+            allowed_origins,
+        }
+    }
+
+    /// Whether browsers on any origin may call the agents
+    pub fn allows_any_origin(&self) -> bool {
+        self.allowed_origins.iter().any(|origin| origin == "*")
+    }
+}
+
+impl Default for NetworkSettings {
+    fn default() -> Self {
+        Self::for_profile(Profile::Local)
+    }
+}
+
+/// How the Agent A server talks to its client
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AgentAMode {
+    /// HTTP API (default)
+    Http,
+    /// JSON-RPC over stdin/stdout
+    JsonRpc,
+}
+
+impl FromStr for AgentAMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "http" => Ok(AgentAMode::Http),
+            "jsonrpc" => Ok(AgentAMode::JsonRpc),
+            other => Err(format!("expected http or jsonrpc, got {}", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AgentASettings {
+    /// `AGENT_A_SERVER_PORT`
+    pub port: u16,
+    /// `AGENT_A_MODE`
+    pub mode: AgentAMode,
+    /// `AGENT_B_URL`
+    pub agent_b_url: String,
+    /// `PAYMENT_AGENT_URL`: probed by the readiness check when set
+    pub payment_agent_url: Option<String>,
+}
+
+impl AgentASettings {
+    fn for_profile(profile: Profile) -> Self {
+        let agent_b_url = match profile {
+            Profile::Local => "http://localhost:8001",
+            Profile::Dev | Profile::Staging | Profile::Prod => "http://agent-b:8001",
+        };
+        Self {
+            port: 3001,
+            mode: AgentAMode::Http,
+            agent_b_url: agent_b_url.to_string(),
+            payment_agent_url: None,
+        }
+    }
+}
+
+impl Default for AgentASettings {
+    fn default() -> Self {
+        Self::for_profile(Profile::Local)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AgentBSettings {
+    /// `AGENT_B_PORT`
+    pub port: u16,
+    /// `BOOKINGS_PATH`: JSON file bookings are persisted to
+    pub bookings_path: PathBuf,
+    /// `FARE_TABLE_PATH`: fare table override; the embedded table otherwise
+    pub fare_table_path: Option<PathBuf>,
+    /// `AGENT_B_PUBLIC_URL`: base URL advertised in the A2A agent card;
+    /// `AGENT_B_URL` otherwise
+    pub public_url: Option<String>,
+}
+
+impl Default for AgentBSettings {
+    fn default() -> Self {
+        Self {
+            port: 8001,
+            bookings_path: PathBuf::from("bookings.json"),
+            fare_table_path: None,
+            public_url: None,
+        }
+    }
+}
+
+/// Local SP1 proving backend
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Prover {
+    Cpu,
+    Cuda,
+    Mock,
+    Network,
+}
+
+impl FromStr for Prover {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "cpu" => Ok(Prover::Cpu),
+            "cuda" => Ok(Prover::Cuda),
+            "mock" => Ok(Prover::Mock),
+            "network" => Ok(Prover::Network),
+            other => Err(format!("expected cpu, cuda, mock or network, got {}", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AttesterSettings {
+    /// `ATTESTER_PORT`
+    pub port: u16,
+    /// `ATTESTER_WORKERS`: proofs generated concurrently
+    pub workers: usize,
+    /// `ATTESTER_PROOF_CACHE_TTL_SECS`
+    pub proof_cache_ttl_secs: u64,
+    /// `SP1_PROVER`
+    pub prover: Prover,
+    /// `ATTESTER_LOCAL_LATENCY_SECS`: typical local proving time; derived
+    /// from `prover` when unset
+    pub local_latency_secs: Option<u64>,
+}
+
+impl Default for AttesterSettings {
+    fn default() -> Self {
+        Self {
+            port: 8000,
+            workers: 1,
+            proof_cache_ttl_secs: 3600,
+            prover: Prover::Cpu,
+            local_latency_secs: None,
+        }
+    }
+}
+
+/// Why the configuration could not be loaded
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(PathBuf, std::io::Error),
+    Parse(PathBuf, toml::de::Error),
+    /// A setting (named by its environment variable or file key) is malformed
+    Invalid { key: &'static str, reason: String },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(path, e) => write!(f, "failed to read {}: {}", path.display(), e),
+            ConfigError::Parse(path, e) => write!(f, "invalid config file {}: {}", path.display(), e),
+            ConfigError::Invalid { key, reason } => write!(f, "invalid {}: {}", key, reason),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Settings {
+    /// Defaults of `profile`
+    pub fn for_profile(profile: Profile) -> Self {
+        Self {
+            profile,
+            network: NetworkSettings::for_profile(profile),
+            agent_a: AgentASettings::for_profile(profile),
+            agent_b: AgentBSettings::default(),
+            attester: AttesterSettings::default(),
+        }
+    }
+
+    /// Profile defaults, then the config file, then the process environment, validated
+    pub fn load() -> Result<Self, ConfigError> {
+        let file = match std::env::var("ZP_CONFIG") {
+            Ok(path) => Some(PathBuf::from(path)),
+            Err(_) => Some(PathBuf::from(DEFAULT_CONFIG_FILE)).filter(|path| path.exists()),
+        };
+        let text = match &file {
+            Some(path) => std::fs::read_to_string(path).map_err(|e| ConfigError::Io(path.clone(), e))?,
+            None => String::new(),
+        };
+        let path = file.as_deref().unwrap_or(Path::new(DEFAULT_CONFIG_FILE));
+        let mut settings = Self::layered(path, &text, std::env::var("ZP_PROFILE").ok())?;
+        settings.apply_env(|var| std::env::var(var).ok())?;
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    /// The file's settings (`text`, read from `path`) over the defaults of
+    /// the profile named by `profile`, the file, or `local`, in that order
+    fn layered(path: &Path, text: &str, profile: Option<String>) -> Result<Self, ConfigError> {
+        let mut file: toml::Table = toml::from_str(text).map_err(|e| ConfigError::Parse(path.to_path_buf(), e))?;
+        let profile = match profile.or_else(|| file.get("profile").and_then(|p| p.as_str()).map(str::to_string)) {
+            Some(name) => name.parse().map_err(|reason| ConfigError::Invalid { key: "ZP_PROFILE", reason })?,
+            None => Profile::Local,
+        };
+        file.insert("profile".to_string(), profile.as_str().into());
+
+        let mut merged = toml::Table::try_from(Self::for_profile(profile)).expect("settings serialize as a TOML table");
+        overlay(&mut merged, file);
+        toml::Value::Table(merged)
+            .try_into()
+            .map_err(|e| ConfigError::Parse(path.to_path_buf(), e))
+    }
+
+    /// Override settings from environment variables looked up with `env`
+    fn apply_env(&mut self, env: impl Fn(&str) -> Option<String>) -> Result<(), ConfigError> {
+        let n = &mut self.network;
+        set(&env, "RPC_URL", &mut n.rpc_url)?;
+        set(&env, "ZEROPROOF_ADDRESS", &mut n.zeroproof_address)?;
+        set(&env, "RECLAIM_ADDRESS", &mut n.reclaim_address)?;
+        set(&env, "ATTESTER_URL", &mut n.attester_url)?;
+        set(&env, "ZKFETCH_URL", &mut n.zkfetch_url)?;
+        if let Some(origins) = env("ALLOWED_ORIGINS") {
+            n.allowed_origins = origins
+                .split(',')
+                .map(str::trim)
+                .filter(|origin| !origin.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+
+        let a = &mut self.agent_a;
+        set(&env, "AGENT_A_SERVER_PORT", &mut a.port)?;
+        set(&env, "AGENT_A_MODE", &mut a.mode)?;
+        set(&env, "AGENT_B_URL", &mut a.agent_b_url)?;
+        set_opt(&env, "PAYMENT_AGENT_URL", &mut a.payment_agent_url)?;
+
+        let b = &mut self.agent_b;
+        set(&env, "AGENT_B_PORT", &mut b.port)?;
+        set(&env, "BOOKINGS_PATH", &mut b.bookings_path)?;
+        set_opt(&env, "FARE_TABLE_PATH", &mut b.fare_table_path)?;
+        set_opt(&env, "AGENT_B_PUBLIC_URL", &mut b.public_url)?;
+
+        let t = &mut self.attester;
+        set(&env, "ATTESTER_PORT", &mut t.port)?;
+        set(&env, "ATTESTER_WORKERS", &mut t.workers)?;
+        set(&env, "ATTESTER_PROOF_CACHE_TTL_SECS", &mut t.proof_cache_ttl_secs)?;
+        set(&env, "SP1_PROVER", &mut t.prover)?;
+        set_opt(&env, "ATTESTER_LOCAL_LATENCY_SECS", &mut t.local_latency_secs)?;
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        let invalid = |key, reason: &str| Err(ConfigError::Invalid { key, reason: reason.to_string() });
+        let required = format!("required in the {} profile", self.profile);
+        for (key, url) in [
+            ("AGENT_B_URL", &self.agent_a.agent_b_url),
+            ("ATTESTER_URL", &self.network.attester_url),
+            ("ZKFETCH_URL", &self.network.zkfetch_url),
+            ("RPC_URL", &self.network.rpc_url),
+        ] {
+            if url.is_empty() {
+                return invalid(key, &required);
+            }
+            if !(url.starts_with("http://") || url.starts_with("https://")) {
+                return invalid(key, "expected an http:// or https:// URL");
+            }
+        }
+        for (key, url) in [
+            ("PAYMENT_AGENT_URL", &self.agent_a.payment_agent_url),
+            ("AGENT_B_PUBLIC_URL", &self.agent_b.public_url),
+        ] {
+            if let Some(url) = url {
+                if !(url.starts_with("http://") || url.starts_with("https://")) {
+                    return invalid(key, "expected an http:// or https:// URL");
+                }
+            }
+        }
+        for (key, address) in [
+            ("ZEROPROOF_ADDRESS", &self.network.zeroproof_address),
+            ("RECLAIM_ADDRESS", &self.network.reclaim_address),
+        ] {
+            if address.is_empty() {
+                return invalid(key, &required);
+            }
+            let hex = address.strip_prefix("0x").unwrap_or_default();
+            if hex.len() != 40 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                return invalid(key, "expected a 0x-prefixed 20-byte address");
+            }
+        }
+        for origin in &self.network.allowed_origins {
+            if origin != "*" && !(origin.starts_with("http://") || origin.starts_with("https://")) {
+                return invalid("ALLOWED_ORIGINS", "expected * or http(s):// origins");
+            }
+        }
+        if matches!(self.profile, Profile::Staging | Profile::Prod) && self.network.allows_any_origin() {
+            return invalid("ALLOWED_ORIGINS", &format!("* is not allowed in the {} profile", self.profile));
+        }
+        for (key, port) in [
+            ("AGENT_A_SERVER_PORT", self.agent_a.port),
+            ("AGENT_B_PORT", self.agent_b.port),
+            ("ATTESTER_PORT", self.attester.port),
+        ] {
+            if port == 0 {
+                return invalid(key, "port must not be 0");
+            }
+        }
+        if self.attester.workers == 0 {
+            return invalid("ATTESTER_WORKERS", "at least one worker is required");
+        }
+        Ok(())
+    }
+
+    /// Resolved settings as TOML, for `--print-config`
+    pub fn to_toml(&self) -> String {
+        toml::to_string_pretty(self).unwrap_or_else(|e| format!("# could not render settings: {}\n", e))
+    }
+}
+
+/// Overlay `overrides` on `base`, section by section
+fn overlay(base: &mut toml::Table, overrides: toml::Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(overrides)) => overlay(base, overrides),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+fn set<T: FromStr>(env: &impl Fn(&str) -> Option<String>, key: &'static str, field: &mut T) -> Result<(), ConfigError>
+where
+    T::Err: fmt::Display,
+{
+    if let Some(value) = env(key) {
+        *field = value.parse().map_err(|e: T::Err| ConfigError::Invalid { key, reason: e.to_string() })?;
+    }
+    Ok(())
+}
+
+fn set_opt<T: FromStr>(
+    env: &impl Fn(&str) -> Option<String>,
+    key: &'static str,
+    field: &mut Option<T>,
+) -> Result<(), ConfigError>
+where
+    T::Err: fmt::Display,
+{
+    if let Some(value) = env(key).filter(|v| !v.is_empty()) {
+        *field = Some(value.parse().map_err(|e: T::Err| ConfigError::Invalid { key, reason: e.to_string() })?);
+    }
+    Ok(())
+}
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+/// Load this process's settings; call once at startup so configuration
+/// errors surface before the service starts
+pub fn init() -> Result<&'static Settings, ConfigError> {
+    if let Some(settings) = SETTINGS.get() {
+        return Ok(settings);
+    }
+    let settings = Settings::load()?;
+    Ok(SETTINGS.get_or_init(|| settings))
+}
+
+/// This process's settings, as loaded by [`init`]
+///
+/// # Panics
+///
+/// If the configuration is invalid and [`init`] was not called first.
+pub fn get() -> &'static Settings {
+    SETTINGS.get_or_init(|| Settings::load().unwrap_or_else(|e| panic!("invalid configuration: {}", e)))
+}
+
+/// Whether the process was started with `--print-config`
+pub fn print_config_requested() -> bool {
+    std::env::args().any(|arg| arg == "--print-config")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_then_env_layering() {
+        let mut settings: Settings = toml::from_str(
+            r#"
+            [agent_a]
+            port = 4001
+            agent_b_url = "http://agent-b:8001"
+
+            [attester]
+            prover = "mock"
+            "#,
+        )
+        .unwrap();
+        settings
+            .apply_env(|var| match var {
+                "AGENT_B_URL" => Some("https://agent-b.internal".to_string()),
+                "ATTESTER_WORKERS" => Some("2".to_string()),
+                _ => None,
+            })
+            .unwrap();
+        settings.validate().unwrap();
+        assert_eq!(settings.agent_a.port, 4001);
+        assert_eq!(settings.agent_a.agent_b_url, "https://agent-b.internal");
+        assert_eq!(settings.network.attester_url, "http://localhost:8000");
+        assert_eq!(settings.attester.prover, Prover::Mock);
+        assert_eq!(settings.attester.workers, 2);
+
+        let err = settings.apply_env(|var| (var == "ATTESTER_WORKERS").then(|| "many".to_string()));
+        assert!(matches!(err, Err(ConfigError::Invalid { key: "ATTESTER_WORKERS", .. })));
+        settings.network.rpc_url = "sepolia".to_string();
+        assert!(matches!(settings.validate(), Err(ConfigError::Invalid { key: "RPC_URL", .. })));
+        assert!(toml::from_str::<Settings>("[agent_a]\nprot = 1").is_err());
+    }
+
+    #[test]
+    fn test_profile_defaults_under_file() {
+        let file = "profile = \"staging\"\n[network]\nallowed_origins = [\"https://app.example.com\"]\n";
+        let settings = Settings::layered(Path::new("zeroproof.toml"), file, None).unwrap();
+        settings.validate().unwrap();
+        assert_eq!(settings.profile, Profile::Staging);
+        assert_eq!(settings.network.attester_url, "http://attester:8000");
+        assert_eq!(settings.agent_a.agent_b_url, "http://agent-b:8001");
+        assert_eq!(settings.network.allowed_origins, ["https://app.example.com"]);
+
+        // ZP_PROFILE wins over the file; prod has no chain defaults to fall back on
+        let prod = Settings::layered(Path::new("zeroproof.toml"), file, Some("prod".to_string())).unwrap();
+        assert!(matches!(prod.validate(), Err(ConfigError::Invalid { key: "RPC_URL", .. })));
+        assert!(Settings::layered(Path::new("zeroproof.toml"), "", Some("qa".to_string())).is_err());
+    }
+}